@@ -1,5 +1,9 @@
+use std::str::FromStr;
+
+use cpuctl::PstateMode;
 use gumdrop::Options;
 use rog_platform::platform::ThrottlePolicy;
+use rog_platform::power::ChargeRule;
 
 use crate::anime_cli::AnimeCommand;
 use crate::aura_cli::{LedBrightness, LedPowerCommand1, LedPowerCommand2, SetAuraBuiltin};
@@ -20,12 +24,50 @@ pub struct CliStart {
     pub next_kbd_bright: bool,
     #[options(help = "Toggle to previous keyboard brightness")]
     pub prev_kbd_bright: bool,
-    #[options(meta = "", help = "Set your battery charge limit <20-100>")]
-    pub chg_limit: Option<u8>,
+    #[options(
+        meta = "",
+        help = "Set your battery charge limit <20-100>, or <full> to charge to 100% until \
+                unplugged"
+    )]
+    pub chg_limit: Option<ChargeLimit>,
+    #[options(
+        no_short,
+        meta = "",
+        help = "Add a time of day charge limit, e.g. \"mon-fri 00:00-07:00 100\". Can be given \
+                more than once, the first active rule is used"
+    )]
+    pub chg_rule: Vec<ChargeRule>,
+    #[options(no_short, help = "Remove all time of day charge limits")]
+    pub chg_rules_clear: bool,
+    #[options(no_short, help = "Show the charge limit and time of day charge limits")]
+    pub chg_show: bool,
     #[options(command)]
     pub command: Option<CliCommand>,
 }
 
+/// The argument to `--chg-limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeLimit {
+    Limit(u8),
+    /// Charge to 100% until power is unplugged
+    Full,
+}
+
+impl FromStr for ChargeLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if s == "full" {
+            return Ok(Self::Full);
+        }
+        match s.parse::<u8>() {
+            Ok(limit) if (20..=100).contains(&limit) => Ok(Self::Limit(limit)),
+            _ => Err(format!("{s} is not <20-100> or <full>")),
+        }
+    }
+}
+
 #[derive(Options)]
 pub enum CliCommand {
     #[options(help = "Set the keyboard lighting from built-in modes")]
@@ -46,6 +88,10 @@ pub enum CliCommand {
     Slash(SlashCommand),
     #[options(help = "Change bios settings")]
     Bios(BiosCommand),
+    #[options(help = "Show battery charge and health")]
+    Battery(BatteryCommand),
+    #[options(help = "Show or change CPU cores, boost, frequency limits, and SMT")]
+    Cpu(CpuCommand),
 }
 
 #[derive(Debug, Clone, Options)]
@@ -62,8 +108,67 @@ pub struct ProfileCommand {
     #[options(help = "get profile")]
     pub profile_get: bool,
 
-    #[options(meta = "", help = "set the active profile")]
-    pub profile_set: Option<ThrottlePolicy>,
+    #[options(
+        meta = "",
+        help = "set the active profile, a throttle policy or user profile"
+    )]
+    pub profile_set: Option<String>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "comma separated user profiles for --next to cycle through, empty for the \
+                throttle policies"
+    )]
+    pub user_order: Option<String>,
+
+    #[options(meta = "", no_short, help = "remove a user profile")]
+    pub remove_user: Option<String>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "<true/false> move between profiles with CPU temperature and load"
+    )]
+    pub governor: Option<bool>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "show or modify the PPT and Nvidia tuning applied with a profile"
+    )]
+    pub tune: Option<ThrottlePolicy>,
+
+    #[options(no_short, help = "use with --tune to modify the battery tuning")]
+    pub battery: bool,
+
+    #[options(meta = "", no_short, help = "PL1 on Intel, SPL on AMD")]
+    pub ppt_pl1_spl: Option<u8>,
+
+    #[options(meta = "", no_short, help = "PL2 on Intel, SPPT on AMD")]
+    pub ppt_pl2_sppt: Option<u8>,
+
+    #[options(meta = "", no_short, help = "FPPT, AMD only")]
+    pub ppt_fppt: Option<u8>,
+
+    #[options(meta = "", no_short, help = "APU SPPT, AMD only")]
+    pub ppt_apu_sppt: Option<u8>,
+
+    #[options(meta = "", no_short, help = "platform SPPT, AMD only")]
+    pub ppt_platform_sppt: Option<u8>,
+
+    #[options(meta = "", no_short, help = "Nvidia dynamic boost")]
+    pub nv_dynamic_boost: Option<u8>,
+
+    #[options(meta = "", no_short, help = "Nvidia temperature target")]
+    pub nv_temp_target: Option<u8>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "use with --tune to unset an attribute, e.g. ppt_fppt. Can be repeated"
+    )]
+    pub unset: Vec<String>,
 }
 
 #[derive(Options)]
@@ -74,6 +179,18 @@ pub struct LedModeCommand {
     pub next_mode: bool,
     #[options(help = "switch to previous aura mode")]
     pub prev_mode: bool,
+    #[options(
+        meta = "",
+        no_short,
+        help = "<true/false> run the per-key effects in the aura config file"
+    )]
+    pub effects: Option<bool>,
+    #[options(
+        meta = "",
+        no_short,
+        help = "frames per second of the per-key effects, 1-60"
+    )]
+    pub effects_fps: Option<u8>,
     #[options(command)]
     pub command: Option<SetAuraBuiltin>,
 }
@@ -84,6 +201,60 @@ pub struct GraphicsCommand {
     pub help: bool,
 }
 
+#[derive(Options, Debug)]
+pub struct BatteryCommand {
+    #[options(help = "print help message")]
+    pub help: bool,
+}
+
+#[derive(Options, Debug)]
+pub struct CpuCommand {
+    #[options(help = "print help message")]
+    pub help: bool,
+
+    #[options(help = "show what can be changed and the state of each CPU")]
+    pub info: bool,
+
+    #[options(
+        meta = "",
+        help = "change the settings kept for a throttle policy instead of the active ones"
+    )]
+    pub policy: Option<ThrottlePolicy>,
+
+    #[options(meta = "", no_short, help = "<true/false> CPU boost or turbo")]
+    pub boost: Option<bool>,
+
+    #[options(meta = "", no_short, help = "<true/false> simultaneous multithreading")]
+    pub smt: Option<bool>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "amd-pstate mode: active, guided, or passive"
+    )]
+    pub pstate: Option<PstateMode>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "minimum scaling frequency in kHz, 0 = unset"
+    )]
+    pub min_freq: Option<u32>,
+
+    #[options(
+        meta = "",
+        no_short,
+        help = "maximum scaling frequency in kHz, 0 = unset"
+    )]
+    pub max_freq: Option<u32>,
+
+    #[options(meta = "", no_short, help = "take a CPU offline")]
+    pub offline: Option<u32>,
+
+    #[options(meta = "", no_short, help = "bring a CPU back online")]
+    pub online: Option<u32>,
+}
+
 #[derive(Options, Debug)]
 pub struct BiosCommand {
     #[options(help = "print help message")]
@@ -115,4 +286,14 @@ pub struct BiosCommand {
     pub panel_overdrive_set: Option<bool>,
     #[options(no_long, short = "o", help = "get panel overdrive")]
     pub panel_overdrive_get: bool,
+    #[options(no_short, help = "list all firmware attributes")]
+    pub list_attrs: bool,
+    #[options(
+        meta = "",
+        no_short,
+        help = "get a firmware attribute, or set it with --value"
+    )]
+    pub attr: Option<String>,
+    #[options(meta = "", no_short, help = "the value to set a firmware attribute to")]
+    pub value: Option<String>,
 }
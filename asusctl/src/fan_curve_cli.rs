@@ -11,6 +11,24 @@ pub struct FanCurveCommand {
     #[options(help = "get enabled fan profiles")]
     pub get_enabled: bool,
 
+    #[options(help = "show the current fan speeds and temperatures")]
+    pub sensors: bool,
+
+    #[options(meta = "", help = "save the fan curves of all profiles to a file")]
+    pub export: Option<String>,
+
+    #[options(
+        meta = "",
+        help = "load the fan curves of all profiles from a file made with `--export`"
+    )]
+    pub import: Option<String>,
+
+    #[options(
+        help = "with `--import`, drop curves for fans this laptop doesn't have and copy the CPU \
+                curve to fans without one"
+    )]
+    pub map_fans: bool,
+
     #[options(help = "set the active profile's fan curve to default")]
     pub default: bool,
 
@@ -43,7 +61,11 @@ pub struct FanCurveCommand {
     #[options(
         meta = "",
         help = "data format = 30c:1%,49c:2%,59c:3%,69c:4%,79c:31%,89c:49%,99c:56%,109c:58%. \
-                `--mod-profile` required. If '%' is omitted the fan range is 0-255"
+                `--mod-profile` required. If '%' is omitted the fan range is 0-255. More than 8 \
+                points are resampled to 8"
     )]
     pub data: Option<CurveData>,
+
+    #[options(help = "draw a smooth curve through the `--data` points before resampling")]
+    pub smooth: bool,
 }
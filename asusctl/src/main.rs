@@ -2,10 +2,12 @@ use std::convert::TryFrom;
 use std::env::args;
 use std::path::Path;
 use std::process::Command;
+use std::str::FromStr;
 use std::thread::sleep;
 
 use anime_cli::{AnimeActions, AnimeCommand};
 use aura_cli::{LedPowerCommand1, LedPowerCommand2};
+use cpuctl::CpuSettings;
 use dmi_id::DMIID;
 use fan_curve_cli::FanCurveCommand;
 use gumdrop::{Opt, Options};
@@ -16,11 +18,18 @@ use rog_aura::{self, AuraDeviceType, AuraEffect, PowerZones};
 use rog_dbus::list_iface_blocking;
 use rog_dbus::zbus_anime::AnimeProxyBlocking;
 use rog_dbus::zbus_aura::AuraProxyBlocking;
+use rog_dbus::zbus_cpu::CpuProxyBlocking;
 use rog_dbus::zbus_fan_curves::FanCurvesProxyBlocking;
 use rog_dbus::zbus_platform::PlatformProxyBlocking;
 use rog_dbus::zbus_slash::SlashProxyBlocking;
+use rog_platform::cpu::CoreType;
 use rog_platform::platform::{GpuMode, Properties, ThrottlePolicy};
+use rog_platform::power::BatteryInfo;
 use rog_profiles::error::ProfileError;
+use rog_profiles::fan_curve_file::FanCurveExport;
+use rog_profiles::fan_curve_points::Interpolation;
+use rog_profiles::fan_curve_set::CurveData;
+use rog_profiles::FanCurvePU;
 use rog_slash::SlashMode;
 use ron::ser::PrettyConfig;
 use zbus::blocking::Connection;
@@ -180,10 +189,15 @@ fn do_parsed(
         Some(CliCommand::Bios(cmd)) => {
             handle_platform_properties(&conn, supported_properties, cmd)?
         }
+        Some(CliCommand::Battery(cmd)) => handle_battery(&conn, cmd)?,
+        Some(CliCommand::Cpu(cmd)) => handle_cpu(&conn, cmd)?,
         None => {
             if (!parsed.show_supported
                 && parsed.kbd_bright.is_none()
                 && parsed.chg_limit.is_none()
+                && parsed.chg_rule.is_empty()
+                && !parsed.chg_rules_clear
+                && !parsed.chg_show
                 && !parsed.next_kbd_bright
                 && !parsed.prev_kbd_bright)
                 || parsed.help
@@ -311,7 +325,39 @@ fn do_parsed(
 
     if let Some(chg_limit) = parsed.chg_limit {
         let proxy = PlatformProxyBlocking::new(&conn)?;
-        proxy.set_charge_control_end_threshold(chg_limit)?;
+        match chg_limit {
+            ChargeLimit::Limit(limit) => {
+                proxy.set_charge_full_until_unplugged(false)?;
+                proxy.set_charge_control_end_threshold(limit)?;
+            }
+            ChargeLimit::Full => proxy.set_charge_full_until_unplugged(true)?,
+        }
+    }
+
+    if parsed.chg_rules_clear || !parsed.chg_rule.is_empty() {
+        let proxy = PlatformProxyBlocking::new(&conn)?;
+        let mut rules = if parsed.chg_rules_clear {
+            Vec::new()
+        } else {
+            proxy.charge_rules()?
+        };
+        rules.extend(parsed.chg_rule.iter().copied());
+        proxy.set_charge_rules(&rules)?;
+    }
+
+    if parsed.chg_show {
+        let proxy = PlatformProxyBlocking::new(&conn)?;
+        println!("Charge limit: {}", proxy.charge_control_end_threshold()?);
+        if proxy.charge_full_until_unplugged()? {
+            println!("Charging to 100% until unplugged");
+        }
+        let rules = proxy.charge_rules()?;
+        if rules.is_empty() {
+            println!("No time of day charge limits");
+        }
+        for rule in rules {
+            println!("  {rule}");
+        }
     }
 
     Ok(())
@@ -560,7 +606,12 @@ fn handle_led_mode(
     aura: &[AuraProxyBlocking],
     mode: &LedModeCommand,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if mode.command.is_none() && !mode.prev_mode && !mode.next_mode {
+    if mode.command.is_none()
+        && !mode.prev_mode
+        && !mode.next_mode
+        && mode.effects.is_none()
+        && mode.effects_fps.is_none()
+    {
         if !mode.help {
             println!("Missing arg or command\n");
         }
@@ -598,6 +649,17 @@ fn handle_led_mode(
         println!("Please specify either next or previous");
         return Ok(());
     }
+    // The frame rate first so enabling the effects starts at the new rate
+    if let Some(fps) = mode.effects_fps {
+        for aura in aura {
+            aura.set_advanced_effects_fps(fps)?;
+        }
+    }
+    if let Some(enabled) = mode.effects {
+        for aura in aura {
+            aura.set_advanced_effects_enabled(enabled)?;
+        }
+    }
     if mode.next_mode {
         for aura in aura {
             let mode = aura.led_mode()?;
@@ -770,7 +832,15 @@ fn handle_throttle_profile(
         return Err(ProfileError::NotSupported.into());
     }
 
-    if !cmd.next && !cmd.list && cmd.profile_set.is_none() && !cmd.profile_get {
+    if !cmd.next
+        && !cmd.list
+        && cmd.profile_set.is_none()
+        && !cmd.profile_get
+        && cmd.tune.is_none()
+        && cmd.user_order.is_none()
+        && cmd.remove_user.is_none()
+        && cmd.governor.is_none()
+    {
         if !cmd.help {
             println!("Missing arg or command\n");
         }
@@ -783,12 +853,35 @@ fn handle_throttle_profile(
     }
 
     let proxy = PlatformProxyBlocking::new(conn)?;
-    let current = proxy.throttle_thermal_policy()?;
+
+    if let Some(name) = &cmd.remove_user {
+        proxy.remove_user_profile(name)?;
+    }
+
+    if let Some(order) = &cmd.user_order {
+        let order: Vec<String> = order
+            .split(',')
+            .map(|name| name.trim().to_owned())
+            .filter(|name| !name.is_empty())
+            .collect();
+        proxy.set_user_profile_order(&order)?;
+    }
+
+    if let Some(enabled) = cmd.governor {
+        proxy.set_governor_enabled(enabled)?;
+    }
 
     if cmd.next {
-        proxy.set_throttle_thermal_policy(current.next())?;
-    } else if let Some(profile) = cmd.profile_set {
-        proxy.set_throttle_thermal_policy(profile)?;
+        proxy.next_throttle_thermal_policy()?;
+    } else if let Some(name) = &cmd.profile_set {
+        // User profiles take priority so one can be named after a policy
+        if proxy.user_profiles()?.iter().any(|p| p.name == *name) {
+            proxy.activate_user_profile(name)?;
+        } else if let Ok(policy) = ThrottlePolicy::from_str(name) {
+            proxy.set_throttle_thermal_policy(policy)?;
+        } else {
+            return Err(format!("{name} is not a throttle policy or user profile").into());
+        }
     }
 
     if cmd.list {
@@ -796,10 +889,63 @@ fn handle_throttle_profile(
         for p in &res {
             println!("{:?}", p);
         }
+        for p in proxy.user_profiles()? {
+            println!("{} (user, {:?})", p.name, p.throttle_policy);
+        }
     }
 
     if cmd.profile_get {
-        println!("Active profile is {current:?}");
+        let current = proxy.throttle_thermal_policy()?;
+        let user = proxy.active_user_profile()?;
+        if user.is_empty() {
+            println!("Active profile is {current:?}");
+        } else {
+            println!("Active profile is {user} ({current:?})");
+        }
+        if proxy.governor_enabled()? {
+            let decision = proxy.governor_decision()?;
+            if decision.reason.is_empty() {
+                println!("Governor is enabled");
+            } else {
+                println!("Governor is enabled, last change {decision}");
+            }
+        }
+    }
+
+    if let Some(policy) = cmd.tune {
+        let mut tuning = proxy.throttle_policy_tuning(policy, cmd.battery)?;
+        let mut changed = false;
+        for (arg, value) in [
+            (cmd.ppt_pl1_spl, &mut tuning.ppt_pl1_spl),
+            (cmd.ppt_pl2_sppt, &mut tuning.ppt_pl2_sppt),
+            (cmd.ppt_fppt, &mut tuning.ppt_fppt),
+            (cmd.ppt_apu_sppt, &mut tuning.ppt_apu_sppt),
+            (cmd.ppt_platform_sppt, &mut tuning.ppt_platform_sppt),
+            (cmd.nv_dynamic_boost, &mut tuning.nv_dynamic_boost),
+            (cmd.nv_temp_target, &mut tuning.nv_temp_target),
+        ] {
+            if let Some(arg) = arg {
+                *value = Some(arg);
+                changed = true;
+            }
+        }
+        for attr in &cmd.unset {
+            *tuning
+                .get_mut(attr)
+                .ok_or_else(|| format!("{attr} is not a tuning attribute"))? = None;
+            changed = true;
+        }
+        let limits = proxy.ppt_limits()?;
+        if changed {
+            if let Some((attr, value)) = limits.check_tuning(&tuning) {
+                let limit = limits.get(attr).unwrap_or_default();
+                return Err(format!("{attr} {value} is out of range, {limit}").into());
+            }
+            proxy.set_throttle_policy_tuning(policy, cmd.battery, tuning)?;
+        }
+        let source = if cmd.battery { "battery" } else { "AC" };
+        println!("Tuning for {policy:?} on {source}:\n{tuning}");
+        println!("\nLimits:\n{limits}");
     }
 
     Ok(())
@@ -815,7 +961,13 @@ fn handle_fan_curve(
         return Err(ProfileError::NotSupported.into());
     };
 
-    if !cmd.get_enabled && !cmd.default && cmd.mod_profile.is_none() {
+    if !cmd.get_enabled
+        && !cmd.sensors
+        && !cmd.default
+        && cmd.mod_profile.is_none()
+        && cmd.export.is_none()
+        && cmd.import.is_none()
+    {
         if !cmd.help {
             println!("Missing arg or command\n");
         }
@@ -846,6 +998,32 @@ fn handle_fan_curve(
         }
     }
 
+    if cmd.sensors {
+        let sensors = fan_proxy.fan_sensors()?;
+        for fan in [FanCurvePU::CPU, FanCurvePU::GPU, FanCurvePU::MID] {
+            if sensors.rpm(fan) > 0 {
+                println!(
+                    "{fan:?} fan: {} RPM at {}c",
+                    sensors.rpm(fan),
+                    sensors.temp(fan)
+                );
+            }
+        }
+    }
+
+    if let Some(path) = &cmd.export {
+        let curves = fan_proxy.export_fan_curves()?;
+        let ron = ron::ser::to_string_pretty(&curves, PrettyConfig::new().depth_limit(4))?;
+        std::fs::write(path, ron)?;
+        println!("Fan curves for {} saved to {path}", curves.board_name);
+    }
+
+    if let Some(path) = &cmd.import {
+        let curves: FanCurveExport = ron::from_str(&std::fs::read_to_string(path)?)?;
+        fan_proxy.import_fan_curves(curves, cmd.map_fans)?;
+        println!("Fan curves loaded from {path}");
+    }
+
     if cmd.default {
         let active = plat_proxy.throttle_thermal_policy()?;
         fan_proxy.set_curves_to_defaults(active)?;
@@ -875,6 +1053,13 @@ fn handle_fan_curve(
 
         if let Some(mut curve) = cmd.data.clone() {
             let fan = cmd.fan.unwrap_or_default();
+            if cmd.smooth {
+                curve = CurveData::from_points(
+                    fan,
+                    curve.authored_points(),
+                    Interpolation::MonotoneCubic,
+                )?;
+            }
             curve.set_fan(fan);
             fan_proxy.set_fan_curve(profile, curve)?;
         }
@@ -883,6 +1068,152 @@ fn handle_fan_curve(
     Ok(())
 }
 
+fn handle_battery(
+    conn: &Connection,
+    cmd: &BatteryCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if cmd.help {
+        println!("{}", BatteryCommand::usage());
+        return Ok(());
+    }
+
+    let proxy = PlatformProxyBlocking::new(conn)?;
+    let info = BatteryInfo {
+        capacity: proxy.battery_capacity()?,
+        status: proxy.battery_status()?,
+        energy_full: proxy.battery_energy_full()?,
+        energy_full_design: proxy.battery_energy_full_design()?,
+        cycle_count: proxy.battery_cycle_count()?,
+        power_now: proxy.battery_power_draw()?,
+        time_to_empty: proxy.battery_time_to_empty()?,
+        ..Default::default()
+    };
+    println!("{info}");
+    if let Ok(limit) = proxy.charge_control_end_threshold() {
+        println!("Charge limit {limit}%");
+    }
+    Ok(())
+}
+
+fn print_cpu_settings(settings: &CpuSettings) {
+    println!("  Boost: {:?}", settings.boost);
+    println!("  SMT: {:?}", settings.smt);
+    println!("  amd-pstate mode: {:?}", settings.pstate_mode);
+    println!("  Min frequency: {}kHz", settings.min_freq);
+    println!("  Max frequency: {}kHz", settings.max_freq);
+    println!("  Offline CPUs: {:?}", settings.offline);
+}
+
+fn handle_cpu(conn: &Connection, cmd: &CpuCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let changes = cmd.boost.is_some()
+        || cmd.smt.is_some()
+        || cmd.pstate.is_some()
+        || cmd.min_freq.is_some()
+        || cmd.max_freq.is_some()
+        || cmd.offline.is_some()
+        || cmd.online.is_some();
+    if (!cmd.info && cmd.policy.is_none() && !changes) || cmd.help {
+        println!("Missing arg or command\n");
+        println!("{}", CpuCommand::usage());
+        return Ok(());
+    }
+
+    let proxy = CpuProxyBlocking::new(conn)?;
+
+    if let Some(policy) = cmd.policy {
+        let mut settings = proxy.policy_settings(policy)?;
+        if changes {
+            if let Some(on) = cmd.boost {
+                settings.boost = on.into();
+            }
+            if let Some(on) = cmd.smt {
+                settings.smt = on.into();
+            }
+            if let Some(mode) = cmd.pstate {
+                settings.pstate_mode = mode;
+            }
+            if let Some(freq) = cmd.min_freq {
+                settings.min_freq = freq;
+            }
+            if let Some(freq) = cmd.max_freq {
+                settings.max_freq = freq;
+            }
+            if let Some(cpu) = cmd.online {
+                settings.offline.retain(|c| *c != cpu);
+            }
+            if let Some(cpu) = cmd.offline {
+                if !settings.offline.contains(&cpu) {
+                    settings.offline.push(cpu);
+                    settings.offline.sort();
+                }
+            }
+            proxy.set_policy_settings(policy, settings.clone())?;
+        }
+        println!("CPU settings for {policy}:");
+        print_cpu_settings(&settings);
+    } else {
+        if let Some(on) = cmd.boost {
+            proxy.set_boost(on)?;
+        }
+        if let Some(on) = cmd.smt {
+            proxy.set_smt(on)?;
+        }
+        if let Some(mode) = cmd.pstate {
+            proxy.set_pstate_mode(mode)?;
+        }
+        if cmd.min_freq.is_some() || cmd.max_freq.is_some() {
+            proxy.set_scaling_freq(cmd.min_freq.unwrap_or(0), cmd.max_freq.unwrap_or(0))?;
+        }
+        if let Some(cpu) = cmd.online {
+            proxy.set_core_online(cpu, true)?;
+        }
+        if let Some(cpu) = cmd.offline {
+            proxy.set_core_online(cpu, false)?;
+        }
+    }
+
+    if cmd.info {
+        let caps = proxy.capabilities()?;
+        println!("CPUs which can be taken offline: {:?}", caps.hotplug);
+        if caps.boost {
+            println!("Boost: {}", proxy.boost()?);
+        }
+        if caps.smt {
+            println!("SMT: {}", proxy.smt()?);
+        }
+        if caps.pstate_mode {
+            println!("amd-pstate mode: {:?}", proxy.pstate_mode()?);
+        }
+        if caps.scaling_freq {
+            println!(
+                "Frequency range: {}kHz to {}kHz",
+                caps.cpuinfo_min_freq, caps.cpuinfo_max_freq
+            );
+        }
+        let states = PlatformProxyBlocking::new(conn)?
+            .cpu_freq_states()
+            .unwrap_or_default();
+        for core in proxy.cores()? {
+            if !core.online {
+                println!("cpu{}: offline", core.cpu);
+                continue;
+            }
+            print!(
+                "cpu{}: online, {}kHz to {}kHz",
+                core.cpu, core.min_freq, core.max_freq
+            );
+            if let Some(state) = states.iter().find(|s| s.cpu == core.cpu) {
+                if state.core_type != CoreType::Unknown {
+                    print!(", {:?} core", state.core_type);
+                }
+                print!(", {:?}, EPP {:?}", state.governor, state.epp);
+            }
+            println!();
+        }
+    }
+    Ok(())
+}
+
 fn handle_platform_properties(
     conn: &Connection,
     supported: &[Properties],
@@ -894,7 +1225,9 @@ fn handle_platform_properties(
             && cmd.post_sound_set.is_none()
             && !cmd.post_sound_get
             && cmd.panel_overdrive_set.is_none()
-            && !cmd.panel_overdrive_get)
+            && !cmd.panel_overdrive_get
+            && !cmd.list_attrs
+            && cmd.attr.is_none())
             || cmd.help
         {
             println!("Missing arg or command\n");
@@ -905,6 +1238,7 @@ fn handle_platform_properties(
                 line.contains("sound") && supported.contains(&Properties::PostAnimationSound)
                     || line.contains("GPU") && supported.contains(&Properties::GpuMuxMode)
                     || line.contains("panel") && supported.contains(&Properties::PanelOd)
+                    || line.contains("firmware attribute")
             }) {
                 println!("{}", line);
             }
@@ -922,7 +1256,7 @@ fn handle_platform_properties(
 
         if let Some(opt) = cmd.gpu_mux_mode_set {
             println!("Rebuilding initrd to include drivers");
-            proxy.set_gpu_mux_mode(GpuMode::from_mux(opt))?;
+            proxy.set_gpu_mux_mode(GpuMode::from_mux(opt) as u8)?;
             println!(
                 "The mode change is not active until you reboot, on boot the bios will make the \
                  required change"
@@ -940,6 +1274,22 @@ fn handle_platform_properties(
             let res = proxy.panel_od()?;
             println!("Panel overdrive on: {}", res);
         }
+
+        if cmd.list_attrs {
+            let attrs = proxy.firmware_attributes()?;
+            if attrs.is_empty() {
+                println!("No firmware attributes, this requires the asus-armoury kernel module");
+            }
+            for attr in attrs {
+                println!("{attr}");
+            }
+        }
+        if let Some(name) = &cmd.attr {
+            if let Some(value) = &cmd.value {
+                proxy.set_firmware_attribute(name, value)?;
+            }
+            println!("{}", proxy.firmware_attribute(name)?);
+        }
     }
     Ok(())
 }
@@ -0,0 +1,429 @@
+//! Keyboard lighting following the colours at the edges of the screen.
+//!
+//! Frames come from the desktop portal through [`crate::screencast`]. Each
+//! keyboard row takes the dominant colours at the left and right edges of the
+//! screen at the same height, blended across the row. On zoned keyboards each
+//! zone takes the dominant colour along the top and bottom edges of its part
+//! of the screen, left to right.
+//!
+//! The colours are taken from plain RGB buffers so none of this needs a screen.
+
+use config_traits::{StdConfig, StdConfigLoad};
+use log::{info, warn};
+use rog_aura::effects::{AdvancedEffects, Effect, Static};
+use rog_aura::keyboard::{AdvancedAuraType, KeyLayout, LedCode};
+use rog_aura::Colour;
+use rog_dbus::zbus_aura::AuraProxyBlocking;
+use serde::{Deserialize, Serialize};
+
+use crate::child_output::read_restarting;
+use crate::config::ConfigBase;
+use crate::error::Error;
+use crate::screencast::{ScreenCapture, ScreenCastSession, FRAME_LEN, HEIGHT, WIDTH};
+use crate::session::SessionProxyBlocking;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScreenAmbientConfig {
+    /// Show the colours at the edges of the screen instead of the
+    /// `active_aura` effects
+    pub enabled: bool,
+    /// How much of the width or height of the screen is its edge, up to 0.5
+    pub edge: f32,
+    /// Frames sent to the keyboard each second
+    pub fps: u32,
+    /// Given by the desktop portal to share the same monitor again without
+    /// asking
+    pub restore_token: Option<String>,
+}
+
+impl Default for ScreenAmbientConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            edge: 0.1,
+            fps: 15,
+            restore_token: None,
+        }
+    }
+}
+
+/// An image of 3 bytes a pixel, row by row with no padding
+#[derive(Debug, Clone, Copy)]
+pub struct RgbImage<'a> {
+    width: usize,
+    height: usize,
+    data: &'a [u8],
+}
+
+impl<'a> RgbImage<'a> {
+    /// `None` if `data` is too short for the size, or the size is empty
+    pub fn new(width: usize, height: usize, data: &'a [u8]) -> Option<Self> {
+        if width == 0 || height == 0 || data.len() < width * height * 3 {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let i = (y * self.width + x) * 3;
+        [self.data[i], self.data[i + 1], self.data[i + 2]]
+    }
+}
+
+/// A rectangle of pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// The most common colour in `regions`. Similar colours are counted
+/// together, and the average of the most common of them is returned.
+pub fn dominant_colour(image: &RgbImage<'_>, regions: &[Region]) -> Colour {
+    // 3 bits of each channel
+    let mut buckets = vec![(0u32, [0u32; 3]); 512];
+    for region in regions {
+        let x_end = (region.x + region.width).min(image.width);
+        let y_end = (region.y + region.height).min(image.height);
+        for y in region.y..y_end {
+            for x in region.x..x_end {
+                let pixel = image.pixel(x, y);
+                let bucket = &mut buckets[((pixel[0] as usize >> 5) << 6)
+                    | ((pixel[1] as usize >> 5) << 3)
+                    | (pixel[2] as usize >> 5)];
+                bucket.0 += 1;
+                for (sum, c) in bucket.1.iter_mut().zip(pixel) {
+                    *sum += c as u32;
+                }
+            }
+        }
+    }
+
+    let (count, sum) = buckets
+        .iter()
+        .fold((0, [0; 3]), |max, b| if b.0 > max.0 { *b } else { max });
+    if count == 0 {
+        return Colour { r: 0, g: 0, b: 0 };
+    }
+    Colour {
+        r: (sum[0] / count) as u8,
+        g: (sum[1] / count) as u8,
+        b: (sum[2] / count) as u8,
+    }
+}
+
+/// The start and length of part `index` of `count` equal parts of `len`
+fn split(len: usize, index: usize, count: usize) -> (usize, usize) {
+    let start = (index * len / count).min(len - 1);
+    let end = ((index + 1) * len / count).max(start + 1);
+    (start, end - start)
+}
+
+/// The width in pixels of an edge which is `edge` of `len`
+fn edge_len(len: usize, edge: f32) -> usize {
+    ((len as f32 * edge.clamp(0.0, 0.5)).round() as usize).clamp(1, len)
+}
+
+/// The dominant colours at the left and right edges of the screen, in `rows`
+/// bands from the top down
+pub fn row_edge_colours(image: &RgbImage<'_>, rows: usize, edge: f32) -> Vec<(Colour, Colour)> {
+    let width = edge_len(image.width, edge);
+    (0..rows)
+        .map(|row| {
+            let (y, height) = split(image.height, row, rows);
+            let left = Region {
+                x: 0,
+                y,
+                width,
+                height,
+            };
+            let right = Region {
+                x: image.width - width,
+                ..left
+            };
+            (
+                dominant_colour(image, &[left]),
+                dominant_colour(image, &[right]),
+            )
+        })
+        .collect()
+}
+
+/// The dominant colours along the top and bottom edges of the screen, in
+/// `zones` bands from the left
+pub fn zone_edge_colours(image: &RgbImage<'_>, zones: usize, edge: f32) -> Vec<Colour> {
+    let height = edge_len(image.height, edge);
+    (0..zones)
+        .map(|zone| {
+            let (x, width) = split(image.width, zone, zones);
+            let top = Region {
+                x,
+                y: 0,
+                width,
+                height,
+            };
+            let bottom = Region {
+                y: image.height - height,
+                ..top
+            };
+            dominant_colour(image, &[top, bottom])
+        })
+        .collect()
+}
+
+/// The colour of each key of a per-key `layout`. Each row of keys is blended
+/// from the colour at the left edge of the screen to the one at the right.
+pub fn key_colours(image: &RgbImage<'_>, layout: &KeyLayout, edge: f32) -> Vec<(LedCode, Colour)> {
+    let mut keys = layout.key_positions();
+    keys.retain(|k| !k.led.is_placeholder());
+
+    let mut rows: Vec<f32> = Vec::new();
+    for key in &keys {
+        if !rows.iter().any(|y| (y - key.y).abs() < 0.01) {
+            rows.push(key.y);
+        }
+    }
+    rows.sort_by(f32::total_cmp);
+    let colours = row_edge_colours(image, rows.len(), edge);
+
+    let min_x = keys.iter().map(|k| k.x).fold(f32::INFINITY, f32::min);
+    let max_x = keys.iter().map(|k| k.x).fold(f32::NEG_INFINITY, f32::max);
+    let width = (max_x - min_x).max(f32::EPSILON);
+    keys.iter()
+        .filter_map(|key| {
+            let row = rows.iter().position(|y| (y - key.y).abs() < 0.01)?;
+            let (left, right) = colours[row];
+            Some((key.led, left.lerp(&right, (key.x - min_x) / width)))
+        })
+        .collect()
+}
+
+/// The effects showing the edges of `image` on the keyboard of `layout`
+pub fn ambient_effects(image: &RgbImage<'_>, layout: &KeyLayout, edge: f32) -> AdvancedEffects {
+    if let AdvancedAuraType::Zoned(zones) = layout.advanced_type() {
+        if !zones.is_empty() {
+            let mut effects = AdvancedEffects::new(true);
+            let colours = zone_edge_colours(image, zones.len(), edge);
+            for (zone, colour) in zones.iter().zip(colours) {
+                effects.push(Effect::Static(Static::new(*zone, colour)));
+            }
+            return effects;
+        }
+    }
+
+    let mut effects = AdvancedEffects::new(false);
+    for (led, colour) in key_colours(image, layout, edge) {
+        effects.push(Effect::Static(Static::new(led, colour)));
+    }
+    effects
+}
+
+/// Keep the monitor chosen in the portal for next time
+fn save_restore_token(token: Option<&str>) {
+    let mut config = ConfigBase::new().load();
+    if config.screen_ambient.restore_token.as_deref() != token {
+        config.screen_ambient.restore_token = token.map(str::to_owned);
+        config.write();
+    }
+}
+
+/// Show the edges of the screen on the keyboard for as long as asusd-user
+/// runs. Frames are only sent while `session` is active. This blocks so should
+/// be given its own thread.
+pub fn run_screen_ambient(
+    config: ScreenAmbientConfig,
+    layout: KeyLayout,
+    proxy: AuraProxyBlocking<'_>,
+    session: Option<SessionProxyBlocking<'_>>,
+) {
+    let mut restore_token = config.restore_token.clone();
+    let start = || -> Result<ScreenCapture, Error> {
+        let cast = ScreenCastSession::start(restore_token.as_deref())?;
+        if cast.restore_token() != restore_token.as_deref() {
+            restore_token = cast.restore_token().map(str::to_owned);
+            save_restore_token(restore_token.as_deref());
+        }
+        let capture = ScreenCapture::start(cast, config.fps)?;
+        info!("ScreenAmbient: started");
+        Ok(capture)
+    };
+    let mut frame = vec![0u8; FRAME_LEN];
+    read_restarting("ScreenAmbient", start, &mut frame, |frame| {
+        if !session
+            .as_ref()
+            .map_or(true, |s| s.active().unwrap_or(true))
+        {
+            return;
+        }
+        let Some(image) = RgbImage::new(WIDTH, HEIGHT, frame) else {
+            return;
+        };
+        let mut effects = ambient_effects(&image, &layout, config.edge);
+        effects.next_state(&layout);
+        if let Err(e) = proxy.direct_addressing_raw(effects.create_packets()) {
+            warn!("ScreenAmbient: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rog_aura::aura_detection::LedSupportData;
+    use rog_aura::effects::{AdvancedEffects, Effect, Static};
+    use rog_aura::keyboard::{AdvancedAuraType, KeyLayout, LedCode};
+    use rog_aura::Colour;
+
+    use super::{ambient_effects, dominant_colour, key_colours, Region, RgbImage};
+
+    const RED: Colour = Colour { r: 250, g: 0, b: 0 };
+    const BLUE: Colour = Colour { r: 0, g: 0, b: 250 };
+    const WHITE: Colour = Colour {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    /// RGB bytes of a `width` x `height` image with `pixel(x, y)` for colours
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Colour) -> Vec<u8> {
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let c = pixel(x, y);
+                data.extend([c.r, c.g, c.b]);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn image_size_checked() {
+        let data = image(4, 2, |_, _| RED);
+        assert!(RgbImage::new(4, 2, &data).is_some());
+        assert!(RgbImage::new(4, 3, &data).is_none());
+        assert!(RgbImage::new(0, 2, &data).is_none());
+    }
+
+    #[test]
+    fn dominant_not_average() {
+        // Mostly red with some white, the average would be pink
+        let data = image(10, 10, |x, _| if x < 7 { RED } else { WHITE });
+        let image = RgbImage::new(10, 10, &data).unwrap();
+        let all = Region {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+        };
+        assert_eq!(dominant_colour(&image, &[all]), RED);
+
+        // Similar colours are averaged together
+        let data = image(2, 1, |x, _| Colour {
+            r: 200 + x as u8 * 10,
+            g: 0,
+            b: 0,
+        });
+        let image = RgbImage::new(2, 1, &data).unwrap();
+        let all = Region {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 1,
+        };
+        assert_eq!(
+            dominant_colour(&image, &[all]),
+            Colour { r: 205, g: 0, b: 0 }
+        );
+
+        // Nothing in the regions is black
+        let outside = Region {
+            x: 5,
+            y: 5,
+            width: 2,
+            height: 2,
+        };
+        assert_eq!(
+            dominant_colour(&image, &[outside]),
+            Colour { r: 0, g: 0, b: 0 }
+        );
+    }
+
+    #[test]
+    fn rows_follow_side_edges() {
+        let layout = KeyLayout::default_layout();
+        // Six bands of 2 pixels, each with its own colour on the left and blue
+        // on the right. The middle is white and never sampled.
+        let left = |row: usize| Colour {
+            r: 0,
+            g: 40 * row as u8 + 40,
+            b: 0,
+        };
+        let data = image(24, 12, |x, y| match x {
+            0..=5 => left(y / 2),
+            18.. => BLUE,
+            _ => WHITE,
+        });
+        let image = RgbImage::new(24, 12, &data).unwrap();
+        let colours = key_colours(&image, &layout, 0.25);
+        let colour_of = |led: LedCode| colours.iter().find(|(l, _)| *l == led).unwrap().1;
+
+        assert_eq!(colour_of(LedCode::Esc), left(0));
+        assert_eq!(colour_of(LedCode::Tilde), left(1));
+        assert_eq!(colour_of(LedCode::LCtrl), left(5));
+        assert_eq!(colour_of(LedCode::Backspace), BLUE);
+        // Keys in between are a blend
+        let space = colour_of(LedCode::Spacebar);
+        assert!(space.b > 0 && space.g > 0);
+        assert!(colours.iter().all(|(_, c)| *c != WHITE));
+    }
+
+    #[test]
+    fn zones_follow_top_and_bottom_edges() {
+        let zones = vec![
+            LedCode::ZonedKbLeft,
+            LedCode::ZonedKbLeftMid,
+            LedCode::ZonedKbRightMid,
+            LedCode::ZonedKbRight,
+        ];
+        let led_data = LedSupportData {
+            layout_name: "ga401q".to_owned(),
+            advanced_type: AdvancedAuraType::Zoned(zones),
+            ..Default::default()
+        };
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../rog-aura/data");
+        let layout = KeyLayout::find_layout(led_data, data).unwrap();
+
+        // Four stripes down the screen, the middle of the screen is white
+        let stripes = [RED, BLUE, RED, BLUE];
+        let data = image(16, 8, |x, y| {
+            if (2..6).contains(&y) {
+                WHITE
+            } else {
+                stripes[x / 4]
+            }
+        });
+        let image = RgbImage::new(16, 8, &data).unwrap();
+
+        let mut effects = ambient_effects(&image, &layout, 0.25);
+        effects.next_state(&layout);
+        let mut expected = AdvancedEffects::new(true);
+        for (zone, colour) in [
+            (LedCode::ZonedKbLeft, RED),
+            (LedCode::ZonedKbLeftMid, BLUE),
+            (LedCode::ZonedKbRightMid, RED),
+            (LedCode::ZonedKbRight, BLUE),
+        ] {
+            expected.push(Effect::Static(Static::new(zone, colour)));
+        }
+        expected.next_state(&layout);
+        assert_eq!(effects.create_packets(), expected.create_packets());
+    }
+}
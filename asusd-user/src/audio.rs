@@ -0,0 +1,508 @@
+//! A music visualiser for per-key and zoned keyboards.
+//!
+//! The default output's monitor is recorded as raw PCM with `parec`, which
+//! works with both PulseAudio and PipeWire (through pipewire-pulse).
+//! [`Spectrum`] takes that PCM and gives the level of each frequency band, and
+//! [`visualiser_effects`] shows the levels as bars on the key columns, or as
+//! the colour of each zone on zoned keyboards.
+
+use std::f32::consts::PI;
+use std::process::Command;
+
+use log::{info, warn};
+use rog_aura::effects::{AdvancedEffects, Effect, Static};
+use rog_aura::keyboard::{AdvancedAuraType, KeyLayout, KeyPosition, LedCode};
+use rog_aura::Colour;
+use rog_dbus::zbus_aura::AuraProxyBlocking;
+use serde::{Deserialize, Serialize};
+
+use crate::child_output::{read_restarting, ChildOutput};
+use crate::error::Error;
+use crate::session::SessionProxyBlocking;
+
+pub const SAMPLE_RATE: u32 = 44100;
+pub const CHANNELS: usize = 2;
+/// Samples in each FFT, about 46ms at 44.1kHz
+const FFT_SIZE: usize = 2048;
+/// The range of frequencies split into bands
+const MIN_FREQ: f32 = 40.0;
+const MAX_FREQ: f32 = 16000.0;
+/// A band this far below full scale, in dB, has a level of 0.0
+const FLOOR_DB: f32 = -60.0;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AudioVisualiserConfig {
+    /// Show the audio playing instead of the `active_aura` effects
+    pub enabled: bool,
+    /// The colour at the bottom of the bars, and of quiet zones
+    pub low_colour: Colour,
+    /// The colour at the top of the bars, and of loud zones
+    pub high_colour: Colour,
+    /// Multiplies the audio before it is measured, raise this for quiet audio
+    pub gain: f32,
+    /// How far a band falls each frame, where 1.0 is from full to nothing
+    pub falloff: f32,
+    /// Frames sent to the keyboard each second
+    pub fps: u32,
+}
+
+impl Default for AudioVisualiserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            low_colour: Colour { r: 0, g: 255, b: 0 },
+            high_colour: Colour { r: 255, g: 0, b: 0 },
+            gain: 1.0,
+            falloff: 0.08,
+            fps: 30,
+        }
+    }
+}
+
+/// The level of each frequency band of PCM audio. The bands are spaced evenly
+/// on a log scale, as heard.
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    channels: usize,
+    /// The last `FFT_SIZE` samples, mixed down to mono
+    samples: Vec<f32>,
+    /// Hann window
+    window: Vec<f32>,
+    /// The range of FFT bins in each band
+    band_bins: Vec<(usize, usize)>,
+    levels: Vec<f32>,
+    gain: f32,
+    falloff: f32,
+}
+
+impl Spectrum {
+    pub fn new(sample_rate: u32, channels: usize, bands: usize) -> Self {
+        let bin_width = sample_rate as f32 / FFT_SIZE as f32;
+        let bands = bands.max(1);
+        let band_bins = (0..bands)
+            .map(|i| {
+                let freq =
+                    |n: usize| MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(n as f32 / bands as f32);
+                let start = (freq(i) / bin_width).floor() as usize;
+                let end = ((freq(i + 1) / bin_width).ceil() as usize).max(start + 1);
+                (start.max(1), end.min(FFT_SIZE / 2))
+            })
+            .collect();
+        Self {
+            channels: channels.max(1),
+            samples: vec![0.0; FFT_SIZE],
+            window: (0..FFT_SIZE)
+                .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+                .collect(),
+            band_bins,
+            levels: vec![0.0; bands],
+            gain: 1.0,
+            falloff: 1.0,
+        }
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain.max(0.0);
+    }
+
+    /// How far a band may fall each `band_levels()`, where 1.0 is from full to
+    /// nothing
+    pub fn set_falloff(&mut self, falloff: f32) {
+        self.falloff = falloff.clamp(0.0, 1.0);
+    }
+
+    /// Add interleaved signed 16-bit little endian PCM. A frame split across
+    /// two calls is dropped.
+    pub fn push_pcm(&mut self, pcm: &[u8]) {
+        let frame_len = 2 * self.channels;
+        let mono = pcm.chunks_exact(frame_len).map(|frame| {
+            let sum: f32 = frame
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / i16::MAX as f32)
+                .sum();
+            sum / self.channels as f32
+        });
+        self.samples.extend(mono);
+        let excess = self.samples.len().saturating_sub(FFT_SIZE);
+        self.samples.drain(..excess);
+    }
+
+    /// The level of each band in the latest samples, from 0.0 to 1.0, lowest
+    /// frequency first. A band falls by no more than the falloff each call.
+    pub fn band_levels(&mut self) -> &[f32] {
+        let mut bins: Vec<(f32, f32)> = self
+            .samples
+            .iter()
+            .zip(&self.window)
+            .map(|(s, w)| (s * w * self.gain, 0.0))
+            .collect();
+        fft(&mut bins);
+
+        for (level, (start, end)) in self.levels.iter_mut().zip(&self.band_bins) {
+            // A full scale sine in the Hann window peaks at FFT_SIZE / 4
+            let amplitude = bins[*start..*end]
+                .iter()
+                .map(|(re, im)| re.hypot(*im))
+                .fold(0.0, f32::max)
+                * 4.0
+                / FFT_SIZE as f32;
+            let db = 20.0 * amplitude.max(f32::MIN_POSITIVE).log10();
+            let new = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+            *level = new.max(*level - self.falloff);
+        }
+        &self.levels
+    }
+}
+
+/// In place radix-2 FFT of complex `(re, im)` values. The length must be a
+/// power of two.
+fn fft(buf: &mut [(f32, f32)]) {
+    let n = buf.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (ar, ai) = buf[start + k];
+                let (br, bi) = buf[start + k + len / 2];
+                let (tr, ti) = (br * cos - bi * sin, br * sin + bi * cos);
+                buf[start + k] = (ar + tr, ai + ti);
+                buf[start + k + len / 2] = (ar - tr, ai - ti);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// The zones of a zoned keyboard, each showing one band
+fn zones(layout: &KeyLayout) -> Option<&[LedCode]> {
+    match layout.advanced_type() {
+        AdvancedAuraType::Zoned(zones) if !zones.is_empty() => Some(zones.as_slice()),
+        _ => None,
+    }
+}
+
+fn keys(layout: &KeyLayout) -> Vec<KeyPosition> {
+    let mut keys = layout.key_positions();
+    keys.retain(|k| !k.led.is_placeholder());
+    keys
+}
+
+/// How many bands `layout` shows: a bar for each key in the longest row, or
+/// one for each zone
+pub fn band_count(layout: &KeyLayout) -> usize {
+    if let Some(zones) = zones(layout) {
+        return zones.len();
+    }
+    let mut rows: Vec<(f32, usize)> = Vec::new();
+    for key in keys(layout) {
+        match rows.iter_mut().find(|(y, _)| (y - key.y).abs() < 0.01) {
+            Some(row) => row.1 += 1,
+            None => rows.push((key.y, 1)),
+        }
+    }
+    rows.iter().map(|(_, count)| *count).max().unwrap_or(1)
+}
+
+/// Show `levels` as bars rising from the bottom row of keys, lowest frequency
+/// on the left, or as the colour of each zone on zoned keyboards. `levels`
+/// should have `band_count()` levels.
+pub fn visualiser_effects(
+    levels: &[f32],
+    layout: &KeyLayout,
+    config: &AudioVisualiserConfig,
+) -> AdvancedEffects {
+    if let Some(zones) = zones(layout) {
+        let mut effects = AdvancedEffects::new(true);
+        for (zone, level) in zones.iter().zip(levels) {
+            let colour = config
+                .low_colour
+                .lerp(&config.high_colour, *level)
+                .scale(*level);
+            effects.push(Effect::Static(Static::new(*zone, colour)));
+        }
+        return effects;
+    }
+
+    let mut effects = AdvancedEffects::new(false);
+    let keys = keys(layout);
+    let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+        return effects;
+    };
+    let (mut min_x, mut max_x) = (first.x, first.x);
+    let (mut top, mut bottom) = (first.y, last.y);
+    for key in &keys {
+        min_x = min_x.min(key.x);
+        max_x = max_x.max(key.x);
+        top = top.min(key.y);
+        bottom = bottom.max(key.y);
+    }
+    let width = (max_x - min_x).max(f32::EPSILON);
+    let height = (bottom - top).max(f32::EPSILON);
+
+    for key in &keys {
+        let band = ((key.x - min_x) / width * levels.len() as f32) as usize;
+        let Some(level) = levels.get(band.min(levels.len().saturating_sub(1))) else {
+            continue;
+        };
+        // 0.0 for the bottom row up to 1.0 for the top
+        let rise = (bottom - key.y) / height;
+        if *level > 0.0 && rise <= *level {
+            let colour = config.low_colour.lerp(&config.high_colour, rise);
+            effects.push(Effect::Static(Static::new(key.led, colour)));
+        }
+    }
+    effects
+}
+
+/// Record the default output's monitor with `parec`, as interleaved signed
+/// 16-bit little endian samples
+pub fn record_monitor() -> Result<ChildOutput, Error> {
+    ChildOutput::spawn(Command::new("parec").args([
+        "--device=@DEFAULT_MONITOR@",
+        "--raw",
+        "--format=s16le",
+        &format!("--rate={SAMPLE_RATE}"),
+        &format!("--channels={CHANNELS}"),
+        "--latency-msec=20",
+    ]))
+}
+
+/// Show the audio playing on the keyboard for as long as asusd-user runs.
+/// Frames are only sent while `session` is active. This blocks so should be
+/// given its own thread.
+pub fn run_audio_visualiser(
+    config: AudioVisualiserConfig,
+    layout: KeyLayout,
+    proxy: AuraProxyBlocking<'_>,
+    session: Option<SessionProxyBlocking<'_>>,
+) {
+    let bands = band_count(&layout);
+    info!("AudioVisualiser: showing {bands} bands");
+    let mut spectrum = Spectrum::new(SAMPLE_RATE, CHANNELS, bands);
+    spectrum.set_gain(config.gain);
+    spectrum.set_falloff(config.falloff);
+    let frame_samples = (SAMPLE_RATE / config.fps.clamp(1, 60)) as usize;
+    let mut pcm = vec![0u8; frame_samples * CHANNELS * 2];
+
+    read_restarting("AudioVisualiser", record_monitor, &mut pcm, |pcm| {
+        spectrum.push_pcm(pcm);
+        let levels = spectrum.band_levels();
+        if !session
+            .as_ref()
+            .map_or(true, |s| s.active().unwrap_or(true))
+        {
+            return;
+        }
+        let mut effects = visualiser_effects(levels, &layout, &config);
+        effects.next_state(&layout);
+        if let Err(e) = proxy.direct_addressing_raw(effects.create_packets()) {
+            warn!("AudioVisualiser: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rog_aura::aura_detection::LedSupportData;
+    use rog_aura::effects::{AdvancedEffects, Effect, Static};
+    use rog_aura::keyboard::{AdvancedAuraType, KeyLayout, LedCode};
+    use rog_aura::Colour;
+
+    use super::{band_count, fft, visualiser_effects, AudioVisualiserConfig, Spectrum, FFT_SIZE};
+
+    const RATE: u32 = 44100;
+
+    /// Stereo PCM of a sine at `freq` with a peak of `amplitude` of full scale
+    fn sine_pcm(freq: f32, amplitude: f32, frames: usize) -> Vec<u8> {
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f32 / RATE as f32;
+                let s = (amplitude
+                    * (2.0 * std::f32::consts::PI * freq * t).sin()
+                    * i16::MAX as f32) as i16;
+                [s.to_le_bytes(), s.to_le_bytes()].concat()
+            })
+            .collect()
+    }
+
+    fn loudest(levels: &[f32]) -> usize {
+        levels
+            .iter()
+            .enumerate()
+            .fold(
+                (0, 0.0),
+                |max, (i, l)| if *l > max.1 { (i, *l) } else { max },
+            )
+            .0
+    }
+
+    #[test]
+    fn fft_matches_dft() {
+        let input: Vec<(f32, f32)> = (0..16)
+            .map(|i| ((i as f32 * 0.7).sin() + (i % 3) as f32, 0.0))
+            .collect();
+        let mut output = input.clone();
+        fft(&mut output);
+        for (k, (re, im)) in output.iter().enumerate() {
+            let (mut dre, mut dim) = (0.0, 0.0);
+            for (n, (x, _)) in input.iter().enumerate() {
+                let angle = -2.0 * std::f32::consts::PI * (k * n) as f32 / 16.0;
+                dre += x * angle.cos();
+                dim += x * angle.sin();
+            }
+            assert!((re - dre).abs() < 1e-3 && (im - dim).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn bands_follow_frequency() {
+        let mut spectrum = Spectrum::new(RATE, 2, 16);
+        assert!(spectrum.band_levels().iter().all(|l| *l == 0.0));
+
+        spectrum.push_pcm(&sine_pcm(100.0, 1.0, FFT_SIZE));
+        let levels = spectrum.band_levels().to_vec();
+        let bass = loudest(&levels);
+        assert!(bass < 4);
+        assert!(levels[bass] > 0.95);
+        assert!(levels[15] < 0.1);
+
+        let mut spectrum = Spectrum::new(RATE, 2, 16);
+        spectrum.push_pcm(&sine_pcm(8000.0, 1.0, FFT_SIZE));
+        let levels = spectrum.band_levels().to_vec();
+        assert!(loudest(&levels) > 12);
+        assert!(levels[0] < 0.1);
+
+        // -30 dB is half way to the floor
+        let mut spectrum = Spectrum::new(RATE, 2, 16);
+        spectrum.push_pcm(&sine_pcm(1000.0, 0.0316, FFT_SIZE));
+        let levels = spectrum.band_levels();
+        let level = levels[loudest(levels)];
+        assert!((level - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn levels_fall_off() {
+        let mut spectrum = Spectrum::new(RATE, 2, 8);
+        spectrum.set_falloff(0.25);
+        spectrum.push_pcm(&sine_pcm(100.0, 1.0, FFT_SIZE));
+        let bass = loudest(spectrum.band_levels());
+        let full = spectrum.band_levels()[bass];
+
+        // Silence only lowers the band by the falloff each time
+        spectrum.push_pcm(&[0u8; FFT_SIZE * 4]);
+        let level = spectrum.band_levels()[bass];
+        assert!((full - level - 0.25).abs() < 0.01);
+        for _ in 0..4 {
+            spectrum.band_levels();
+        }
+        assert_eq!(spectrum.band_levels()[bass], 0.0);
+    }
+
+    #[test]
+    fn bars_on_key_columns() {
+        let layout = KeyLayout::default_layout();
+        let blue = Colour { r: 0, g: 0, b: 255 };
+        let config = AudioVisualiserConfig {
+            low_colour: blue,
+            high_colour: blue,
+            ..Default::default()
+        };
+        let packets = |effects: AdvancedEffects| {
+            let mut effects = effects;
+            effects.next_state(&layout);
+            effects.create_packets()
+        };
+        let keys = |leds: &[(LedCode, Colour)]| {
+            let mut effects = AdvancedEffects::new(false);
+            for (led, colour) in leds {
+                effects.push(Effect::Static(Static::new(*led, *colour)));
+            }
+            packets(effects)
+        };
+
+        // The number row is the longest
+        let bands = band_count(&layout);
+        assert_eq!(bands, 14);
+        assert!(visualiser_effects(&vec![0.0; bands], &layout, &config).is_empty());
+
+        let mut levels = vec![0.0; bands];
+        levels[0] = 1.0;
+        assert_eq!(
+            packets(visualiser_effects(&levels, &layout, &config)),
+            keys(&[
+                (LedCode::Esc, blue),
+                (LedCode::Tilde, blue),
+                (LedCode::Tab, blue),
+                (LedCode::Caps, blue),
+                (LedCode::LShift, blue),
+                (LedCode::LCtrl, blue),
+            ])
+        );
+        levels[0] = 0.3;
+        assert_eq!(
+            packets(visualiser_effects(&levels, &layout, &config)),
+            keys(&[(LedCode::LShift, blue), (LedCode::LCtrl, blue)])
+        );
+
+        // The bottom of a bar is the low colour
+        let config = AudioVisualiserConfig::default();
+        levels[0] = 0.01;
+        assert_eq!(
+            packets(visualiser_effects(&levels, &layout, &config)),
+            keys(&[(LedCode::LCtrl, config.low_colour)])
+        );
+    }
+
+    #[test]
+    fn levels_on_zones() {
+        let zones = vec![
+            LedCode::ZonedKbLeft,
+            LedCode::ZonedKbLeftMid,
+            LedCode::ZonedKbRightMid,
+            LedCode::ZonedKbRight,
+        ];
+        let led_data = LedSupportData {
+            layout_name: "ga401q".to_owned(),
+            advanced_type: AdvancedAuraType::Zoned(zones),
+            ..Default::default()
+        };
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../rog-aura/data");
+        let layout = KeyLayout::find_layout(led_data, data).unwrap();
+        let config = AudioVisualiserConfig::default();
+        assert_eq!(band_count(&layout), 4);
+
+        let mut effects = visualiser_effects(&[1.0, 0.5, 0.0, 0.0], &layout, &config);
+        effects.next_state(&layout);
+        let mut expected = AdvancedEffects::new(true);
+        let half = config.low_colour.lerp(&config.high_colour, 0.5).scale(0.5);
+        let off = Colour { r: 0, g: 0, b: 0 };
+        for (zone, colour) in [
+            (LedCode::ZonedKbLeft, config.high_colour),
+            (LedCode::ZonedKbLeftMid, half),
+            (LedCode::ZonedKbRightMid, off),
+            (LedCode::ZonedKbRight, off),
+        ] {
+            expected.push(Effect::Static(Static::new(zone, colour)));
+        }
+        expected.next_state(&layout);
+        assert_eq!(effects.create_packets(), expected.create_packets());
+    }
+}
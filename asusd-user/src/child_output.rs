@@ -0,0 +1,107 @@
+//! Reading the output of the programs asusd-user records with, `parec` for the
+//! audio visualiser and `gst-launch-1.0` for screen ambient lighting.
+//!
+//! Those run for as long as asusd-user does, so [`read_restarting`] starts the
+//! program again whenever it can't be started or stops.
+
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+use log::warn;
+
+use crate::error::Error;
+
+/// How long to wait before starting a program again if it stops or can't be
+/// started
+pub const RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// The stdout of a running program. The program is killed when this is
+/// dropped.
+pub struct ChildOutput {
+    program: String,
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl ChildOutput {
+    /// Run `command` with its stdout piped to this. Its stderr is dropped.
+    pub fn spawn(command: &mut Command) -> Result<Self, Error> {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Io(std::io::Error::new(e.kind(), format!("{program}: {e}"))))?;
+        let Some(stdout) = child.stdout.take() else {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(Error::Io(std::io::Error::other(format!(
+                "{program} has no stdout"
+            ))));
+        };
+        Ok(Self {
+            program,
+            child,
+            stdout,
+        })
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Wait for the next of the output and fill `buf` with it
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.stdout.read_exact(buf)?;
+        Ok(())
+    }
+}
+
+impl AsMut<ChildOutput> for ChildOutput {
+    fn as_mut(&mut self) -> &mut ChildOutput {
+        self
+    }
+}
+
+impl Drop for ChildOutput {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Read the output of the program run by `start` for as long as asusd-user
+/// runs, a `buf` at a time, and give each to `each`. If the program can't be
+/// started or stops it's started again after `RESTART_DELAY`. This blocks so
+/// should be given its own thread. `name` is the feature logged with errors.
+pub fn read_restarting<C: AsMut<ChildOutput>>(
+    name: &str,
+    mut start: impl FnMut() -> Result<C, Error>,
+    buf: &mut [u8],
+    mut each: impl FnMut(&[u8]),
+) {
+    loop {
+        match start() {
+            Ok(mut running) => {
+                let output = running.as_mut();
+                let mut reads = 0;
+                // Reading blocks until the program writes, which paces this
+                while output.read(buf).is_ok() {
+                    reads += 1;
+                    each(buf);
+                }
+                if reads == 0 {
+                    warn!(
+                        "{name}: no output from {}, starting it again",
+                        output.program()
+                    );
+                } else {
+                    warn!("{name}: {} stopped, starting it again", output.program());
+                }
+            }
+            Err(e) => warn!("{name}: could not start, trying again: {e}"),
+        }
+        std::thread::sleep(RESTART_DELAY);
+    }
+}
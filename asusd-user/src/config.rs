@@ -8,7 +8,10 @@ use rog_aura::keyboard::LedCode;
 use rog_aura::{Colour, Speed};
 use serde::{Deserialize, Serialize};
 
+use crate::ambient::ScreenAmbientConfig;
+use crate::audio::AudioVisualiserConfig;
 use crate::error::Error;
+use crate::process_rules::ProcessRulesConfig;
 
 const ROOT_CONF_DIR: &str = "rog";
 
@@ -210,6 +213,14 @@ pub struct ConfigBase {
     pub active_anime: Option<String>,
     /// Name of active aura config file in the user config directory
     pub active_aura: Option<String>,
+    /// Throttle policies or asusd user profiles to switch to while certain
+    /// programs run
+    pub process_rules: ProcessRulesConfig,
+    /// Show the audio playing on the keyboard instead of the Aura config
+    pub audio_visualiser: AudioVisualiserConfig,
+    /// Show the colours at the edges of the screen on the keyboard instead of
+    /// the Aura config
+    pub screen_ambient: ScreenAmbientConfig,
 }
 
 impl StdConfig for ConfigBase {
@@ -217,6 +228,9 @@ impl StdConfig for ConfigBase {
         Self {
             active_anime: Some("anime-default".to_owned()),
             active_aura: Some("aura-default".to_owned()),
+            process_rules: ProcessRulesConfig::default(),
+            audio_visualiser: AudioVisualiserConfig::default(),
+            screen_ambient: ScreenAmbientConfig::default(),
         }
     }
 
@@ -3,20 +3,25 @@ use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
+use asusd_user::ambient::run_screen_ambient;
+use asusd_user::audio::run_audio_visualiser;
 use asusd_user::config::*;
 use asusd_user::ctrl_anime::{CtrlAnime, CtrlAnimeInner};
+use asusd_user::process_rules::run_process_rules;
+use asusd_user::session::SessionProxyBlocking;
 use config_traits::{StdConfig, StdConfigLoad};
 use rog_anime::usb::get_maybe_anime_type;
 use rog_aura::aura_detection::LedSupportData;
 use rog_aura::keyboard::KeyLayout;
 use rog_dbus::zbus_anime::AnimeProxyBlocking;
 use rog_dbus::zbus_aura::AuraProxyBlocking;
+use rog_dbus::zbus_platform::PlatformProxyBlocking;
 use rog_dbus::{list_iface_blocking, DBUS_NAME};
 use smol::Executor;
 use zbus::Connection;
 
 #[cfg(not(feature = "local_data"))]
-const DATA_DIR: &str = "/usr/share/rog-gui/";
+const DATA_DIR: &str = rog_aura::keyboard::LAYOUT_DATA_DIR;
 #[cfg(feature = "local_data")]
 const DATA_DIR: &str = env!("CARGO_MANIFEST_DIR");
 const BOARD_NAME: &str = "/sys/class/dmi/id/board_name";
@@ -84,8 +89,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    if !config.process_rules.rules.is_empty()
+        && supported.contains(&"org.asuslinux.Platform".to_string())
+    {
+        let platform_proxy_blocking = PlatformProxyBlocking::new(&conn).unwrap();
+        let rules = config.process_rules.clone();
+        // Scanning /proc blocks, so it gets a thread of its own
+        std::thread::spawn(move || run_process_rules(rules, platform_proxy_blocking));
+    }
+
+    // Both take over the keyboard, the visualiser is used if both are enabled
+    if config.audio_visualiser.enabled || config.screen_ambient.enabled {
+        let layout = KeyLayout::find_layout(LedSupportData::get_data(""), PathBuf::from(DATA_DIR))
+            .map_err(|e| log::warn!("{BOARD_NAME}, {e}"))
+            .unwrap_or_else(|_| KeyLayout::default_layout());
+        let aura_proxy_blocking = AuraProxyBlocking::new(&conn).unwrap();
+        let session = SessionProxyBlocking::new(&conn)
+            .map_err(|e| log::warn!("Could not get the logind session: {e}"))
+            .ok();
+        let visualiser = config.audio_visualiser.clone();
+        let ambient = config.screen_ambient.clone();
+        // Reading the audio or screen blocks, so it gets a thread of its own
+        std::thread::spawn(move || {
+            if visualiser.enabled {
+                run_audio_visualiser(visualiser, layout, aura_proxy_blocking, session)
+            } else {
+                run_screen_ambient(ambient, layout, aura_proxy_blocking, session)
+            }
+        });
+    }
+
     // if supported.keyboard_led.per_key_led_mode {
-    if let Some(cfg) = config.active_aura {
+    if let Some(cfg) = config
+        .active_aura
+        .filter(|_| !config.audio_visualiser.enabled && !config.screen_ambient.enabled)
+    {
         let mut aura_config = ConfigAura::new().set_name(cfg).load();
         // let baord_name = std::fs::read_to_string(BOARD_NAME)?;
 
@@ -98,13 +136,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap_or_else(|_| KeyLayout::default_layout());
 
         let aura_proxy_blocking = AuraProxyBlocking::new(&conn).unwrap();
+        // Without logind the session is taken to always be active
+        let session = SessionProxyBlocking::new(&conn)
+            .map_err(|e| log::warn!("Could not get the logind session: {e}"))
+            .ok();
         executor
             .spawn(async move {
                 loop {
-                    aura_config.aura.next_state(&layout);
-                    let packets = aura_config.aura.create_packets();
+                    // asusd runs its own effects while this session isn't in use
+                    if session
+                        .as_ref()
+                        .map_or(true, |s| s.active().unwrap_or(true))
+                    {
+                        aura_config.aura.next_state(&layout);
+                        let packets = aura_config.aura.create_packets();
 
-                    aura_proxy_blocking.direct_addressing_raw(packets).unwrap();
+                        aura_proxy_blocking.direct_addressing_raw(packets).unwrap();
+                    }
                     std::thread::sleep(std::time::Duration::from_millis(33));
                 }
             })
@@ -6,4 +6,16 @@ pub mod ctrl_anime;
 
 pub mod zbus_anime;
 
+pub mod process_rules;
+
+pub mod session;
+
+pub mod child_output;
+
+pub mod audio;
+
+pub mod screencast;
+
+pub mod ambient;
+
 pub static VERSION: &str = env!("CARGO_PKG_VERSION");
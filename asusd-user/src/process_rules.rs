@@ -0,0 +1,281 @@
+//! Switch the throttle policy, or an asusd user profile along with its fan
+//! curves, while a matching program runs.
+//!
+//! Programs are found by scanning `/proc`. Whatever was active before the
+//! first rule matched is restored once the last matching program exits.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::{info, warn};
+use rog_dbus::zbus_platform::PlatformProxyBlocking;
+use rog_platform::platform::ThrottlePolicy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProcessRule {
+    /// The program name, matched against the file name of
+    /// `/proc/<pid>/exe`, `/proc/<pid>/comm`, or the first argument of the
+    /// command line. The last catches games run through Wine.
+    pub exe: String,
+    /// A throttle policy, or the name of a user profile in asusd
+    pub profile: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProcessRulesConfig {
+    /// How often `/proc` is scanned, in milliseconds
+    pub interval: u64,
+    /// The first rule with a running program is applied
+    pub rules: Vec<ProcessRule>,
+}
+
+impl Default for ProcessRulesConfig {
+    fn default() -> Self {
+        Self {
+            interval: 2000,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// What asusd has active, to be restored later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActiveProfile {
+    User(String),
+    Policy(ThrottlePolicy),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleAction {
+    /// Switch to the profile of a rule
+    Apply(String),
+    /// No rule matches any more, go back to what was active before
+    Restore(ActiveProfile),
+}
+
+/// Decides when to switch profiles as programs start and exit
+#[derive(Debug, Default)]
+pub struct RuleEngine {
+    rules: Vec<ProcessRule>,
+    /// Active before the first rule matched
+    previous: Option<ActiveProfile>,
+    /// The profile of the rule which was last applied
+    applied: Option<String>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<ProcessRule>) -> Self {
+        Self {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    /// What to change now that the `running` programs are known. `current` is
+    /// only called when a rule starts to match after none did.
+    pub fn update(
+        &mut self,
+        running: &HashSet<String>,
+        current: impl FnOnce() -> Option<ActiveProfile>,
+    ) -> Option<RuleAction> {
+        let matched = self
+            .rules
+            .iter()
+            .find(|rule| running.contains(&rule.exe))
+            .map(|rule| rule.profile.clone());
+        match matched {
+            Some(profile) => {
+                if self.applied.as_ref() == Some(&profile) {
+                    return None;
+                }
+                if self.applied.is_none() {
+                    self.previous = current();
+                }
+                self.applied = Some(profile.clone());
+                Some(RuleAction::Apply(profile))
+            }
+            None => {
+                self.applied.take()?;
+                self.previous.take().map(RuleAction::Restore)
+            }
+        }
+    }
+}
+
+/// The names of every running program which can be read, see
+/// [`ProcessRule::exe`]
+pub fn running_programs(proc_root: &Path) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return names;
+    };
+    for entry in entries.flatten() {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
+            continue;
+        }
+        let dir = entry.path();
+        if let Ok(exe) = std::fs::read_link(dir.join("exe")) {
+            if let Some(name) = exe.file_name() {
+                let name = name.to_string_lossy();
+                names.insert(name.trim_end_matches(" (deleted)").to_owned());
+            }
+        }
+        if let Ok(comm) = std::fs::read_to_string(dir.join("comm")) {
+            names.insert(comm.trim().to_owned());
+        }
+        if let Ok(cmdline) = std::fs::read(dir.join("cmdline")) {
+            let arg0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+            let arg0 = String::from_utf8_lossy(arg0);
+            if let Some(name) = arg0.rsplit(['/', '\\']).next() {
+                if !name.is_empty() {
+                    names.insert(name.to_owned());
+                }
+            }
+        }
+    }
+    names
+}
+
+fn current_profile(proxy: &PlatformProxyBlocking<'_>) -> Option<ActiveProfile> {
+    match proxy.active_user_profile() {
+        Ok(name) if !name.is_empty() => Some(ActiveProfile::User(name)),
+        _ => proxy
+            .throttle_thermal_policy()
+            .map(ActiveProfile::Policy)
+            .map_err(|e| warn!("ProcessRules: {e}"))
+            .ok(),
+    }
+}
+
+/// Activate the user profile called `profile`, or the throttle policy of that
+/// name if there is no such user profile
+fn apply_profile(proxy: &PlatformProxyBlocking<'_>, profile: &str) -> zbus::Result<()> {
+    let user_profiles = proxy.user_profiles().unwrap_or_default();
+    if user_profiles.iter().any(|p| p.name == profile) {
+        proxy.activate_user_profile(profile)
+    } else if let Ok(policy) = ThrottlePolicy::from_str(profile) {
+        proxy.set_throttle_thermal_policy(policy)
+    } else {
+        warn!("ProcessRules: {profile} is not a throttle policy or user profile");
+        Ok(())
+    }
+}
+
+/// Apply the rules for as long as asusd-user runs. This blocks so should be
+/// given its own thread.
+pub fn run_process_rules(config: ProcessRulesConfig, proxy: PlatformProxyBlocking<'_>) {
+    info!("ProcessRules: watching for {} rules", config.rules.len());
+    let interval = Duration::from_millis(config.interval.max(100));
+    let mut engine = RuleEngine::new(config.rules);
+    loop {
+        let running = running_programs(Path::new("/proc"));
+        let action = engine.update(&running, || current_profile(&proxy));
+        let result = match &action {
+            Some(RuleAction::Apply(profile)) => {
+                info!("ProcessRules: switching to {profile}");
+                apply_profile(&proxy, profile)
+            }
+            Some(RuleAction::Restore(ActiveProfile::User(name))) => {
+                info!("ProcessRules: restoring user profile {name}");
+                proxy.activate_user_profile(name)
+            }
+            Some(RuleAction::Restore(ActiveProfile::Policy(policy))) => {
+                info!("ProcessRules: restoring {policy:?}");
+                proxy.set_throttle_thermal_policy(*policy)
+            }
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("ProcessRules: {action:?}: {e}");
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rog_platform::platform::ThrottlePolicy;
+
+    use super::{running_programs, ActiveProfile, ProcessRule, RuleAction, RuleEngine};
+
+    fn running(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn rules_apply_and_restore() {
+        let rule = |exe: &str, profile: &str| ProcessRule {
+            exe: exe.to_owned(),
+            profile: profile.to_owned(),
+        };
+        let mut engine =
+            RuleEngine::new(vec![rule("steam", "Gaming"), rule("cargo", "performance")]);
+        let quiet = || Some(ActiveProfile::Policy(ThrottlePolicy::Quiet));
+        let never = || -> Option<ActiveProfile> { panic!("only asked on the first match") };
+
+        assert_eq!(engine.update(&running(&["bash"]), quiet), None);
+        assert_eq!(
+            engine.update(&running(&["bash", "cargo"]), quiet),
+            Some(RuleAction::Apply("performance".to_owned()))
+        );
+        assert_eq!(engine.update(&running(&["cargo"]), never), None);
+        // An earlier rule takes over, but what to restore is kept
+        assert_eq!(
+            engine.update(&running(&["cargo", "steam"]), never),
+            Some(RuleAction::Apply("Gaming".to_owned()))
+        );
+        assert_eq!(
+            engine.update(&running(&["cargo"]), never),
+            Some(RuleAction::Apply("performance".to_owned()))
+        );
+        assert_eq!(
+            engine.update(&running(&["bash"]), never),
+            Some(RuleAction::Restore(ActiveProfile::Policy(
+                ThrottlePolicy::Quiet
+            )))
+        );
+        assert_eq!(engine.update(&running(&["bash"]), never), None);
+
+        let meeting = || Some(ActiveProfile::User("Meeting".to_owned()));
+        engine.update(&running(&["steam"]), meeting);
+        assert_eq!(
+            engine.update(&running(&[]), never),
+            Some(RuleAction::Restore(ActiveProfile::User(
+                "Meeting".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn programs_from_proc() {
+        let root = std::env::temp_dir().join(format!("asusd-user-proc-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        for (pid, comm, cmdline) in [
+            ("1", "systemd\n", "/usr/lib/systemd/systemd\0--user\0"),
+            ("42", "Game.exe\n", "Z:\\games\\Game.exe\0"),
+            ("77", "rustc\n", ""),
+        ] {
+            let dir = root.join(pid);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("comm"), comm).unwrap();
+            std::fs::write(dir.join("cmdline"), cmdline).unwrap();
+        }
+        std::fs::create_dir_all(root.join("self")).unwrap();
+        std::os::unix::fs::symlink("/usr/bin/cargo", root.join("77/exe")).unwrap();
+
+        let names = running_programs(&root);
+        assert_eq!(names, running(&["systemd", "Game.exe", "rustc", "cargo"]));
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,228 @@
+//! Screen capture through the xdg-desktop-portal ScreenCast interface.
+//!
+//! The portal asks the user which monitor to share and hands back a PipeWire
+//! remote with the monitor's stream. The stream is read with GStreamer's
+//! `pipewiresrc` and scaled down to `WIDTH` x `HEIGHT` RGB frames.
+
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::process::{Command, Stdio};
+
+use log::info;
+use zbus::blocking::Connection;
+use zbus::proxy;
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::child_output::ChildOutput;
+use crate::error::Error;
+
+/// The size frames are scaled to. Colours are only taken from the edges so
+/// this can be small. A row of RGB is a multiple of 4 bytes, so GStreamer adds
+/// no padding.
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 36;
+pub const FRAME_LEN: usize = WIDTH * HEIGHT * 3;
+/// `SelectSources` source type for monitors
+const SOURCE_MONITOR: u32 = 1;
+/// `SelectSources` persist mode to keep sharing the monitor until revoked
+const PERSIST_UNTIL_REVOKED: u32 = 2;
+const REQUEST_PATH: &str = "/org/freedesktop/portal/desktop/request";
+
+#[proxy(
+    interface = "org.freedesktop.portal.ScreenCast",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait ScreenCast {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn select_sources(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn start(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(name = "OpenPipeWireRemote")]
+    fn open_pipewire_remote(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<zvariant::OwnedFd>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Request",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+trait Request {
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+/// A portal session sharing one monitor. The session ends when this is
+/// dropped.
+pub struct ScreenCastSession {
+    /// The session is closed with the connection
+    _connection: Connection,
+    proxy: ScreenCastProxyBlocking<'static>,
+    session: OwnedObjectPath,
+    /// The PipeWire node of the monitor's stream
+    node: u32,
+    restore_token: Option<String>,
+}
+
+impl ScreenCastSession {
+    /// Ask the portal to share a monitor. A `restore_token` from an earlier
+    /// session shares the same monitor without asking the user again.
+    pub fn start(restore_token: Option<&str>) -> Result<Self, Error> {
+        let connection = Connection::session()?;
+        let proxy = ScreenCastProxyBlocking::new(&connection)?;
+
+        let results = request(&connection, "asusd_user_session", |mut options| {
+            options.insert("session_handle_token", Value::from("asusd_user"));
+            proxy.create_session(options)
+        })?;
+        let session = results
+            .get("session_handle")
+            .and_then(|v| <&str>::try_from(&**v).ok())
+            .ok_or_else(|| Error::Portal("CreateSession gave no session".to_owned()))?;
+        let session = OwnedObjectPath::try_from(session.to_owned())?;
+
+        request(&connection, "asusd_user_select", |mut options| {
+            options.insert("types", Value::from(SOURCE_MONITOR));
+            options.insert("multiple", Value::from(false));
+            options.insert("persist_mode", Value::from(PERSIST_UNTIL_REVOKED));
+            if let Some(token) = restore_token {
+                options.insert("restore_token", Value::from(token.to_owned()));
+            }
+            proxy.select_sources(&session, options)
+        })?;
+
+        let results = request(&connection, "asusd_user_start", |options| {
+            proxy.start(&session, "", options)
+        })?;
+        let node = first_stream_node(&results)
+            .ok_or_else(|| Error::Portal("Start gave no stream".to_owned()))?;
+        let restore_token = results
+            .get("restore_token")
+            .and_then(|v| <&str>::try_from(&**v).ok())
+            .map(str::to_owned);
+        info!("ScreenCast: sharing PipeWire node {node}");
+        Ok(Self {
+            _connection: connection,
+            proxy,
+            session,
+            node,
+            restore_token,
+        })
+    }
+
+    /// Given by the portal to share the same monitor next time without
+    /// asking, if it supports that
+    pub fn restore_token(&self) -> Option<&str> {
+        self.restore_token.as_deref()
+    }
+
+    fn open_pipewire_remote(&self) -> Result<OwnedFd, Error> {
+        let fd = self
+            .proxy
+            .open_pipewire_remote(&self.session, HashMap::new())?;
+        Ok(fd.into())
+    }
+}
+
+/// Make a portal call with `token` as the `handle_token` in its `options`, and
+/// wait for the results in the `Response` of its request
+fn request(
+    connection: &Connection,
+    token: &str,
+    call: impl FnOnce(HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>,
+) -> Result<HashMap<String, OwnedValue>, Error> {
+    // The request path is known ahead so the response can't be missed
+    let sender = connection
+        .unique_name()
+        .ok_or_else(|| Error::Portal("no unique name on the session bus".to_owned()))?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request = RequestProxyBlocking::builder(connection)
+        .path(format!("{REQUEST_PATH}/{sender}/{token}"))?
+        .build()?;
+    let mut responses = request.receive_response()?;
+
+    call(HashMap::from([("handle_token", Value::from(token))]))?;
+
+    let response = responses
+        .next()
+        .ok_or_else(|| Error::Portal("no response to a request".to_owned()))?;
+    let args = response.args()?;
+    match *args.response() {
+        0 => Ok(args
+            .results()
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.try_clone().ok()?)))
+            .collect()),
+        1 => Err(Error::Portal("screen sharing was cancelled".to_owned())),
+        _ => Err(Error::Portal("screen sharing failed".to_owned())),
+    }
+}
+
+/// The PipeWire node of the first stream in the results of `Start`, which
+/// are an array of `(node, properties)`
+fn first_stream_node(results: &HashMap<String, OwnedValue>) -> Option<u32> {
+    let Value::Array(streams) = &**results.get("streams")? else {
+        return None;
+    };
+    streams.iter().find_map(|stream| match stream {
+        Value::Structure(s) => match s.fields().first() {
+            Some(Value::U32(node)) => Some(*node),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// RGB frames of a shared monitor, read with `gst-launch-1.0`. Each read of
+/// `FRAME_LEN` bytes is a frame, row by row.
+pub struct ScreenCapture {
+    /// Stopped before the session ends
+    output: ChildOutput,
+    /// Kept for as long as the stream is read
+    _session: ScreenCastSession,
+}
+
+impl ScreenCapture {
+    pub fn start(session: ScreenCastSession, fps: u32) -> Result<Self, Error> {
+        // The PipeWire remote is given to pipewiresrc as its stdin
+        let remote = session.open_pipewire_remote()?;
+        let output = ChildOutput::spawn(
+            Command::new("gst-launch-1.0")
+                .arg("-q")
+                .args(["pipewiresrc", "fd=0", &format!("path={}", session.node)])
+                .args(["always-copy=true", "!", "videoconvert", "!"])
+                .args(["videoscale", "add-borders=false", "!", "videorate", "!"])
+                .arg(format!(
+                    "video/x-raw,format=RGB,width={WIDTH},height={HEIGHT},framerate={}/1",
+                    fps.clamp(1, 60)
+                ))
+                .args(["!", "fdsink", "fd=1"])
+                .stdin(Stdio::from(remote)),
+        )?;
+        Ok(Self {
+            output,
+            _session: session,
+        })
+    }
+}
+
+impl AsMut<ChildOutput> for ScreenCapture {
+    fn as_mut(&mut self) -> &mut ChildOutput {
+        &mut self.output
+    }
+}
@@ -0,0 +1,15 @@
+//! The logind session the user daemon runs in. Effects are only sent to asusd
+//! while it is the active session, otherwise asusd runs its own.
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    /// If the session is in the foreground of its seat
+    #[zbus(property)]
+    fn active(&self) -> zbus::Result<bool>;
+}
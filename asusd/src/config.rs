@@ -1,14 +1,34 @@
 use config_traits::{StdConfig, StdConfigLoad1};
 use rog_platform::cpu::CPUEPP;
-use rog_platform::platform::ThrottlePolicy;
+use rog_platform::governor::GovernorConfig;
+use rog_platform::hooks::HookEvent;
+use rog_platform::platform::{PlatformTuning, ThrottlePolicy};
+use rog_platform::power::ChargeRule;
+use rog_profiles::user_profile::UserProfile;
 use serde::{Deserialize, Serialize};
 
+use crate::hooks::Hook;
+
 const CONFIG_FILE: &str = "asusd.ron";
+const HOOK_TIMEOUT_DEFAULT: u64 = 10;
+
+fn hook_timeout_default() -> u64 {
+    HOOK_TIMEOUT_DEFAULT
+}
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, PartialOrd)]
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub struct Config {
-    /// Save charge limit for restoring on boot/resume
+    /// Save charge limit for restoring on boot/resume. This is the default
+    /// limit, used when no charge rule is active.
     pub charge_control_end_threshold: u8,
+    /// Charge to 100% until the next time power is unplugged
+    #[serde(default)]
+    pub charge_full_until_unplugged: bool,
+    /// Time of day charge limits which take the place of
+    /// `charge_control_end_threshold` while active. The first active rule in
+    /// the list is used.
+    #[serde(default)]
+    pub charge_rules: Vec<ChargeRule>,
     pub panel_od: bool,
     pub boot_sound: bool,
     pub mini_led_mode: bool,
@@ -17,6 +37,12 @@ pub struct Config {
     pub ac_command: String,
     /// An optional command/script to run when power is changed to battery
     pub bat_command: String,
+    /// Commands to run on events, see `crate::hooks`
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Seconds a hook may run before it is killed
+    #[serde(default = "hook_timeout_default")]
+    pub hook_timeout: u64,
     /// Set true if energy_performance_preference should be set if the
     /// throttle/platform profile is changed
     pub throttle_policy_linked_epp: bool,
@@ -34,6 +60,11 @@ pub struct Config {
     pub throttle_balanced_epp: CPUEPP,
     /// The energy_performance_preference for this throttle/platform profile
     pub throttle_performance_epp: CPUEPP,
+    /// The energy_performance_preference for the efficiency cores of hybrid
+    /// CPUs with each throttle policy. `None` uses the EPP of the performance
+    /// cores.
+    #[serde(default)]
+    pub throttle_efficiency_core_epp: ThrottleEpp,
     /// Defaults to `None` if not supported
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub ppt_pl1_spl: Option<u8>,
@@ -55,21 +86,167 @@ pub struct Config {
     /// Defaults to `None` if not supported
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub nv_temp_target: Option<u8>,
+    /// The PPT and Nvidia tunings applied when a throttle policy is activated
+    /// on AC
+    #[serde(default)]
+    pub throttle_tunings_ac: ThrottleTunings,
+    /// The PPT and Nvidia tunings applied when a throttle policy is activated
+    /// on battery. Any unset value falls back to the AC tuning.
+    #[serde(default)]
+    pub throttle_tunings_battery: ThrottleTunings,
+    /// Named bundles of a throttle policy with its fan curves, tuning, EPP
+    /// and Aura mode
+    #[serde(default)]
+    pub user_profiles: Vec<UserProfile>,
+    /// The user profiles `next_throttle_thermal_policy` cycles through, in
+    /// order. Empty to cycle the throttle policies.
+    #[serde(default)]
+    pub user_profile_order: Vec<String>,
+    /// The name of the active user profile, empty if none
+    #[serde(default)]
+    pub active_user_profile: String,
+    /// Move between throttle policies with the CPU temperature and load
+    #[serde(default)]
+    pub governor: GovernorConfig,
     /// Temporary state for AC/Batt
     #[serde(skip)]
     pub last_power_plugged: u8,
 }
 
+impl Config {
+    /// The charge limit to use at `minute` past midnight on `weekday`, where
+    /// Monday is `0`
+    pub fn charge_limit_at(&self, weekday: u8, minute: u16) -> u8 {
+        if self.charge_full_until_unplugged {
+            return 100;
+        }
+        self.charge_rules
+            .iter()
+            .find(|rule| rule.is_active(weekday, minute))
+            .map(|rule| rule.limit)
+            .unwrap_or(self.charge_control_end_threshold)
+    }
+
+    /// The argv of each hook in the config for `event`. `ac_command` and
+    /// `bat_command` are split on whitespace and run first.
+    pub fn hook_commands(&self, event: HookEvent) -> Vec<Vec<String>> {
+        let legacy = match event {
+            HookEvent::Ac => self.ac_command.as_str(),
+            HookEvent::Battery => self.bat_command.as_str(),
+            _ => "",
+        };
+        let legacy: Vec<String> = legacy.split_whitespace().map(|s| s.to_owned()).collect();
+        (!legacy.is_empty())
+            .then_some(legacy)
+            .into_iter()
+            .chain(
+                self.hooks
+                    .iter()
+                    .filter(|hook| hook.event == event)
+                    .map(|hook| hook.argv.clone()),
+            )
+            .collect()
+    }
+
+    pub fn user_profile(&self, name: &str) -> Option<&UserProfile> {
+        self.user_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+    }
+
+    /// The user profile after `current` in `user_profile_order`, or the first
+    /// if `current` isn't in it. `None` if there is no order to follow.
+    pub fn next_user_profile(&self, current: &str) -> Option<&UserProfile> {
+        let order: Vec<&UserProfile> = self
+            .user_profile_order
+            .iter()
+            .filter_map(|name| self.user_profile(name))
+            .collect();
+        let next = order
+            .iter()
+            .position(|profile| profile.name == current)
+            .map_or(0, |i| (i + 1) % order.len());
+        order.get(next).copied()
+    }
+
+    /// The tuning to apply for `policy` on the given power source
+    pub fn tuning_for(&self, policy: ThrottlePolicy, power_plugged: bool) -> PlatformTuning {
+        let ac = self.throttle_tunings_ac.get(policy);
+        if power_plugged {
+            ac
+        } else {
+            self.throttle_tunings_battery.get(policy).or(ac)
+        }
+    }
+}
+
+/// A `PlatformTuning` for each `ThrottlePolicy`
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct ThrottleTunings {
+    pub quiet: PlatformTuning,
+    pub balanced: PlatformTuning,
+    pub performance: PlatformTuning,
+}
+
+impl ThrottleTunings {
+    pub fn get(&self, policy: ThrottlePolicy) -> PlatformTuning {
+        match policy {
+            ThrottlePolicy::Balanced => self.balanced,
+            ThrottlePolicy::Performance => self.performance,
+            ThrottlePolicy::Quiet => self.quiet,
+        }
+    }
+
+    pub fn get_mut(&mut self, policy: ThrottlePolicy) -> &mut PlatformTuning {
+        match policy {
+            ThrottlePolicy::Balanced => &mut self.balanced,
+            ThrottlePolicy::Performance => &mut self.performance,
+            ThrottlePolicy::Quiet => &mut self.quiet,
+        }
+    }
+}
+
+/// A `CPUEPP` for each `ThrottlePolicy`, `None` if unset
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ThrottleEpp {
+    pub quiet: Option<CPUEPP>,
+    pub balanced: Option<CPUEPP>,
+    pub performance: Option<CPUEPP>,
+}
+
+impl ThrottleEpp {
+    pub fn get(&self, policy: ThrottlePolicy) -> Option<CPUEPP> {
+        match policy {
+            ThrottlePolicy::Balanced => self.balanced,
+            ThrottlePolicy::Performance => self.performance,
+            ThrottlePolicy::Quiet => self.quiet,
+        }
+    }
+
+    pub fn get_mut(&mut self, policy: ThrottlePolicy) -> &mut Option<CPUEPP> {
+        match policy {
+            ThrottlePolicy::Balanced => &mut self.balanced,
+            ThrottlePolicy::Performance => &mut self.performance,
+            ThrottlePolicy::Quiet => &mut self.quiet,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             charge_control_end_threshold: 100,
+            charge_full_until_unplugged: false,
+            charge_rules: Vec::new(),
             panel_od: false,
             boot_sound: false,
             mini_led_mode: false,
             disable_nvidia_powerd_on_battery: true,
             ac_command: Default::default(),
             bat_command: Default::default(),
+            hooks: Vec::new(),
+            hook_timeout: HOOK_TIMEOUT_DEFAULT,
             throttle_policy_linked_epp: true,
             throttle_policy_on_battery: ThrottlePolicy::Quiet,
             change_throttle_policy_on_battery: true,
@@ -78,6 +255,7 @@ impl Default for Config {
             throttle_quiet_epp: CPUEPP::Power,
             throttle_balanced_epp: CPUEPP::BalancePower,
             throttle_performance_epp: CPUEPP::Performance,
+            throttle_efficiency_core_epp: Default::default(),
             ppt_pl1_spl: Default::default(),
             ppt_pl2_sppt: Default::default(),
             ppt_fppt: Default::default(),
@@ -85,6 +263,12 @@ impl Default for Config {
             ppt_platform_sppt: Default::default(),
             nv_dynamic_boost: Default::default(),
             nv_temp_target: Default::default(),
+            throttle_tunings_ac: Default::default(),
+            throttle_tunings_battery: Default::default(),
+            user_profiles: Vec::new(),
+            user_profile_order: Vec::new(),
+            active_user_profile: String::new(),
+            governor: GovernorConfig::default(),
             last_power_plugged: Default::default(),
         }
     }
@@ -108,7 +292,7 @@ impl StdConfig for Config {
     }
 
     fn config_dir() -> std::path::PathBuf {
-        std::path::PathBuf::from(crate::CONFIG_PATH_BASE)
+        crate::config_dir()
     }
 }
 
@@ -140,11 +324,15 @@ impl From<Config507> for Config {
     fn from(c: Config507) -> Self {
         Self {
             charge_control_end_threshold: c.charge_control_end_threshold,
+            charge_full_until_unplugged: false,
+            charge_rules: Vec::new(),
             panel_od: c.panel_od,
             boot_sound: false,
             disable_nvidia_powerd_on_battery: c.disable_nvidia_powerd_on_battery,
             ac_command: c.ac_command,
             bat_command: c.bat_command,
+            hooks: Vec::new(),
+            hook_timeout: HOOK_TIMEOUT_DEFAULT,
             mini_led_mode: c.mini_led_mode,
             throttle_policy_linked_epp: true,
             throttle_policy_on_battery: c.platform_policy_on_battery,
@@ -154,6 +342,7 @@ impl From<Config507> for Config {
             throttle_quiet_epp: CPUEPP::Power,
             throttle_balanced_epp: CPUEPP::BalancePower,
             throttle_performance_epp: CPUEPP::Performance,
+            throttle_efficiency_core_epp: Default::default(),
             ppt_pl1_spl: c.ppt_pl1_spl,
             ppt_pl2_sppt: c.ppt_pl2_sppt,
             ppt_fppt: c.ppt_fppt,
@@ -161,7 +350,119 @@ impl From<Config507> for Config {
             ppt_platform_sppt: c.ppt_platform_sppt,
             nv_dynamic_boost: c.nv_dynamic_boost,
             nv_temp_target: c.nv_temp_target,
+            throttle_tunings_ac: Default::default(),
+            throttle_tunings_battery: Default::default(),
+            user_profiles: Vec::new(),
+            user_profile_order: Vec::new(),
+            active_user_profile: String::new(),
+            governor: GovernorConfig::default(),
             last_power_plugged: 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rog_platform::hooks::HookEvent;
+    use rog_platform::platform::{PlatformTuning, ThrottlePolicy};
+    use rog_profiles::user_profile::UserProfile;
+
+    use super::Config;
+    use crate::hooks::Hook;
+
+    #[test]
+    fn charge_limit_from_rules_and_override() {
+        let mut config = Config {
+            charge_control_end_threshold: 60,
+            charge_rules: vec![
+                "mon-fri 05:00-07:00 100".parse().unwrap(),
+                "daily 00:00-08:00 80".parse().unwrap(),
+            ],
+            ..Default::default()
+        };
+        // Monday 06:00 matches both, the first wins
+        assert_eq!(config.charge_limit_at(0, 6 * 60), 100);
+        // Saturday only matches the second
+        assert_eq!(config.charge_limit_at(5, 6 * 60), 80);
+        assert_eq!(config.charge_limit_at(0, 12 * 60), 60);
+
+        config.charge_full_until_unplugged = true;
+        assert_eq!(config.charge_limit_at(0, 12 * 60), 100);
+    }
+
+    #[test]
+    fn battery_tuning_falls_back_to_ac() {
+        let mut config = Config::default();
+        config.throttle_tunings_ac.quiet = PlatformTuning {
+            ppt_pl1_spl: Some(45),
+            nv_dynamic_boost: Some(10),
+            ..Default::default()
+        };
+        config.throttle_tunings_battery.quiet.ppt_pl1_spl = Some(25);
+
+        let ac = config.tuning_for(ThrottlePolicy::Quiet, true);
+        assert_eq!(ac.ppt_pl1_spl, Some(45));
+        assert_eq!(ac.nv_dynamic_boost, Some(10));
+
+        let battery = config.tuning_for(ThrottlePolicy::Quiet, false);
+        assert_eq!(battery.ppt_pl1_spl, Some(25));
+        assert_eq!(battery.nv_dynamic_boost, Some(10));
+
+        // 0 is a value like any other
+        config.throttle_tunings_battery.quiet.nv_dynamic_boost = Some(0);
+        let battery = config.tuning_for(ThrottlePolicy::Quiet, false);
+        assert_eq!(battery.nv_dynamic_boost, Some(0));
+
+        assert!(config
+            .tuning_for(ThrottlePolicy::Performance, false)
+            .is_unset());
+    }
+
+    #[test]
+    fn next_user_profile_follows_order() {
+        let profile = |name: &str| UserProfile {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+        let mut config = Config {
+            user_profiles: vec![profile("Meeting"), profile("Compile"), profile("Gaming")],
+            ..Default::default()
+        };
+        assert!(config.next_user_profile("").is_none());
+
+        config.user_profile_order =
+            vec!["Gaming".to_owned(), "Gone".to_owned(), "Meeting".to_owned()];
+        let next = |config: &Config, current: &str| {
+            config.next_user_profile(current).map(|p| p.name.clone())
+        };
+        assert_eq!(next(&config, "").as_deref(), Some("Gaming"));
+        assert_eq!(next(&config, "Gaming").as_deref(), Some("Meeting"));
+        assert_eq!(next(&config, "Meeting").as_deref(), Some("Gaming"));
+        // Not in the order so it starts again
+        assert_eq!(next(&config, "Compile").as_deref(), Some("Gaming"));
+    }
+
+    #[test]
+    fn hook_commands_include_legacy() {
+        let config = Config {
+            ac_command: "notify-send  plugged".to_owned(),
+            hooks: vec![
+                Hook {
+                    event: HookEvent::Ac,
+                    argv: vec!["logger".to_owned(), "on AC".to_owned()],
+                },
+                Hook {
+                    event: HookEvent::Battery,
+                    argv: vec!["true".to_owned()],
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.hook_commands(HookEvent::Ac),
+            vec![vec!["notify-send", "plugged"], vec!["logger", "on AC"]]
+        );
+        assert_eq!(config.hook_commands(HookEvent::Battery), vec![vec!["true"]]);
+        assert!(config.hook_commands(HookEvent::Resume).is_empty());
+    }
+}
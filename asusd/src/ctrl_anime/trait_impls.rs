@@ -10,25 +10,26 @@ use rog_anime::usb::{
 };
 use rog_anime::{Animations, AnimeDataBuffer, DeviceState};
 use zbus::export::futures_util::lock::Mutex;
+use zbus::message::Header;
 use zbus::{interface, CacheProperties, Connection, SignalContext};
 
 use super::config::AnimeConfig;
 use super::CtrlAnime;
 use crate::error::RogError;
+use crate::polkit::{Action, Authority};
 
 pub const ANIME_ZBUS_NAME: &str = "Anime";
 pub const ANIME_ZBUS_PATH: &str = "/org/asuslinux";
 
-async fn get_logind_manager<'a>() -> ManagerProxy<'a> {
-    let connection = Connection::system()
-        .await
-        .expect("Controller could not create dbus connection");
+async fn get_logind_manager<'a>() -> Option<ManagerProxy<'a>> {
+    let connection = crate::sys_events::logind_connection()?;
 
     ManagerProxy::builder(&connection)
         .cache_properties(CacheProperties::No)
         .build()
         .await
-        .expect("Controller could not create ManagerProxy")
+        .map_err(|e| warn!("Controller could not create ManagerProxy: {e}"))
+        .ok()
 }
 
 #[derive(Clone)]
@@ -72,8 +73,15 @@ impl CtrlAnimeZbus {
     }
 
     /// Set base brightness level
-    #[zbus(property)]
-    async fn set_brightness(&self, brightness: Brightness) {
+    async fn set_brightness(
+        &self,
+        brightness: Brightness,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         self.0
             .lock()
             .await
@@ -96,6 +104,8 @@ impl CtrlAnimeZbus {
         self.0.lock().await.config.display_enabled = brightness != Brightness::Off;
         self.0.lock().await.config.display_brightness = brightness;
         self.0.lock().await.config.write();
+        self.brightness_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -106,8 +116,15 @@ impl CtrlAnimeZbus {
 
     /// Enable the builtin animations or not. This is quivalent to "Powersave
     /// animations" in Armory crate
-    #[zbus(property)]
-    async fn set_builtins_enabled(&self, enabled: bool) {
+    async fn set_builtins_enabled(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let brightness = self.0.lock().await.config.display_brightness;
         self.0
             .lock()
@@ -146,6 +163,8 @@ impl CtrlAnimeZbus {
                 .thread_exit
                 .store(true, Ordering::Release);
         }
+        self.builtins_enabled_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -154,8 +173,15 @@ impl CtrlAnimeZbus {
     }
 
     /// Set which builtin animation is used for each stage
-    #[zbus(property)]
-    async fn set_builtin_animations(&self, settings: Animations) {
+    async fn set_builtin_animations(
+        &self,
+        settings: Animations,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         self.0
             .lock()
             .await
@@ -182,6 +208,8 @@ impl CtrlAnimeZbus {
         self.0.lock().await.config.display_enabled = true;
         self.0.lock().await.config.builtin_anims = settings;
         self.0.lock().await.config.write();
+        self.builtin_animations_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -190,8 +218,15 @@ impl CtrlAnimeZbus {
     }
 
     /// Set whether the AniMe is enabled at all
-    #[zbus(property)]
-    async fn set_enable_display(&self, enabled: bool) {
+    async fn set_enable_display(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         self.0
             .lock()
             .await
@@ -203,6 +238,8 @@ impl CtrlAnimeZbus {
             .ok();
         self.0.lock().await.config.display_enabled = enabled;
         self.0.lock().await.config.write();
+        self.enable_display_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -211,10 +248,19 @@ impl CtrlAnimeZbus {
     }
 
     /// Set if to turn the AniMe Matrix off when external power is unplugged
-    #[zbus(property)]
-    async fn set_off_when_unplugged(&self, enabled: bool) {
-        let manager = get_logind_manager().await;
-        let pow = manager.on_external_power().await.unwrap_or_default();
+    async fn set_off_when_unplugged(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
+        let pow = match get_logind_manager().await {
+            Some(manager) => manager.on_external_power().await.unwrap_or_default(),
+            None => false,
+        };
 
         self.0
             .lock()
@@ -228,6 +274,8 @@ impl CtrlAnimeZbus {
 
         self.0.lock().await.config.off_when_unplugged = enabled;
         self.0.lock().await.config.write();
+        self.off_when_unplugged_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -236,10 +284,19 @@ impl CtrlAnimeZbus {
     }
 
     /// Set if to turn the AniMe Matrix off when the laptop is suspended
-    #[zbus(property)]
-    async fn set_off_when_suspended(&self, enabled: bool) {
+    async fn set_off_when_suspended(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         self.0.lock().await.config.off_when_suspended = enabled;
         self.0.lock().await.config.write();
+        self.off_when_suspended_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -248,10 +305,19 @@ impl CtrlAnimeZbus {
     }
 
     /// Set if to turn the AniMe Matrix off when the lid is closed
-    #[zbus(property)]
-    async fn set_off_when_lid_closed(&self, enabled: bool) {
-        let manager = get_logind_manager().await;
-        let lid = manager.lid_closed().await.unwrap_or_default();
+    async fn set_off_when_lid_closed(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
+        let lid = match get_logind_manager().await {
+            Some(manager) => manager.lid_closed().await.unwrap_or_default(),
+            None => false,
+        };
 
         self.0
             .lock()
@@ -265,6 +331,8 @@ impl CtrlAnimeZbus {
 
         self.0.lock().await.config.off_when_lid_closed = enabled;
         self.0.lock().await.config.write();
+        self.off_when_lid_closed_changed(&ctxt).await?;
+        Ok(())
     }
 
     /// The main loop is the base system set action if the user isn't running
@@ -489,9 +557,14 @@ impl crate::Reloadable for CtrlAnimeZbus {
                 lock.config.display_brightness,
             )?;
 
-            let manager = get_logind_manager().await;
-            let lid_closed = manager.lid_closed().await.unwrap_or_default();
-            let power_plugged = manager.on_external_power().await.unwrap_or_default();
+            // Without logind assume the lid is open and on AC
+            let (lid_closed, power_plugged) = match get_logind_manager().await {
+                Some(manager) => (
+                    manager.lid_closed().await.unwrap_or_default(),
+                    manager.on_external_power().await.unwrap_or_default(),
+                ),
+                None => (false, true),
+            };
 
             let turn_off = (lid_closed && lock.config.off_when_lid_closed)
                 || (!power_plugged && lock.config.off_when_unplugged);
@@ -1,14 +1,17 @@
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 use config_traits::{StdConfig, StdConfigLoad};
 use log::{debug, info, warn};
 use rog_aura::aura_detection::LedSupportData;
-use rog_aura::keyboard::LaptopAuraPower;
+use rog_aura::effects::AdvancedEffects;
+use rog_aura::keyboard::{AdvancedAuraType, LaptopAuraPower};
 use rog_aura::{
     AuraDeviceType, AuraEffect, AuraModeNum, AuraZone, Direction, LedBrightness, Speed, GRADIENT,
 };
 use serde::{Deserialize, Serialize};
 
+use super::scheduler::{DEFAULT_EFFECTS_FPS, MAX_EFFECTS_FPS};
 use crate::error::RogError;
 
 #[derive(Deserialize, Serialize, Default, Debug, Clone)]
@@ -22,6 +25,19 @@ pub struct AuraConfig {
     pub multizone: Option<BTreeMap<AuraModeNum, Vec<AuraEffect>>>,
     pub multizone_on: bool,
     pub enabled: LaptopAuraPower,
+    /// The per-key or zoned effects asusd runs when enabled. These are only
+    /// changed by editing the config file.
+    #[serde(default)]
+    pub advanced_effects: AdvancedEffects,
+    #[serde(default)]
+    pub advanced_effects_enabled: bool,
+    /// Frames per second the advanced effects are run at
+    #[serde(default = "default_effects_fps")]
+    pub advanced_effects_fps: u8,
+}
+
+fn default_effects_fps() -> u8 {
+    DEFAULT_EFFECTS_FPS
 }
 
 impl StdConfig for AuraConfig {
@@ -38,7 +54,7 @@ impl StdConfig for AuraConfig {
     }
 
     fn config_dir() -> std::path::PathBuf {
-        std::path::PathBuf::from(crate::CONFIG_PATH_BASE)
+        crate::config_dir()
     }
 }
 
@@ -63,6 +79,12 @@ impl AuraConfig {
             multizone: None,
             multizone_on: false,
             enabled,
+            advanced_effects: AdvancedEffects::new(matches!(
+                support_data.advanced_type,
+                AdvancedAuraType::Zoned(_)
+            )),
+            advanced_effects_enabled: false,
+            advanced_effects_fps: DEFAULT_EFFECTS_FPS,
         };
 
         for n in &support_data.basic_modes {
@@ -126,6 +148,12 @@ impl AuraConfig {
         }
     }
 
+    /// The time between frames of the advanced effects
+    pub fn effects_frame_time(&self) -> Duration {
+        let fps = self.advanced_effects_fps.clamp(1, MAX_EFFECTS_FPS);
+        Duration::from_secs(1) / fps as u32
+    }
+
     pub fn get_multizone(&self, aura_type: AuraModeNum) -> Option<&[AuraEffect]> {
         if let Some(multi) = &self.multizone {
             return multi.get(&aura_type).map(|v| v.as_slice());
@@ -1,4 +1,7 @@
 use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use config_traits::{StdConfig, StdConfigLoad};
 use dmi_id::DMIID;
@@ -8,15 +11,19 @@ use rog_aura::aura_detection::LedSupportData;
 use rog_aura::keyboard::{LedUsbPackets, UsbPackets};
 use rog_aura::usb::{LED_APPLY, LED_SET};
 use rog_aura::{AuraDeviceType, AuraEffect, LedBrightness, LED_MSG_LEN};
+use rog_platform::discovery::Discovery;
 use rog_platform::hid_raw::HidRaw;
 use rog_platform::keyboard_led::KeyboardBacklight;
+use rog_platform::read_attr_string;
+use tokio::sync::Notify;
 use udev::Device;
-use zbus::zvariant::OwnedObjectPath;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
 use zbus::Connection;
 
 use super::config::AuraConfig;
+use super::scheduler::DIRECT_FRAMES_HOLD;
 use crate::ctrl_aura::manager::{dbus_path_for_dev, dbus_path_for_tuf, start_tasks};
-use crate::ctrl_aura::trait_impls::CtrlAuraZbus;
+use crate::ctrl_aura::trait_impls::{CtrlAuraZbus, AURA_ZBUS_PATH};
 use crate::error::RogError;
 use crate::CtrlTask;
 
@@ -107,6 +114,10 @@ pub struct CtrlKbdLed {
     pub per_key_mode_active: bool,
     pub config: AuraConfig,
     pub dbus_path: OwnedObjectPath,
+    /// When a frame was last written with `direct_addressing_raw`
+    pub last_direct_frame: Option<Instant>,
+    /// Wakes the advanced effects while they aren't running
+    pub effects_wake: Arc<Notify>,
 }
 
 impl CtrlKbdLed {
@@ -185,25 +196,64 @@ impl CtrlKbdLed {
         Ok(None)
     }
 
+    /// The same as `maybe_device` but for an endpoint in a fake sysfs tree,
+    /// where the `driver` and `idProduct` of the parents are plain files in the
+    /// endpoint directory
+    fn maybe_fake_device(
+        root: &Path,
+        syspath: &Path,
+        interfaces: &mut HashSet<OwnedObjectPath>,
+    ) -> Result<Option<Self>, RogError> {
+        if read_attr_string(syspath, "driver").ok().as_deref() != Some("asus") {
+            return Ok(None);
+        }
+        let Ok(prod_id) = read_attr_string(syspath, "idProduct") else {
+            return Ok(None);
+        };
+
+        let dbus_path: OwnedObjectPath =
+            ObjectPath::from_str_unchecked(&format!("{AURA_ZBUS_PATH}/{prod_id}")).into();
+        if interfaces.contains(&dbus_path) {
+            debug!("Already a ctrl at {dbus_path:?}, ignoring this end-point");
+            return Ok(None);
+        }
+
+        info!("AuraControl found fake device at: {:?}", syspath);
+        let dev = HidRaw::from_fake(root, syspath)?;
+        let mut controller = Self::from_hidraw(dev, dbus_path.clone())?;
+        controller.config = Self::load_and_update_config(&prod_id);
+        interfaces.insert(dbus_path);
+        Ok(Some(controller))
+    }
+
     pub fn find_all() -> Result<Vec<Self>, RogError> {
         info!("Searching for all Aura devices");
         let mut devices = Vec::new();
         let mut interfaces = HashSet::new(); // track and ensure we use only one hidraw per prod_id
 
-        let mut enumerator = udev::Enumerator::new().map_err(|err| {
-            warn!("{}", err);
-            err
-        })?;
-
-        enumerator.match_subsystem("hidraw").map_err(|err| {
-            warn!("{}", err);
-            err
-        })?;
-
-        for end_point in enumerator.scan_devices()? {
-            // maybe?
-            if let Some(device) = Self::maybe_device(end_point, &mut interfaces)? {
-                devices.push(device);
+        let discovery = Discovery::from_env();
+        if let Discovery::Root(root) = &discovery {
+            for syspath in discovery.scan("hidraw")? {
+                if let Some(device) = Self::maybe_fake_device(root, &syspath, &mut interfaces)? {
+                    devices.push(device);
+                }
+            }
+        } else {
+            let mut enumerator = udev::Enumerator::new().map_err(|err| {
+                warn!("{}", err);
+                err
+            })?;
+
+            enumerator.match_subsystem("hidraw").map_err(|err| {
+                warn!("{}", err);
+                err
+            })?;
+
+            for end_point in enumerator.scan_devices()? {
+                // maybe?
+                if let Some(device) = Self::maybe_device(end_point, &mut interfaces)? {
+                    devices.push(device);
+                }
             }
         }
 
@@ -225,6 +275,8 @@ impl CtrlKbdLed {
                         per_key_mode_active: false,
                         config: Self::load_and_update_config("tuf"),
                         dbus_path: dbus_path_for_tuf(),
+                        last_direct_frame: None,
+                        effects_wake: Arc::default(),
                     };
                     devices.push(ctrl);
                 }
@@ -268,6 +320,8 @@ impl CtrlKbdLed {
             per_key_mode_active: false,
             config: AuraConfig::default(),
             dbus_path,
+            last_direct_frame: None,
+            effects_wake: Arc::default(),
         };
         Ok(ctrl)
     }
@@ -379,6 +433,44 @@ impl CtrlKbdLed {
         Ok(())
     }
 
+    /// If the advanced effects in the config should be run. They are held off
+    /// while frames are written with `direct_addressing_raw`, so a user
+    /// session can run its own effects, and while the LEDs are off.
+    pub(super) fn advanced_effects_active(&self) -> bool {
+        self.config.advanced_effects_enabled
+            && !self.config.advanced_effects.is_empty()
+            && self.config.brightness != LedBrightness::Off
+            && self
+                .last_direct_frame
+                .map_or(true, |t| t.elapsed() > DIRECT_FRAMES_HOLD)
+    }
+
+    /// How long until the advanced effects are active again without a change
+    /// to the config, if they are only held off by frames written with
+    /// `direct_addressing_raw`
+    pub(super) fn advanced_effects_held_for(&self) -> Option<Duration> {
+        if !self.config.advanced_effects_enabled
+            || self.config.advanced_effects.is_empty()
+            || self.config.brightness == LedBrightness::Off
+        {
+            return None;
+        }
+        self.last_direct_frame
+            .map(|t| DIRECT_FRAMES_HOLD.saturating_sub(t.elapsed()))
+    }
+
+    /// Wake the advanced effects to check if they should run, after a change
+    /// to the config
+    pub(super) fn wake_advanced_effects(&self) {
+        self.effects_wake.notify_one();
+    }
+
+    /// Stop the advanced effects so a built-in mode can be set. Returns if they
+    /// were enabled.
+    pub(super) fn stop_advanced_effects(&mut self) -> bool {
+        std::mem::take(&mut self.config.advanced_effects_enabled)
+    }
+
     /// Write the AuraEffect to the device
     pub fn write_effect_and_apply(&mut self, mode: &AuraEffect) -> Result<(), RogError> {
         if let LEDNode::KbdLed(platform) = &self.led_node {
@@ -477,6 +569,8 @@ mod tests {
             per_key_mode_active: false,
             config,
             dbus_path: OwnedObjectPath::default(),
+            last_direct_frame: None,
+            effects_wake: Arc::default(),
         };
 
         assert!(controller.config.multizone.is_none());
@@ -527,6 +621,8 @@ mod tests {
             per_key_mode_active: false,
             config,
             dbus_path: OwnedObjectPath::default(),
+            last_direct_frame: None,
+            effects_wake: Arc::default(),
         };
 
         assert!(controller.config.multizone.is_none());
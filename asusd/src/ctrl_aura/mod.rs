@@ -5,6 +5,7 @@ use zbus::zvariant::{ObjectPath, OwnedObjectPath};
 pub mod config;
 pub mod controller;
 pub mod manager;
+pub mod scheduler;
 /// Implements `CtrlTask`, `Reloadable`, `ZbusRun`
 pub mod trait_impls;
 
@@ -0,0 +1,139 @@
+//! Runs the `AdvancedEffects` from the Aura config of each keyboard in asusd,
+//! so the effects also run at the login screen and without asusd-user.
+//!
+//! Frames written with `direct_addressing_raw`, such as by asusd-user while
+//! its session is active, take over from these effects until no frame has been
+//! written for `DIRECT_FRAMES_HOLD`.
+//!
+//! The first time an effect reacting to keys runs, each evdev node of the
+//! laptop keyboard gets a thread reading its key presses for as long as asusd
+//! runs. The presses are shared by the effects of every Aura device, and each
+//! key pressed while an effect reacting to keys is running triggers the
+//! effects at its LEDs. Presses at any other time are dropped.
+
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use rog_aura::keyboard::{KeyLayout, LedCode, LAYOUT_DATA_DIR};
+use rog_platform::key_events::KeyEvents;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::broadcast::{self, Receiver};
+use zbus::export::futures_util::lock::Mutex;
+
+use super::controller::CtrlKbdLed;
+
+pub const DEFAULT_EFFECTS_FPS: u8 = 30;
+pub const MAX_EFFECTS_FPS: u8 = 60;
+/// How long a frame written with `direct_addressing_raw` holds off the effects
+pub const DIRECT_FRAMES_HOLD: Duration = Duration::from_secs(1);
+/// How many key presses are kept for an effect which hasn't taken them yet
+const KEY_PRESS_BACKLOG: usize = 64;
+
+/// The key presses of the laptop keyboard, `None` if it can't be read. Set the
+/// first time an effect reacting to keys runs.
+static KEY_PRESSES: OnceLock<Option<broadcast::Sender<u16>>> = OnceLock::new();
+
+/// Tick the advanced effects of `ctrl` at its configured frame rate. Stops if
+/// the keyboard can't be written to.
+pub async fn run_advanced_effects(ctrl: Arc<Mutex<CtrlKbdLed>>) {
+    let layout = {
+        let lock = ctrl.lock().await;
+        KeyLayout::find_layout(lock.supported_data.clone(), PathBuf::from(LAYOUT_DATA_DIR))
+            .map_err(|e| info!("Advanced effects using the default layout: {e}"))
+            .unwrap_or_else(|_| KeyLayout::default_layout())
+    };
+
+    let mut key_presses = None;
+    let mut last_frame = Instant::now();
+    loop {
+        let mut lock = ctrl.lock().await;
+        if !lock.advanced_effects_active() {
+            let held_for = lock.advanced_effects_held_for();
+            let wake = lock.effects_wake.clone();
+            drop(lock);
+            key_presses = None;
+            match held_for {
+                Some(held_for) => {
+                    tokio::time::timeout(held_for, wake.notified()).await.ok();
+                }
+                None => wake.notified().await,
+            }
+            last_frame = Instant::now();
+            continue;
+        }
+
+        if lock.config.advanced_effects.is_reactive() {
+            if key_presses.is_none() {
+                key_presses = subscribe_key_presses();
+            }
+            if let Some(presses) = &mut key_presses {
+                loop {
+                    match presses.try_recv() {
+                        Ok(code) => {
+                            for led in LedCode::from_evdev(code) {
+                                lock.config.advanced_effects.trigger(*led);
+                            }
+                        }
+                        Err(TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
+            }
+        } else {
+            key_presses = None;
+        }
+
+        // The effects across the layout move with the time taken, so they keep
+        // their speed at any frame rate
+        let now = Instant::now();
+        lock.config
+            .advanced_effects
+            .next_state_after(&layout, now - last_frame);
+        last_frame = now;
+        let packets = lock.config.advanced_effects.create_packets();
+        if let Err(e) = lock.write_effect_block(&packets) {
+            warn!(
+                "Stopping the advanced effects for {:?}: {e}",
+                lock.dbus_path
+            );
+            return;
+        }
+        let frame_time = lock.config.effects_frame_time();
+        drop(lock);
+        tokio::time::sleep(frame_time).await;
+    }
+}
+
+/// Take the keys pressed on the laptop keyboard from now on, as evdev key
+/// codes. The keyboard is read from the first call on.
+fn subscribe_key_presses() -> Option<Receiver<u16>> {
+    // The first receiver is made before the readers start, so it gets every
+    // press they read
+    let mut first = None;
+    let presses = KEY_PRESSES
+        .get_or_init(|| {
+            let keyboards = KeyEvents::find_all()
+                .map_err(|e| warn!("Effects reacting to keys can't read the keyboard: {e}"))
+                .ok()?;
+            let (tx, rx) = broadcast::channel(KEY_PRESS_BACKLOG);
+            first = Some(rx);
+            for mut keyboard in keyboards {
+                let tx = tx.clone();
+                std::thread::spawn(move || loop {
+                    match keyboard.next_press() {
+                        // Errors if no effect reacting to keys is running
+                        Ok(code) => tx.send(code).ok(),
+                        Err(e) => {
+                            warn!("Stopped reading key presses: {e}");
+                            break;
+                        }
+                    };
+                });
+            }
+            Some(tx)
+        })
+        .as_ref()?;
+    Some(first.unwrap_or_else(|| presses.subscribe()))
+}
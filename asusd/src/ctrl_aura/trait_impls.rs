@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use config_traits::StdConfig;
 use log::{debug, error, info, warn};
@@ -8,10 +9,14 @@ use rog_aura::{AuraDeviceType, AuraEffect, AuraModeNum, AuraZone, LedBrightness,
 use zbus::export::futures_util::lock::{Mutex, MutexGuard};
 use zbus::export::futures_util::StreamExt;
 use zbus::fdo::Error as ZbErr;
+use zbus::message::Header;
 use zbus::{interface, SignalContext};
 
 use super::controller::CtrlKbdLed;
+use super::scheduler::{run_advanced_effects, MAX_EFFECTS_FPS};
 use crate::error::RogError;
+use crate::polkit::{Action, Authority};
+use crate::user_profiles::subscribe_user_profile;
 use crate::CtrlTask;
 
 pub const AURA_ZBUS_NAME: &str = "Aura";
@@ -54,10 +59,20 @@ impl CtrlAuraZbus {
     }
 
     /// Set the keyboard brightness level (0-3)
-    #[zbus(property)]
-    async fn set_brightness(&mut self, brightness: LedBrightness) -> Result<(), ZbErr> {
+    async fn set_brightness(
+        &self,
+        brightness: LedBrightness,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let ctrl = self.0.lock().await;
-        Ok(ctrl.led_node.set_brightness(brightness.into())?)
+        ctrl.led_node.set_brightness(brightness.into())?;
+        drop(ctrl);
+        self.brightness_changed(&ctxt).await?;
+        Ok(())
     }
 
     /// Total levels of brightness available
@@ -101,9 +116,17 @@ impl CtrlAuraZbus {
     ///
     /// On success the aura config file is read to refresh cached values, then
     /// the effect is stored and config written to disk.
-    #[zbus(property)]
-    async fn set_led_mode(&mut self, num: AuraModeNum) -> Result<(), ZbErr> {
+    async fn set_led_mode(
+        &self,
+        num: AuraModeNum,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut ctrl = self.0.lock().await;
+        let effects_stopped = ctrl.stop_advanced_effects();
         ctrl.config.current_mode = num;
         ctrl.write_current_config_mode()?;
         if ctrl.config.brightness == LedBrightness::Off {
@@ -114,8 +137,13 @@ impl CtrlAuraZbus {
                 .set_brightness(ctrl.config.brightness.into())?;
         }
         ctrl.config.write();
+        drop(ctrl);
 
         self.led_mode_data_invalidate(&self.1).await.ok();
+        if effects_stopped {
+            self.advanced_effects_enabled_changed(&self.1).await.ok();
+        }
+        self.led_mode_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -134,8 +162,15 @@ impl CtrlAuraZbus {
     ///
     /// On success the aura config file is read to refresh cached values, then
     /// the effect is stored and config written to disk.
-    #[zbus(property)]
-    async fn set_led_mode_data(&mut self, effect: AuraEffect) -> Result<(), ZbErr> {
+    async fn set_led_mode_data(
+        &self,
+        effect: AuraEffect,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut ctrl = self.0.lock().await;
         if !ctrl.supported_data.basic_modes.contains(&effect.mode)
             || effect.zone != AuraZone::None
@@ -146,6 +181,7 @@ impl CtrlAuraZbus {
             )));
         }
 
+        let effects_stopped = ctrl.stop_advanced_effects();
         ctrl.write_effect_and_apply(&effect)?;
         if ctrl.config.brightness == LedBrightness::Off {
             ctrl.config.brightness = LedBrightness::Med;
@@ -156,8 +192,13 @@ impl CtrlAuraZbus {
         }
         ctrl.config.set_builtin(effect);
         ctrl.config.write();
+        drop(ctrl);
 
         self.led_mode_invalidate(&self.1).await.ok();
+        if effects_stopped {
+            self.advanced_effects_enabled_changed(&self.1).await.ok();
+        }
+        self.led_mode_data_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -178,8 +219,15 @@ impl CtrlAuraZbus {
     /// `enabled` sets if the sent array should be disabled or enabled
     ///
     /// For Modern ROG devices the "enabled" flag is ignored.
-    #[zbus(property)]
-    async fn set_led_power(&mut self, options: LaptopAuraPower) -> Result<(), ZbErr> {
+    async fn set_led_power(
+        &self,
+        options: LaptopAuraPower,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut ctrl = self.0.lock().await;
         for opt in options.states {
             let zone = opt.zone;
@@ -190,17 +238,91 @@ impl CtrlAuraZbus {
             }
         }
         ctrl.config.write();
-        Ok(ctrl.set_power_states().map_err(|e| {
+        ctrl.set_power_states().map_err(|e| {
             warn!("{}", e);
             e
-        })?)
+        })?;
+        drop(ctrl);
+        self.led_power_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// If the advanced effects in the config file are run
+    #[zbus(property)]
+    async fn advanced_effects_enabled(&self) -> bool {
+        let ctrl = self.0.lock().await;
+        ctrl.config.advanced_effects_enabled
+    }
+
+    /// Run the advanced effects in the config file, which is read again so
+    /// edits to it are used. Disabling them restores the built-in mode.
+    async fn set_advanced_effects_enabled(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
+        let mut ctrl = self.0.lock().await;
+        if enabled {
+            ctrl.config.read();
+            if ctrl.config.advanced_effects.is_empty() {
+                return Err(ZbErr::Failed(
+                    "There are no advanced effects in the config".into(),
+                ));
+            }
+        }
+        ctrl.config.advanced_effects_enabled = enabled;
+        ctrl.config.write();
+        ctrl.wake_advanced_effects();
+        if !enabled {
+            ctrl.write_current_config_mode()?;
+        }
+        drop(ctrl);
+        self.advanced_effects_enabled_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Frames per second the advanced effects are run at
+    #[zbus(property)]
+    async fn advanced_effects_fps(&self) -> u8 {
+        let ctrl = self.0.lock().await;
+        ctrl.config.advanced_effects_fps
+    }
+
+    async fn set_advanced_effects_fps(
+        &self,
+        fps: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), ZbErr> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
+        if !(1..=MAX_EFFECTS_FPS).contains(&fps) {
+            return Err(ZbErr::InvalidArgs(format!(
+                "{fps} is not between 1 and {MAX_EFFECTS_FPS}"
+            )));
+        }
+        let mut ctrl = self.0.lock().await;
+        ctrl.config.advanced_effects_fps = fps;
+        ctrl.config.write();
+        drop(ctrl);
+        self.advanced_effects_fps_changed(&ctxt).await?;
+        Ok(())
     }
 
     /// On machine that have some form of either per-key keyboard or per-zone
     /// this can be used to write custom effects over dbus. The input is a
     /// nested `Vec<Vec<8>>` where `Vec<u8>` is a raw USB packet
+    ///
+    /// The advanced effects run by asusd are paused while frames are written
+    /// with this.
     async fn direct_addressing_raw(&self, data: UsbPackets) -> Result<(), ZbErr> {
         let mut ctrl = self.0.lock().await;
+        ctrl.last_direct_frame = Some(Instant::now());
         ctrl.write_effect_block(&data)?;
         Ok(())
     }
@@ -234,6 +356,7 @@ impl CtrlTask for CtrlAuraZbus {
                         error!("CtrlKbdLedTask: {e}");
                         e
                     })?;
+                    lock.wake_advanced_effects();
                 }
                 Ok(())
             };
@@ -268,6 +391,44 @@ impl CtrlTask for CtrlAuraZbus {
         )
         .await;
 
+        // Take the mode of a user profile when it is activated
+        let aura = self.clone();
+        tokio::spawn(async move {
+            let mut user_profile = subscribe_user_profile();
+            while user_profile.changed().await.is_ok() {
+                let Some(profile) = user_profile.borrow_and_update().clone() else {
+                    continue;
+                };
+                if !profile.set_aura_mode {
+                    continue;
+                }
+                let mode = AuraModeNum::from(profile.aura_mode);
+                let mut ctrl = aura.0.lock().await;
+                if mode as u8 != profile.aura_mode || !ctrl.config.builtins.contains_key(&mode) {
+                    debug!(
+                        "User profile {}: Aura mode {} is not supported",
+                        profile.name, profile.aura_mode
+                    );
+                    continue;
+                }
+                let effects_stopped = ctrl.stop_advanced_effects();
+                ctrl.config.current_mode = mode;
+                if let Err(e) = ctrl.write_current_config_mode() {
+                    warn!("User profile {}: {e}", profile.name);
+                    continue;
+                }
+                ctrl.config.write();
+                drop(ctrl);
+                aura.led_mode_changed(&aura.1).await.ok();
+                aura.led_mode_data_invalidate(&aura.1).await.ok();
+                if effects_stopped {
+                    aura.advanced_effects_enabled_changed(&aura.1).await.ok();
+                }
+            }
+        });
+
+        tokio::spawn(run_advanced_effects(self.0.clone()));
+
         let ctrl2 = self.0.clone();
         let ctrl = self.0.lock().await;
         if ctrl.led_node.has_brightness_control() {
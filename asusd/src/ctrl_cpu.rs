@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use config_traits::{StdConfig, StdConfigLoad};
+use cpuctl::{CoreState, CpuCapabilities, CpuCtl, CpuSettings, PstateMode};
+use log::{debug, info, warn};
+use rog_platform::platform::{RogPlatform, ThrottlePolicy};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use zbus::fdo::Error as FdoErr;
+use zbus::message::Header;
+use zbus::{interface, Connection, SignalContext};
+
+use crate::error::RogError;
+use crate::polkit::{Action, Authority};
+use crate::throttle_policy::subscribe_throttle_policy;
+use crate::CtrlTask;
+
+pub const CPU_ZBUS_NAME: &str = "Cpu";
+pub const CPU_ZBUS_PATH: &str = "/org/asuslinux";
+
+/// The CPU settings applied with each throttle policy
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct CpuConfig {
+    #[serde(default)]
+    pub quiet: CpuSettings,
+    #[serde(default)]
+    pub balanced: CpuSettings,
+    #[serde(default)]
+    pub performance: CpuSettings,
+    /// The policy the settings were last applied for
+    #[serde(skip)]
+    pub current: Option<ThrottlePolicy>,
+}
+
+impl CpuConfig {
+    pub fn get(&self, policy: ThrottlePolicy) -> &CpuSettings {
+        match policy {
+            ThrottlePolicy::Balanced => &self.balanced,
+            ThrottlePolicy::Performance => &self.performance,
+            ThrottlePolicy::Quiet => &self.quiet,
+        }
+    }
+
+    pub fn get_mut(&mut self, policy: ThrottlePolicy) -> &mut CpuSettings {
+        match policy {
+            ThrottlePolicy::Balanced => &mut self.balanced,
+            ThrottlePolicy::Performance => &mut self.performance,
+            ThrottlePolicy::Quiet => &mut self.quiet,
+        }
+    }
+}
+
+impl StdConfig for CpuConfig {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn file_name(&self) -> String {
+        "cpu.ron".to_owned()
+    }
+
+    fn config_dir() -> std::path::PathBuf {
+        crate::config_dir()
+    }
+}
+
+impl StdConfigLoad for CpuConfig {}
+
+#[derive(Clone)]
+pub struct CtrlCpu {
+    cpu: CpuCtl,
+    config: Arc<Mutex<CpuConfig>>,
+    /// Only set if the throttle policy can be read, otherwise the balanced
+    /// settings are used
+    platform: Option<RogPlatform>,
+}
+
+impl CtrlCpu {
+    pub fn new() -> Result<Self, RogError> {
+        let cpu = CpuCtl::new()?;
+        let platform = RogPlatform::new()
+            .ok()
+            .filter(|p| p.has_throttle_thermal_policy());
+        let config = CpuConfig::new().load();
+        Ok(Self {
+            cpu,
+            config: Arc::new(Mutex::new(config)),
+            platform,
+        })
+    }
+
+    fn throttle_policy(&self) -> ThrottlePolicy {
+        self.platform
+            .as_ref()
+            .and_then(|p| p.get_throttle_thermal_policy().ok())
+            .map(ThrottlePolicy::from)
+            .unwrap_or(ThrottlePolicy::Balanced)
+    }
+
+    /// Apply the settings of `policy` if it isn't already applied, returning
+    /// true if it wasn't
+    async fn apply_for_policy(&self, policy: ThrottlePolicy) -> bool {
+        let mut config = self.config.lock().await;
+        if config.current == Some(policy) {
+            return false;
+        }
+        info!("CpuCtl: applying the {policy:?} CPU settings");
+        let previous = config
+            .current
+            .map(|p| config.get(p).clone())
+            .unwrap_or_default();
+        self.cpu
+            .apply_replacing(config.get(policy), &previous)
+            .map_err(|e| warn!("CpuCtl: {policy:?}: {e}"))
+            .ok();
+        config.current = Some(policy);
+        true
+    }
+
+    /// Record a change made directly in the settings of the active policy, so
+    /// it is kept the next time that policy is used
+    async fn update_active(&self, update: impl FnOnce(&mut CpuSettings)) {
+        let policy = self.throttle_policy();
+        let mut config = self.config.lock().await;
+        update(config.get_mut(policy));
+        config.write();
+    }
+}
+
+#[interface(name = "org.asuslinux.Cpu")]
+impl CtrlCpu {
+    /// What can be changed on this machine
+    #[zbus(property)]
+    fn capabilities(&self) -> CpuCapabilities {
+        self.cpu.capabilities()
+    }
+
+    /// The online state and frequency limits of each CPU
+    fn cores(&self) -> Vec<CoreState> {
+        self.cpu.cores()
+    }
+
+    /// Take a CPU offline or bring it back. This is kept for the active
+    /// throttle policy.
+    async fn set_core_online(
+        &self,
+        cpu: u32,
+        online: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        self.cpu.set_online(cpu, online)?;
+        self.update_active(|s| {
+            s.offline.retain(|c| *c != cpu);
+            if !online {
+                s.offline.push(cpu);
+                s.offline.sort();
+            }
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Set the scaling frequency limits of every online CPU in kHz, `0` to
+    /// leave a limit as it is. This is kept for the active throttle policy.
+    async fn set_scaling_freq(
+        &self,
+        min: u32,
+        max: u32,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        self.cpu.set_scaling_freq(min, max)?;
+        self.update_active(|s| {
+            if min != 0 {
+                s.min_freq = min;
+            }
+            if max != 0 {
+                s.max_freq = max;
+            }
+        })
+        .await;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn boost(&self) -> Result<bool, FdoErr> {
+        Ok(self.cpu.boost()?)
+    }
+
+    /// Kept for the active throttle policy
+    async fn set_boost(
+        &self,
+        on: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        self.cpu.set_boost(on)?;
+        self.update_active(|s| s.boost = on.into()).await;
+        self.boost_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn smt(&self) -> Result<bool, FdoErr> {
+        Ok(self.cpu.smt()?)
+    }
+
+    /// Kept for the active throttle policy
+    async fn set_smt(
+        &self,
+        on: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        self.cpu.set_smt(on)?;
+        self.update_active(|s| s.smt = on.into()).await;
+        self.smt_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn pstate_mode(&self) -> Result<PstateMode, FdoErr> {
+        Ok(self.cpu.pstate_mode()?)
+    }
+
+    /// Kept for the active throttle policy
+    async fn set_pstate_mode(
+        &self,
+        mode: PstateMode,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        self.cpu.set_pstate_mode(mode)?;
+        self.update_active(|s| s.pstate_mode = mode).await;
+        self.pstate_mode_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// The CPU settings applied with `policy`
+    async fn policy_settings(&self, policy: ThrottlePolicy) -> CpuSettings {
+        self.config.lock().await.get(policy).clone()
+    }
+
+    /// Replace the CPU settings applied with `policy`, applying them now if
+    /// `policy` is active
+    async fn set_policy_settings(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        policy: ThrottlePolicy,
+        settings: CpuSettings,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetCpuTuning)
+            .await?;
+        settings
+            .check(&self.cpu.capabilities())
+            .map_err(|e| FdoErr::InvalidArgs(e.to_string()))?;
+        let mut config = self.config.lock().await;
+        let previous = std::mem::replace(config.get_mut(policy), settings);
+        config.write();
+        if self.throttle_policy() == policy {
+            config.current = Some(policy);
+            self.cpu.apply_replacing(config.get(policy), &previous)?;
+            drop(config);
+            self.notify_applied(&ctxt).await;
+        }
+        Ok(())
+    }
+}
+
+impl CtrlCpu {
+    async fn notify_applied(&self, ctxt: &SignalContext<'_>) {
+        self.boost_changed(ctxt).await.ok();
+        self.smt_changed(ctxt).await.ok();
+        self.pstate_mode_changed(ctxt).await.ok();
+    }
+}
+
+impl crate::ZbusRun for CtrlCpu {
+    async fn add_to_server(self, server: &mut Connection) {
+        Self::add_to_server_helper(self, CPU_ZBUS_PATH, server).await;
+    }
+}
+
+impl CtrlTask for CtrlCpu {
+    fn zbus_path() -> &'static str {
+        CPU_ZBUS_PATH
+    }
+
+    async fn create_tasks(&self, signal_ctxt: SignalContext<'static>) -> Result<(), RogError> {
+        if self.platform.is_none() {
+            return Ok(());
+        }
+        let ctrl = self.clone();
+        tokio::spawn(async move {
+            let mut policy = subscribe_throttle_policy();
+            while policy.changed().await.is_ok() {
+                let Some(policy) = *policy.borrow_and_update() else {
+                    continue;
+                };
+                debug!("CpuCtl: throttle policy changed to {policy:?}");
+                if ctrl.apply_for_policy(policy).await {
+                    ctrl.notify_applied(&signal_ctxt).await;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+impl crate::Reloadable for CtrlCpu {
+    async fn reload(&mut self) -> Result<(), RogError> {
+        let policy = self.throttle_policy();
+        self.config.lock().await.current = None;
+        self.apply_for_policy(policy).await;
+        Ok(())
+    }
+}
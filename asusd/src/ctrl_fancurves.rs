@@ -1,26 +1,66 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use config_traits::{StdConfig, StdConfigLoad};
 use futures_lite::StreamExt;
 use log::{debug, error, info, warn};
+use rog_platform::discovery::Discovery;
 use rog_platform::platform::{RogPlatform, ThrottlePolicy};
 use rog_profiles::error::ProfileError;
-use rog_profiles::fan_curve_set::CurveData;
-use rog_profiles::{find_fan_curve_node, FanCurvePU, FanCurveProfiles};
+use rog_profiles::fan_curve_file::FanCurveExport;
+use rog_profiles::fan_curve_set::{CurveData, FanCurveSafety};
+use rog_profiles::fan_sensors::{FanSensors, FanSensorsConfig, SensorReader};
+use rog_profiles::soft_fan_curve::{
+    default_soft_curve, read_temp, FanStep, SoftFanCurve, SoftFanCurveConfig,
+};
+use rog_profiles::{find_fan_curve_node, write_curves_to_device, FanCurvePU, FanCurveProfiles};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
+use tokio::time::sleep;
+use zbus::message::Header;
 use zbus::{interface, Connection, SignalContext};
 
 use crate::error::RogError;
-use crate::{CtrlTask, CONFIG_PATH_BASE};
+use crate::polkit::{Action, Authority};
+use crate::user_profiles::{active_user_profile, subscribe_user_profile};
+use crate::CtrlTask;
 
 pub const FAN_CURVE_ZBUS_NAME: &str = "FanCurves";
 pub const FAN_CURVE_ZBUS_PATH: &str = "/org/asuslinux";
+/// The shortest time between checks of the sensors, in milliseconds
+const MIN_SENSOR_INTERVAL: u64 = 100;
+
+/// The fans driven by the software engine, kept so they can be given back to
+/// the firmware when asusd exits
+static SOFT_FANS: OnceLock<SoftFanCurve> = OnceLock::new();
+
+/// Put any fans driven by the software engine back in automatic mode. This is
+/// also called from the panic hook, so it must not panic.
+pub fn release_soft_fans() {
+    if let Some(soft) = SOFT_FANS.get() {
+        info!("Setting software controlled fans to auto");
+        soft.set_all_auto();
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Default)]
 pub struct FanCurveConfig {
     pub profiles: FanCurveProfiles,
+    /// The rules a curve must follow before it is accepted
+    #[serde(default)]
+    pub safety: FanCurveSafety,
+    /// Accept any curve, skipping the `safety` checks. Only for those who
+    /// know what their hardware can take.
+    #[serde(default)]
+    pub expert_override: bool,
+    /// Run the curves in asusd on laptops without firmware fan curves
+    #[serde(default)]
+    pub software: SoftFanCurveConfig,
+    /// The fan speed and temperature sensors, and how often they are signalled
+    #[serde(default)]
+    pub sensors: FanSensorsConfig,
     #[serde(skip)]
     pub current: u8,
 }
@@ -37,16 +77,43 @@ impl StdConfig for FanCurveConfig {
     }
 
     fn config_dir() -> std::path::PathBuf {
-        PathBuf::from(CONFIG_PATH_BASE)
+        crate::config_dir()
     }
 }
 
 impl StdConfigLoad for FanCurveConfig {}
 
+impl FanCurveConfig {
+    /// The curves to use for `policy`. These are the curves of the active user
+    /// profile if it has its own for `policy` and they pass the safety rules.
+    fn curves_for(&self, policy: ThrottlePolicy) -> Vec<CurveData> {
+        if let Some(profile) = active_user_profile() {
+            if let Some(curves) = profile.fan_curves_for(policy) {
+                let checked = if self.expert_override {
+                    Ok(())
+                } else {
+                    curves.iter().try_for_each(|c| c.validate(&self.safety))
+                };
+                match checked {
+                    Ok(()) => return curves.to_vec(),
+                    Err(e) => warn!(
+                        "User profile {}: {e}, using the {policy:?} fan curves",
+                        profile.name
+                    ),
+                }
+            }
+        }
+        self.profiles.get_fan_curves_for(policy).to_vec()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CtrlFanCurveZbus {
     config: Arc<Mutex<FanCurveConfig>>,
     platform: RogPlatform,
+    /// Set if the curves are run by the software engine
+    soft: Option<SoftFanCurve>,
+    sensors: SensorReader,
 }
 
 // Non-zbus-derive impl
@@ -55,7 +122,9 @@ impl CtrlFanCurveZbus {
         let platform = RogPlatform::new()?;
         if platform.has_throttle_thermal_policy() {
             info!("Device has profile control available");
-            find_fan_curve_node()?;
+            if find_fan_curve_node().is_err() {
+                return Self::new_software(platform);
+            }
             info!("Device has fan curves available");
             let mut config = FanCurveConfig::new().load();
             let mut fan_curves = FanCurveProfiles::default();
@@ -74,8 +143,8 @@ impl CtrlFanCurveZbus {
                     // can read the existing values from hardware. The ACPI method used
                     // for this is what limits us.
                     platform.set_throttle_thermal_policy(this.into())?;
-                    let mut dev = find_fan_curve_node()?;
-                    fan_curves.set_active_curve_to_defaults(this, &mut dev)?;
+                    let dev = find_fan_curve_node()?;
+                    fan_curves.set_active_curve_to_defaults(this, &dev)?;
 
                     info!("{this:?}:");
                     for curve in fan_curves.get_fan_curves_for(this) {
@@ -90,14 +159,199 @@ impl CtrlFanCurveZbus {
                 config = config.load();
             }
 
+            let sensors = SensorReader::new_with(&Discovery::from_env(), &config.sensors);
             return Ok(Self {
                 config: Arc::new(Mutex::new(config)),
                 platform,
+                soft: None,
+                sensors,
             });
         }
 
         Err(ProfileError::NotSupported.into())
     }
+
+    /// Use the software fan curve engine, if it is enabled in the config and
+    /// the `asus` hwmon allows manual fan control
+    fn new_software(platform: RogPlatform) -> Result<Self, RogError> {
+        let mut config = FanCurveConfig::new().load();
+        if !config.software.enabled {
+            info!("No fan curve support, software fan curves can be enabled in the config");
+            return Err(ProfileError::NotSupported.into());
+        }
+        let soft = SoftFanCurve::new_with(&Discovery::from_env())?;
+        info!("Using software fan curves for {:?}", soft.fans());
+
+        if config.profiles.balanced.is_empty() {
+            for profile in [
+                ThrottlePolicy::Balanced,
+                ThrottlePolicy::Performance,
+                ThrottlePolicy::Quiet,
+            ] {
+                Self::set_soft_defaults(&mut config, &soft, profile);
+            }
+            config.write();
+        }
+        SOFT_FANS.set(soft.clone()).ok();
+
+        let sensors = SensorReader::new_with(&Discovery::from_env(), &config.sensors);
+        Ok(Self {
+            config: Arc::new(Mutex::new(config)),
+            platform,
+            soft: Some(soft),
+            sensors,
+        })
+    }
+
+    fn set_soft_defaults(
+        config: &mut FanCurveConfig,
+        soft: &SoftFanCurve,
+        profile: ThrottlePolicy,
+    ) {
+        let curves = soft.fans().iter().map(|fan| default_soft_curve(*fan));
+        match profile {
+            ThrottlePolicy::Balanced => config.profiles.balanced = curves.collect(),
+            ThrottlePolicy::Performance => config.profiles.performance = curves.collect(),
+            ThrottlePolicy::Quiet => config.profiles.quiet = curves.collect(),
+        }
+    }
+
+    /// The fans which can have a curve
+    fn fans(&self) -> Result<Vec<FanCurvePU>, ProfileError> {
+        match &self.soft {
+            Some(soft) => Ok(soft.fans().to_vec()),
+            None => Ok(FanCurveProfiles::supported_fans_of(&find_fan_curve_node()?)),
+        }
+    }
+
+    /// Write the curves for `profile` to the device. The software engine reads
+    /// them from the config so there is nothing to write for it.
+    fn write_curves(
+        &self,
+        config: &FanCurveConfig,
+        profile: ThrottlePolicy,
+    ) -> Result<(), ProfileError> {
+        if self.soft.is_some() {
+            return Ok(());
+        }
+        write_curves_to_device(&config.curves_for(profile), &find_fan_curve_node()?)
+    }
+}
+
+/// Drive the fans from the enabled curves of the active throttle policy. Fans
+/// without an enabled curve are left to the firmware, as are all fans while
+/// the temperature can't be read.
+async fn run_soft_fan_curves(
+    soft: SoftFanCurve,
+    config: Arc<Mutex<FanCurveConfig>>,
+    platform: RogPlatform,
+) {
+    let settings = config.lock().await.software.clone();
+    let discovery = Discovery::from_env();
+    let sensors: Vec<PathBuf> = settings
+        .sensors
+        .iter()
+        .filter_map(|sensor| sensor.find_with(&discovery).ok())
+        .collect();
+    if sensors.is_empty() {
+        error!(
+            "SoftFanCurve: none of {:?} found, leaving fans on auto",
+            settings.sensors
+        );
+        soft.set_all_auto();
+        return;
+    }
+    info!("SoftFanCurve: using sensors {sensors:?}");
+    // Start from a known state in case asusd didn't exit cleanly last time
+    soft.set_all_auto();
+
+    let poll = Duration::from_millis(settings.poll_interval.max(100));
+    let interval = Duration::from_millis(settings.step_interval);
+    let mut steps: HashMap<FanCurvePU, FanStep> = HashMap::new();
+    let mut last_curves = Vec::new();
+    let mut failed = false;
+    loop {
+        let temp = sensors
+            .iter()
+            .map(|path| read_temp(path))
+            .try_fold(0, |max, temp| temp.map(|temp| temp.max(max)));
+        let policy = platform
+            .get_throttle_thermal_policy()
+            .map(ThrottlePolicy::from)
+            .map_err(ProfileError::from);
+        let (temp, policy) = match (temp, policy) {
+            (Ok(temp), Ok(policy)) => (temp, policy),
+            (Err(e), _) | (_, Err(e)) => {
+                if !failed {
+                    error!("SoftFanCurve: {e}, setting fans to auto");
+                    soft.set_all_auto();
+                    steps.clear();
+                    failed = true;
+                }
+                sleep(poll).await;
+                continue;
+            }
+        };
+        failed = false;
+
+        let curves = config.lock().await.curves_for(policy);
+        let curves_changed = curves != last_curves;
+        if curves_changed {
+            last_curves.clone_from(&curves);
+        }
+
+        for fan in soft.fans() {
+            let step = steps.entry(*fan).or_default();
+            match curves.iter().find(|c| c.fan == *fan && c.enabled) {
+                Some(curve) => {
+                    if curves_changed {
+                        // Apply a new curve straight away rather than waiting
+                        // out the hysteresis of the old one
+                        step.reset();
+                    }
+                    let Some(pwm) =
+                        step.step(curve, temp, settings.hysteresis, interval, Instant::now())
+                    else {
+                        continue;
+                    };
+                    if let Err(e) = soft.set_pwm(*fan, pwm) {
+                        error!("SoftFanCurve: {e}, setting {fan:?} to auto");
+                        soft.set_auto(*fan).ok();
+                        step.reset();
+                    }
+                }
+                None if step.is_manual() => {
+                    soft.set_auto(*fan)
+                        .map_err(|e| error!("SoftFanCurve: {e}"))
+                        .ok();
+                    step.reset();
+                }
+                None => {}
+            }
+        }
+        sleep(poll).await;
+    }
+}
+
+/// Signal `FanSensors` when it changes, checking as often as the config asks
+async fn run_fan_sensors_signal(ctrl: CtrlFanCurveZbus, signal_ctxt: SignalContext<'static>) {
+    let mut last = FanSensors::default();
+    loop {
+        let interval = ctrl.config.lock().await.sensors.interval;
+        if interval == 0 {
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let sensors = ctrl.sensors.read();
+        if sensors != last {
+            last = sensors;
+            ctrl.fan_sensors_changed(&signal_ctxt)
+                .await
+                .map_err(|e| warn!("fan_sensors_changed: {e}"))
+                .ok();
+        }
+        sleep(Duration::from_millis(interval.max(MIN_SENSOR_INTERVAL))).await;
+    }
 }
 
 #[interface(name = "org.asuslinux.FanCurves")]
@@ -105,51 +359,46 @@ impl CtrlFanCurveZbus {
     /// Set all fan curves for a profile to enabled status. Will also activate a
     /// fan curve if in the same profile mode
     async fn set_fan_curves_enabled(
-        &mut self,
+        &self,
         profile: ThrottlePolicy,
         enabled: bool,
+        #[zbus(header)] header: Header<'_>,
     ) -> zbus::fdo::Result<()> {
-        self.config
-            .lock()
-            .await
-            .profiles
-            .set_profile_curves_enabled(profile, enabled);
-        self.config
-            .lock()
-            .await
-            .profiles
-            .write_profile_curve_to_platform(profile, &mut find_fan_curve_node()?)?;
-        self.config.lock().await.write();
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        let mut config = self.config.lock().await;
+        config.profiles.set_profile_curves_enabled(profile, enabled);
+        self.write_curves(&config, profile)?;
+        config.write();
         Ok(())
     }
 
     /// Set a single fan curve for a profile to enabled status. Will also
     /// activate a fan curve if in the same profile mode
     async fn set_profile_fan_curve_enabled(
-        &mut self,
+        &self,
         profile: ThrottlePolicy,
         fan: FanCurvePU,
         enabled: bool,
+        #[zbus(header)] header: Header<'_>,
     ) -> zbus::fdo::Result<()> {
-        self.config
-            .lock()
-            .await
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        let mut config = self.config.lock().await;
+        config
             .profiles
             .set_profile_fan_curve_enabled(profile, fan, enabled);
-        self.config
-            .lock()
-            .await
-            .profiles
-            .write_profile_curve_to_platform(profile, &mut find_fan_curve_node()?)?;
-        self.config.lock().await.write();
+        self.write_curves(&config, profile)?;
+        config.write();
         Ok(())
     }
 
-    /// Get the fan-curve data for the currently active ThrottlePolicy
-    async fn fan_curve_data(
-        &mut self,
-        profile: ThrottlePolicy,
-    ) -> zbus::fdo::Result<Vec<CurveData>> {
+    /// Get the fan-curve data for the currently active ThrottlePolicy. Each
+    /// curve has the 8 points used by the kernel, and `points` as the user
+    /// made it if it was resampled.
+    async fn fan_curve_data(&self, profile: ThrottlePolicy) -> zbus::fdo::Result<Vec<CurveData>> {
         let curve = self
             .config
             .lock()
@@ -162,11 +411,29 @@ impl CtrlFanCurveZbus {
 
     /// Set the fan curve for the specified profile.
     /// Will also activate the fan curve if the user is in the same mode.
+    /// The curve is rejected if it breaks the safety rules in the config,
+    /// unless `expert_override` is set.
+    ///
+    /// If `points` is set it is resampled to the 8 points the kernel takes,
+    /// and `pwm` and `temp` are ignored.
     async fn set_fan_curve(
-        &mut self,
+        &self,
         profile: ThrottlePolicy,
-        curve: CurveData,
+        mut curve: CurveData,
+        #[zbus(header)] header: Header<'_>,
     ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        curve.resample()?;
+        {
+            let config = self.config.lock().await;
+            if config.expert_override {
+                warn!("Fan curve safety checks are disabled by expert_override");
+            } else {
+                curve.validate(&config.safety)?;
+            }
+        }
         self.config
             .lock()
             .await
@@ -174,11 +441,7 @@ impl CtrlFanCurveZbus {
             .save_fan_curve(curve, profile)?;
         let active: ThrottlePolicy = self.platform.get_throttle_thermal_policy()?.into();
         if active == profile {
-            self.config
-                .lock()
-                .await
-                .profiles
-                .write_profile_curve_to_platform(profile, &mut find_fan_curve_node()?)?;
+            self.write_curves(&self.config.lock().await, profile)?;
         }
         self.config.lock().await.write();
         Ok(())
@@ -189,14 +452,27 @@ impl CtrlFanCurveZbus {
     ///
     /// Each platform_profile has a different default and the default can be
     /// read only for the currently active profile.
-    async fn set_curves_to_defaults(&mut self, profile: ThrottlePolicy) -> zbus::fdo::Result<()> {
+    async fn set_curves_to_defaults(
+        &self,
+        profile: ThrottlePolicy,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        if let Some(soft) = &self.soft {
+            let mut config = self.config.lock().await;
+            Self::set_soft_defaults(&mut config, soft, profile);
+            config.write();
+            return Ok(());
+        }
         let active = self.platform.get_throttle_thermal_policy()?;
         self.platform.set_throttle_thermal_policy(profile.into())?;
         self.config
             .lock()
             .await
             .profiles
-            .set_active_curve_to_defaults(profile, &mut find_fan_curve_node()?)?;
+            .set_active_curve_to_defaults(profile, &find_fan_curve_node()?)?;
         self.platform.set_throttle_thermal_policy(active)?;
         self.config.lock().await.write();
         Ok(())
@@ -207,7 +483,20 @@ impl CtrlFanCurveZbus {
     ///
     /// Each platform_profile has a different default and the defualt can be
     /// read only for the currently active profile.
-    async fn reset_profile_curves(&self, profile: ThrottlePolicy) -> zbus::fdo::Result<()> {
+    async fn reset_profile_curves(
+        &self,
+        profile: ThrottlePolicy,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        if let Some(soft) = &self.soft {
+            let mut config = self.config.lock().await;
+            Self::set_soft_defaults(&mut config, soft, profile);
+            config.write();
+            return Ok(());
+        }
         let active = self.platform.get_throttle_thermal_policy()?;
 
         self.platform.set_throttle_thermal_policy(profile.into())?;
@@ -215,12 +504,102 @@ impl CtrlFanCurveZbus {
             .lock()
             .await
             .profiles
-            .set_active_curve_to_defaults(active.into(), &mut find_fan_curve_node()?)?;
+            .set_active_curve_to_defaults(active.into(), &find_fan_curve_node()?)?;
         self.platform.set_throttle_thermal_policy(active)?;
 
         self.config.lock().await.write();
         Ok(())
     }
+
+    /// Get the fan curves of every ThrottlePolicy, with the board name, fans
+    /// and version to check them against when imported elsewhere
+    async fn export_fan_curves(&self) -> zbus::fdo::Result<FanCurveExport> {
+        let profiles = self.config.lock().await.profiles.clone();
+        Ok(FanCurveExport::new(
+            self.platform.board_name(),
+            self.fans()?,
+            profiles,
+        ))
+    }
+
+    /// Replace the fan curves of every ThrottlePolicy with exported ones.
+    ///
+    /// Curves which don't match the fans of this machine are rejected, unless
+    /// `map_fans` is set to drop those for missing fans and copy the CPU fan
+    /// curve to any fan without one. Every curve must pass the same checks as
+    /// `SetFanCurve`, and the curves of the active policy are applied.
+    async fn import_fan_curves(
+        &self,
+        curves: FanCurveExport,
+        map_fans: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        let mut profiles = curves.fit_to(self.platform.board_name(), &self.fans()?, map_fans)?;
+        let mut config = self.config.lock().await;
+        for curve in profiles
+            .balanced
+            .iter_mut()
+            .chain(profiles.performance.iter_mut())
+            .chain(profiles.quiet.iter_mut())
+        {
+            curve.resample()?;
+            if !config.expert_override {
+                curve.validate(&config.safety)?;
+            }
+        }
+        if config.expert_override {
+            warn!("Fan curve safety checks are disabled by expert_override");
+        }
+        info!(
+            "Importing fan curves from {} made by asusd {}",
+            curves.board_name, curves.version
+        );
+        config.profiles = profiles;
+        let active: ThrottlePolicy = self.platform.get_throttle_thermal_policy()?.into();
+        self.write_curves(&config, active)?;
+        config.write();
+        Ok(())
+    }
+
+    /// The current fan speeds in RPM and temperatures in degrees C, `0` for
+    /// any which can't be read. A change is signalled at most once every
+    /// `SensorInterval`.
+    #[zbus(property)]
+    fn fan_sensors(&self) -> FanSensors {
+        self.sensors.read()
+    }
+
+    /// How often `FanSensors` is checked for changes to signal, in
+    /// milliseconds. `0` stops the signal.
+    #[zbus(property)]
+    async fn sensor_interval(&self) -> u64 {
+        self.config.lock().await.sensors.interval
+    }
+
+    async fn set_sensor_interval(
+        &self,
+        interval: u64,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetFanCurves)
+            .await?;
+        if interval != 0 && interval < MIN_SENSOR_INTERVAL {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "SensorInterval must be 0 or at least {MIN_SENSOR_INTERVAL}ms"
+            )));
+        }
+        let mut config = self.config.lock().await;
+        config.sensors.interval = interval;
+        config.write();
+        drop(config);
+        self.sensor_interval_changed(&ctxt).await?;
+        Ok(())
+    }
 }
 
 impl crate::ZbusRun for CtrlFanCurveZbus {
@@ -234,7 +613,32 @@ impl CtrlTask for CtrlFanCurveZbus {
         FAN_CURVE_ZBUS_PATH
     }
 
-    async fn create_tasks(&self, _signal_ctxt: SignalContext<'static>) -> Result<(), RogError> {
+    async fn create_tasks(&self, signal_ctxt: SignalContext<'static>) -> Result<(), RogError> {
+        tokio::spawn(run_fan_sensors_signal(self.clone(), signal_ctxt));
+        if let Some(soft) = &self.soft {
+            // The engine follows the throttle policy itself
+            tokio::spawn(run_soft_fan_curves(
+                soft.clone(),
+                self.config.clone(),
+                self.platform.clone(),
+            ));
+            return Ok(());
+        }
+
+        // A user profile can bring its own curves for the active policy
+        let ctrl = self.clone();
+        tokio::spawn(async move {
+            let mut user_profile = subscribe_user_profile();
+            while user_profile.changed().await.is_ok() {
+                if let Ok(active) = ctrl.platform.get_throttle_thermal_policy() {
+                    let config = ctrl.config.lock().await;
+                    ctrl.write_curves(&config, active.into())
+                        .map_err(|e| warn!("User profile fan curves: {e}"))
+                        .ok();
+                }
+            }
+        });
+
         let watch_throttle_thermal_policy = self.platform.monitor_throttle_thermal_policy()?;
         let platform = self.platform.clone();
         let config = self.config.clone();
@@ -249,15 +653,10 @@ impl CtrlTask for CtrlFanCurveZbus {
                         error!("get_throttle_thermal_policy error: {e}");
                     }) {
                         if profile != config.lock().await.current {
-                            fan_curves
-                                .lock()
-                                .await
-                                .profiles
-                                .write_profile_curve_to_platform(
-                                    profile.into(),
-                                    &mut find_fan_curve_node().unwrap(),
-                                )
-                                .map_err(|e| warn!("write_profile_curve_to_platform, {}", e))
+                            let curves = fan_curves.lock().await.curves_for(profile.into());
+                            find_fan_curve_node()
+                                .and_then(|device| write_curves_to_device(&curves, &device))
+                                .map_err(|e| warn!("write_curves_to_device, {}", e))
                                 .ok();
                             config.lock().await.current = profile;
                         }
@@ -274,11 +673,9 @@ impl crate::Reloadable for CtrlFanCurveZbus {
     /// Fetch the active profile and use that to set all related components up
     async fn reload(&mut self) -> Result<(), RogError> {
         let active = self.platform.get_throttle_thermal_policy()?.into();
-        let mut config = self.config.lock().await;
-        if let Ok(mut device) = find_fan_curve_node() {
-            config
-                .profiles
-                .write_profile_curve_to_platform(active, &mut device)?;
+        let config = self.config.lock().await;
+        if let Ok(device) = find_fan_curve_node() {
+            write_curves_to_device(&config.curves_for(active), &device)?;
         }
 
         Ok(())
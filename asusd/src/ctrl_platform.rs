@@ -1,21 +1,60 @@
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use chrono::{Datelike, Timelike};
 use config_traits::StdConfig;
 use log::{debug, error, info, warn};
-use rog_platform::cpu::{CPUControl, CPUGovernor, CPUEPP};
-use rog_platform::platform::{GpuMode, Properties, RogPlatform, ThrottlePolicy};
-use rog_platform::power::AsusPower;
+use rog_platform::cpu::{CPUControl, CPUGovernor, CoreType, CpuFreqState, CpuWrite, CPUEPP};
+use rog_platform::discovery::Discovery;
+use rog_platform::firmware_attributes::FirmwareAttribute;
+use rog_platform::governor::{CpuTimes, Governor, GovernorDecision};
+use rog_platform::hooks::{HookEvent, HookResult};
+use rog_platform::platform::{
+    GpuMode, PlatformTuning, PptLimits, Properties, RogPlatform, ThrottlePolicy,
+};
+use rog_platform::power::{AsusPower, BatteryInfo, BatteryStatus, ChargeRule};
+use rog_profiles::fan_sensors::default_cpu_sensors;
+use rog_profiles::soft_fan_curve::read_temp;
+use rog_profiles::user_profile::UserProfile;
+use tokio::sync::Notify;
 use zbus::export::futures_util::lock::Mutex;
 use zbus::fdo::Error as FdoErr;
+use zbus::message::Header;
 use zbus::{interface, Connection, SignalContext};
 
 use crate::config::Config;
 use crate::error::RogError;
-use crate::{task_watch_item, task_watch_item_notify, CtrlTask, ReloadAndNotify};
+use crate::hooks::{self, HookEnv};
+use crate::polkit::{Action, Authority};
+use crate::sys_events::SysEvents;
+use crate::{
+    config_dir, task_watch_item, task_watch_item_notify, throttle_policy, user_profiles, CtrlTask,
+    ReloadAndNotify,
+};
 
 const PLATFORM_ZBUS_PATH: &str = "/org/asuslinux";
+/// Where hook scripts are found in the config dir, in a subdirectory per event
+const HOOKS_DIR: &str = "hooks.d";
+/// The shortest time between governor checks, in milliseconds
+const MIN_GOVERNOR_INTERVAL: u64 = 500;
+
+fn power_event(power_plugged: bool) -> HookEvent {
+    if power_plugged {
+        HookEvent::Ac
+    } else {
+        HookEvent::Battery
+    }
+}
+
+/// The local weekday, with Monday as `0`, and minutes since midnight
+fn local_time_of_week() -> (u8, u16) {
+    let now = chrono::Local::now();
+    (
+        now.weekday().num_days_from_monday() as u8,
+        (now.hour() * 60 + now.minute()) as u16,
+    )
+}
 
 macro_rules! platform_get_value {
     ($self:ident, $property:tt, $prop_name:literal) => {
@@ -62,6 +101,13 @@ macro_rules! platform_ppt_set_value {
     ($self:ident, $property:tt, $prop_name:literal, $new_value:expr) => {
         concat_idents::concat_idents!(has = has_, $property {
             if $self.platform.has() {
+                let limit = $self.ppt_limits.$property;
+                if !limit.contains($new_value) {
+                    return Err(FdoErr::InvalidArgs(format!(
+                        "RogPlatform: {} {} is out of range, {limit}",
+                        $prop_name, $new_value
+                    )));
+                }
                 concat_idents::concat_idents!(set = set_, $property {
                     $self.platform.set($new_value).map_err(|err| {
                         error!("RogPlatform: {} {err}", $prop_name);
@@ -84,8 +130,19 @@ macro_rules! platform_ppt_set_value {
 pub struct CtrlPlatform {
     power: AsusPower,
     platform: RogPlatform,
+    ppt_limits: PptLimits,
     cpu_control: Option<CPUControl>,
     config: Arc<Mutex<Config>>,
+    last_hook_result: Arc<Mutex<HookResult>>,
+    /// How each CPU took the last EPP change
+    last_epp_result: Arc<Mutex<Vec<CpuWrite>>>,
+    governor_decision: Arc<Mutex<GovernorDecision>>,
+    signal_context: SignalContext<'static>,
+    /// Wakes the charge rule task to work out when the rules next change
+    charge_rules_changed: Arc<Notify>,
+    /// The policy the `ThrottlePolicy` hooks last ran for, so the policy
+    /// watcher doesn't run them again for changes asusd made
+    hooked_policy: Arc<Mutex<Option<ThrottlePolicy>>>,
 }
 
 impl CtrlPlatform {
@@ -105,13 +162,23 @@ impl CtrlPlatform {
         let config1 = config.clone();
         let config_path = config_path.to_owned();
 
+        let ppt_limits = platform.ppt_limits();
+        debug!("PPT limits:\n{ppt_limits}");
+
         let ret_self = CtrlPlatform {
             power,
+            ppt_limits,
             platform,
             config,
             cpu_control: CPUControl::new()
                 .map_err(|e| error!("Couldn't get CPU control sysfs: {e}"))
                 .ok(),
+            last_hook_result: Arc::new(Mutex::new(HookResult::default())),
+            last_epp_result: Arc::new(Mutex::new(Vec::new())),
+            governor_decision: Arc::new(Mutex::new(GovernorDecision::default())),
+            signal_context: signal_context.clone(),
+            charge_rules_changed: Arc::new(Notify::new()),
+            hooked_policy: Arc::new(Mutex::new(None)),
         };
         let mut inotify_self = ret_self.clone();
 
@@ -179,72 +246,173 @@ impl CtrlPlatform {
         Ok(())
     }
 
-    async fn run_ac_or_bat_cmd(&self, power_plugged: bool) {
-        let prog: Vec<String> = if power_plugged {
-            // AC ONLINE
-            self.config
-                .lock()
-                .await
-                .ac_command
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            // BATTERY
-            self.config
-                .lock()
-                .await
-                .bat_command
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect()
+    async fn hook_env(&self, event: HookEvent) -> HookEnv {
+        HookEnv {
+            event,
+            power_plugged: self.power_plugged(),
+            throttle_policy: self
+                .platform
+                .get_throttle_thermal_policy()
+                .map(|p| ThrottlePolicy::from(p).to_string())
+                .unwrap_or_default(),
+            charge_level: self.power.battery_info().capacity,
+            lid_closed: SysEvents::get().await.lid_closed(),
+        }
+    }
+
+    /// Run the hooks for `event` in the background. The result of each is
+    /// logged, and the last kept as `LastHookResult`.
+    async fn run_hooks(&self, event: HookEvent) {
+        let (mut commands, timeout) = {
+            let config = self.config.lock().await;
+            (
+                config.hook_commands(event),
+                Duration::from_secs(config.hook_timeout),
+            )
         };
-        if prog.len() > 1 {
-            let mut cmd = Command::new(&prog[0]);
-            for arg in prog.iter().skip(1) {
-                cmd.arg(arg);
-            }
-            if let Err(e) = cmd.spawn() {
-                if power_plugged {
-                    error!("AC power command error: {e}");
-                } else {
-                    error!("Battery power command error: {e}");
-                }
+        commands.extend(
+            hooks::hook_scripts(&config_dir().join(HOOKS_DIR), event)
+                .into_iter()
+                .map(|path| vec![path.to_string_lossy().to_string()]),
+        );
+        if commands.is_empty() {
+            return;
+        }
+
+        let env = self.hook_env(event).await;
+        let ctrl = self.clone();
+        tokio::spawn(async move {
+            let results =
+                tokio::task::spawn_blocking(move || hooks::run_hooks(&commands, &env, timeout))
+                    .await;
+            if let Some(last) = results.ok().and_then(|r| r.into_iter().last()) {
+                *ctrl.last_hook_result.lock().await = last;
+                ctrl.last_hook_result_changed(&ctrl.signal_context)
+                    .await
+                    .ok();
             }
+        });
+    }
+
+    /// Run the `ThrottlePolicy` hooks unless they already ran for `policy`
+    async fn run_policy_hooks(&self, policy: ThrottlePolicy) {
+        if self.hooked_policy.lock().await.replace(policy) != Some(policy) {
+            self.run_hooks(HookEvent::ThrottlePolicy).await;
         }
     }
 
-    fn check_and_set_epp(&self, enegy_pref: CPUEPP, change_epp: bool) {
+    /// Set the EPP of the performance cores to `enegy_pref` and of the
+    /// efficiency cores to `efficiency`, which is `enegy_pref` if `None`.
+    /// A core type which doesn't take its EPP, even after switching to the
+    /// powersave governor, gets `Default` instead. How each CPU took it is kept
+    /// in `LastEppResult`.
+    async fn check_and_set_epp(
+        &self,
+        enegy_pref: CPUEPP,
+        efficiency: Option<CPUEPP>,
+        change_epp: bool,
+    ) {
         if !change_epp {
             info!("ThrottlePolicy unlinked from EPP");
             return;
         }
         info!("ThrottlePolicy setting EPP");
-        if let Some(cpu) = self.cpu_control.as_ref() {
-            if let Ok(epp) = cpu.get_available_epp() {
-                debug!("Available EPP: {epp:?}");
-                if epp.contains(&enegy_pref) {
-                    debug!("Setting {enegy_pref:?}");
-                    cpu.set_epp(enegy_pref).ok();
-                } else if let Ok(gov) = cpu.get_governor() {
-                    if gov != CPUGovernor::Powersave {
-                        warn!("powersave governor is not is use, trying to set.");
-                        cpu.set_governor(CPUGovernor::Powersave)
-                            .map_err(|e| error!("couldn't set powersave: {e:?}"))
-                            .ok();
-                        if epp.contains(&enegy_pref) {
-                            debug!("Setting {enegy_pref:?}");
-                            cpu.set_epp(enegy_pref)
-                                .map_err(|e| error!("couldn't set EPP: {e:?}"))
-                                .ok();
+        let Some(cpu) = self.cpu_control.as_ref() else {
+            return;
+        };
+        let efficiency = efficiency.unwrap_or(enegy_pref);
+        let mut wanted = vec![(CoreType::Performance, enegy_pref)];
+        if cpu.is_hybrid() {
+            wanted.push((CoreType::Efficiency, efficiency));
+        }
+        let available_for = |core_type: CoreType| {
+            cpu.get_available_epp_for(core_type)
+                .map_err(|e| warn!("couldn't read the available EPP of {core_type:?} cores: {e}"))
+                .ok()
+        };
+        // Core types whose available EPP can't be read are left as they are
+        let mut wanted: Vec<_> = wanted
+            .into_iter()
+            .filter_map(|(core_type, epp)| Some((core_type, epp, available_for(core_type)?)))
+            .collect();
+        for (core_type, _, available) in &wanted {
+            debug!("Available EPP on {core_type:?} cores: {available:?}");
+        }
+        if wanted
+            .iter()
+            .any(|(_, epp, available)| !available.contains(epp))
+        {
+            if let Ok(gov) = cpu.get_governor() {
+                if gov != CPUGovernor::Powersave {
+                    warn!("powersave governor is not is use, trying to set.");
+                    match cpu.set_governor(CPUGovernor::Powersave) {
+                        Ok(writes) => {
+                            for write in writes.iter().filter(|w| !w.error.is_empty()) {
+                                warn!(
+                                    "couldn't set powersave on cpu{}: {}",
+                                    write.cpu, write.error
+                                );
+                            }
                         }
+                        Err(e) => error!("couldn't set powersave: {e:?}"),
                     }
+                    // The available EPP changes with the governor
+                    for (core_type, _, available) in &mut wanted {
+                        if let Some(now) = available_for(*core_type) {
+                            *available = now;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut writes = Vec::new();
+        for (core_type, mut epp, available) in wanted {
+            if !available.contains(&epp) {
+                if !available.contains(&CPUEPP::Default) {
+                    warn!("{epp:?} is not available on {core_type:?} cores, leaving them as is");
+                    continue;
                 }
+                warn!("{epp:?} is not available on {core_type:?} cores, using Default");
+                epp = CPUEPP::Default;
+            }
+            debug!("Setting {epp:?} on {core_type:?} cores");
+            match cpu.set_epp_for(core_type, epp) {
+                Ok(written) => writes.extend(written),
+                Err(e) => warn!("couldn't set EPP on {core_type:?} cores: {e}"),
             }
         }
+        if writes.is_empty() {
+            return;
+        }
+        let changed: Vec<u32> = writes.iter().filter(|w| w.changed).map(|w| w.cpu).collect();
+        info!("EPP changed on CPUs {changed:?}");
+        for write in writes.iter().filter(|w| !w.error.is_empty()) {
+            warn!("couldn't set EPP on cpu{}: {}", write.cpu, write.error);
+        }
+        *self.last_epp_result.lock().await = writes;
+        self.last_epp_result_changed(&self.signal_context)
+            .await
+            .ok();
+    }
+
+    /// The EPP for the efficiency cores with `throttle`, `None` to use the
+    /// EPP of the performance cores
+    async fn get_config_efficiency_epp_for_throttle(
+        &self,
+        throttle: ThrottlePolicy,
+    ) -> Option<CPUEPP> {
+        self.config
+            .lock()
+            .await
+            .throttle_efficiency_core_epp
+            .get(throttle)
     }
 
     async fn get_config_epp_for_throttle(&self, throttle: ThrottlePolicy) -> CPUEPP {
+        if let Some(epp) = user_profile_epp(throttle) {
+            return epp;
+        }
         match throttle {
             ThrottlePolicy::Balanced => self.config.lock().await.throttle_balanced_epp,
             ThrottlePolicy::Performance => self.config.lock().await.throttle_performance_epp,
@@ -252,19 +420,150 @@ impl CtrlPlatform {
         }
     }
 
-    async fn update_policy_ac_or_bat(&self, power_plugged: bool, change_epp: bool) {
-        if power_plugged && !self.config.lock().await.change_throttle_policy_on_ac {
-            debug!(
-                "Power status changed but set_throttle_policy_on_ac set false. Not setting the \
-                 thing"
-            );
+    /// The current power source, assumed to be AC if it can't be read
+    fn power_plugged(&self) -> bool {
+        self.power.get_online().map(|o| o > 0).unwrap_or(true)
+    }
+
+    /// Set the charge limit to what the charge policy says it should be now.
+    /// Returns true if the limit was changed.
+    async fn apply_charge_limit(&self) -> Result<bool, RogError> {
+        if !self.power.has_charge_control_end_threshold() {
+            return Ok(false);
+        }
+        let (weekday, minute) = local_time_of_week();
+        let limit = self.config.lock().await.charge_limit_at(weekday, minute);
+        if self.power.get_charge_control_end_threshold()? != limit {
+            info!("Setting charge_control_end_threshold to {limit}");
+            self.power.set_charge_control_end_threshold(limit)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Emit a change signal for each battery property which differs
+    async fn notify_battery_changes(
+        &self,
+        old: &BatteryInfo,
+        new: &BatteryInfo,
+        signal_ctxt: &SignalContext<'_>,
+    ) -> zbus::Result<()> {
+        if old.capacity != new.capacity {
+            self.battery_capacity_changed(signal_ctxt).await?;
+        }
+        if old.status != new.status {
+            self.battery_status_changed(signal_ctxt).await?;
+        }
+        if old.health() != new.health() {
+            self.battery_health_changed(signal_ctxt).await?;
+        }
+        if old.cycle_count != new.cycle_count {
+            self.battery_cycle_count_changed(signal_ctxt).await?;
+        }
+        if old.energy_full != new.energy_full {
+            self.battery_energy_full_changed(signal_ctxt).await?;
+        }
+        if old.energy_full_design != new.energy_full_design {
+            self.battery_energy_full_design_changed(signal_ctxt).await?;
+        }
+        if old.power_now != new.power_now {
+            self.battery_power_draw_changed(signal_ctxt).await?;
+        }
+        if old.time_to_empty != new.time_to_empty {
+            self.battery_time_to_empty_changed(signal_ctxt).await?;
+        }
+        Ok(())
+    }
+
+    /// A full charge lasts until power is unplugged, after which the charge
+    /// limit goes back to the usual policy
+    async fn charge_on_power_change(&self, power_plugged: bool, signal_ctxt: &SignalContext<'_>) {
+        if !power_plugged {
+            let mut config = self.config.lock().await;
+            if config.charge_full_until_unplugged {
+                info!("Power unplugged, ending full charge");
+                config.charge_full_until_unplugged = false;
+                config.write();
+                drop(config);
+                self.charge_full_until_unplugged_changed(signal_ctxt)
+                    .await
+                    .ok();
+            }
+        }
+        match self.apply_charge_limit().await {
+            Ok(true) => {
+                self.charge_control_end_threshold_changed(signal_ctxt)
+                    .await
+                    .ok();
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Couldn't set the charge limit: {e}"),
+        }
+    }
+
+    /// Apply the PPT and Nvidia tuning stored for the throttle policy, and
+    /// update the stored values so they are restored on reload. Unset,
+    /// unsupported, or out of range values are left alone.
+    async fn check_and_set_tuning(&self, throttle: ThrottlePolicy, power_plugged: bool) {
+        let mut tuning = self.config.lock().await.tuning_for(throttle, power_plugged);
+        if let Some(profile) = user_profiles::active_user_profile() {
+            if profile.throttle_policy == throttle {
+                tuning = profile.tuning.or(tuning);
+            }
+        }
+        if tuning.is_unset() {
             return;
         }
-        if !power_plugged && !self.config.lock().await.change_throttle_policy_on_battery {
+        info!("ThrottlePolicy {throttle:?} setting PPT tuning");
+
+        macro_rules! tuning_set {
+            ($property:tt) => {
+                concat_idents::concat_idents!(has = has_, $property {
+                    if let Some(value) = tuning.$property.filter(|_| self.platform.has()) {
+                        concat_idents::concat_idents!(set = set_, $property {
+                            let limit = self.ppt_limits.$property;
+                            if !limit.contains(value) {
+                                warn!(
+                                    "Tuning: {} {value} is out of range, {limit}",
+                                    stringify!($property),
+                                );
+                            } else if let Err(e) = self.platform.set(value) {
+                                warn!("Tuning: {} {e}", stringify!($property));
+                            } else {
+                                self.config.lock().await.$property = Some(value);
+                            }
+                        });
+                    }
+                })
+            };
+        }
+        tuning_set!(ppt_pl1_spl);
+        tuning_set!(ppt_pl2_sppt);
+        tuning_set!(ppt_fppt);
+        tuning_set!(ppt_apu_sppt);
+        tuning_set!(ppt_platform_sppt);
+        tuning_set!(nv_dynamic_boost);
+        tuning_set!(nv_temp_target);
+        self.config.lock().await.write();
+    }
+
+    async fn update_policy_ac_or_bat(&self, power_plugged: bool, change_epp: bool) {
+        let change_policy = if power_plugged {
+            self.config.lock().await.change_throttle_policy_on_ac
+        } else {
+            self.config.lock().await.change_throttle_policy_on_battery
+        };
+        if !change_policy {
             debug!(
-                "Power status changed but set_throttle_policy_on_battery set false. Not setting \
-                 the thing"
+                "Power status changed but set_throttle_policy_on_{} set false. Not setting the \
+                 thing",
+                if power_plugged { "ac" } else { "battery" }
             );
+            // The policy stays, but its tuning can differ on AC and battery
+            if let Ok(policy) = self.platform.get_throttle_thermal_policy() {
+                self.check_and_set_tuning(policy.into(), power_plugged)
+                    .await;
+            }
             return;
         }
 
@@ -273,15 +572,172 @@ impl CtrlPlatform {
         } else {
             self.config.lock().await.throttle_policy_on_battery
         };
+        self.clear_user_profile().await;
         debug!("Setting {throttle:?} before EPP");
         let epp = self.get_config_epp_for_throttle(throttle).await;
+        let efficiency = self.get_config_efficiency_epp_for_throttle(throttle).await;
         self.platform
             .set_throttle_thermal_policy(throttle.into())
             .ok();
-        self.check_and_set_epp(epp, change_epp);
+        throttle_policy::set_throttle_policy(throttle);
+        self.check_and_set_epp(epp, efficiency, change_epp).await;
+        self.check_and_set_tuning(throttle, power_plugged).await;
+        self.run_policy_hooks(throttle).await;
+    }
+
+    /// Set the throttle policy along with its EPP and tuning, then run the
+    /// `ThrottlePolicy` hooks
+    async fn apply_throttle_policy(&self, policy: ThrottlePolicy) -> Result<(), FdoErr> {
+        let change_epp = self.config.lock().await.throttle_policy_linked_epp
+            || user_profile_epp(policy).is_some();
+        let epp = self.get_config_epp_for_throttle(policy).await;
+        let efficiency = self.get_config_efficiency_epp_for_throttle(policy).await;
+        self.check_and_set_epp(epp, efficiency, change_epp).await;
+        self.platform
+            .set_throttle_thermal_policy(policy.into())
+            .map_err(|err| {
+                warn!("throttle_thermal_policy {}", err);
+                FdoErr::Failed(format!("RogPlatform: throttle_thermal_policy: {err}"))
+            })?;
+        throttle_policy::set_throttle_policy(policy);
+        self.check_and_set_tuning(policy, self.power_plugged())
+            .await;
+        self.run_policy_hooks(policy).await;
+        Ok(())
+    }
+
+    /// Make `profile` the active user profile and apply its throttle policy,
+    /// EPP and tuning. The fan curve and Aura controllers follow it to apply
+    /// the rest.
+    async fn apply_user_profile(&self, profile: UserProfile) -> Result<(), FdoErr> {
+        if !self.platform.has_throttle_thermal_policy() {
+            return Err(FdoErr::NotSupported(
+                "RogPlatform: throttle_thermal_policy not supported".to_owned(),
+            ));
+        }
+        info!(
+            "Activating user profile {} based on {:?}",
+            profile.name, profile.throttle_policy
+        );
+        let policy = profile.throttle_policy;
+        {
+            let mut config = self.config.lock().await;
+            config.active_user_profile.clone_from(&profile.name);
+            config.write();
+        }
+        user_profiles::set_active_user_profile(Some(profile));
+        self.apply_throttle_policy(policy).await?;
+        self.throttle_thermal_policy_changed(&self.signal_context)
+            .await
+            .ok();
+        self.active_user_profile_changed(&self.signal_context)
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// Stop using the active user profile, if there is one, because a throttle
+    /// policy was chosen directly
+    async fn clear_user_profile(&self) {
+        let mut config = self.config.lock().await;
+        if config.active_user_profile.is_empty() && user_profiles::active_user_profile().is_none() {
+            return;
+        }
+        info!("Leaving user profile {}", config.active_user_profile);
+        config.active_user_profile.clear();
+        config.write();
+        drop(config);
+        user_profiles::set_active_user_profile(None);
+        self.active_user_profile_changed(&self.signal_context)
+            .await
+            .ok();
+    }
+
+    /// Use a throttle policy chosen directly, leaving the active user profile
+    async fn set_throttle_policy(&self, policy: ThrottlePolicy) -> Result<(), FdoErr> {
+        // TODO: watch for external changes
+        if self.platform.has_throttle_thermal_policy() {
+            self.clear_user_profile().await;
+            self.apply_throttle_policy(policy).await
+        } else {
+            Err(FdoErr::NotSupported(
+                "RogPlatform: throttle_thermal_policy not supported".to_owned(),
+            ))
+        }
     }
 }
 
+/// Move between throttle policies with the CPU temperature and load while the
+/// governor is enabled and no user profile is active. The policy for the
+/// power source is the highest it will go.
+async fn run_governor(ctrl: CtrlPlatform) {
+    let discovery = Discovery::from_env();
+    let sensors: Vec<PathBuf> = default_cpu_sensors()
+        .iter()
+        .filter_map(|sensor| sensor.find_with(&discovery).ok())
+        .collect();
+    let stat = Path::new("/proc/stat");
+    let mut last_times = CpuTimes::read(stat).unwrap_or_default();
+    let mut governor: Option<Governor> = None;
+    loop {
+        let (config, ceiling) = {
+            let config = ctrl.config.lock().await;
+            let ceiling = if ctrl.power_plugged() {
+                config.throttle_policy_on_ac
+            } else {
+                config.throttle_policy_on_battery
+            };
+            (config.governor.clone(), ceiling)
+        };
+        tokio::time::sleep(Duration::from_millis(
+            config.interval.max(MIN_GOVERNOR_INTERVAL),
+        ))
+        .await;
+        let times = CpuTimes::read(stat)
+            .map_err(|e| warn!("Governor: {e}"))
+            .unwrap_or_default();
+        let load = times.load_since(&last_times);
+        last_times = times;
+        if !config.enabled || user_profiles::active_user_profile().is_some() {
+            governor = None;
+            continue;
+        }
+        let Ok(current) = ctrl.platform.get_throttle_thermal_policy() else {
+            continue;
+        };
+        let now = Instant::now();
+        let gov = governor.get_or_insert_with(|| Governor::new(current.into(), now));
+        gov.observe(current.into(), now);
+
+        let temp = sensors
+            .iter()
+            .filter_map(|path| read_temp(path).ok())
+            .max()
+            .unwrap_or_default();
+        if let Some(decision) = gov.decide(&config, temp, load, ceiling, now) {
+            info!("Governor: {decision}");
+            ctrl.apply_throttle_policy(decision.policy)
+                .await
+                .map_err(|e| warn!("Governor: {e}"))
+                .ok();
+            *ctrl.governor_decision.lock().await = decision;
+            ctrl.throttle_thermal_policy_changed(&ctrl.signal_context)
+                .await
+                .ok();
+            ctrl.governor_decision_changed(&ctrl.signal_context)
+                .await
+                .ok();
+        }
+    }
+}
+
+/// The EPP the active user profile sets for `policy`, if it sets one
+fn user_profile_epp(policy: ThrottlePolicy) -> Option<CPUEPP> {
+    user_profiles::active_user_profile()
+        .filter(|profile| profile.throttle_policy == policy && profile.epp != CPUEPP::Default)
+        .map(|profile| profile.epp)
+}
+
 #[interface(name = "org.asuslinux.Platform")]
 impl CtrlPlatform {
     #[zbus(property)]
@@ -344,17 +800,203 @@ impl CtrlPlatform {
         Ok(limit)
     }
 
-    #[zbus(property)]
-    async fn set_charge_control_end_threshold(&mut self, limit: u8) -> Result<(), FdoErr> {
+    /// Set the default charge limit. This is applied unless a full charge or
+    /// a charge rule is active, in which case it is applied once they end.
+    async fn set_charge_control_end_threshold(
+        &self,
+        limit: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetChargeLimit)
+            .await?;
         if !(20..=100).contains(&limit) {
             return Err(RogError::ChargeLimit(limit))?;
         }
-        self.power.set_charge_control_end_threshold(limit)?;
         self.config.lock().await.charge_control_end_threshold = limit;
         self.config.lock().await.write();
+        self.apply_charge_limit().await?;
+        self.charge_control_end_threshold_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Charge to 100% until the next time power is unplugged
+    #[zbus(property)]
+    async fn charge_full_until_unplugged(&self) -> bool {
+        self.config.lock().await.charge_full_until_unplugged
+    }
+
+    async fn set_charge_full_until_unplugged(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetChargeLimit)
+            .await?;
+        if !self.power.has_charge_control_end_threshold() {
+            return Err(FdoErr::NotSupported(
+                "RogPlatform: charge_control_end_threshold not supported".to_owned(),
+            ));
+        }
+        {
+            let mut config = self.config.lock().await;
+            config.charge_full_until_unplugged = enabled;
+            config.write();
+        }
+        if self.apply_charge_limit().await? {
+            self.charge_control_end_threshold_changed(&ctxt).await?;
+        }
+        self.charge_full_until_unplugged_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// The time of day charge limits, the first active rule is used in place
+    /// of the default limit
+    async fn charge_rules(&self) -> Vec<ChargeRule> {
+        self.config.lock().await.charge_rules.clone()
+    }
+
+    async fn set_charge_rules(
+        &self,
+        rules: Vec<ChargeRule>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetChargeLimit)
+            .await?;
+        if !self.power.has_charge_control_end_threshold() {
+            return Err(FdoErr::NotSupported(
+                "RogPlatform: charge_control_end_threshold not supported".to_owned(),
+            ));
+        }
+        for rule in &rules {
+            rule.validate()?;
+        }
+        {
+            let mut config = self.config.lock().await;
+            config.charge_rules = rules;
+            config.write();
+        }
+        self.charge_rules_changed.notify_one();
+        if self.apply_charge_limit().await? {
+            self.charge_control_end_threshold_changed(&ctxt).await?;
+        }
+        Ok(())
+    }
+
+    /// The result of the last hook run, see `asusd::hooks`
+    #[zbus(property)]
+    async fn last_hook_result(&self) -> HookResult {
+        self.last_hook_result.lock().await.clone()
+    }
+
+    /// How each CPU took the last EPP change, and which were changed
+    #[zbus(property)]
+    async fn last_epp_result(&self) -> Vec<CpuWrite> {
+        self.last_epp_result.lock().await.clone()
+    }
+
+    /// The governor, EPP, and core type of each online CPU
+    fn cpu_freq_states(&self) -> Result<Vec<CpuFreqState>, FdoErr> {
+        self.cpu_control
+            .as_ref()
+            .map(|cpu| cpu.states())
+            .ok_or_else(|| FdoErr::NotSupported("RogPlatform: CPU control not supported".into()))
+    }
+
+    /// The EPP for the efficiency cores of hybrid CPUs with `policy`, empty
+    /// if they use the EPP of the performance cores
+    async fn efficiency_core_epp(&self, policy: ThrottlePolicy) -> Vec<CPUEPP> {
+        self.get_config_efficiency_epp_for_throttle(policy)
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    /// Set the EPP for the efficiency cores with `policy`, applied now if
+    /// `policy` is active and linked to EPP. Empty to use the EPP of the
+    /// performance cores.
+    async fn set_efficiency_core_epp(
+        &self,
+        policy: ThrottlePolicy,
+        epp: Vec<CPUEPP>,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        let epp = match epp[..] {
+            [] => None,
+            [epp] => Some(epp),
+            _ => return Err(FdoErr::InvalidArgs("Expected at most one EPP".to_owned())),
+        };
+        let change_epp = {
+            let mut config = self.config.lock().await;
+            *config.throttle_efficiency_core_epp.get_mut(policy) = epp;
+            config.write();
+            config.throttle_policy_linked_epp
+        };
+        if self.platform.has_throttle_thermal_policy() {
+            let active: ThrottlePolicy = self.platform.get_throttle_thermal_policy()?.into();
+            if active == policy {
+                let performance = self.get_config_epp_for_throttle(policy).await;
+                self.check_and_set_epp(performance, epp, change_epp).await;
+            }
+        }
         Ok(())
     }
 
+    /// Battery charge in percent
+    #[zbus(property)]
+    fn battery_capacity(&self) -> u8 {
+        self.power.battery_info().capacity
+    }
+
+    #[zbus(property)]
+    fn battery_status(&self) -> BatteryStatus {
+        self.power.battery_info().status
+    }
+
+    /// How much of the design capacity the battery still holds, in percent,
+    /// or `0` if unknown
+    #[zbus(property)]
+    fn battery_health(&self) -> u8 {
+        self.power.battery_info().health()
+    }
+
+    #[zbus(property)]
+    fn battery_cycle_count(&self) -> u32 {
+        self.power.battery_info().cycle_count
+    }
+
+    /// Energy of a full charge in mWh
+    #[zbus(property)]
+    fn battery_energy_full(&self) -> u32 {
+        self.power.battery_info().energy_full
+    }
+
+    /// Energy of a full charge when new in mWh
+    #[zbus(property)]
+    fn battery_energy_full_design(&self) -> u32 {
+        self.power.battery_info().energy_full_design
+    }
+
+    /// Power draw, or charge rate when charging, in mW
+    #[zbus(property)]
+    fn battery_power_draw(&self) -> u32 {
+        self.power.battery_info().power_now
+    }
+
+    /// Minutes until empty, `0` unless discharging
+    #[zbus(property)]
+    fn battery_time_to_empty(&self) -> u32 {
+        self.power.battery_info().time_to_empty
+    }
+
     #[zbus(property)]
     fn gpu_mux_mode(&self) -> Result<u8, FdoErr> {
         self.platform.get_gpu_mux_mode().map_err(|err| {
@@ -363,8 +1005,15 @@ impl CtrlPlatform {
         })
     }
 
-    #[zbus(property)]
-    async fn set_gpu_mux_mode(&mut self, mode: u8) -> Result<(), FdoErr> {
+    async fn set_gpu_mux_mode(
+        &self,
+        mode: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetGpuMux)
+            .await?;
         if self.platform.has_gpu_mux_mode() {
             self.set_gfx_mode(mode.into()).map_err(|err| {
                 warn!("set_gpu_mux_mode {}", err);
@@ -376,30 +1025,41 @@ impl CtrlPlatform {
                 "RogPlatform: set_gpu_mux_mode not supported".to_owned(),
             ));
         }
+        self.gpu_mux_mode_changed(&ctxt).await?;
         Ok(())
     }
 
     /// Toggle to next platform_profile. Names provided by `Profiles`.
     /// If fan-curves are supported will also activate a fan curve for profile.
+    ///
+    /// If `UserProfileOrder` is set the user profiles in it are cycled
+    /// through instead.
     async fn next_throttle_thermal_policy(
-        &mut self,
+        &self,
         #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        #[zbus(header)] header: Header<'_>,
     ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        let next_profile = {
+            let config = self.config.lock().await;
+            config
+                .next_user_profile(&config.active_user_profile)
+                .cloned()
+        };
+        if let Some(profile) = next_profile {
+            return self.apply_user_profile(profile).await;
+        }
+
         let policy: ThrottlePolicy =
             platform_get_value!(self, throttle_thermal_policy, "throttle_thermal_policy")
                 .map(|n| n.into())?;
         let policy = ThrottlePolicy::next(policy);
 
         if self.platform.has_throttle_thermal_policy() {
-            let change_epp = self.config.lock().await.throttle_policy_linked_epp;
-            let epp = self.get_config_epp_for_throttle(policy).await;
-            self.check_and_set_epp(epp, change_epp);
-            self.platform
-                .set_throttle_thermal_policy(policy.into())
-                .map_err(|err| {
-                    warn!("throttle_thermal_policy {}", err);
-                    FdoErr::Failed(format!("RogPlatform: throttle_thermal_policy: {err}"))
-                })?;
+            self.clear_user_profile().await;
+            self.apply_throttle_policy(policy).await?;
             Ok(self.throttle_thermal_policy_changed(&ctxt).await?)
         } else {
             Err(FdoErr::NotSupported(
@@ -414,25 +1074,18 @@ impl CtrlPlatform {
             .map(|n| n.into())
     }
 
-    #[zbus(property)]
-    async fn set_throttle_thermal_policy(&mut self, policy: ThrottlePolicy) -> Result<(), FdoErr> {
-        // TODO: watch for external changes
-        if self.platform.has_throttle_thermal_policy() {
-            let change_epp = self.config.lock().await.throttle_policy_linked_epp;
-            let epp = self.get_config_epp_for_throttle(policy).await;
-            self.check_and_set_epp(epp, change_epp);
-            self.config.lock().await.write();
-            self.platform
-                .set_throttle_thermal_policy(policy.into())
-                .map_err(|err| {
-                    warn!("throttle_thermal_policy {}", err);
-                    FdoErr::Failed(format!("RogPlatform: throttle_thermal_policy: {err}"))
-                })
-        } else {
-            Err(FdoErr::NotSupported(
-                "RogPlatform: throttle_thermal_policy not supported".to_owned(),
-            ))
-        }
+    async fn set_throttle_thermal_policy(
+        &self,
+        policy: ThrottlePolicy,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        self.set_throttle_policy(policy).await?;
+        self.throttle_thermal_policy_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -440,10 +1093,18 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_policy_linked_epp)
     }
 
-    #[zbus(property)]
-    async fn set_throttle_policy_linked_epp(&self, linked: bool) -> Result<(), zbus::Error> {
+    async fn set_throttle_policy_linked_epp(
+        &self,
+        linked: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), zbus::Error> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         self.config.lock().await.throttle_policy_linked_epp = linked;
         self.config.lock().await.write();
+        self.throttle_policy_linked_epp_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -452,14 +1113,19 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_policy_on_battery)
     }
 
-    #[zbus(property)]
     async fn set_throttle_policy_on_battery(
-        &mut self,
+        &self,
         policy: ThrottlePolicy,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
     ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         self.config.lock().await.throttle_policy_on_battery = policy;
-        self.set_throttle_thermal_policy(policy).await?;
+        self.set_throttle_policy(policy).await?;
         self.config.lock().await.write();
+        self.throttle_policy_on_battery_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -468,10 +1134,19 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.change_throttle_policy_on_battery)
     }
 
-    #[zbus(property)]
-    async fn set_change_throttle_policy_on_battery(&mut self, change: bool) -> Result<(), FdoErr> {
+    async fn set_change_throttle_policy_on_battery(
+        &self,
+        change: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         self.config.lock().await.change_throttle_policy_on_battery = change;
         self.config.lock().await.write();
+        self.change_throttle_policy_on_battery_changed(&ctxt)
+            .await?;
         Ok(())
     }
 
@@ -480,11 +1155,19 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_policy_on_ac)
     }
 
-    #[zbus(property)]
-    async fn set_throttle_policy_on_ac(&mut self, policy: ThrottlePolicy) -> Result<(), FdoErr> {
+    async fn set_throttle_policy_on_ac(
+        &self,
+        policy: ThrottlePolicy,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         self.config.lock().await.throttle_policy_on_ac = policy;
-        self.set_throttle_thermal_policy(policy).await?;
+        self.set_throttle_policy(policy).await?;
         self.config.lock().await.write();
+        self.throttle_policy_on_ac_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -493,13 +1176,310 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.change_throttle_policy_on_ac)
     }
 
-    #[zbus(property)]
-    async fn set_change_throttle_policy_on_ac(&mut self, change: bool) -> Result<(), FdoErr> {
+    async fn set_change_throttle_policy_on_ac(
+        &self,
+        change: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         self.config.lock().await.change_throttle_policy_on_ac = change;
         self.config.lock().await.write();
+        self.change_throttle_policy_on_ac_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Get the PPT and Nvidia tuning stored for a throttle policy, as a dict
+    /// of the values which are set
+    async fn throttle_policy_tuning(
+        &self,
+        policy: ThrottlePolicy,
+        on_battery: bool,
+    ) -> PlatformTuning {
+        let config = self.config.lock().await;
+        if on_battery {
+            config.throttle_tunings_battery.get(policy)
+        } else {
+            config.throttle_tunings_ac.get(policy)
+        }
+    }
+
+    /// Store the PPT and Nvidia tuning for a throttle policy, to be applied
+    /// each time the policy is activated on AC, or battery if `on_battery`.
+    /// Applied immediately if the policy and power source are active.
+    async fn set_throttle_policy_tuning(
+        &self,
+        policy: ThrottlePolicy,
+        on_battery: bool,
+        tuning: PlatformTuning,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
+        if let Some((attr, value)) = self.ppt_limits.check_tuning(&tuning) {
+            let limit = self.ppt_limits.get(attr).unwrap_or_default();
+            return Err(FdoErr::InvalidArgs(format!(
+                "RogPlatform: {attr} {value} is out of range, {limit}"
+            )));
+        }
+        {
+            let mut config = self.config.lock().await;
+            if on_battery {
+                *config.throttle_tunings_battery.get_mut(policy) = tuning;
+            } else {
+                *config.throttle_tunings_ac.get_mut(policy) = tuning;
+            }
+            config.write();
+        }
+
+        if self.platform.has_throttle_thermal_policy() {
+            let active: ThrottlePolicy = self.platform.get_throttle_thermal_policy()?.into();
+            let power_plugged = self.power_plugged();
+            if active == policy && power_plugged != on_battery {
+                self.check_and_set_tuning(policy, power_plugged).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// The user profiles. Each is a named throttle policy which can have its
+    /// own fan curves, tuning and EPP, and set an Aura mode.
+    async fn user_profiles(&self) -> Vec<UserProfile> {
+        self.config.lock().await.user_profiles.clone()
+    }
+
+    /// Add a user profile, or replace the one with the same name. It is
+    /// applied again if it is active.
+    async fn set_user_profile(
+        &self,
+        mut profile: UserProfile,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
+        profile.check()?;
+        if let Some((attr, value)) = self.ppt_limits.check_tuning(&profile.tuning) {
+            let limit = self.ppt_limits.get(attr).unwrap_or_default();
+            return Err(FdoErr::InvalidArgs(format!(
+                "RogPlatform: {attr} {value} is out of range, {limit}"
+            )));
+        }
+        let active = {
+            let mut config = self.config.lock().await;
+            match config
+                .user_profiles
+                .iter_mut()
+                .find(|p| p.name == profile.name)
+            {
+                Some(old) => *old = profile.clone(),
+                None => config.user_profiles.push(profile.clone()),
+            }
+            config.write();
+            config.active_user_profile == profile.name
+        };
+        if active {
+            self.apply_user_profile(profile).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a user profile, and take it out of `UserProfileOrder`. If it is
+    /// active the throttle policy it was based on stays in use.
+    async fn remove_user_profile(
+        &self,
+        name: String,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
+        let active = {
+            let mut config = self.config.lock().await;
+            if config.user_profile(&name).is_none() {
+                return Err(FdoErr::InvalidArgs(format!(
+                    "RogPlatform: no user profile named {name}"
+                )));
+            }
+            config.user_profiles.retain(|p| p.name != name);
+            config.user_profile_order.retain(|n| *n != name);
+            config.write();
+            config.active_user_profile == name
+        };
+        if active {
+            self.clear_user_profile().await;
+        }
+        self.user_profile_order_changed(&self.signal_context)
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// Activate a user profile by name, setting its throttle policy, fan
+    /// curves, tuning, EPP and Aura mode
+    async fn activate_user_profile(
+        &self,
+        name: String,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        let profile = self
+            .config
+            .lock()
+            .await
+            .user_profile(&name)
+            .cloned()
+            .ok_or_else(|| {
+                FdoErr::InvalidArgs(format!("RogPlatform: no user profile named {name}"))
+            })?;
+        self.apply_user_profile(profile).await
+    }
+
+    /// The name of the active user profile, empty if a throttle policy was
+    /// chosen directly
+    #[zbus(property)]
+    async fn active_user_profile(&self) -> String {
+        self.config.lock().await.active_user_profile.clone()
+    }
+
+    /// The user profiles `NextThrottleThermalPolicy` cycles through, in
+    /// order. Empty to cycle the throttle policies.
+    #[zbus(property)]
+    async fn user_profile_order(&self) -> Vec<String> {
+        self.config.lock().await.user_profile_order.clone()
+    }
+
+    async fn set_user_profile_order(
+        &self,
+        order: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        let mut config = self.config.lock().await;
+        if let Some(name) = order.iter().find(|n| config.user_profile(n).is_none()) {
+            return Err(FdoErr::InvalidArgs(format!(
+                "RogPlatform: no user profile named {name}"
+            )));
+        }
+        config.user_profile_order = order;
+        config.write();
+        drop(config);
+        self.user_profile_order_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Move between throttle policies with the CPU temperature and load, up
+    /// to the policy set for the power source. The thresholds are in the
+    /// `governor` section of `asusd.ron`.
+    #[zbus(property)]
+    async fn governor_enabled(&self) -> bool {
+        self.config.lock().await.governor.enabled
+    }
+
+    async fn set_governor_enabled(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
+        if !self.platform.has_throttle_thermal_policy() {
+            return Err(FdoErr::NotSupported(
+                "RogPlatform: throttle_thermal_policy not supported".to_owned(),
+            ));
+        }
+        let mut config = self.config.lock().await;
+        config.governor.enabled = enabled;
+        config.write();
+        drop(config);
+        self.governor_enabled_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// The last policy change made by the governor, with the temperature and
+    /// load which caused it
+    #[zbus(property)]
+    async fn governor_decision(&self) -> GovernorDecision {
+        self.governor_decision.lock().await.clone()
+    }
+
+    /// All of the asus-armoury firmware attributes along with their type and
+    /// constraints. Empty if the kernel doesn't provide them.
+    async fn firmware_attributes(&self) -> Vec<FirmwareAttribute> {
+        self.platform
+            .firmware_attributes()
+            .map(|attrs| attrs.list())
+            .unwrap_or_default()
+    }
+
+    async fn firmware_attribute(&self, name: String) -> Result<FirmwareAttribute, FdoErr> {
+        let attrs = self
+            .platform
+            .firmware_attributes()
+            .ok_or_else(|| FdoErr::NotSupported("No firmware attributes".to_owned()))?;
+        attrs
+            .get(&name)
+            .map_err(|e| FdoErr::InvalidArgs(format!("{name}: {e}")))
+    }
+
+    /// Set a firmware attribute. The value is checked against the attribute
+    /// constraints. Attributes which are also named properties, such as the
+    /// PPT values, are stored so they are restored on reload.
+    async fn set_firmware_attribute(
+        &self,
+        name: String,
+        value: String,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::for_firmware_attribute(&name))
+            .await?;
+        let attrs = self
+            .platform
+            .firmware_attributes()
+            .ok_or_else(|| FdoErr::NotSupported("No firmware attributes".to_owned()))?;
+        if !attrs.has(&name) {
+            return Err(FdoErr::InvalidArgs(format!("{name}: not found")));
+        }
+        attrs.set(&name, &value)?;
+
+        if let Ok(value) = value.trim().parse::<u8>() {
+            let mut config = self.config.lock().await;
+            let stored = match name.as_str() {
+                "ppt_pl1_spl" => Some(&mut config.ppt_pl1_spl),
+                "ppt_pl2_sppt" => Some(&mut config.ppt_pl2_sppt),
+                "ppt_fppt" => Some(&mut config.ppt_fppt),
+                "ppt_apu_sppt" => Some(&mut config.ppt_apu_sppt),
+                "ppt_platform_sppt" => Some(&mut config.ppt_platform_sppt),
+                "nv_dynamic_boost" => Some(&mut config.nv_dynamic_boost),
+                "nv_temp_target" => Some(&mut config.nv_temp_target),
+                _ => None,
+            };
+            if let Some(stored) = stored {
+                *stored = Some(value);
+                config.write();
+            }
+        }
         Ok(())
     }
 
+    /// The min, max, and default of each PPT and Nvidia attribute. Values
+    /// outside of these are rejected.
+    #[zbus(property)]
+    async fn ppt_limits(&self) -> PptLimits {
+        self.ppt_limits
+    }
+
     /// The energy_performance_preference for the quiet throttle/platform
     /// profile
     #[zbus(property)]
@@ -507,12 +1487,23 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_quiet_epp)
     }
 
-    #[zbus(property)]
-    async fn set_throttle_quiet_epp(&mut self, epp: CPUEPP) -> Result<(), FdoErr> {
+    async fn set_throttle_quiet_epp(
+        &self,
+        epp: CPUEPP,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         let change_pp = self.config.lock().await.throttle_policy_linked_epp;
         self.config.lock().await.throttle_quiet_epp = epp;
-        self.check_and_set_epp(epp, change_pp);
+        let efficiency = self
+            .get_config_efficiency_epp_for_throttle(ThrottlePolicy::Quiet)
+            .await;
+        self.check_and_set_epp(epp, efficiency, change_pp).await;
         self.config.lock().await.write();
+        self.throttle_quiet_epp_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -523,12 +1514,23 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_balanced_epp)
     }
 
-    #[zbus(property)]
-    async fn set_throttle_balanced_epp(&mut self, epp: CPUEPP) -> Result<(), FdoErr> {
+    async fn set_throttle_balanced_epp(
+        &self,
+        epp: CPUEPP,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         let change_pp = self.config.lock().await.throttle_policy_linked_epp;
         self.config.lock().await.throttle_balanced_epp = epp;
-        self.check_and_set_epp(epp, change_pp);
+        let efficiency = self
+            .get_config_efficiency_epp_for_throttle(ThrottlePolicy::Balanced)
+            .await;
+        self.check_and_set_epp(epp, efficiency, change_pp).await;
         self.config.lock().await.write();
+        self.throttle_balanced_epp_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -539,12 +1541,23 @@ impl CtrlPlatform {
         Ok(self.config.lock().await.throttle_performance_epp)
     }
 
-    #[zbus(property)]
-    async fn set_throttle_performance_epp(&mut self, epp: CPUEPP) -> Result<(), FdoErr> {
+    async fn set_throttle_performance_epp(
+        &self,
+        epp: CPUEPP,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetThrottlePolicy)
+            .await?;
         let change_pp = self.config.lock().await.throttle_policy_linked_epp;
         self.config.lock().await.throttle_performance_epp = epp;
-        self.check_and_set_epp(epp, change_pp);
+        let efficiency = self
+            .get_config_efficiency_epp_for_throttle(ThrottlePolicy::Performance)
+            .await;
+        self.check_and_set_epp(epp, efficiency, change_pp).await;
         self.config.lock().await.write();
+        self.throttle_performance_epp_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -555,10 +1568,18 @@ impl CtrlPlatform {
         platform_get_value!(self, panel_od, "panel_od")
     }
 
-    #[zbus(property)]
-    async fn set_panel_od(&mut self, overdrive: bool) -> Result<(), FdoErr> {
+    async fn set_panel_od(
+        &self,
+        overdrive: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetFirmwareSettings)
+            .await?;
         platform_set_value!(self, panel_od, "panel_od", overdrive)?;
         self.config.lock().await.write();
+        self.panel_od_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -569,10 +1590,18 @@ impl CtrlPlatform {
         platform_get_value!(self, boot_sound, "boot_sound")
     }
 
-    #[zbus(property)]
-    async fn set_boot_sound(&mut self, on: bool) -> Result<(), FdoErr> {
+    async fn set_boot_sound(
+        &self,
+        on: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetFirmwareSettings)
+            .await?;
         platform_set_value!(self, boot_sound, "boot_sound", on)?;
         self.config.lock().await.write();
+        self.boot_sound_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -583,10 +1612,18 @@ impl CtrlPlatform {
         platform_get_value!(self, mini_led_mode, "mini_led_mode")
     }
 
-    #[zbus(property)]
-    async fn set_mini_led_mode(&mut self, on: bool) -> Result<(), FdoErr> {
+    async fn set_mini_led_mode(
+        &self,
+        on: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetFirmwareSettings)
+            .await?;
         platform_set_value!(self, mini_led_mode, "mini_led_mode", on)?;
         self.config.lock().await.write();
+        self.mini_led_mode_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -609,10 +1646,18 @@ impl CtrlPlatform {
         platform_get_value!(self, ppt_pl1_spl, "ppt_pl1_spl")
     }
 
-    #[zbus(property)]
-    async fn set_ppt_pl1_spl(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_ppt_pl1_spl(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, ppt_pl1_spl, "ppt_pl1_spl", value)?;
         self.config.lock().await.write();
+        self.ppt_pl1_spl_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -624,10 +1669,18 @@ impl CtrlPlatform {
         platform_get_value!(self, ppt_pl2_sppt, "ppt_pl2_sppt")
     }
 
-    #[zbus(property)]
-    async fn set_ppt_pl2_sppt(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_ppt_pl2_sppt(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, ppt_pl2_sppt, "ppt_pl2_sppt", value)?;
         self.config.lock().await.write();
+        self.ppt_pl2_sppt_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -638,10 +1691,18 @@ impl CtrlPlatform {
         platform_get_value!(self, ppt_fppt, "ppt_fppt")
     }
 
-    #[zbus(property)]
-    async fn set_ppt_fppt(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_ppt_fppt(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, ppt_fppt, "ppt_fppt", value)?;
         self.config.lock().await.write();
+        self.ppt_fppt_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -652,10 +1713,18 @@ impl CtrlPlatform {
         platform_get_value!(self, ppt_apu_sppt, "ppt_apu_sppt")
     }
 
-    #[zbus(property)]
-    async fn set_ppt_apu_sppt(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_ppt_apu_sppt(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, ppt_apu_sppt, "ppt_apu_sppt", value)?;
         self.config.lock().await.write();
+        self.ppt_apu_sppt_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -666,10 +1735,18 @@ impl CtrlPlatform {
         platform_get_value!(self, ppt_platform_sppt, "ppt_platform_sppt")
     }
 
-    #[zbus(property)]
-    async fn set_ppt_platform_sppt(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_ppt_platform_sppt(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, ppt_platform_sppt, "ppt_platform_sppt", value)?;
         self.config.lock().await.write();
+        self.ppt_platform_sppt_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -680,10 +1757,18 @@ impl CtrlPlatform {
         platform_get_value!(self, nv_dynamic_boost, "nv_dynamic_boost")
     }
 
-    #[zbus(property)]
-    async fn set_nv_dynamic_boost(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_nv_dynamic_boost(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, nv_dynamic_boost, "nv_dynamic_boost", value)?;
         self.config.lock().await.write();
+        self.nv_dynamic_boost_changed(&ctxt).await?;
         Ok(())
     }
 
@@ -694,10 +1779,18 @@ impl CtrlPlatform {
         platform_get_value!(self, nv_temp_target, "nv_temp_target")
     }
 
-    #[zbus(property)]
-    async fn set_nv_temp_target(&mut self, value: u8) -> Result<(), FdoErr> {
+    async fn set_nv_temp_target(
+        &self,
+        value: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), FdoErr> {
+        Authority::get()
+            .authorize(&header, Action::SetPowerLimits)
+            .await?;
         platform_ppt_set_value!(self, nv_temp_target, "nv_temp_target", value)?;
         self.config.lock().await.write();
+        self.nv_temp_target_changed(&ctxt).await?;
         Ok(())
     }
 }
@@ -722,11 +1815,15 @@ impl ReloadAndNotify for CtrlPlatform {
             info!("asusd.ron updated externally, reloading and updating internal copy");
 
             if self.power.has_charge_control_end_threshold() {
-                let limit = data.charge_control_end_threshold;
-                warn!("setting charge_control_end_threshold to {limit}");
-                self.power.set_charge_control_end_threshold(limit)?;
-                self.charge_control_end_threshold_changed(signal_context)
-                    .await?;
+                self.charge_rules_changed.notify_one();
+                let (weekday, minute) = local_time_of_week();
+                let limit = data.charge_limit_at(weekday, minute);
+                if self.power.get_charge_control_end_threshold()? != limit {
+                    warn!("setting charge_control_end_threshold to {limit}");
+                    self.power.set_charge_control_end_threshold(limit)?;
+                    self.charge_control_end_threshold_changed(signal_context)
+                        .await?;
+                }
             }
 
             if self.platform.has_throttle_thermal_policy()
@@ -757,11 +1854,21 @@ impl ReloadAndNotify for CtrlPlatform {
                 ($property:tt, $prop_name:literal) => {
                     concat_idents::concat_idents!(has = has_, $property {
                         if self.platform.has() && config.$property != data.$property {
-                            concat_idents::concat_idents!(set = set_, $property {
-                            self.platform
-                                .set(data.$property.unwrap_or_default())?;});
-                            concat_idents::concat_idents!(changed = $property, _changed {
-                            self.changed(signal_context).await?;});
+                            // A removed value is left as the device is now
+                            if let Some(value) = data.$property {
+                                let limit = self.ppt_limits.$property;
+                                if !limit.contains(value) {
+                                    warn!(
+                                        "Reload: {} {value} is out of range, {limit}",
+                                        $prop_name
+                                    );
+                                } else {
+                                    concat_idents::concat_idents!(set = set_, $property {
+                                    self.platform.set(value)?;});
+                                    concat_idents::concat_idents!(changed = $property, _changed {
+                                    self.changed(signal_context).await?;});
+                                }
+                            }
                         }
                     })
                 }
@@ -785,9 +1892,8 @@ impl crate::Reloadable for CtrlPlatform {
     async fn reload(&mut self) -> Result<(), RogError> {
         info!("Begin Platform settings restore");
         if self.power.has_charge_control_end_threshold() {
-            let limit = self.config.lock().await.charge_control_end_threshold;
-            info!("reloading charge_control_end_threshold to {limit}");
-            self.power.set_charge_control_end_threshold(limit)?;
+            info!("reloading charge_control_end_threshold");
+            self.apply_charge_limit().await?;
         } else {
             warn!("No charge_control_end_threshold found")
         }
@@ -812,9 +1918,16 @@ impl crate::Reloadable for CtrlPlatform {
             ($property:tt, $prop_name:literal) => {
                 concat_idents::concat_idents!(has = has_, $property {
                     if self.platform.has() {
-                        concat_idents::concat_idents!(set = set_, $property {
-                        self.platform
-                            .set(self.config.lock().await.$property.unwrap_or_default())?;});
+                        // An unset value is left as the device booted with
+                        if let Some(value) = self.config.lock().await.$property {
+                            let limit = self.ppt_limits.$property;
+                            if !limit.contains(value) {
+                                warn!("Reload: {} {value} is out of range, {limit}", $prop_name);
+                            } else {
+                                concat_idents::concat_idents!(set = set_, $property {
+                                self.platform.set(value)?;});
+                            }
+                        }
                     }
                 })
             }
@@ -829,12 +1942,21 @@ impl crate::Reloadable for CtrlPlatform {
 
         if let Ok(power_plugged) = self.power.get_online() {
             self.config.lock().await.last_power_plugged = power_plugged;
-            if self.platform.has_throttle_thermal_policy() {
+            let user_profile = {
+                let config = self.config.lock().await;
+                config.user_profile(&config.active_user_profile).cloned()
+            };
+            if let Some(profile) = user_profile {
+                self.apply_user_profile(profile)
+                    .await
+                    .map_err(|e| warn!("User profile: {e}"))
+                    .ok();
+            } else if self.platform.has_throttle_thermal_policy() {
                 let change_epp = self.config.lock().await.throttle_policy_linked_epp;
                 self.update_policy_ac_or_bat(power_plugged > 0, change_epp)
                     .await;
             }
-            self.run_ac_or_bat_cmd(power_plugged > 0).await;
+            self.run_hooks(power_event(power_plugged > 0)).await;
         }
 
         Ok(())
@@ -846,7 +1968,51 @@ impl CtrlPlatform {
 
     task_watch_item!(mini_led_mode "mini_led_mode" platform);
 
-    task_watch_item!(charge_control_end_threshold "charge_control_end_threshold" power);
+    /// Save a limit written outside of asusd as the default, unless it is
+    /// the limit asusd set for a full charge or charge rule
+    async fn watch_charge_control_end_threshold(
+        &self,
+        signal_ctxt: SignalContext<'static>,
+    ) -> Result<(), RogError> {
+        use zbus::export::futures_util::StreamExt;
+
+        let ctrl = self.clone();
+        match self.power.monitor_charge_control_end_threshold() {
+            Ok(watch) => {
+                tokio::spawn(async move {
+                    let mut buffer = [0; 32];
+                    watch
+                        .into_event_stream(&mut buffer)
+                        .unwrap()
+                        .for_each(|_| async {
+                            if let Ok(value) = ctrl.power.get_charge_control_end_threshold() {
+                                let (weekday, minute) = local_time_of_week();
+                                let mut lock = ctrl.config.lock().await;
+                                if lock.charge_limit_at(weekday, minute) != value {
+                                    debug!(
+                                        "charge_control_end_threshold was changed to {value} \
+                                         externally"
+                                    );
+                                    lock.charge_control_end_threshold = value;
+                                    lock.write();
+                                }
+                                drop(lock);
+                                ctrl.charge_control_end_threshold_changed(&signal_ctxt)
+                                    .await
+                                    .ok();
+                            }
+                        })
+                        .await;
+                });
+            }
+            Err(e) => info!(
+                "inotify watch failed: {}. You can ignore this if your device does not support \
+                 the feature",
+                e
+            ),
+        }
+        Ok(())
+    }
 
     task_watch_item_notify!(boot_sound platform);
 
@@ -881,10 +2047,21 @@ impl CtrlTask for CtrlPlatform {
         let platform1 = self.clone();
         let platform2 = self.clone();
         let platform3 = self.clone();
+        let platform4 = self.clone();
+        let signal_ctxt1 = signal_ctxt.clone();
+        let signal_ctxt3 = signal_ctxt.clone();
         self.create_sys_event_tasks(
             move |sleeping| {
                 let platform1 = platform1.clone();
+                let signal_ctxt1 = signal_ctxt1.clone();
                 async move {
+                    platform1
+                        .run_hooks(if sleeping {
+                            HookEvent::Suspend
+                        } else {
+                            HookEvent::Resume
+                        })
+                        .await;
                     info!("RogPlatform reloading panel_od");
                     if !sleeping && platform1.platform.has_panel_od() {
                         platform1
@@ -906,15 +2083,17 @@ impl CtrlTask for CtrlPlatform {
                     //         .unwrap_or(100);
                     // } else
                     if !sleeping && platform1.power.has_charge_control_end_threshold() {
-                        platform1
-                            .power
-                            .set_charge_control_end_threshold(
-                                platform1.config.lock().await.charge_control_end_threshold,
-                            )
-                            .ok();
+                        platform1.apply_charge_limit().await.ok();
+                        // The rule timer doesn't count time asleep
+                        platform1.charge_rules_changed.notify_one();
                     }
                     if let Ok(power_plugged) = platform1.power.get_online() {
                         if platform1.config.lock().await.last_power_plugged != power_plugged {
+                            if !sleeping {
+                                platform1
+                                    .charge_on_power_change(power_plugged > 0, &signal_ctxt1)
+                                    .await;
+                            }
                             if !sleeping && platform1.platform.has_throttle_thermal_policy() {
                                 let change_epp =
                                     platform1.config.lock().await.throttle_policy_linked_epp;
@@ -923,7 +2102,7 @@ impl CtrlTask for CtrlPlatform {
                                     .await;
                             }
                             if !sleeping {
-                                platform1.run_ac_or_bat_cmd(power_plugged > 0).await;
+                                platform1.run_hooks(power_event(power_plugged > 0)).await;
                             }
                             platform1.config.lock().await.last_power_plugged = power_plugged;
                         }
@@ -947,21 +2126,34 @@ impl CtrlTask for CtrlPlatform {
                     }
                 }
             },
-            move |_lid_closed| {
+            move |lid_closed| {
+                let platform4 = platform4.clone();
                 // on lid change
-                async move {}
+                async move {
+                    platform4
+                        .run_hooks(if lid_closed {
+                            HookEvent::LidClosed
+                        } else {
+                            HookEvent::LidOpened
+                        })
+                        .await;
+                }
             },
             move |power_plugged| {
                 let platform3 = platform3.clone();
+                let signal_ctxt3 = signal_ctxt3.clone();
                 // power change
                 async move {
+                    platform3
+                        .charge_on_power_change(power_plugged, &signal_ctxt3)
+                        .await;
                     if platform3.platform.has_throttle_thermal_policy() {
                         let change_epp = platform3.config.lock().await.throttle_policy_linked_epp;
                         platform3
                             .update_policy_ac_or_bat(power_plugged, change_epp)
                             .await;
                     }
-                    platform3.run_ac_or_bat_cmd(power_plugged).await;
+                    platform3.run_hooks(power_event(power_plugged)).await;
                 }
             },
         )
@@ -974,6 +2166,65 @@ impl CtrlTask for CtrlPlatform {
         self.watch_charge_control_end_threshold(signal_ctxt.clone())
             .await?;
 
+        if self.power.has_charge_control_end_threshold() {
+            // Charge rules start and end with the time of day
+            let ctrl = self.clone();
+            let signal_ctxt = signal_ctxt.clone();
+            tokio::spawn(async move {
+                loop {
+                    // Sleep until the next rule starts or ends, or the rules change
+                    let minutes = {
+                        let (_, minute) = local_time_of_week();
+                        ctrl.config
+                            .lock()
+                            .await
+                            .charge_rules
+                            .iter()
+                            .map(|rule| rule.minutes_to_change(minute))
+                            .min()
+                    };
+                    if let Some(minutes) = minutes {
+                        let second = chrono::Local::now().second() as u64;
+                        let wait =
+                            Duration::from_secs((minutes as u64 * 60).saturating_sub(second));
+                        tokio::select! {
+                            _ = tokio::time::sleep(wait) => {}
+                            _ = ctrl.charge_rules_changed.notified() => continue,
+                        }
+                    } else {
+                        ctrl.charge_rules_changed.notified().await;
+                        continue;
+                    }
+                    match ctrl.apply_charge_limit().await {
+                        Ok(true) => {
+                            ctrl.charge_control_end_threshold_changed(&signal_ctxt)
+                                .await
+                                .ok();
+                        }
+                        Ok(false) => {}
+                        Err(e) => warn!("Couldn't set the charge limit: {e}"),
+                    }
+                }
+            });
+        }
+
+        {
+            // Woken by the power_supply udev events
+            let ctrl = self.clone();
+            let signal_ctxt = signal_ctxt.clone();
+            let mut battery = SysEvents::get().await.subscribe_battery();
+            tokio::spawn(async move {
+                let mut last = *battery.borrow_and_update();
+                while battery.changed().await.is_ok() {
+                    let info = *battery.borrow_and_update();
+                    ctrl.notify_battery_changes(&last, &info, &signal_ctxt)
+                        .await
+                        .ok();
+                    last = info;
+                }
+            });
+        }
+
         self.watch_dgpu_disable(signal_ctxt.clone()).await?;
         self.watch_egpu_enable(signal_ctxt.clone()).await?;
 
@@ -990,6 +2241,10 @@ impl CtrlTask for CtrlPlatform {
         self.watch_nv_dynamic_boost(signal_ctxt.clone()).await?;
         self.watch_nv_temp_target(signal_ctxt.clone()).await?;
 
+        if self.platform.has_throttle_thermal_policy() {
+            tokio::spawn(run_governor(self.clone()));
+        }
+
         let watch_throttle_thermal_policy = self.platform.monitor_throttle_thermal_policy()?;
         let ctrl = self.clone();
 
@@ -1008,9 +2263,21 @@ impl CtrlTask for CtrlPlatform {
                             error!("Platform: get_throttle_thermal_policy error: {e}");
                         })
                     {
+                        // Such as Fn+F5, which leaves the user profile like any other
+                        // directly chosen policy
+                        if user_profiles::active_user_profile()
+                            .is_some_and(|user| user.throttle_policy != profile)
+                        {
+                            ctrl.clear_user_profile().await;
+                        }
+                        throttle_policy::set_throttle_policy(profile);
                         let change_epp = ctrl.config.lock().await.throttle_policy_linked_epp;
                         let epp = ctrl.get_config_epp_for_throttle(profile).await;
-                        ctrl.check_and_set_epp(epp, change_epp);
+                        let efficiency = ctrl.get_config_efficiency_epp_for_throttle(profile).await;
+                        ctrl.check_and_set_epp(epp, efficiency, change_epp).await;
+                        ctrl.check_and_set_tuning(profile, ctrl.power_plugged())
+                            .await;
+                        ctrl.run_policy_hooks(profile).await;
                     }
                 }
             }
@@ -5,10 +5,12 @@ use log::warn;
 use rog_slash::usb::{pkt_set_mode, pkt_set_options};
 use rog_slash::{DeviceState, SlashMode};
 use zbus::export::futures_util::lock::Mutex;
+use zbus::message::Header;
 use zbus::{interface, Connection, SignalContext};
 
 use crate::ctrl_slash::CtrlSlash;
 use crate::error::RogError;
+use crate::polkit::{Action, Authority};
 
 pub const SLASH_ZBUS_NAME: &str = "Slash";
 pub const SLASH_ZBUS_PATH: &str = "/org/asuslinux";
@@ -33,8 +35,15 @@ impl CtrlSlashZbus {
     }
 
     /// Set enabled true or false
-    #[zbus(property)]
-    async fn set_enabled(&self, enabled: bool) {
+    async fn set_enabled(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut lock = self.0.lock().await;
         let brightness = if enabled && lock.config.slash_brightness == 0 {
             0x88
@@ -55,6 +64,9 @@ impl CtrlSlashZbus {
         lock.config.slash_enabled = enabled;
         lock.config.slash_brightness = brightness;
         lock.config.write();
+        drop(lock);
+        self.enabled_changed(&ctxt).await?;
+        Ok(())
     }
 
     /// Get brightness level
@@ -65,8 +77,15 @@ impl CtrlSlashZbus {
     }
 
     /// Set brightness level
-    #[zbus(property)]
-    async fn set_brightness(&self, brightness: u8) {
+    async fn set_brightness(
+        &self,
+        brightness: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut lock = self.0.lock().await;
         let enabled = brightness > 0;
         lock.node
@@ -83,6 +102,9 @@ impl CtrlSlashZbus {
         lock.config.slash_enabled = enabled;
         lock.config.slash_brightness = brightness;
         lock.config.write();
+        drop(lock);
+        self.brightness_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -92,8 +114,15 @@ impl CtrlSlashZbus {
     }
 
     /// Set interval between slash animations (0-255)
-    #[zbus(property)]
-    async fn set_interval(&self, interval: u8) {
+    async fn set_interval(
+        &self,
+        interval: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut lock = self.0.lock().await;
         lock.node
             .write_bytes(&pkt_set_options(
@@ -108,6 +137,9 @@ impl CtrlSlashZbus {
 
         lock.config.slash_interval = interval;
         lock.config.write();
+        drop(lock);
+        self.interval_changed(&ctxt).await?;
+        Ok(())
     }
 
     #[zbus(property)]
@@ -117,8 +149,15 @@ impl CtrlSlashZbus {
     }
 
     /// Set interval between slash animations (0-255)
-    #[zbus(property)]
-    async fn set_slash_mode(&self, slash_mode: SlashMode) {
+    async fn set_slash_mode(
+        &self,
+        slash_mode: SlashMode,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        Authority::get()
+            .authorize(&header, Action::SetLighting)
+            .await?;
         let mut lock = self.0.lock().await;
 
         let command_packets = pkt_set_mode(slash_mode);
@@ -138,6 +177,9 @@ impl CtrlSlashZbus {
 
         lock.config.slash_mode = slash_mode;
         lock.config.write();
+        drop(lock);
+        self.slash_mode_changed(&ctxt).await?;
+        Ok(())
     }
 
     /// Get the device state as stored by asusd
@@ -0,0 +1,282 @@
+//! Hooks are commands run by asusd when something happens, such as AC power
+//! being plugged in or the throttle policy changing. They come from the
+//! `hooks` list in the config, where each is an argv array run without a
+//! shell, and from the executable files in `hooks.d/<event>/` in the config
+//! dir, which are run in name order.
+//!
+//! Each hook is given the event and the current state through the
+//! environment, and is killed along with anything it started if it runs
+//! longer than the timeout. The exit status and stderr of every hook are
+//! logged.
+
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use rog_platform::hooks::{HookEvent, HookResult};
+use serde::{Deserialize, Serialize};
+
+/// How much of the end of stderr is kept in a `HookResult`
+const STDERR_MAX: usize = 1024;
+
+/// A command to run on an event
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct Hook {
+    pub event: HookEvent,
+    /// The program followed by its arguments
+    pub argv: Vec<String>,
+}
+
+/// The state passed to hooks as environment variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookEnv {
+    pub event: HookEvent,
+    pub power_plugged: bool,
+    pub throttle_policy: String,
+    /// Battery charge in percent
+    pub charge_level: u8,
+    pub lid_closed: bool,
+}
+
+impl HookEnv {
+    pub fn vars(&self) -> Vec<(&'static str, String)> {
+        let flag = |b: bool| if b { "1" } else { "0" }.to_owned();
+        vec![
+            ("ASUSD_EVENT", self.event.name().to_owned()),
+            ("ASUSD_POWER_PLUGGED", flag(self.power_plugged)),
+            ("ASUSD_THROTTLE_POLICY", self.throttle_policy.clone()),
+            ("ASUSD_CHARGE_LEVEL", self.charge_level.to_string()),
+            ("ASUSD_LID_CLOSED", flag(self.lid_closed)),
+        ]
+    }
+}
+
+/// The executable files in `<hooks_dir>/<event>/`, sorted by name
+pub fn hook_scripts(hooks_dir: &Path, event: HookEvent) -> Vec<PathBuf> {
+    let mut scripts: Vec<PathBuf> = hooks_dir
+        .join(event.name())
+        .read_dir()
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|path| {
+                    path.metadata()
+                        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    scripts.sort();
+    scripts
+}
+
+/// Run one hook, waiting at most `timeout` for it to finish
+pub fn run_hook(argv: &[String], env: &HookEnv, timeout: Duration) -> HookResult {
+    let mut result = HookResult {
+        event: env.event,
+        command: argv.join(" "),
+        exit_code: -1,
+        ..Default::default()
+    };
+    let Some((program, args)) = argv.split_first() else {
+        result.stderr = "empty command".to_owned();
+        return result;
+    };
+
+    let mut child = match Command::new(program)
+        .args(args)
+        .envs(env.vars())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        // In a group of its own, so anything it forks is killed with it
+        .process_group(0)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            result.stderr = e.to_string();
+            return result;
+        }
+    };
+
+    // Read on another thread so a hook can't block on a full pipe
+    let (tx, rx) = mpsc::channel();
+    if let Some(mut stderr) = child.stderr.take() {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            stderr.read_to_end(&mut buf).ok();
+            tx.send(buf).ok();
+        });
+    }
+
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                result.exit_code = status.code().unwrap_or(-1);
+                break;
+            }
+            Ok(None) if start.elapsed() >= timeout => {
+                // The hook leads its group, so the group ID is its PID
+                let group = Pid::from_raw(child.id() as i32);
+                if let Err(e) = killpg(group, Signal::SIGKILL) {
+                    warn!(
+                        "Could not kill the process group of {}: {e}",
+                        result.command
+                    );
+                    child.kill().ok();
+                }
+                child.wait().ok();
+                result.timed_out = true;
+                break;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => {
+                result.stderr = e.to_string();
+                return result;
+            }
+        }
+    }
+
+    // Anything the hook started in the background may still hold stderr open
+    if let Ok(buf) = rx.recv_timeout(Duration::from_millis(100)) {
+        let stderr = String::from_utf8_lossy(&buf);
+        let stderr = stderr.trim();
+        let mut from = stderr.len().saturating_sub(STDERR_MAX);
+        while !stderr.is_char_boundary(from) {
+            from += 1;
+        }
+        result.stderr = stderr[from..].to_owned();
+    }
+    result
+}
+
+/// Run each hook in turn, logging the results
+pub fn run_hooks(commands: &[Vec<String>], env: &HookEnv, timeout: Duration) -> Vec<HookResult> {
+    commands
+        .iter()
+        .map(|argv| {
+            let result = run_hook(argv, env, timeout);
+            if result.success() {
+                info!("{result}");
+            } else {
+                warn!("{result}");
+            }
+            result
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rog_platform::hooks::HookEvent;
+
+    use super::{hook_scripts, run_hook, HookEnv};
+
+    fn env() -> HookEnv {
+        HookEnv {
+            event: HookEvent::Battery,
+            power_plugged: false,
+            throttle_policy: "Quiet".to_owned(),
+            charge_level: 64,
+            lid_closed: false,
+        }
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_owned(), "-c".to_owned(), script.to_owned()]
+    }
+
+    #[test]
+    fn hook_result_and_env() {
+        let timeout = Duration::from_secs(5);
+        let script = concat!(
+            r#"test "$ASUSD_EVENT $ASUSD_POWER_PLUGGED $ASUSD_LID_CLOSED" = "battery 0 0" && "#,
+            r#"test "$ASUSD_THROTTLE_POLICY $ASUSD_CHARGE_LEVEL" = "Quiet 64""#
+        );
+        let result = run_hook(&sh(script), &env(), timeout);
+        assert!(result.success(), "{result}");
+
+        let result = run_hook(&sh("echo 'it broke' >&2; exit 3"), &env(), timeout);
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.stderr, "it broke");
+        assert!(!result.success());
+
+        // Arguments with spaces are kept whole
+        let result = run_hook(
+            &sh(r#"test "$0" = "a b""#)
+                .into_iter()
+                .chain(["a b".to_owned()])
+                .collect::<Vec<_>>(),
+            &env(),
+            timeout,
+        );
+        assert!(result.success(), "{result}");
+
+        let result = run_hook(&["/does/not/exist".to_owned()], &env(), timeout);
+        assert_eq!(result.exit_code, -1);
+        assert!(!result.stderr.is_empty());
+    }
+
+    #[test]
+    fn hook_timeout() {
+        let result = run_hook(&sh("sleep 10"), &env(), Duration::from_millis(100));
+        assert!(result.timed_out);
+        assert!(!result.success());
+    }
+
+    #[test]
+    fn hook_timeout_kills_background() {
+        // The PID of the background sleep comes back as stderr
+        let result = run_hook(
+            &sh("sleep 30 & echo $! >&2; wait"),
+            &env(),
+            Duration::from_millis(200),
+        );
+        assert!(result.timed_out);
+        let pid: u32 = result.stderr.parse().unwrap();
+        // Gone, or a zombie waiting on whatever it was reparented to
+        let running = || {
+            std::fs::read_to_string(format!("/proc/{pid}/stat"))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let start = Instant::now();
+        while running() && start.elapsed() < Duration::from_secs(2) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!running(), "sleep {pid} is still running");
+    }
+
+    #[test]
+    fn hook_scripts_sorted_and_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("asusd-hooks-{}", std::process::id()));
+        let battery = dir.join("battery");
+        std::fs::create_dir_all(&battery).unwrap();
+        for (name, mode) in [("20-second", 0o755), ("10-first", 0o755), ("README", 0o644)] {
+            let path = battery.join(name);
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        assert_eq!(
+            hook_scripts(&dir, HookEvent::Battery),
+            vec![battery.join("10-first"), battery.join("20-second")]
+        );
+        assert!(hook_scripts(&dir, HookEvent::Ac).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,29 +5,59 @@ pub mod config;
 pub mod ctrl_anime;
 /// Keyboard LED brightness control, RGB, and LED display modes
 pub mod ctrl_aura;
+/// CPU cores, boost, frequency limits, amd-pstate mode, and SMT per throttle
+/// policy
+pub mod ctrl_cpu;
 /// Control platform profiles + fan-curves if available
 pub mod ctrl_fancurves;
 /// Control ASUS bios function such as boot sound, Optimus/Dedicated gfx mode
 pub mod ctrl_platform;
 /// Control of Slash led bar
 pub mod ctrl_slash;
+/// Polkit authorization of the methods and properties which change something
+pub mod polkit;
+/// AC power and lid events shared by the controllers
+pub mod sys_events;
+/// The throttle policy in use, shared by the controllers
+pub mod throttle_policy;
+/// The active user profile shared by the controllers
+pub mod user_profiles;
 
 pub mod error;
+/// Commands run on power, throttle policy, sleep, and lid events
+pub mod hooks;
 
 use std::future::Future;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use config_traits::{StdConfig, StdConfigLoad1};
 use dmi_id::DMIID;
 use futures_lite::stream::StreamExt;
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
 use logind_zbus::manager::ManagerProxy;
-use tokio::time::sleep;
+use zbus::export::futures_util::lock::Mutex;
+use zbus::fdo::ObjectManager;
 use zbus::zvariant::ObjectPath;
 use zbus::{CacheProperties, Connection, SignalContext};
 
+use crate::config::Config;
+use crate::ctrl_anime::trait_impls::CtrlAnimeZbus;
+use crate::ctrl_anime::CtrlAnime;
+use crate::ctrl_aura::manager::AuraManager;
+use crate::ctrl_cpu::CtrlCpu;
+use crate::ctrl_fancurves::CtrlFanCurveZbus;
+use crate::ctrl_platform::CtrlPlatform;
+use crate::ctrl_slash::trait_impls::CtrlSlashZbus;
+use crate::ctrl_slash::CtrlSlash;
 use crate::error::RogError;
+use crate::polkit::Authority;
+use crate::sys_events::SysEvents;
 
 const CONFIG_PATH_BASE: &str = "/etc/asusd/";
+/// Set this to a directory to have all configs read from and written there
+/// instead of `/etc/asusd/`
+pub const CONFIG_DIR_ENV: &str = "ASUSD_CONFIG_DIR";
 pub static DBUS_NAME: &str = "org.asuslinux.Daemon";
 pub static DBUS_PATH: &str = "/org/asuslinux/Daemon";
 pub static DBUS_IFACE: &str = "org.asuslinux.Daemon";
@@ -128,6 +158,15 @@ macro_rules! task_watch_item_notify {
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The directory all daemon configs live in, `ASUSD_CONFIG_DIR` if set or
+/// else `/etc/asusd/`
+pub fn config_dir() -> PathBuf {
+    match std::env::var_os(CONFIG_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(CONFIG_PATH_BASE),
+    }
+}
+
 pub fn print_board_info() {
     let dmi = DMIID::new().unwrap_or_default();
     info!("Product family: {}", dmi.product_family);
@@ -198,7 +237,7 @@ pub trait CtrlTask {
     // }
 
     /// Free helper method to create tasks to run on: sleep, wake, shutdown,
-    /// boot
+    /// boot, and AC power or lid changes from the shared `SysEvents`
     ///
     /// The closures can potentially block, so execution time should be the
     /// minimal possible such as save a variable.
@@ -220,15 +259,41 @@ pub trait CtrlTask {
         Fut4: Future<Output = ()> + Send,
     {
         async {
-            let connection = Connection::system()
-                .await
-                .expect("Controller could not create dbus connection");
+            let events = SysEvents::get().await;
+            let mut power = events.subscribe_power();
+            tokio::spawn(async move {
+                while power.changed().await.is_ok() {
+                    let power_plugged = *power.borrow_and_update();
+                    debug!("Doing on_external_power_change({power_plugged})");
+                    on_external_power_change(power_plugged).await;
+                }
+            });
+
+            let mut lid = events.subscribe_lid();
+            tokio::spawn(async move {
+                while lid.changed().await.is_ok() {
+                    let lid_closed = *lid.borrow_and_update();
+                    debug!("Doing on_lid_change({lid_closed})");
+                    on_lid_change(lid_closed).await;
+                }
+            });
+
+            // Without logind sleep and shutdown are simply never triggered
+            let Some(connection) = sys_events::logind_connection() else {
+                return;
+            };
 
-            let manager = ManagerProxy::builder(&connection)
+            let manager = match ManagerProxy::builder(&connection)
                 .cache_properties(CacheProperties::No)
                 .build()
                 .await
-                .expect("Controller could not create ManagerProxy");
+            {
+                Ok(manager) => manager,
+                Err(e) => {
+                    warn!("Controller could not create ManagerProxy: {e}");
+                    return;
+                }
+            };
 
             let manager1 = manager.clone();
             tokio::spawn(async move {
@@ -243,9 +308,8 @@ pub trait CtrlTask {
                 }
             });
 
-            let manager2 = manager.clone();
             tokio::spawn(async move {
-                if let Ok(mut notif) = manager2.receive_prepare_for_sleep().await {
+                if let Ok(mut notif) = manager.receive_prepare_for_sleep().await {
                     while let Some(event) = notif.next().await {
                         // blocks thread :|
                         if let Ok(args) = event.args() {
@@ -255,35 +319,6 @@ pub trait CtrlTask {
                     }
                 }
             });
-
-            let manager3 = manager.clone();
-            tokio::spawn(async move {
-                let mut last_power = manager3.on_external_power().await.unwrap_or_default();
-
-                loop {
-                    if let Ok(next) = manager3.on_external_power().await {
-                        if next != last_power {
-                            last_power = next;
-                            on_external_power_change(next).await;
-                        }
-                    }
-                    sleep(Duration::from_secs(2)).await;
-                }
-            });
-
-            tokio::spawn(async move {
-                let mut last_lid = manager.lid_closed().await.unwrap_or_default();
-                // need to loop on these as they don't emit signals
-                loop {
-                    if let Ok(next) = manager.lid_closed().await {
-                        if next != last_lid {
-                            last_lid = next;
-                            on_lid_change(next).await;
-                        }
-                    }
-                    sleep(Duration::from_secs(2)).await;
-                }
-            });
         }
     }
 }
@@ -312,3 +347,124 @@ where
     zbus_clone.create_tasks(signal_ctx).await.ok();
     Ok(())
 }
+
+/// Create all the controllers and add them to `connection`. If the connection
+/// is to a message bus then `DBUS_NAME` is requested once everything is added,
+/// a peer to peer connection (as used in tests) has no names.
+///
+/// Devices are found through `rog_platform::discovery::Discovery::from_env()`
+/// and configs are kept in `config_dir()`, so the daemon can be pointed at a
+/// fake sysfs tree and a scratch config dir.
+///
+/// Changes are authorized with polkit when it runs on the bus, see
+/// `polkit::Authority`.
+pub async fn start_daemon(connection: &mut Connection) -> Result<(), RogError> {
+    // let supported = SupportedFunctions::get_supported();
+    print_board_info();
+    // println!("{:?}", supported.supported_functions());
+
+    Authority::start(connection).await;
+    sys_events::set_bus(connection);
+    connection.object_server().at("/", ObjectManager).await?;
+
+    let config = Config::new().load();
+    let cfg_path = config.file_path();
+    let config = Arc::new(Mutex::new(config));
+
+    // supported.add_to_server(&mut connection).await;
+
+    match CtrlFanCurveZbus::new() {
+        Ok(ctrl) => {
+            let sig_ctx = CtrlFanCurveZbus::signal_context(connection)?;
+            start_tasks(ctrl, connection, sig_ctx).await?;
+        }
+        Err(err) => {
+            error!("FanCurves: {}", err);
+        }
+    }
+
+    match CtrlPlatform::new(
+        config.clone(),
+        &cfg_path,
+        CtrlPlatform::signal_context(connection)?,
+    ) {
+        Ok(ctrl) => {
+            let sig_ctx = CtrlPlatform::signal_context(connection)?;
+            start_tasks(ctrl, connection, sig_ctx).await?;
+        }
+        Err(err) => {
+            error!("CtrlPlatform: {}", err);
+        }
+    }
+
+    match CtrlCpu::new() {
+        Ok(ctrl) => {
+            let sig_ctx = CtrlCpu::signal_context(connection)?;
+            start_tasks(ctrl, connection, sig_ctx).await?;
+        }
+        Err(err) => {
+            info!("CPU control: {}", err);
+        }
+    }
+
+    match CtrlAnime::new() {
+        Ok(ctrl) => {
+            let zbus = CtrlAnimeZbus(Arc::new(Mutex::new(ctrl)));
+            let sig_ctx = CtrlAnimeZbus::signal_context(connection)?;
+            start_tasks(zbus, connection, sig_ctx).await?;
+        }
+        Err(err) => {
+            info!("AniMe control: {}", err);
+        }
+    }
+
+    match CtrlSlash::new() {
+        Ok(ctrl) => {
+            let zbus = CtrlSlashZbus(Arc::new(Mutex::new(ctrl)));
+            // Currently, the Slash has no need for a loop watching power events, however,
+            // it could be cool to have the slash do some power-on/off animation
+            // (It has a built-in power on animation which plays when u plug in the power
+            // supply)
+            let sig_ctx = CtrlSlashZbus::signal_context(connection)?;
+            start_tasks(zbus, connection, sig_ctx).await?;
+        }
+        Err(err) => {
+            info!("Slash control: {}", err);
+        }
+    }
+
+    let _ = AuraManager::new(connection.clone()).await?;
+
+    // Request dbus name after finishing initalizing all functions
+    if connection.is_bus() {
+        connection.request_name(DBUS_NAME).await?;
+    }
+    Ok(())
+}
+
+/// Put anything asusd has taken control of back in the hands of the firmware.
+/// This is called when asusd exits, by `StopGuard` or the panic hook.
+pub fn stop_daemon() {
+    ctrl_fancurves::release_soft_fans();
+}
+
+/// Calls `stop_daemon()` when dropped, so it runs however the daemon's main
+/// loop ends, including by an error being returned
+pub struct StopGuard;
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        stop_daemon();
+    }
+}
+
+/// Call `stop_daemon()` after the default panic hook. A panic which aborts
+/// never drops a `StopGuard`, and a panic in a task may leave the daemon
+/// running without the task it needs, such as the software fan curves.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        stop_daemon();
+    }));
+}
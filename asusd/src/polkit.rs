@@ -0,0 +1,264 @@
+//! Polkit authorization of the D-Bus methods and properties which change
+//! something.
+//!
+//! Each kind of change is an [`Action`], with the defaults for who may do it in
+//! `data/asusd.policy`. Methods check the caller from the message header with
+//! [`Authority::authorize()`]. zbus doesn't give property setters the message,
+//! so the properties which need authorization are read-only and changed with a
+//! `Set<Property>` method instead.
+//!
+//! polkit only asks the user to authenticate if the call was sent with the
+//! `ALLOW_INTERACTIVE_AUTHORIZATION` flag, as the rog-dbus proxies do. Methods
+//! which are checked take `&self`, so the interface isn't locked while polkit
+//! waits on the user.
+//!
+//! The Aura and AniMe methods used to stream frames, as asusd-user does, are
+//! not checked so polkit isn't asked on every frame.
+//!
+//! Without polkit, such as on the session bus, a peer to peer connection, or if
+//! polkit isn't installed, anything the D-Bus policy lets through is allowed.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use log::{info, warn};
+use serde::Deserialize;
+use zbus::fdo::{DBusProxy, Error as FdoErr};
+use zbus::message::{Flags, Header};
+use zbus::names::BusName;
+use zbus::zvariant::{Type, Value};
+use zbus::{proxy, Connection};
+
+const POLKIT_NAME: &str = "org.freedesktop.PolicyKit1";
+/// `CheckAuthorization` flag to let polkit ask the user to authenticate
+const ALLOW_USER_INTERACTION: u32 = 1;
+
+static AUTHORITY: OnceLock<Authority> = OnceLock::new();
+
+/// A kind of change which needs authorization, one per action in
+/// `data/asusd.policy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Keyboard, AniMe, and Slash lighting
+    SetLighting,
+    /// Throttle policy, EPP, the policies used on AC and battery, and switching
+    /// user profiles
+    SetThrottlePolicy,
+    /// Charge limit and charge rules
+    SetChargeLimit,
+    /// Fan curves
+    SetFanCurves,
+    /// PPT and Nvidia limits, the per policy tunings, and user profiles
+    SetPowerLimits,
+    /// GPU MUX, dGPU disable, and eGPU
+    SetGpuMux,
+    /// Boot sound, panel overdrive, mini-LED, and other firmware attributes
+    SetFirmwareSettings,
+    /// CPU cores, boost, frequency limits, amd-pstate, and SMT
+    SetCpuTuning,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::SetLighting,
+        Action::SetThrottlePolicy,
+        Action::SetChargeLimit,
+        Action::SetFanCurves,
+        Action::SetPowerLimits,
+        Action::SetGpuMux,
+        Action::SetFirmwareSettings,
+        Action::SetCpuTuning,
+    ];
+
+    /// The polkit action id
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::SetLighting => "org.asuslinux.aura.set-lighting",
+            Action::SetThrottlePolicy => "org.asuslinux.platform.set-throttle-policy",
+            Action::SetChargeLimit => "org.asuslinux.platform.set-charge-limit",
+            Action::SetFanCurves => "org.asuslinux.platform.set-fan-curves",
+            Action::SetPowerLimits => "org.asuslinux.platform.set-power-limits",
+            Action::SetGpuMux => "org.asuslinux.platform.set-gpu-mux",
+            Action::SetFirmwareSettings => "org.asuslinux.platform.set-firmware-settings",
+            Action::SetCpuTuning => "org.asuslinux.cpu.set-cpu-tuning",
+        }
+    }
+
+    /// The action for changing the firmware attribute `name`, as some of them
+    /// are also PPT or GPU MUX settings
+    pub fn for_firmware_attribute(name: &str) -> Action {
+        match name {
+            "gpu_mux_mode" | "dgpu_disable" | "egpu_enable" => Action::SetGpuMux,
+            _ if name.starts_with("ppt_") || name.starts_with("nv_") => Action::SetPowerLimits,
+            _ => Action::SetFirmwareSettings,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Type)]
+struct AuthorizationResult {
+    is_authorized: bool,
+    is_challenge: bool,
+    _details: HashMap<String, String>,
+}
+
+#[proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+trait PolkitAuthority {
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: &HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<AuthorizationResult>;
+}
+
+pub struct Authority {
+    /// `None` if everything is allowed
+    polkit: Option<PolkitAuthorityProxy<'static>>,
+}
+
+impl Authority {
+    fn new(polkit: Option<PolkitAuthorityProxy<'static>>) -> Self {
+        Self { polkit }
+    }
+
+    /// The authority set up by `start()`, or one allowing everything if it
+    /// wasn't
+    pub fn get() -> &'static Authority {
+        AUTHORITY.get_or_init(|| Self::new(None))
+    }
+
+    /// Use polkit if `connection` is to a bus it runs on. This must be done
+    /// before any interfaces are added to `connection`.
+    pub async fn start(connection: &Connection) {
+        let polkit = if connection.is_bus() {
+            polkit_authority(connection).await
+        } else {
+            None
+        };
+        if AUTHORITY.set(Self::new(polkit)).is_err() {
+            warn!("polkit: authorization was already set up");
+        }
+    }
+
+    /// Check the caller of the method call with `header` is allowed `action`.
+    /// The user is only asked to authenticate if the caller allowed it.
+    pub async fn authorize(&self, header: &Header<'_>, action: Action) -> Result<(), FdoErr> {
+        let (Some(polkit), Some(sender)) = (&self.polkit, header.sender()) else {
+            return Ok(());
+        };
+        let interactive = header
+            .primary()
+            .flags()
+            .contains(Flags::AllowInteractiveAuth);
+        check(polkit, sender.as_str(), action, interactive).await
+    }
+}
+
+/// The polkit authority if polkit is running or can be activated
+async fn polkit_authority(connection: &Connection) -> Option<PolkitAuthorityProxy<'static>> {
+    let dbus = DBusProxy::new(connection).await.ok()?;
+    let name = BusName::try_from(POLKIT_NAME).ok()?;
+    let running = dbus.name_has_owner(name).await.unwrap_or(false);
+    let activatable = dbus
+        .list_activatable_names()
+        .await
+        .is_ok_and(|names| names.iter().any(|n| n.as_str() == POLKIT_NAME));
+    if !running && !activatable {
+        warn!("polkit is not available, only the D-Bus policy limits what can be changed");
+        return None;
+    }
+    PolkitAuthorityProxy::new(connection)
+        .await
+        .map_err(|e| warn!("polkit: could not create the authority proxy: {e}"))
+        .ok()
+}
+
+async fn check(
+    polkit: &PolkitAuthorityProxy<'_>,
+    sender: &str,
+    action: Action,
+    interactive: bool,
+) -> Result<(), FdoErr> {
+    let subject = (
+        "system-bus-name",
+        HashMap::from([("name", Value::from(sender))]),
+    );
+    let result = polkit
+        .check_authorization(
+            &subject,
+            action.id(),
+            &HashMap::new(),
+            if interactive {
+                ALLOW_USER_INTERACTION
+            } else {
+                0
+            },
+            "",
+        )
+        .await
+        .map_err(|e| {
+            warn!("polkit: checking {} for {sender}: {e}", action.id());
+            FdoErr::AccessDenied(format!("polkit: {e}"))
+        })?;
+    if result.is_authorized {
+        return Ok(());
+    }
+    info!("polkit: {sender} is not authorized for {}", action.id());
+    if result.is_challenge {
+        Err(FdoErr::AccessDenied(format!(
+            "{} needs authentication",
+            action.id()
+        )))
+    } else {
+        Err(FdoErr::AccessDenied(format!(
+            "{} is not allowed",
+            action.id()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Action;
+
+    #[test]
+    fn actions_are_defined() {
+        let policy = include_str!("../../data/asusd.policy");
+        let mut defined: Vec<&str> = policy
+            .split("<action id=\"")
+            .skip(1)
+            .filter_map(|s| s.split('"').next())
+            .collect();
+        defined.sort();
+        let mut ids: Vec<&str> = Action::ALL.iter().map(|a| a.id()).collect();
+        ids.sort();
+        assert_eq!(ids, defined);
+    }
+
+    #[test]
+    fn firmware_attribute_actions() {
+        assert_eq!(
+            Action::for_firmware_attribute("ppt_pl1_spl"),
+            Action::SetPowerLimits
+        );
+        assert_eq!(
+            Action::for_firmware_attribute("nv_temp_target"),
+            Action::SetPowerLimits
+        );
+        assert_eq!(
+            Action::for_firmware_attribute("gpu_mux_mode"),
+            Action::SetGpuMux
+        );
+        assert_eq!(
+            Action::for_firmware_attribute("boot_sound"),
+            Action::SetFirmwareSettings
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! A single source of AC power, battery and lid events shared by every
+//! controller.
+//!
+//! AC power and battery changes come from udev `power_supply` events, and lid
+//! changes from the logind `PropertiesChanged` signal. Polling is only used
+//! when those are unavailable, such as with a fake sysfs tree or if the signal
+//! can't be subscribed to.
+//!
+//! logind is reached on the daemon's own bus, set with [`set_bus`], and is not
+//! used at all when that is a private connection.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use futures_lite::stream::StreamExt;
+use log::{debug, info, warn};
+use logind_zbus::manager::ManagerProxy;
+use rog_platform::power::{AsusPower, BatteryInfo};
+use tokio::sync::{watch, OnceCell};
+use tokio::time::sleep;
+use zbus::fdo::PropertiesProxy;
+use zbus::{CacheProperties, Connection};
+
+const LOGIND_DEST: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_MANAGER: &str = "org.freedesktop.login1.Manager";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static SYS_EVENTS: OnceCell<SysEvents> = OnceCell::const_new();
+static BUS: OnceLock<Connection> = OnceLock::new();
+
+/// Use the daemon's `connection` to reach logind. This must be called before
+/// the first `SysEvents::get()`.
+pub fn set_bus(connection: &Connection) {
+    BUS.set(connection.clone()).ok();
+}
+
+/// The daemon's connection if it is on a message bus. There is no logind on a
+/// private connection, such as in tests.
+pub fn logind_connection() -> Option<Connection> {
+    BUS.get().filter(|connection| connection.is_bus()).cloned()
+}
+
+pub struct SysEvents {
+    power_plugged: Arc<watch::Sender<bool>>,
+    lid_closed: Arc<watch::Sender<bool>>,
+    battery: Arc<watch::Sender<BatteryInfo>>,
+}
+
+/// Update the value and wake the receivers only if it changed
+fn send_changed<T: PartialEq>(sender: &watch::Sender<T>, value: T) {
+    sender.send_if_modified(|old| {
+        let changed = *old != value;
+        *old = value;
+        changed
+    });
+}
+
+async fn logind_manager(connection: &Connection) -> Option<ManagerProxy<'static>> {
+    ManagerProxy::builder(connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .map_err(|e| warn!("SysEvents: could not create ManagerProxy: {e}"))
+        .ok()
+}
+
+impl SysEvents {
+    /// The shared event source, which is started on first use
+    pub async fn get() -> &'static SysEvents {
+        SYS_EVENTS.get_or_init(Self::start).await
+    }
+
+    pub fn power_plugged(&self) -> bool {
+        *self.power_plugged.borrow()
+    }
+
+    pub fn lid_closed(&self) -> bool {
+        *self.lid_closed.borrow()
+    }
+
+    /// Receive the AC power state each time it changes
+    pub fn subscribe_power(&self) -> watch::Receiver<bool> {
+        self.power_plugged.subscribe()
+    }
+
+    /// Receive the lid state each time it changes
+    pub fn subscribe_lid(&self) -> watch::Receiver<bool> {
+        self.lid_closed.subscribe()
+    }
+
+    /// Receive the battery state each time it changes. This never changes if
+    /// there is no ASUS battery.
+    pub fn subscribe_battery(&self) -> watch::Receiver<BatteryInfo> {
+        self.battery.subscribe()
+    }
+
+    async fn start() -> Self {
+        let power_plugged = Arc::new(watch::channel(true).0);
+        let lid_closed = Arc::new(watch::channel(false).0);
+        let battery = Arc::new(watch::channel(BatteryInfo::default()).0);
+
+        let asus_power = AsusPower::new()
+            .map_err(|e| warn!("SysEvents: {e}, falling back to logind for AC power"))
+            .ok();
+        let logind_power = asus_power.is_none();
+        if let Some(asus_power) = asus_power {
+            send_changed(
+                &power_plugged,
+                asus_power.get_online().map(|o| o > 0).unwrap_or(true),
+            );
+            send_changed(&battery, asus_power.battery_info());
+            let power_plugged = power_plugged.clone();
+            let battery = battery.clone();
+            tokio::task::spawn_blocking(move || {
+                asus_power.watch(
+                    |online| {
+                        debug!("SysEvents: power plugged {online}");
+                        send_changed(&power_plugged, online);
+                    },
+                    |info| send_changed(&battery, *info),
+                )
+            });
+        }
+
+        // Without logind the lid is never closed
+        let connection = logind_connection();
+        let manager = match &connection {
+            Some(connection) => logind_manager(connection).await,
+            None => None,
+        };
+        if let (Some(connection), Some(manager)) = (connection, manager) {
+            send_changed(&lid_closed, manager.lid_closed().await.unwrap_or_default());
+            if logind_power {
+                send_changed(
+                    &power_plugged,
+                    manager.on_external_power().await.unwrap_or(true),
+                );
+            }
+            Self::watch_logind(
+                connection,
+                manager,
+                lid_closed.clone(),
+                logind_power.then(|| power_plugged.clone()),
+            );
+        }
+
+        Self {
+            power_plugged,
+            lid_closed,
+            battery,
+        }
+    }
+
+    /// Follow `LidClosed`, and `OnExternalPower` if `power_plugged` is given,
+    /// from logind. Both are re-read on resume in case a change was missed
+    /// while sleeping.
+    fn watch_logind(
+        connection: Connection,
+        manager: ManagerProxy<'static>,
+        lid_closed: Arc<watch::Sender<bool>>,
+        power_plugged: Option<Arc<watch::Sender<bool>>>,
+    ) {
+        let refresh = {
+            let manager = manager.clone();
+            let lid_closed = lid_closed.clone();
+            let power_plugged = power_plugged.clone();
+            move || {
+                let manager = manager.clone();
+                let lid_closed = lid_closed.clone();
+                let power_plugged = power_plugged.clone();
+                async move {
+                    if let Ok(closed) = manager.lid_closed().await {
+                        send_changed(&lid_closed, closed);
+                    }
+                    if let Some(power_plugged) = &power_plugged {
+                        if let Ok(plugged) = manager.on_external_power().await {
+                            send_changed(power_plugged, plugged);
+                        }
+                    }
+                }
+            }
+        };
+
+        let on_wake = refresh.clone();
+        let manager1 = manager.clone();
+        tokio::spawn(async move {
+            if let Ok(mut notif) = manager1.receive_prepare_for_sleep().await {
+                while let Some(event) = notif.next().await {
+                    if matches!(event.args(), Ok(args) if !args.start) {
+                        on_wake().await;
+                    }
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let changes = match PropertiesProxy::builder(&connection)
+                .destination(LOGIND_DEST)
+                .and_then(|b| b.path(LOGIND_PATH))
+            {
+                Ok(builder) => match builder.build().await {
+                    Ok(proxy) => proxy.receive_properties_changed().await.ok(),
+                    Err(_) => None,
+                },
+                Err(_) => None,
+            };
+
+            let Some(mut changes) = changes else {
+                info!("SysEvents: logind PropertiesChanged unavailable, polling instead");
+                loop {
+                    refresh().await;
+                    sleep(POLL_INTERVAL).await;
+                }
+            };
+
+            while let Some(signal) = changes.next().await {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if args.interface_name.as_str() != LOGIND_MANAGER {
+                    continue;
+                }
+                let changed = |name: &str| {
+                    args.changed_properties
+                        .get(name)
+                        .and_then(|v| bool::try_from(v).ok())
+                };
+                let invalidated = |name: &str| args.invalidated_properties.contains(&name);
+
+                match changed("LidClosed") {
+                    Some(closed) => send_changed(&lid_closed, closed),
+                    None if invalidated("LidClosed") => refresh().await,
+                    None => {}
+                }
+                if let Some(power_plugged) = &power_plugged {
+                    match changed("OnExternalPower") {
+                        Some(plugged) => send_changed(power_plugged, plugged),
+                        None if invalidated("OnExternalPower") => refresh().await,
+                        None => {}
+                    }
+                }
+            }
+        });
+    }
+}
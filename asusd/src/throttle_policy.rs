@@ -0,0 +1,30 @@
+//! The throttle policy in use, shared by every controller which applies
+//! settings per policy. The Platform controller sends it each time it sets the
+//! policy or sees it changed, such as with Fn+F5, so the others don't need
+//! their own watch on `throttle_thermal_policy`.
+
+use std::sync::OnceLock;
+
+use rog_platform::platform::ThrottlePolicy;
+use tokio::sync::watch;
+
+static THROTTLE_POLICY: OnceLock<watch::Sender<Option<ThrottlePolicy>>> = OnceLock::new();
+
+fn sender() -> &'static watch::Sender<Option<ThrottlePolicy>> {
+    THROTTLE_POLICY.get_or_init(|| watch::channel(None).0)
+}
+
+/// Change the throttle policy in use, waking the receivers only if it changed
+pub fn set_throttle_policy(policy: ThrottlePolicy) {
+    sender().send_if_modified(|old| {
+        let changed = *old != Some(policy);
+        *old = Some(policy);
+        changed
+    });
+}
+
+/// Receive the throttle policy each time it changes, `None` until the
+/// Platform controller first sets or sees one
+pub fn subscribe_throttle_policy() -> watch::Receiver<Option<ThrottlePolicy>> {
+    sender().subscribe()
+}
@@ -0,0 +1,34 @@
+//! The active user profile, shared by every controller which applies part of
+//! it. The Platform controller decides which profile is active, the fan curve
+//! and Aura controllers follow it.
+
+use std::sync::OnceLock;
+
+use rog_profiles::user_profile::UserProfile;
+use tokio::sync::watch;
+
+static ACTIVE_USER_PROFILE: OnceLock<watch::Sender<Option<UserProfile>>> = OnceLock::new();
+
+fn sender() -> &'static watch::Sender<Option<UserProfile>> {
+    ACTIVE_USER_PROFILE.get_or_init(|| watch::channel(None).0)
+}
+
+/// The active user profile, `None` if the plain throttle policy settings are
+/// in use
+pub fn active_user_profile() -> Option<UserProfile> {
+    sender().borrow().clone()
+}
+
+/// Change the active user profile, waking the receivers only if it changed
+pub fn set_active_user_profile(profile: Option<UserProfile>) {
+    sender().send_if_modified(|old| {
+        let changed = *old != profile;
+        *old = profile;
+        changed
+    });
+}
+
+/// Receive the active user profile each time it changes
+pub fn subscribe_user_profile() -> watch::Receiver<Option<UserProfile>> {
+    sender().subscribe()
+}
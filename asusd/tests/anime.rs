@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_anime::usb::{pkt_set_brightness, pkt_set_enable_display, Brightness};
+    use rog_dbus::zbus_anime::AnimeProxy;
+
+    use crate::common::{next, start};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga402_anime_over_dbus() {
+        let daemon = start("ga402").await;
+        let proxy = AnimeProxy::new(&daemon.client).await.unwrap();
+
+        // Without a USB device the hidraw endpoint is used
+        assert!(!daemon.dev_bytes("hidraw0").is_empty());
+        assert_eq!(proxy.brightness().await.unwrap(), Brightness::Med);
+
+        let mut changed = proxy.receive_brightness_changed().await;
+        let before = daemon.dev_bytes("hidraw0").len();
+        proxy.set_brightness(Brightness::High).await.unwrap();
+        loop {
+            if next(&mut changed).await.get().await.unwrap() == Brightness::High {
+                break;
+            }
+        }
+
+        let written = daemon.dev_bytes("hidraw0");
+        assert_eq!(
+            written[before..],
+            [
+                pkt_set_brightness(Brightness::High).as_slice(),
+                pkt_set_enable_display(true).as_slice()
+            ]
+            .concat()
+        );
+        assert!(daemon
+            .config("anime.ron")
+            .contains("display_brightness: High"));
+    }
+}
@@ -0,0 +1,52 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_aura::usb::{LED_APPLY, LED_SET};
+    use rog_aura::{AuraDeviceType, LedBrightness};
+    use rog_dbus::zbus_aura::AuraProxy;
+
+    use crate::common::start;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_aura_over_dbus() {
+        let daemon = start("gu604").await;
+        let proxy = AuraProxy::builder(&daemon.client)
+            .path("/org/asuslinux/19b6")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            proxy.device_type().await.unwrap(),
+            AuraDeviceType::LaptopPost2021
+        );
+        // Reload always finishes by setting the power states
+        let written = daemon.dev_bytes("hidraw1");
+        assert!(written.ends_with(&[LED_SET.as_slice(), LED_APPLY.as_slice()].concat()));
+        assert!(daemon.config_dir.join("aura_19b6.ron").exists());
+
+        proxy.set_brightness(LedBrightness::High).await.unwrap();
+        assert_eq!(
+            daemon.read("class/leds/asus::kbd_backlight/brightness"),
+            "3"
+        );
+        assert_eq!(proxy.brightness().await.unwrap(), LedBrightness::High);
+
+        let mut packet = vec![0u8; 17];
+        packet[0] = 0x5d;
+        packet[1] = 0xb3;
+        packet[4] = 0xff;
+        let before = daemon.dev_bytes("hidraw1").len();
+        proxy
+            .direct_addressing_raw(vec![packet.clone()])
+            .await
+            .unwrap();
+        let written = daemon.dev_bytes("hidraw1");
+        assert_eq!(
+            written[before..],
+            [packet.as_slice(), LED_SET.as_slice()].concat()
+        );
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use asusd::ctrl_aura::config::AuraConfig;
+    use config_traits::StdConfig;
+    use rog_aura::effects::{AdvancedEffects, Effect, Static};
+    use rog_aura::keyboard::{KeyLayout, LedCode};
+    use rog_aura::{AuraModeNum, Colour};
+    use rog_dbus::zbus_aura::AuraProxy;
+
+    use crate::common::{contains, start};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_advanced_effects_in_daemon() {
+        let daemon = start("gu604").await;
+        let proxy = AuraProxy::builder(&daemon.client)
+            .path("/org/asuslinux/19b6")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(!proxy.advanced_effects_enabled().await.unwrap());
+        assert_eq!(proxy.advanced_effects_fps().await.unwrap(), 30);
+        assert!(proxy.set_advanced_effects_fps(0).await.is_err());
+        // Nothing to run yet
+        assert!(proxy.set_advanced_effects_enabled(true).await.is_err());
+
+        let mut effects = AdvancedEffects::new(false);
+        effects.push(Effect::Static(Static::new(
+            LedCode::F,
+            Colour {
+                r: 255,
+                g: 127,
+                b: 0,
+            },
+        )));
+        let mut config = AuraConfig::new("19b6");
+        config.read();
+        config.advanced_effects = effects.clone();
+        config.write();
+
+        proxy.set_advanced_effects_fps(60).await.unwrap();
+        proxy.set_advanced_effects_enabled(true).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        effects.next_state(&KeyLayout::default_layout());
+        let frame = effects.create_packets().concat();
+        assert!(contains(&daemon.dev_bytes("hidraw1"), &frame));
+
+        // Frames from a user session hold off the effects
+        let mut packet = vec![0u8; 64];
+        packet[0] = 0x5d;
+        packet[1] = 0xbc;
+        proxy
+            .direct_addressing_raw(vec![packet.clone()])
+            .await
+            .unwrap();
+        let held = daemon.dev_bytes("hidraw1").len();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(daemon.dev_bytes("hidraw1").len(), held);
+        // and the effects take over again once they stop
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let written = daemon.dev_bytes("hidraw1");
+        assert!(contains(&written[held..], &frame));
+
+        // A built-in mode stops them
+        proxy.set_led_mode(AuraModeNum::Static).await.unwrap();
+        assert!(!proxy.advanced_effects_enabled().await.unwrap());
+        assert!(daemon
+            .config("aura_19b6.ron")
+            .contains("advanced_effects_enabled: false"));
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use asusd::ctrl_aura::config::AuraConfig;
+    use config_traits::StdConfig;
+    use rog_aura::effects::{AdvancedEffects, Effect, Reactive};
+    use rog_aura::keyboard::{KeyLayout, LedCode};
+    use rog_aura::{Colour, Speed};
+    use rog_dbus::zbus_aura::AuraProxy;
+    use rog_platform::test_util::input_event;
+
+    use crate::common::{contains, start};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_reactive_effects_read_keyboard() {
+        let daemon = start("gu604").await;
+        let proxy = AuraProxy::builder(&daemon.client)
+            .path("/org/asuslinux/19b6")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let colour = Colour {
+            r: 0,
+            g: 255,
+            b: 127,
+        };
+        let mut effects = AdvancedEffects::new(false);
+        effects.push(Effect::Reactive(Reactive::new(colour, Speed::Med)));
+        let mut config = AuraConfig::new("19b6");
+        config.read();
+        config.advanced_effects = effects.clone();
+        config.write();
+
+        // KEY_A pressed and released
+        let events = [input_event(1, 30, 1), input_event(1, 30, 0)].concat();
+        std::fs::write(daemon.root.join("dev/input/event4"), events).unwrap();
+
+        proxy.set_advanced_effects_enabled(true).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        for led in LedCode::from_evdev(30) {
+            effects.trigger(*led);
+        }
+        effects.next_state(&KeyLayout::default_layout());
+        let frame = effects.create_packets().concat();
+        assert!(contains(&daemon.dev_bytes("hidraw1"), &frame));
+    }
+}
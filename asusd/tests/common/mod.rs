@@ -0,0 +1,110 @@
+// Each test binary uses only some of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use asusd::CONFIG_DIR_ENV;
+use rog_platform::discovery::SYSFS_ROOT_ENV;
+use rog_platform::test_util::copy_fixture;
+use zbus::connection::Builder;
+use zbus::export::futures_util::{try_join, Stream, StreamExt};
+use zbus::{Connection, Guid};
+
+/// A daemon running against a scratch copy of one of the sysfs fixtures in
+/// `rog-platform`, served over a peer to peer connection
+pub struct Daemon {
+    /// The client end, proxies are built on this
+    pub client: Connection,
+    /// The daemon end, kept alive for as long as the test runs
+    _server: Connection,
+    /// The fake sysfs root
+    pub root: PathBuf,
+    /// Where the daemon keeps its configs
+    pub config_dir: PathBuf,
+}
+
+/// Start the daemon on the fixture for `model`. The env vars used for the
+/// sysfs root and config dir are process wide, so there can only be one
+/// daemon, and so one test, per test binary.
+pub async fn start(model: &str) -> Daemon {
+    start_with(model, &[]).await
+}
+
+/// As `start`, with config files written before the daemon loads them, given
+/// as the file name and contents
+pub async fn start_with(model: &str, configs: &[(&str, &str)]) -> Daemon {
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("asusd")
+        .join(model);
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+    let root = scratch.join("sysfs");
+    let config_dir = scratch.join("config");
+    copy_fixture(model, &root);
+    fs::create_dir_all(&config_dir).unwrap();
+    for (name, contents) in configs {
+        fs::write(config_dir.join(name), contents).unwrap();
+    }
+
+    std::env::set_var(SYSFS_ROOT_ENV, &root);
+    std::env::set_var(CONFIG_DIR_ENV, &config_dir);
+
+    let (p0, p1) = UnixStream::pair().unwrap();
+    let (client, mut server) = try_join!(
+        Builder::unix_stream(p0).p2p().build(),
+        Builder::unix_stream(p1)
+            .server(Guid::generate())
+            .unwrap()
+            .p2p()
+            .build(),
+    )
+    .unwrap();
+
+    asusd::start_daemon(&mut server).await.unwrap();
+
+    Daemon {
+        client,
+        _server: server,
+        root,
+        config_dir,
+    }
+}
+
+impl Daemon {
+    /// Read a sysfs attribute, trimmed
+    pub fn read(&self, rel: &str) -> String {
+        fs::read_to_string(self.root.join(rel))
+            .unwrap()
+            .trim()
+            .to_owned()
+    }
+
+    /// Everything written to a fake device node so far
+    pub fn dev_bytes(&self, node: &str) -> Vec<u8> {
+        fs::read(self.root.join("dev").join(node)).unwrap()
+    }
+
+    /// Read a config file written by the daemon
+    pub fn config(&self, name: &str) -> String {
+        fs::read_to_string(self.config_dir.join(name)).unwrap()
+    }
+}
+
+/// Wait for the next item of a signal or property stream, failing the test if
+/// nothing arrives
+pub async fn next<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("timed out waiting for a signal")
+        .expect("signal stream ended")
+}
+
+/// If `needle` is anywhere in `haystack`, such as a frame in the bytes written
+/// to a device node
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
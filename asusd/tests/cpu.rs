@@ -0,0 +1,95 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cpuctl::{CpuSettings, CpuSwitch};
+    use rog_dbus::zbus_cpu::CpuProxy;
+    use rog_dbus::zbus_platform::PlatformProxy;
+    use rog_platform::platform::ThrottlePolicy;
+
+    use crate::common::{start_with, Daemon};
+
+    const CPU: &str = "devices/system/cpu";
+    const CPU3: &str = "bus/cpu/devices/cpu3";
+
+    /// The settings are applied by a task following the throttle policy
+    async fn wait_for(daemon: &Daemon, rel: &str, value: &str) {
+        for _ in 0..100 {
+            if daemon.read(rel) == value {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(daemon.read(rel), value);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga402_cpu_over_dbus() {
+        let daemon = start_with(
+            "ga402",
+            &[(
+                "cpu.ron",
+                "(quiet: (pstate_mode: Passive, max_freq: 3000000, offline: [3]))",
+            )],
+        )
+        .await;
+        let cpu = CpuProxy::new(&daemon.client).await.unwrap();
+        let platform = PlatformProxy::new(&daemon.client).await.unwrap();
+
+        let caps = cpu.capabilities().await.unwrap();
+        assert_eq!(caps.hotplug, [1, 2, 3]);
+        assert!(caps.boost && caps.smt && caps.pstate_mode && caps.scaling_freq);
+        assert_eq!(cpu.cores().await.unwrap().len(), 4);
+
+        // A direct change is kept for the active policy, performance on AC
+        cpu.set_boost(false).await.unwrap();
+        assert_eq!(daemon.read(&format!("{CPU}/cpufreq/boost")), "0");
+        let performance = cpu
+            .policy_settings(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        assert_eq!(performance.boost, CpuSwitch::Off);
+        assert!(daemon.config("cpu.ron").contains("boost: Off"));
+
+        let bad = CpuSettings {
+            offline: vec![0],
+            ..Default::default()
+        };
+        assert!(cpu
+            .set_policy_settings(ThrottlePolicy::Quiet, bad)
+            .await
+            .is_err());
+
+        platform
+            .set_throttle_thermal_policy(ThrottlePolicy::Quiet)
+            .await
+            .unwrap();
+        wait_for(&daemon, &format!("{CPU3}/online"), "0").await;
+        assert_eq!(daemon.read(&format!("{CPU}/amd_pstate/status")), "passive");
+        assert_eq!(
+            daemon.read("bus/cpu/devices/cpu0/cpufreq/scaling_max_freq"),
+            "3000000"
+        );
+
+        // Only the offline CPUs are put back, the rest is kept
+        platform
+            .set_throttle_thermal_policy(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        wait_for(&daemon, &format!("{CPU3}/online"), "1").await;
+        assert_eq!(daemon.read(&format!("{CPU}/amd_pstate/status")), "passive");
+        assert_eq!(daemon.read(&format!("{CPU}/cpufreq/boost")), "0");
+
+        cpu.set_core_online(2, false).await.unwrap();
+        assert_eq!(
+            cpu.policy_settings(ThrottlePolicy::Performance)
+                .await
+                .unwrap()
+                .offline,
+            [2]
+        );
+        assert!(cpu.set_core_online(0, false).await.is_err());
+    }
+}
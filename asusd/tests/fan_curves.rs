@@ -0,0 +1,147 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rog_dbus::zbus_fan_curves::FanCurvesProxy;
+    use rog_platform::platform::ThrottlePolicy;
+    use rog_profiles::fan_curve_points::{CurvePoint, Interpolation};
+    use rog_profiles::fan_curve_set::CurveData;
+    use rog_profiles::FanCurvePU;
+
+    use crate::common::start;
+
+    const NODE: &str = "class/hwmon/hwmon5";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_fan_curves_over_dbus() {
+        let daemon = start("gu604").await;
+        let proxy = FanCurvesProxy::new(&daemon.client).await.unwrap();
+
+        // Defaults are read from the device for each policy on first start
+        let curves = proxy
+            .fan_curve_data(ThrottlePolicy::Balanced)
+            .await
+            .unwrap();
+        assert_eq!(curves.len(), 3);
+        assert_eq!(curves[0].fan, FanCurvePU::CPU);
+        assert_eq!(curves[0].temp, [30, 49, 59, 69, 79, 89, 99, 109]);
+        assert_eq!(curves[2].fan, FanCurvePU::MID);
+        assert!(daemon.config_dir.join("fan_curves.ron").exists());
+
+        // Performance is the active policy as the fixture is on AC, so a new
+        // curve for it goes straight to the device
+        let mut curve = CurveData::from_str(
+            "40c:10%,50c:20%,60c:30%,70c:40%,80c:50%,90c:60%,95c:80%,100c:100%",
+        )
+        .unwrap();
+        curve.set_fan(FanCurvePU::GPU);
+        curve.enabled = true;
+        proxy
+            .set_fan_curve(ThrottlePolicy::Performance, curve.clone())
+            .await
+            .unwrap();
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_auto_point1_temp")), "40");
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_auto_point8_pwm")), "255");
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_enable")), "1");
+
+        let stored = proxy
+            .fan_curve_data(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        assert_eq!(stored[1].fan, FanCurvePU::GPU);
+        assert_eq!(stored[1].temp, curve.temp);
+        assert!(stored[1].enabled);
+
+        // A curve for an inactive policy is only stored
+        let mut quiet = curve.clone();
+        quiet.set_fan(FanCurvePU::CPU);
+        proxy
+            .set_fan_curve(ThrottlePolicy::Quiet, quiet)
+            .await
+            .unwrap();
+        assert_eq!(daemon.read(&format!("{NODE}/pwm1_auto_point1_temp")), "30");
+        let stored = proxy.fan_curve_data(ThrottlePolicy::Quiet).await.unwrap();
+        assert_eq!(stored[0].temp, curve.temp);
+
+        // Curves breaking the default safety rules are rejected and not written
+        let mut unsafe_curve =
+            CurveData::from_str("30c:0%,40c:0%,50c:0%,60c:0%,70c:0%,80c:0%,90c:0%,95c:0%").unwrap();
+        unsafe_curve.set_fan(FanCurvePU::GPU);
+        let err = proxy
+            .set_fan_curve(ThrottlePolicy::Performance, unsafe_curve)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsafe GPU fan curve"), "{err}");
+        assert_eq!(
+            daemon.read(&format!("{NODE}/pwm2_auto_point1_pwm")),
+            curve.pwm[0].to_string()
+        );
+        let stored = proxy
+            .fan_curve_data(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        assert_eq!(stored[1].pwm, curve.pwm);
+
+        // A smooth curve of any number of points is resampled to 8
+        let points: Vec<CurvePoint> = (0..12u8)
+            .zip([40, 45, 50, 60, 70, 90, 110, 140, 170, 200, 230, 255])
+            .map(|(i, pwm)| CurvePoint {
+                temp: 30 + i * 5,
+                pwm,
+            })
+            .collect();
+        let smooth = CurveData {
+            fan: FanCurvePU::MID,
+            points: points.clone(),
+            interpolation: Interpolation::MonotoneCubic,
+            enabled: true,
+            ..Default::default()
+        };
+        proxy
+            .set_fan_curve(ThrottlePolicy::Performance, smooth)
+            .await
+            .unwrap();
+        let stored = proxy
+            .fan_curve_data(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        assert_eq!(stored[2].points, points);
+        assert_eq!(stored[2].temp, [30, 38, 46, 54, 61, 69, 77, 85]);
+        assert_eq!(stored[2].pwm[7], 255);
+        assert_eq!(daemon.read(&format!("{NODE}/pwm3_auto_point8_temp")), "85");
+        assert_eq!(daemon.read(&format!("{NODE}/pwm3_auto_point2_temp")), "38");
+
+        // The curves can be moved to another machine, where one missing a fan
+        // is only taken if the fans are mapped
+        let export = proxy.export_fan_curves().await.unwrap();
+        assert_eq!(export.board_name, "GU604VY");
+        assert_eq!(
+            export.fans,
+            vec![FanCurvePU::CPU, FanCurvePU::GPU, FanCurvePU::MID]
+        );
+        assert_eq!(export.profiles.performance, stored);
+        let mut other = export.clone();
+        other.board_name = "GU604VI".to_owned();
+        for curves in [
+            &mut other.profiles.balanced,
+            &mut other.profiles.performance,
+            &mut other.profiles.quiet,
+        ] {
+            curves.retain(|c| c.fan != FanCurvePU::MID);
+        }
+        assert!(proxy.import_fan_curves(other.clone(), false).await.is_err());
+        proxy.import_fan_curves(other, true).await.unwrap();
+        let stored = proxy
+            .fan_curve_data(ThrottlePolicy::Performance)
+            .await
+            .unwrap();
+        assert_eq!(stored[2].fan, FanCurvePU::MID);
+        assert_eq!(stored[2].temp, stored[0].temp);
+        assert_eq!(
+            daemon.read(&format!("{NODE}/pwm3_auto_point1_temp")),
+            stored[0].temp[0].to_string()
+        );
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_dbus::zbus_fan_curves::FanCurvesProxy;
+    use rog_profiles::FanCurvePU;
+
+    use crate::common::{next, start_with};
+
+    const FANS: &str = "class/hwmon/hwmon3";
+    const CONFIG: &str = "(profiles: (balanced: [], performance: [], quiet: []), sensors: \
+                          (interval: 100))";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga402_fan_sensors_over_dbus() {
+        let daemon = start_with("ga402", &[("fan_curves.ron", CONFIG)]).await;
+        let proxy = FanCurvesProxy::new(&daemon.client).await.unwrap();
+
+        let sensors = proxy.fan_sensors().await.unwrap();
+        assert_eq!(sensors.cpu_fan, 2900);
+        assert_eq!(sensors.gpu_fan, 2700);
+        assert_eq!(sensors.mid_fan, 0);
+        assert_eq!(sensors.cpu_temp, 58);
+        assert_eq!(sensors.gpu_temp, 49);
+        assert_eq!(proxy.sensor_interval().await.unwrap(), 100);
+
+        // Changes are signalled without being asked for
+        let mut changed = proxy.receive_fan_sensors_changed().await;
+        std::fs::write(daemon.root.join(FANS).join("fan1_input"), "4100").unwrap();
+        loop {
+            let sensors = next(&mut changed).await.get().await.unwrap();
+            if sensors.rpm(FanCurvePU::CPU) == 4100 {
+                break;
+            }
+        }
+
+        assert!(proxy.set_sensor_interval(10).await.is_err());
+        proxy.set_sensor_interval(0).await.unwrap();
+        assert!(daemon.config("fan_curves.ron").contains("interval: 0"));
+    }
+}
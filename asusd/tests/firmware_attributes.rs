@@ -0,0 +1,62 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_dbus::zbus_platform::PlatformProxy;
+
+    use crate::common::start;
+
+    const ARMOURY: &str = "class/firmware-attributes/asus-armoury/attributes";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fa507_firmware_attributes_over_dbus() {
+        let daemon = start("fa507").await;
+        let proxy = PlatformProxy::new(&daemon.client).await.unwrap();
+
+        let attrs = proxy.firmware_attributes().await.unwrap();
+        let names: Vec<&str> = attrs.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "apu_mem",
+                "mcu_powersave",
+                "ppt_apu_sppt",
+                "ppt_pl1_spl",
+                "ppt_pl2_sppt"
+            ]
+        );
+
+        proxy.set_firmware_attribute("apu_mem", "4").await.unwrap();
+        assert_eq!(
+            daemon.read(&format!("{ARMOURY}/apu_mem/current_value")),
+            "4"
+        );
+        let err = proxy
+            .set_firmware_attribute("apu_mem", "12")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must be one of"), "{err}");
+        assert!(proxy.set_firmware_attribute("nope", "1").await.is_err());
+        assert!(proxy.set_firmware_attribute("../x", "2").await.is_err());
+        assert!(proxy.firmware_attribute("../x").await.is_err());
+
+        // The named PPT properties and their limits come from the firmware
+        assert_eq!(proxy.ppt_pl1_spl().await.unwrap(), 70);
+        let limits = proxy.ppt_limits().await.unwrap();
+        assert_eq!((limits.ppt_pl1_spl.min, limits.ppt_pl1_spl.max), (10, 90));
+
+        proxy
+            .set_firmware_attribute("ppt_pl1_spl", "45")
+            .await
+            .unwrap();
+        assert_eq!(
+            proxy
+                .firmware_attribute("ppt_pl1_spl")
+                .await
+                .unwrap()
+                .current_value,
+            "45"
+        );
+        assert!(daemon.config("asusd.ron").contains("ppt_pl1_spl: Some(45)"));
+    }
+}
@@ -0,0 +1,83 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_dbus::zbus_platform::PlatformProxy;
+    use rog_platform::cpu::{CoreType, CPUEPP};
+
+    use crate::common::start;
+
+    fn epp_path(cpu: u32) -> String {
+        format!("bus/cpu/devices/cpu{cpu}/cpufreq/energy_performance_preference")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_hybrid_epp_over_dbus() {
+        let daemon = start("gu604").await;
+        let proxy = PlatformProxy::new(&daemon.client).await.unwrap();
+
+        let states = proxy.cpu_freq_states().await.unwrap();
+        let types: Vec<CoreType> = states.iter().map(|s| s.core_type).collect();
+        assert_eq!(
+            types,
+            [
+                CoreType::Performance,
+                CoreType::Performance,
+                CoreType::Efficiency,
+                CoreType::Efficiency
+            ]
+        );
+
+        // Without an efficiency core EPP every core gets the same
+        let policy = proxy.throttle_thermal_policy().await.unwrap();
+        assert!(proxy.efficiency_core_epp(policy).await.unwrap().is_empty());
+        let epp = daemon.read(&epp_path(0));
+        assert!((1..4).all(|cpu| daemon.read(&epp_path(cpu)) == epp));
+
+        proxy
+            .set_efficiency_core_epp(policy, &[CPUEPP::BalancePower])
+            .await
+            .unwrap();
+        assert_eq!(daemon.read(&epp_path(0)), epp);
+        assert_eq!(daemon.read(&epp_path(1)), epp);
+        assert_eq!(daemon.read(&epp_path(2)), "balance_power");
+        assert_eq!(daemon.read(&epp_path(3)), "balance_power");
+        assert!(daemon
+            .config("asusd.ron")
+            .contains("throttle_efficiency_core_epp"));
+
+        // Only the cores which changed are reported as changed
+        let result = proxy.last_epp_result().await.unwrap();
+        let changed: Vec<u32> = result.iter().filter(|w| w.changed).map(|w| w.cpu).collect();
+        assert_eq!(changed, [2, 3]);
+        assert!(result.iter().all(|w| w.error.is_empty()));
+
+        // Unset, the efficiency cores follow the performance cores again
+        proxy.set_efficiency_core_epp(policy, &[]).await.unwrap();
+        assert!(proxy.efficiency_core_epp(policy).await.unwrap().is_empty());
+        assert_eq!(daemon.read(&epp_path(2)), epp);
+        assert!(proxy
+            .set_efficiency_core_epp(policy, &[CPUEPP::Power, CPUEPP::Power])
+            .await
+            .is_err());
+
+        // An EPP the efficiency cores don't take falls back to the default,
+        // and the performance cores still get theirs
+        for cpu in [2, 3] {
+            std::fs::write(
+                daemon.root.join(format!(
+                    "bus/cpu/devices/cpu{cpu}/cpufreq/energy_performance_available_preferences"
+                )),
+                "default balance_performance balance_power power\n",
+            )
+            .unwrap();
+        }
+        proxy
+            .set_efficiency_core_epp(policy, &[CPUEPP::Performance])
+            .await
+            .unwrap();
+        assert_eq!(daemon.read(&epp_path(0)), epp);
+        assert_eq!(daemon.read(&epp_path(2)), "default");
+        assert_eq!(daemon.read(&epp_path(3)), "default");
+    }
+}
@@ -0,0 +1,154 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use asusd::config::Config;
+    use config_traits::StdConfig;
+    use rog_dbus::zbus_platform::PlatformProxy;
+    use rog_platform::platform::{PlatformTuning, ThrottlePolicy};
+    use rog_platform::power::{BatteryStatus, ChargeRule};
+
+    use crate::common::{next, start};
+
+    const WMI: &str = "bus/platform/devices/asus-nb-wmi";
+    const BAT: &str = "class/power_supply/BAT0/charge_control_end_threshold";
+    const EPP: &str = "bus/cpu/devices/cpu0/cpufreq/energy_performance_preference";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga402_platform_over_dbus() {
+        let daemon = start("ga402").await;
+        let proxy = PlatformProxy::new(&daemon.client).await.unwrap();
+
+        // The fixture is on AC, so reload switches to the AC policy and its EPP
+        assert_eq!(
+            proxy.throttle_thermal_policy().await.unwrap(),
+            ThrottlePolicy::Performance
+        );
+        assert_eq!(daemon.read(&format!("{WMI}/throttle_thermal_policy")), "1");
+        assert_eq!(daemon.read(EPP), "performance");
+        // Config is written out on the first load
+        assert!(daemon.config("asusd.ron").contains("panel_od: false"));
+
+        assert_eq!(proxy.battery_capacity().await.unwrap(), 64);
+        assert_eq!(
+            proxy.battery_status().await.unwrap(),
+            BatteryStatus::Charging
+        );
+        assert_eq!(proxy.battery_health().await.unwrap(), 90);
+        assert_eq!(proxy.battery_cycle_count().await.unwrap(), 120);
+        assert_eq!(proxy.battery_power_draw().await.unwrap(), 35000);
+        assert_eq!(proxy.battery_time_to_empty().await.unwrap(), 0);
+
+        let mut changed = proxy.receive_panel_od_changed().await;
+        proxy.set_panel_od(true).await.unwrap();
+        loop {
+            if next(&mut changed).await.get().await.unwrap() {
+                break;
+            }
+        }
+        assert_eq!(daemon.read(&format!("{WMI}/panel_od")), "1");
+        assert!(proxy.panel_od().await.unwrap());
+        assert!(daemon.config("asusd.ron").contains("panel_od: true"));
+
+        // GA402 has no firmware attributes, so the limits are the generic ones
+        let limits = proxy.ppt_limits().await.unwrap();
+        assert_eq!((limits.ppt_pl1_spl.min, limits.ppt_pl1_spl.max), (5, 250));
+        let err = proxy.set_ppt_pl1_spl(251).await.unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{err}");
+        assert_eq!(daemon.read(&format!("{WMI}/ppt_pl1_spl")), "80");
+        let err = proxy
+            .set_throttle_policy_tuning(
+                ThrottlePolicy::Quiet,
+                false,
+                PlatformTuning {
+                    nv_temp_target: Some(90),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("nv_temp_target"), "{err}");
+
+        let tuning = PlatformTuning {
+            ppt_pl1_spl: Some(45),
+            ..Default::default()
+        };
+        proxy
+            .set_throttle_policy_tuning(ThrottlePolicy::Quiet, false, tuning)
+            .await
+            .unwrap();
+        assert_eq!(
+            proxy
+                .throttle_policy_tuning(ThrottlePolicy::Quiet, false)
+                .await
+                .unwrap(),
+            tuning
+        );
+        // Not the active policy so nothing is applied yet
+        assert_eq!(daemon.read(&format!("{WMI}/ppt_pl1_spl")), "80");
+
+        proxy
+            .set_throttle_thermal_policy(ThrottlePolicy::Quiet)
+            .await
+            .unwrap();
+        assert_eq!(daemon.read(&format!("{WMI}/throttle_thermal_policy")), "2");
+        assert_eq!(daemon.read(EPP), "power");
+        assert_eq!(daemon.read(&format!("{WMI}/ppt_pl1_spl")), "45");
+        assert!(daemon.config("asusd.ron").contains("ppt_pl1_spl: Some(45)"));
+
+        // A PPT value removed from the config file is left as it is, while
+        // the others are set
+        let mut boost_changed = proxy.receive_nv_dynamic_boost_changed().await;
+        let mut config = Config::new();
+        config.read();
+        config.ppt_pl1_spl = None;
+        config.nv_dynamic_boost = Some(20);
+        config.write();
+        loop {
+            if next(&mut boost_changed).await.get().await.unwrap() == 20 {
+                break;
+            }
+        }
+        assert_eq!(daemon.read(&format!("{WMI}/nv_dynamic_boost")), "20");
+        assert_eq!(daemon.read(&format!("{WMI}/ppt_pl1_spl")), "45");
+
+        // An out of range value in the config file isn't written
+        config.nv_temp_target = Some(95);
+        config.nv_dynamic_boost = Some(15);
+        config.write();
+        loop {
+            if next(&mut boost_changed).await.get().await.unwrap() == 15 {
+                break;
+            }
+        }
+        assert_eq!(daemon.read(&format!("{WMI}/nv_temp_target")), "87");
+
+        proxy.set_charge_control_end_threshold(60).await.unwrap();
+        assert_eq!(daemon.read(BAT), "60");
+        assert!(daemon
+            .config("asusd.ron")
+            .contains("charge_control_end_threshold: 60"));
+
+        // A rule active all day replaces the default limit, but isn't stored
+        // as the default
+        let rule: ChargeRule = "daily 00:00-24:00 90".parse().unwrap();
+        proxy.set_charge_rules(&[rule]).await.unwrap();
+        assert_eq!(daemon.read(BAT), "90");
+        assert_eq!(proxy.charge_rules().await.unwrap(), vec![rule]);
+        assert!(daemon
+            .config("asusd.ron")
+            .contains("charge_control_end_threshold: 60"));
+        let bad = ChargeRule { limit: 10, ..rule };
+        assert!(proxy.set_charge_rules(&[bad]).await.is_err());
+
+        proxy.set_charge_full_until_unplugged(true).await.unwrap();
+        assert_eq!(daemon.read(BAT), "100");
+        proxy.set_charge_full_until_unplugged(false).await.unwrap();
+        assert_eq!(daemon.read(BAT), "90");
+        proxy.set_charge_rules(&[]).await.unwrap();
+        assert_eq!(daemon.read(BAT), "60");
+
+        // ga402 has no mini-LED
+        assert!(proxy.set_mini_led_mode(true).await.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    use rog_dbus::zbus_platform::PlatformProxy;
+    use rog_platform::hooks::HookEvent;
+    use rog_platform::platform::ThrottlePolicy;
+
+    use crate::common::start;
+
+    const POLICY: &str = "bus/platform/devices/asus-nb-wmi/throttle_thermal_policy";
+
+    /// A fake sysfs tree has no udev events, so this also covers the polling
+    /// fallback
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga402_unplug_applies_battery_policy() {
+        let daemon = start("ga402").await;
+        let proxy = PlatformProxy::new(&daemon.client).await.unwrap();
+        assert_eq!(
+            proxy.throttle_thermal_policy().await.unwrap(),
+            ThrottlePolicy::Performance
+        );
+
+        // Hook scripts are found when they run, so can be added at any time
+        let hook_dir = daemon.config_dir.join("hooks.d/battery");
+        let hook_out = daemon.config_dir.join("hook-out");
+        std::fs::create_dir_all(&hook_dir).unwrap();
+        let hook = hook_dir.join("10-test");
+        std::fs::write(
+            &hook,
+            format!(
+                "#!/bin/sh\necho \"$ASUSD_EVENT $ASUSD_POWER_PLUGGED\" > {}\necho done >&2\n",
+                hook_out.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        std::fs::write(daemon.root.join("class/power_supply/ACAD/online"), "0\n").unwrap();
+        let start = Instant::now();
+        while daemon.read(POLICY) != "2" {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "battery policy was not applied"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let start = Instant::now();
+        loop {
+            let result = proxy.last_hook_result().await.unwrap();
+            if result.event == HookEvent::Battery {
+                assert!(result.success());
+                assert_eq!(result.command, hook.to_string_lossy());
+                assert_eq!(result.stderr, "done");
+                break;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "the battery hook did not run"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(
+            std::fs::read_to_string(&hook_out).unwrap().trim(),
+            "battery 0"
+        );
+
+        std::fs::write(daemon.root.join("class/power_supply/ACAD/online"), "1\n").unwrap();
+        let start = Instant::now();
+        while daemon.read(POLICY) != "1" {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "AC policy was not applied"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
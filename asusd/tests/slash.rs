@@ -0,0 +1,35 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_dbus::zbus_slash::SlashProxy;
+    use rog_slash::usb::pkt_set_options;
+
+    use crate::common::{next, start};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ga403_slash_over_dbus() {
+        let daemon = start("ga403").await;
+        let proxy = SlashProxy::new(&daemon.client).await.unwrap();
+
+        assert!(proxy.enabled().await.unwrap());
+        let brightness = proxy.brightness().await.unwrap();
+        let interval = proxy.interval().await.unwrap();
+
+        let mut changed = proxy.receive_enabled_changed().await;
+        let before = daemon.dev_bytes("hidraw0").len();
+        proxy.set_enabled(false).await.unwrap();
+        loop {
+            if !next(&mut changed).await.get().await.unwrap() {
+                break;
+            }
+        }
+
+        let written = daemon.dev_bytes("hidraw0");
+        assert_eq!(
+            written[before..],
+            pkt_set_options(false, brightness, interval)
+        );
+        assert!(daemon.config("slash.ron").contains("slash_enabled: false"));
+    }
+}
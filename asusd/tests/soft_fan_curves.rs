@@ -0,0 +1,87 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rog_dbus::zbus_fan_curves::FanCurvesProxy;
+    use rog_platform::platform::ThrottlePolicy;
+    use rog_profiles::FanCurvePU;
+
+    use crate::common::{start_with, Daemon};
+
+    const NODE: &str = "class/hwmon/hwmon3";
+    const SENSOR: &str = "class/hwmon/hwmon1/temp1_input";
+    const CONFIG: &str = "(profiles: (balanced: [], performance: [], quiet: []), software: \
+                          (enabled: true, poll_interval: 20, step_interval: 0))";
+
+    /// Wait for the engine to write `want` to `attr` of the pwm node
+    async fn wait_for(daemon: &Daemon, attr: &str, want: &str) {
+        let rel = format!("{NODE}/{attr}");
+        for _ in 0..250 {
+            if daemon.read(&rel) == want {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("{rel} is {}, expected {want}", daemon.read(&rel));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fx506_software_fan_curves() {
+        let daemon = start_with("fx506", &[("fan_curves.ron", CONFIG)]).await;
+        let proxy = FanCurvesProxy::new(&daemon.client).await.unwrap();
+
+        // Default curves are made for each fan the `asus` hwmon can control,
+        // and are left to the firmware until enabled
+        let curves = proxy.fan_curve_data(ThrottlePolicy::Quiet).await.unwrap();
+        assert_eq!(curves.len(), 2);
+        assert_eq!(curves[1].fan, FanCurvePU::GPU);
+        assert!(!curves[0].enabled);
+        assert_eq!(daemon.read(&format!("{NODE}/pwm1_enable")), "2");
+
+        for policy in [
+            ThrottlePolicy::Balanced,
+            ThrottlePolicy::Performance,
+            ThrottlePolicy::Quiet,
+        ] {
+            proxy.set_fan_curves_enabled(policy, true).await.unwrap();
+        }
+        // k10temp is at 65c, between the 60c:77 and 70c:102 points
+        wait_for(&daemon, "pwm1_enable", "1").await;
+        wait_for(&daemon, "pwm1", "89").await;
+        wait_for(&daemon, "pwm2", "89").await;
+
+        std::fs::write(daemon.root.join(SENSOR), "20000").unwrap();
+        wait_for(&daemon, "pwm1", "38").await;
+
+        // A sensor which can't be read gives the fans back to the firmware
+        std::fs::write(daemon.root.join(SENSOR), "garbage").unwrap();
+        wait_for(&daemon, "pwm1_enable", "2").await;
+        wait_for(&daemon, "pwm2_enable", "2").await;
+        std::fs::write(daemon.root.join(SENSOR), "65000").unwrap();
+        wait_for(&daemon, "pwm1_enable", "1").await;
+        wait_for(&daemon, "pwm1", "89").await;
+
+        for policy in [
+            ThrottlePolicy::Balanced,
+            ThrottlePolicy::Performance,
+            ThrottlePolicy::Quiet,
+        ] {
+            proxy
+                .set_profile_fan_curve_enabled(policy, FanCurvePU::CPU, false)
+                .await
+                .unwrap();
+        }
+        wait_for(&daemon, "pwm1_enable", "2").await;
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_enable")), "1");
+
+        // However asusd exits the fans go back to auto
+        drop(asusd::StopGuard);
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_enable")), "2");
+        std::fs::write(daemon.root.join(format!("{NODE}/pwm2_enable")), "1").unwrap();
+        asusd::install_panic_hook();
+        assert!(std::panic::catch_unwind(|| panic!("test panic")).is_err());
+        assert_eq!(daemon.read(&format!("{NODE}/pwm2_enable")), "2");
+    }
+}
@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use rog_dbus::zbus_fan_curves::FanCurvesProxy;
+    use rog_dbus::zbus_platform::PlatformProxy;
+    use rog_platform::cpu::CPUEPP;
+    use rog_platform::platform::{PlatformTuning, ThrottlePolicy};
+    use rog_profiles::fan_curve_set::CurveData;
+    use rog_profiles::user_profile::UserProfile;
+    use rog_profiles::FanCurvePU;
+
+    use crate::common::{start, Daemon};
+
+    const WMI: &str = "bus/platform/devices/asus-nb-wmi";
+    const EPP: &str = "bus/cpu/devices/cpu0/cpufreq/energy_performance_preference";
+    const FANS: &str = "class/hwmon/hwmon5";
+
+    /// The fan curves are written by a task following the profile
+    async fn wait_for(daemon: &Daemon, rel: &str, value: &str) {
+        for _ in 0..100 {
+            if daemon.read(rel) == value {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(daemon.read(rel), value);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gu604_user_profiles_over_dbus() {
+        let daemon = start("gu604").await;
+        let proxy = PlatformProxy::new(&daemon.client).await.unwrap();
+        let fans = FanCurvesProxy::new(&daemon.client).await.unwrap();
+        assert_eq!(proxy.active_user_profile().await.unwrap(), "");
+
+        let mut curve = CurveData::from_str(
+            "40c:10%,50c:20%,60c:30%,70c:40%,80c:50%,90c:60%,95c:80%,100c:100%",
+        )
+        .unwrap();
+        curve.set_fan(FanCurvePU::CPU);
+        curve.enabled = true;
+        let meeting = UserProfile {
+            name: "Meeting".to_owned(),
+            throttle_policy: ThrottlePolicy::Quiet,
+            fan_curves: vec![curve],
+            tuning: PlatformTuning {
+                ppt_pl1_spl: Some(35),
+                ..Default::default()
+            },
+            epp: CPUEPP::Power,
+            ..Default::default()
+        };
+        proxy.set_user_profile(&meeting).await.unwrap();
+        let compile = UserProfile {
+            name: "Compile".to_owned(),
+            throttle_policy: ThrottlePolicy::Performance,
+            ..Default::default()
+        };
+        proxy.set_user_profile(&compile).await.unwrap();
+        let names: Vec<String> = proxy
+            .user_profiles()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["Meeting", "Compile"]);
+
+        let bad = UserProfile {
+            name: " ".to_owned(),
+            ..Default::default()
+        };
+        assert!(proxy.set_user_profile(&bad).await.is_err());
+        assert!(proxy.activate_user_profile("Nope").await.is_err());
+
+        // Everything in the profile is applied together
+        proxy.activate_user_profile("Meeting").await.unwrap();
+        assert_eq!(proxy.active_user_profile().await.unwrap(), "Meeting");
+        assert_eq!(daemon.read(&format!("{WMI}/throttle_thermal_policy")), "2");
+        assert_eq!(daemon.read(&format!("{WMI}/ppt_pl1_spl")), "35");
+        assert_eq!(daemon.read(EPP), "power");
+        wait_for(&daemon, &format!("{FANS}/pwm1_auto_point1_temp"), "40").await;
+        assert!(daemon
+            .config("asusd.ron")
+            .contains("active_user_profile: \"Meeting\""));
+
+        // Next cycles the user order once there is one
+        assert!(proxy
+            .set_user_profile_order(&["Nope".to_owned()])
+            .await
+            .is_err());
+        proxy
+            .set_user_profile_order(&["Meeting".to_owned(), "Compile".to_owned()])
+            .await
+            .unwrap();
+        proxy.next_throttle_thermal_policy().await.unwrap();
+        assert_eq!(proxy.active_user_profile().await.unwrap(), "Compile");
+        assert_eq!(daemon.read(&format!("{WMI}/throttle_thermal_policy")), "1");
+        proxy.next_throttle_thermal_policy().await.unwrap();
+        assert_eq!(proxy.active_user_profile().await.unwrap(), "Meeting");
+
+        // Choosing a policy directly leaves the profile and its curves
+        proxy
+            .set_throttle_thermal_policy(ThrottlePolicy::Quiet)
+            .await
+            .unwrap();
+        assert_eq!(proxy.active_user_profile().await.unwrap(), "");
+        let quiet = fans.fan_curve_data(ThrottlePolicy::Quiet).await.unwrap();
+        wait_for(
+            &daemon,
+            &format!("{FANS}/pwm1_auto_point1_temp"),
+            &quiet[0].temp[0].to_string(),
+        )
+        .await;
+
+        proxy.remove_user_profile("Compile").await.unwrap();
+        assert_eq!(proxy.user_profile_order().await.unwrap(), ["Meeting"]);
+        assert!(proxy.remove_user_profile("Compile").await.is_err());
+    }
+}
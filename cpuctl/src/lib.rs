@@ -1,14 +1,527 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+//! CPU control through sysfs. This covers taking cores offline, boost, the
+//! scaling frequency limits, the amd-pstate mode, and SMT, along with finding
+//! which of those a machine supports.
+//!
+//! Governor and EPP control live in `rog_platform::cpu` and are re-exported
+//! here so that this crate is the one place to go for the CPU.
+
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+pub use rog_platform::cpu::{CPUControl, CPUGovernor, CPUEPP};
+use rog_platform::discovery::{sysname_of, Discovery};
+use rog_platform::error::{PlatformError, Result};
+use rog_platform::{has_attr, read_attr_string, write_attr_string};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use zbus::zvariant::{OwnedValue, Type, Value};
+
+/// Holds the controls shared by every CPU, relative to the sysfs root
+const CPU_ROOT: &str = "devices/system/cpu";
+const ATTR_ONLINE: &str = "online";
+const ATTR_MIN_FREQ: &str = "cpufreq/scaling_min_freq";
+const ATTR_MAX_FREQ: &str = "cpufreq/scaling_max_freq";
+const ATTR_CPUINFO_MIN_FREQ: &str = "cpufreq/cpuinfo_min_freq";
+const ATTR_CPUINFO_MAX_FREQ: &str = "cpufreq/cpuinfo_max_freq";
+const ATTR_BOOST: &str = "cpufreq/boost";
+const ATTR_NO_TURBO: &str = "intel_pstate/no_turbo";
+const ATTR_PSTATE_STATUS: &str = "amd_pstate/status";
+const ATTR_SMT: &str = "smt/control";
+
+/// The way boost is switched, which depends on the cpufreq driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Boost {
+    /// `cpufreq/boost`, used by acpi-cpufreq and amd-pstate
+    Cpufreq,
+    /// `intel_pstate/no_turbo`, which is inverted
+    NoTurbo,
+}
+
+#[typeshare]
+#[repr(u32)]
+#[derive(
+    Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, PartialEq, Eq, Clone, Copy,
+)]
+#[zvariant(signature = "u")]
+pub enum PstateMode {
+    /// amd-pstate isn't in use, or in a mode which can't be changed
+    #[default]
+    Unknown = 0,
+    Active = 1,
+    Guided = 2,
+    Passive = 3,
+}
+
+impl From<&str> for PstateMode {
+    fn from(s: &str) -> Self {
+        match s {
+            "active" => Self::Active,
+            "guided" => Self::Guided,
+            "passive" => Self::Passive,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<PstateMode> for String {
+    fn from(m: PstateMode) -> Self {
+        match m {
+            PstateMode::Unknown => "unknown".to_string(),
+            PstateMode::Active => "active".to_string(),
+            PstateMode::Guided => "guided".to_string(),
+            PstateMode::Passive => "passive".to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for PstateMode {
+    type Err = PlatformError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match PstateMode::from(s.to_ascii_lowercase().trim()) {
+            PstateMode::Unknown => Err(PlatformError::InvalidValue(format!(
+                "{s} is not active, guided, or passive"
+            ))),
+            mode => Ok(mode),
+        }
+    }
+}
+
+/// A setting which can be left as it is
+#[typeshare]
+#[repr(u32)]
+#[derive(
+    Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, PartialEq, Eq, Clone, Copy,
+)]
+#[zvariant(signature = "u")]
+pub enum CpuSwitch {
+    #[default]
+    Keep = 0,
+    On = 1,
+    Off = 2,
+}
+
+impl CpuSwitch {
+    /// The state to set, `None` for `Keep`
+    pub fn value(self) -> Option<bool> {
+        match self {
+            CpuSwitch::Keep => None,
+            CpuSwitch::On => Some(true),
+            CpuSwitch::Off => Some(false),
+        }
+    }
+}
+
+impl From<bool> for CpuSwitch {
+    fn from(on: bool) -> Self {
+        if on {
+            CpuSwitch::On
+        } else {
+            CpuSwitch::Off
+        }
+    }
+}
+
+/// What can be changed on this machine
+#[typeshare]
+#[derive(Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, Clone, PartialEq)]
+pub struct CpuCapabilities {
+    /// The CPUs which can be taken offline. The boot CPU usually can't be.
+    pub hotplug: Vec<u32>,
+    pub boost: bool,
+    /// The min and max scaling frequency can be set
+    pub scaling_freq: bool,
+    /// The lowest and highest frequency of the online CPUs, in kHz
+    pub cpuinfo_min_freq: u32,
+    pub cpuinfo_max_freq: u32,
+    /// amd-pstate is in use and its mode can be changed
+    pub pstate_mode: bool,
+    /// SMT can be turned on and off
+    pub smt: bool,
+}
+
+/// The state of one CPU
+#[typeshare]
+#[derive(Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, Clone, PartialEq)]
+pub struct CoreState {
+    pub cpu: u32,
+    pub online: bool,
+    /// The scaling frequency limits in kHz, `0` while the CPU is offline
+    pub min_freq: u32,
+    pub max_freq: u32,
+}
+
+/// CPU settings which are applied together, such as with a throttle policy
+#[typeshare]
+#[derive(Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CpuSettings {
+    pub boost: CpuSwitch,
+    pub smt: CpuSwitch,
+    /// `Unknown` leaves the mode as it is
+    pub pstate_mode: PstateMode,
+    /// In kHz, `0` leaves the limit as it is
+    pub min_freq: u32,
+    pub max_freq: u32,
+    /// The CPUs to take offline. CPUs not named are left as they are.
+    pub offline: Vec<u32>,
+}
+
+impl CpuSettings {
+    /// Check that everything set is supported by `caps`
+    pub fn check(&self, caps: &CpuCapabilities) -> Result<()> {
+        let unsupported = |what: &str| Err(PlatformError::CPU(format!("{what} is not supported")));
+        if self.boost != CpuSwitch::Keep && !caps.boost {
+            return unsupported("Boost");
+        }
+        if self.smt != CpuSwitch::Keep && !caps.smt {
+            return unsupported("SMT control");
+        }
+        if self.pstate_mode != PstateMode::Unknown && !caps.pstate_mode {
+            return unsupported("Changing the amd-pstate mode");
+        }
+        if self.min_freq != 0 || self.max_freq != 0 {
+            check_freq(caps, self.min_freq, self.max_freq)?;
+        }
+        if let Some(cpu) = self.offline.iter().find(|c| !caps.hotplug.contains(c)) {
+            return Err(PlatformError::InvalidValue(format!(
+                "cpu{cpu} can't be taken offline"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Check the frequency limits fit the range of the CPUs, where `0` is unset
+fn check_freq(caps: &CpuCapabilities, min: u32, max: u32) -> Result<()> {
+    if !caps.scaling_freq {
+        return Err(PlatformError::CPU(
+            "Scaling frequency limits are not supported".to_string(),
+        ));
+    }
+    let range = caps.cpuinfo_min_freq..=caps.cpuinfo_max_freq;
+    for freq in [min, max] {
+        if freq != 0 && !range.contains(&freq) {
+            return Err(PlatformError::InvalidValue(format!(
+                "{freq}kHz is outside {}kHz to {}kHz",
+                caps.cpuinfo_min_freq, caps.cpuinfo_max_freq
+            )));
+        }
+    }
+    if min != 0 && max != 0 && min > max {
+        return Err(PlatformError::InvalidValue(format!(
+            "min {min}kHz is above max {max}kHz"
+        )));
+    }
+    Ok(())
+}
+
+fn read_attr_u32(path: &Path, attr: &str) -> Result<u32> {
+    read_attr_string(path, attr)?
+        .trim()
+        .parse()
+        .map_err(|_| PlatformError::ParseNum)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CpuCtl {
+    /// See `CPU_ROOT`
+    root: PathBuf,
+    /// Each CPU by number, in order
+    cpus: Vec<(u32, PathBuf)>,
+    boost: Option<Boost>,
+}
+
+impl CpuCtl {
+    pub fn new() -> Result<Self> {
+        Self::new_with(&Discovery::from_env())
+    }
+
+    /// Find the CPUs using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        let mut cpus: Vec<(u32, PathBuf)> = discovery
+            .scan("cpu")?
+            .into_iter()
+            .filter_map(|path| {
+                let num = sysname_of(&path).strip_prefix("cpu")?.parse().ok()?;
+                Some((num, path))
+            })
+            .collect();
+        if cpus.is_empty() {
+            return Err(PlatformError::CPU("No CPUs found".to_string()));
+        }
+        cpus.sort_by_key(|(num, _)| *num);
+
+        let root = discovery.sys_path(CPU_ROOT);
+        let boost = if has_attr(&root, ATTR_BOOST) {
+            Some(Boost::Cpufreq)
+        } else if has_attr(&root, ATTR_NO_TURBO) {
+            Some(Boost::NoTurbo)
+        } else {
+            None
+        };
+        let ctl = Self { root, cpus, boost };
+        info!("Found {} CPUs: {:?}", ctl.cpus.len(), ctl.capabilities());
+        Ok(ctl)
+    }
+
+    fn cpu_path(&self, cpu: u32) -> Result<&PathBuf> {
+        self.cpus
+            .iter()
+            .find(|(num, _)| *num == cpu)
+            .map(|(_, path)| path)
+            .ok_or_else(|| PlatformError::InvalidValue(format!("There is no cpu{cpu}")))
+    }
+
+    /// A CPU without an `online` attribute can't be taken offline
+    fn is_online(path: &Path) -> bool {
+        read_attr_string(path, ATTR_ONLINE).map_or(true, |s| s.trim() != "0")
+    }
+
+    /// The online CPUs which have scaling frequency limits
+    fn freq_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.cpus
+            .iter()
+            .map(|(_, path)| path)
+            .filter(|path| Self::is_online(path) && has_attr(*path, ATTR_MAX_FREQ))
+    }
+
+    pub fn capabilities(&self) -> CpuCapabilities {
+        let mut caps = CpuCapabilities {
+            hotplug: self
+                .cpus
+                .iter()
+                .filter(|(_, path)| has_attr(path, ATTR_ONLINE))
+                .map(|(num, _)| *num)
+                .collect(),
+            boost: self.boost.is_some(),
+            pstate_mode: self.pstate_mode().is_ok_and(|m| m != PstateMode::Unknown),
+            smt: read_attr_string(&self.root, ATTR_SMT).is_ok_and(|s| s == "on" || s == "off"),
+            ..Default::default()
+        };
+        for path in self.freq_paths() {
+            let (Ok(min), Ok(max)) = (
+                read_attr_u32(path, ATTR_CPUINFO_MIN_FREQ),
+                read_attr_u32(path, ATTR_CPUINFO_MAX_FREQ),
+            ) else {
+                continue;
+            };
+            if !caps.scaling_freq || min < caps.cpuinfo_min_freq {
+                caps.cpuinfo_min_freq = min;
+            }
+            caps.cpuinfo_max_freq = caps.cpuinfo_max_freq.max(max);
+            caps.scaling_freq = true;
+        }
+        caps
+    }
+
+    pub fn cores(&self) -> Vec<CoreState> {
+        self.cpus
+            .iter()
+            .map(|(num, path)| {
+                let online = Self::is_online(path);
+                let freq = |attr| {
+                    online
+                        .then(|| read_attr_u32(path, attr).ok())
+                        .flatten()
+                        .unwrap_or_default()
+                };
+                CoreState {
+                    cpu: *num,
+                    online,
+                    min_freq: freq(ATTR_MIN_FREQ),
+                    max_freq: freq(ATTR_MAX_FREQ),
+                }
+            })
+            .collect()
+    }
+
+    pub fn set_online(&self, cpu: u32, online: bool) -> Result<()> {
+        let path = self.cpu_path(cpu)?;
+        if !has_attr(path, ATTR_ONLINE) {
+            return Err(PlatformError::CPU(format!(
+                "cpu{cpu} can't be taken offline"
+            )));
+        }
+        write_attr_string(path, ATTR_ONLINE, if online { "1" } else { "0" })
+    }
+
+    pub fn boost(&self) -> Result<bool> {
+        match self.boost {
+            Some(Boost::Cpufreq) => Ok(read_attr_string(&self.root, ATTR_BOOST)? != "0"),
+            Some(Boost::NoTurbo) => Ok(read_attr_string(&self.root, ATTR_NO_TURBO)? == "0"),
+            None => Err(PlatformError::NotSupported),
+        }
+    }
+
+    pub fn set_boost(&self, on: bool) -> Result<()> {
+        match self.boost {
+            Some(Boost::Cpufreq) => {
+                write_attr_string(&self.root, ATTR_BOOST, if on { "1" } else { "0" })
+            }
+            Some(Boost::NoTurbo) => {
+                write_attr_string(&self.root, ATTR_NO_TURBO, if on { "0" } else { "1" })
+            }
+            None => Err(PlatformError::NotSupported),
+        }
+    }
+
+    pub fn smt(&self) -> Result<bool> {
+        match read_attr_string(&self.root, ATTR_SMT)?.as_str() {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(PlatformError::NotSupported),
+        }
+    }
+
+    pub fn set_smt(&self, on: bool) -> Result<()> {
+        self.smt()?;
+        write_attr_string(&self.root, ATTR_SMT, if on { "on" } else { "off" })
+    }
+
+    pub fn pstate_mode(&self) -> Result<PstateMode> {
+        read_attr_string(&self.root, ATTR_PSTATE_STATUS).map(|s| s.as_str().into())
+    }
+
+    pub fn set_pstate_mode(&self, mode: PstateMode) -> Result<()> {
+        if mode == PstateMode::Unknown {
+            return Err(PlatformError::InvalidValue(
+                "amd-pstate mode must be active, guided, or passive".to_string(),
+            ));
+        }
+        if self.pstate_mode()? == PstateMode::Unknown {
+            return Err(PlatformError::NotSupported);
+        }
+        write_attr_string(&self.root, ATTR_PSTATE_STATUS, &String::from(mode))
+    }
+
+    /// Set the scaling frequency limits in kHz of every online CPU. A limit of
+    /// `0` is left as it is.
+    pub fn set_scaling_freq(&self, min: u32, max: u32) -> Result<()> {
+        check_freq(&self.capabilities(), min, max)?;
+        for path in self.freq_paths() {
+            let current_min = read_attr_u32(path, ATTR_MIN_FREQ)?;
+            let current_max = read_attr_u32(path, ATTR_MAX_FREQ)?;
+            let min = if min == 0 { current_min } else { min };
+            let max = if max == 0 { current_max } else { max };
+            if min > max {
+                return Err(PlatformError::InvalidValue(format!(
+                    "min {min}kHz is above max {max}kHz"
+                )));
+            }
+            // The kernel refuses a min above the max in place, or the reverse
+            let writes = if min > current_max {
+                [(ATTR_MAX_FREQ, max), (ATTR_MIN_FREQ, min)]
+            } else {
+                [(ATTR_MIN_FREQ, min), (ATTR_MAX_FREQ, max)]
+            };
+            for (attr, freq) in writes {
+                write_attr_string(path, attr, &freq.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply everything in `settings`. Each setting is tried even if one
+    /// fails, and the first error is returned.
+    pub fn apply(&self, settings: &CpuSettings) -> Result<()> {
+        self.apply_replacing(settings, &CpuSettings::default())
+    }
+
+    /// As `apply()`, and bring back online the CPUs `previous` took offline
+    /// which `settings` doesn't
+    pub fn apply_replacing(&self, settings: &CpuSettings, previous: &CpuSettings) -> Result<()> {
+        let mut results = Vec::new();
+        // SMT and the amd-pstate mode change which CPUs are online and reset
+        // the frequency limits, so they go first
+        if let Some(on) = settings.smt.value() {
+            if self.smt().ok() != Some(on) {
+                results.push(self.set_smt(on));
+            }
+        }
+        if settings.pstate_mode != PstateMode::Unknown
+            && self.pstate_mode().ok() != Some(settings.pstate_mode)
+        {
+            results.push(self.set_pstate_mode(settings.pstate_mode));
+        }
+        // With SMT off the siblings can't be brought back online
+        let smt_off = self.smt().is_ok_and(|on| !on);
+        for (num, path) in &self.cpus {
+            let online = !settings.offline.contains(num);
+            if online && !previous.offline.contains(num) {
+                continue;
+            }
+            if has_attr(path, ATTR_ONLINE)
+                && Self::is_online(path) != online
+                && !(online && smt_off)
+            {
+                results.push(self.set_online(*num, online));
+            }
+        }
+        if let Some(on) = settings.boost.value() {
+            if self.boost().ok() != Some(on) {
+                results.push(self.set_boost(on));
+            }
+        }
+        if settings.min_freq != 0 || settings.max_freq != 0 {
+            results.push(self.set_scaling_freq(settings.min_freq, settings.max_freq));
+        }
+
+        let mut first = Ok(());
+        for result in results {
+            if let Err(e) = result {
+                warn!("CpuCtl: {e}");
+                if first.is_ok() {
+                    first = Err(e);
+                }
+            }
+        }
+        first
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use super::{CpuCapabilities, CpuSettings, CpuSwitch, PstateMode};
+
+    #[test]
+    fn check_settings() {
+        let caps = CpuCapabilities {
+            hotplug: vec![1, 2, 3],
+            scaling_freq: true,
+            cpuinfo_min_freq: 400_000,
+            cpuinfo_max_freq: 4_900_000,
+            smt: true,
+            ..Default::default()
+        };
+        let mut settings = CpuSettings {
+            smt: CpuSwitch::Off,
+            max_freq: 3_000_000,
+            offline: vec![3],
+            ..Default::default()
+        };
+        assert!(settings.check(&caps).is_ok());
+        assert!(CpuSettings::default().check(&caps).is_ok());
+
+        settings.min_freq = 3_500_000;
+        assert!(settings.check(&caps).is_err());
+        settings.min_freq = 300_000;
+        assert!(settings.check(&caps).is_err());
+        settings.min_freq = 0;
+        settings.offline.push(0);
+        assert!(settings.check(&caps).is_err());
+        settings.offline.pop();
+        settings.boost = CpuSwitch::On;
+        assert!(settings.check(&caps).is_err());
+        settings.boost = CpuSwitch::Keep;
+        settings.pstate_mode = PstateMode::Guided;
+        assert!(settings.check(&caps).is_err());
+    }
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn pstate_mode_from_str() {
+        assert_eq!("Guided".parse::<PstateMode>().unwrap(), PstateMode::Guided);
+        assert_eq!(PstateMode::from("disable"), PstateMode::Unknown);
+        assert!("disable".parse::<PstateMode>().is_err());
     }
 }
@@ -0,0 +1,24 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rog_platform::discovery::Discovery;
+use rog_platform::test_util::{fixture_at, read_trimmed};
+
+/// Copy the `rog-platform` fixture tree for `model` to a scratch dir so that
+/// tests can write to it, and return a `Discovery` rooted there.
+pub fn fixture(model: &str, test: &str) -> (Discovery, PathBuf) {
+    fixture_at(
+        model,
+        Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("cpuctl")
+            .join(format!("{model}-{test}")),
+    )
+}
+
+pub fn read(root: &Path, rel: &str) -> String {
+    read_trimmed(&root.join(rel))
+}
+
+pub fn write(root: &Path, rel: &str, value: &str) {
+    fs::write(root.join(rel), value).unwrap();
+}
@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use cpuctl::{CoreState, CpuCtl, CpuSettings, CpuSwitch, PstateMode};
+
+    use crate::common::{fixture, read, write};
+
+    const CPU: &str = "devices/system/cpu";
+
+    #[test]
+    fn ga402_cpu_capabilities() {
+        let (discovery, root) = fixture("ga402", "capabilities");
+        let cpu = CpuCtl::new_with(&discovery).unwrap();
+
+        let caps = cpu.capabilities();
+        assert_eq!(caps.hotplug, [1, 2, 3]);
+        assert!(caps.boost);
+        assert!(caps.scaling_freq);
+        assert_eq!(caps.cpuinfo_min_freq, 400000);
+        assert_eq!(caps.cpuinfo_max_freq, 4935000);
+        assert!(caps.pstate_mode);
+        assert!(caps.smt);
+
+        // Once it can't be changed the capability goes away
+        write(&root, &format!("{CPU}/smt/control"), "forceoff");
+        write(&root, &format!("{CPU}/amd_pstate/status"), "disable");
+        let caps = cpu.capabilities();
+        assert!(!caps.smt);
+        assert!(!caps.pstate_mode);
+        assert!(cpu.set_smt(true).is_err());
+
+        // Intel has turbo in place of boost
+        fs::remove_file(root.join(CPU).join("cpufreq/boost")).unwrap();
+        fs::create_dir_all(root.join(CPU).join("intel_pstate")).unwrap();
+        write(&root, &format!("{CPU}/intel_pstate/no_turbo"), "0");
+        let cpu = CpuCtl::new_with(&discovery).unwrap();
+        assert!(cpu.boost().unwrap());
+        cpu.set_boost(false).unwrap();
+        assert_eq!(read(&root, &format!("{CPU}/intel_pstate/no_turbo")), "1");
+    }
+
+    #[test]
+    fn ga402_cpu_control() {
+        let (discovery, root) = fixture("ga402", "control");
+        let cpu = CpuCtl::new_with(&discovery).unwrap();
+
+        assert!(cpu.set_online(0, false).is_err());
+        assert!(cpu.set_online(9, false).is_err());
+        cpu.set_online(3, false).unwrap();
+        assert_eq!(read(&root, "bus/cpu/devices/cpu3/online"), "0");
+        assert_eq!(
+            cpu.cores()[2..],
+            [
+                CoreState {
+                    cpu: 2,
+                    online: true,
+                    min_freq: 400000,
+                    max_freq: 4935000,
+                },
+                CoreState {
+                    cpu: 3,
+                    online: false,
+                    min_freq: 0,
+                    max_freq: 0,
+                },
+            ]
+        );
+
+        assert!(cpu.boost().unwrap());
+        cpu.set_boost(false).unwrap();
+        assert_eq!(read(&root, &format!("{CPU}/cpufreq/boost")), "0");
+        assert!(cpu.smt().unwrap());
+        cpu.set_smt(false).unwrap();
+        assert_eq!(read(&root, &format!("{CPU}/smt/control")), "off");
+        assert_eq!(cpu.pstate_mode().unwrap(), PstateMode::Active);
+        cpu.set_pstate_mode(PstateMode::Guided).unwrap();
+        assert_eq!(read(&root, &format!("{CPU}/amd_pstate/status")), "guided");
+        assert!(cpu.set_pstate_mode(PstateMode::Unknown).is_err());
+
+        // Offline CPUs are left alone
+        cpu.set_scaling_freq(0, 3000000).unwrap();
+        cpu.set_scaling_freq(3500000, 4000000).unwrap();
+        assert_eq!(
+            read(&root, "bus/cpu/devices/cpu0/cpufreq/scaling_min_freq"),
+            "3500000"
+        );
+        assert_eq!(
+            read(&root, "bus/cpu/devices/cpu0/cpufreq/scaling_max_freq"),
+            "4000000"
+        );
+        assert_eq!(
+            read(&root, "bus/cpu/devices/cpu3/cpufreq/scaling_max_freq"),
+            "4935000"
+        );
+        assert!(cpu.set_scaling_freq(0, 3000000).is_err());
+        assert!(cpu.set_scaling_freq(100000, 0).is_err());
+
+        // Anything not in the settings is kept, and only the CPUs named by
+        // either settings are taken offline or brought back
+        write(&root, "bus/cpu/devices/cpu2/online", "0");
+        let settings = CpuSettings {
+            boost: CpuSwitch::On,
+            smt: CpuSwitch::On,
+            max_freq: 4500000,
+            offline: vec![1],
+            ..Default::default()
+        };
+        let previous = CpuSettings {
+            offline: vec![1, 3],
+            ..Default::default()
+        };
+        cpu.apply_replacing(&settings, &previous).unwrap();
+        assert_eq!(read(&root, &format!("{CPU}/cpufreq/boost")), "1");
+        assert_eq!(read(&root, &format!("{CPU}/smt/control")), "on");
+        assert_eq!(read(&root, &format!("{CPU}/amd_pstate/status")), "guided");
+        assert_eq!(read(&root, "bus/cpu/devices/cpu1/online"), "0");
+        assert_eq!(read(&root, "bus/cpu/devices/cpu2/online"), "0");
+        assert_eq!(read(&root, "bus/cpu/devices/cpu3/online"), "1");
+        assert_eq!(
+            read(&root, "bus/cpu/devices/cpu3/cpufreq/scaling_min_freq"),
+            "400000"
+        );
+        assert_eq!(
+            read(&root, "bus/cpu/devices/cpu3/cpufreq/scaling_max_freq"),
+            "4500000"
+        );
+    }
+}
@@ -57,6 +57,12 @@ BuildRequires:  pkgconfig(libzstd)
 BuildRequires:  pkgconfig(gtk+-3.0)
 BuildRequires:  pkgconfig(gdk-3.0)
 BuildRequires:  desktop-file-utils
+# parec, for the audio visualiser of asusd-user
+Recommends:     pulseaudio-utils
+# gst-launch-1.0 with pipewiresrc, for the screen ambient lighting of asusd-user
+Recommends:     gstreamer1
+Recommends:     gstreamer1-plugins-base
+Recommends:     pipewire-gstreamer
 
 # expat-devel pcre2-devel
 
@@ -114,6 +120,7 @@ desktop-file-validate %{buildroot}/%{_datadir}/applications/rog-control-center.d
 #%dir %{_sysconfdir}/asusd/
 %{_datadir}/asusd/aura_support.ron
 %{_datadir}/dbus-1/system.d/asusd.conf
+%{_datadir}/polkit-1/actions/org.asuslinux.Daemon.policy
 %{_datadir}/icons/hicolor/512x512/apps/asus_notif_yellow.png
 %{_datadir}/icons/hicolor/512x512/apps/asus_notif_green.png
 %{_datadir}/icons/hicolor/512x512/apps/asus_notif_red.png
@@ -133,6 +140,5 @@ desktop-file-validate %{buildroot}/%{_datadir}/applications/rog-control-center.d
 %{_bindir}/rog-control-center
 %{_datadir}/applications/rog-control-center.desktop
 %{_datadir}/icons/hicolor/512x512/apps/rog-control-center.png
-%{_datadir}/rog-gui
 
 %changelog
@@ -1,4 +1,8 @@
+use std::path::Path;
+use std::{env, fs};
+
 use log::{info, warn};
+use rog_platform::discovery::SYSFS_ROOT_ENV;
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Clone)]
 pub struct DMIID {
@@ -16,7 +20,13 @@ pub struct DMIID {
 }
 
 impl DMIID {
+    /// Read the DMI info with udev, or from `<root>/class/dmi/id/` if
+    /// `ASUSD_SYSFS_ROOT` points to a fake sysfs tree
     pub fn new() -> Result<Self, String> {
+        if let Some(root) = env::var_os(SYSFS_ROOT_ENV).filter(|r| !r.is_empty()) {
+            return Self::from_dir(&Path::new(&root).join("class/dmi/id"));
+        }
+
         let mut enumerator = udev::Enumerator::new().map_err(|err| {
             warn!("{}", err);
             format!("dmi enumerator failed: {err}")
@@ -84,6 +94,36 @@ impl DMIID {
         }
         Err("dmi not found".into())
     }
+
+    /// Read the info from a directory laid out like `/sys/class/dmi/id`. The
+    /// udev properties have no file there so `id_model`, `dmi_family` and
+    /// `dmi_vendor` are taken from `product_name`, `product_family` and
+    /// `sys_vendor`.
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        if !dir.is_dir() {
+            return Err(format!("dmi not found at {dir:?}"));
+        }
+        info!("Found dmi ID info at {:?}", dir);
+
+        let read = |name: &str| {
+            fs::read_to_string(dir.join(name))
+                .map(|s| s.trim_end().to_string())
+                .unwrap_or("Unknown".to_string())
+        };
+        Ok(Self {
+            id_model: read("product_name"),
+            dmi_family: read("product_family"),
+            dmi_vendor: read("sys_vendor"),
+            board_name: read("board_name"),
+            board_vendor: read("board_vendor"),
+            bios_date: read("bios_date"),
+            bios_release: read("bios_release"),
+            bios_vendor: read("bios_vendor"),
+            bios_version: read("bios_version"),
+            product_family: read("product_family"),
+            product_name: read("product_name"),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -94,6 +94,45 @@ impl Default for Colour {
     }
 }
 
+impl Colour {
+    /// Blend from `self` to `other`, where `t` is 0.0 for `self` and 1.0 for
+    /// `other`
+    pub fn lerp(&self, other: &Colour, t: f32) -> Colour {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Colour {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+
+    /// Scale the brightness, where `amount` is 0.0 for off and 1.0 for
+    /// unchanged
+    pub fn scale(&self, amount: f32) -> Colour {
+        Colour { r: 0, g: 0, b: 0 }.lerp(self, amount)
+    }
+
+    /// The fully saturated colour of `hue`, in degrees
+    pub fn from_hue(hue: f32) -> Colour {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = 1.0 - (h % 2.0 - 1.0).abs();
+        let (r, g, b) = match h as u8 {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        };
+        Colour {
+            r: (r * 255.0_f32).round() as u8,
+            g: (g * 255.0_f32).round() as u8,
+            b: (b * 255.0_f32).round() as u8,
+        }
+    }
+}
+
 impl FromStr for Colour {
     type Err = Error;
 
@@ -618,6 +657,34 @@ mod tests {
         assert_eq!(ar, check);
     }
 
+    #[test]
+    fn colour_blending() {
+        let red = Colour { r: 255, g: 0, b: 0 };
+        let blue = Colour { r: 0, g: 0, b: 255 };
+        assert_eq!(red.lerp(&blue, 0.0), red);
+        assert_eq!(red.lerp(&blue, 1.0), blue);
+        assert_eq!(
+            red.lerp(&blue, 0.5),
+            Colour {
+                r: 128,
+                g: 0,
+                b: 128
+            }
+        );
+        assert_eq!(red.scale(0.0), Colour { r: 0, g: 0, b: 0 });
+        assert_eq!(Colour::from_hue(0.0), red);
+        assert_eq!(Colour::from_hue(120.0), Colour { r: 0, g: 255, b: 0 });
+        assert_eq!(Colour::from_hue(600.0), blue);
+        assert_eq!(
+            Colour::from_hue(-60.0),
+            Colour {
+                r: 255,
+                g: 0,
+                b: 255
+            }
+        );
+    }
+
     #[test]
     fn check_led_static_zone_packet() {
         let mut st = AuraEffect {
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{along, LayoutEffectState};
+use crate::keyboard::{KeyLayout, LedCode};
+use crate::{Colour, Direction};
+
+/// A still blend between colours across the layout
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Gradient {
+    /// The colours to blend between, spaced evenly from the first key to the
+    /// last in `direction`
+    stops: Vec<Colour>,
+    direction: Direction,
+    #[serde(skip)]
+    colours: Vec<(LedCode, Colour)>,
+}
+
+impl Gradient {
+    pub fn new(stops: Vec<Colour>, direction: Direction) -> Self {
+        Self {
+            stops,
+            direction,
+            colours: Vec::new(),
+        }
+    }
+
+    /// The colour at `t` from 0.0 at the first stop to 1.0 at the last
+    fn colour_at(&self, t: f32) -> Colour {
+        match self.stops.as_slice() {
+            [] => Colour { r: 0, g: 0, b: 0 },
+            [only] => *only,
+            stops => {
+                let segment = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+                let index = (segment as usize).min(stops.len() - 2);
+                stops[index].lerp(&stops[index + 1], segment - index as f32)
+            }
+        }
+    }
+}
+
+impl LayoutEffectState for Gradient {
+    fn next_colour_state(&mut self, layout: &KeyLayout, _elapsed: Duration) {
+        if self.stops.is_empty() {
+            self.colours.clear();
+            return;
+        }
+        let positions = layout.key_positions();
+        let distances: Vec<f32> = positions
+            .iter()
+            .map(|pos| along(self.direction, pos))
+            .collect();
+        let start = distances.iter().copied().fold(f32::MAX, f32::min);
+        let end = distances.iter().copied().fold(f32::MIN, f32::max);
+        let length = (end - start).max(f32::EPSILON);
+        self.colours = positions
+            .iter()
+            .zip(distances)
+            .map(|(pos, d)| (pos.led, self.colour_at((d - start) / length)))
+            .collect();
+    }
+
+    fn get_colours(&self) -> &[(LedCode, Colour)] {
+        &self.colours
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
 mod doom;
@@ -12,8 +14,23 @@ pub use breathe::*;
 mod static_;
 pub use static_::*;
 
-use crate::keyboard::{KeyLayout, LedCode, LedUsbPackets, UsbPackets};
-use crate::Colour;
+mod wave;
+pub use wave::*;
+
+mod rainbow;
+pub use rainbow::*;
+
+mod gradient;
+pub use gradient::*;
+
+mod ripple;
+pub use ripple::*;
+
+mod reactive;
+pub use reactive::*;
+
+use crate::keyboard::{KeyLayout, KeyPosition, LedCode, LedUsbPackets, UsbPackets};
+use crate::{Colour, Direction, Speed};
 
 // static mut RNDINDEX: usize = 0;
 static mut PRNDINDEX: usize = 0;
@@ -51,6 +68,12 @@ pub trait InputForEffect {
     fn get_colour(&self) -> Colour;
 }
 
+/// An input which triggers an effect at a key, such as the key being pressed
+pub trait TriggerForEffect {
+    /// Trigger the effect at `led`, shown from the next colour state
+    fn trigger(&mut self, led: LedCode);
+}
+
 pub(crate) trait EffectState {
     /// Calculate the next colour state
     fn next_colour_state(&mut self, _layout: &KeyLayout);
@@ -64,7 +87,36 @@ pub(crate) trait EffectState {
     fn set_led(&mut self, address: LedCode);
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+/// An effect which sets many keys, using where they are in the `KeyLayout`
+pub(crate) trait LayoutEffectState {
+    /// Calculate the colour of every key `elapsed` after the last state
+    fn next_colour_state(&mut self, layout: &KeyLayout, elapsed: Duration);
+
+    /// Return the keys set by the last `next_colour_state()` and their colours
+    fn get_colours(&self) -> &[(LedCode, Colour)];
+}
+
+/// How far a key is in `direction`, so something moving that way reaches keys
+/// with a larger distance later
+pub(crate) fn along(direction: Direction, pos: &KeyPosition) -> f32 {
+    match direction {
+        Direction::Right => pos.x,
+        Direction::Left => -pos.x,
+        Direction::Down => pos.y,
+        Direction::Up => -pos.y,
+    }
+}
+
+/// The time between states taken by `next_state()`, for 30 frames a second
+pub const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 30);
+
+/// The fraction of a cycle a moving effect makes in `elapsed`. `Speed::Med` is
+/// one cycle a second.
+pub(crate) fn cycles_in(speed: Speed, elapsed: Duration) -> f32 {
+    (u8::from(speed) + 1) as f32 / 2.0 * elapsed.as_secs_f32()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct AdvancedEffects {
     effects: Vec<Effect>,
     zoned: bool,
@@ -79,6 +131,11 @@ impl AdvancedEffects {
         }
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
     #[inline]
     pub fn push(&mut self, action: Effect) {
         self.effects.push(action);
@@ -100,9 +157,27 @@ impl AdvancedEffects {
         None
     }
 
+    /// If any of the effects are triggered by keys being pressed
+    pub fn is_reactive(&self) -> bool {
+        self.effects.iter().any(Effect::is_reactive)
+    }
+
+    /// Trigger each effect which reacts to keys at `led`
+    pub fn trigger(&mut self, led: LedCode) {
+        for effect in &mut self.effects {
+            effect.trigger(led);
+        }
+    }
+
     pub fn next_state(&mut self, layout: &KeyLayout) {
+        self.next_state_after(layout, FRAME_TIME);
+    }
+
+    /// As `next_state()`, with the effects across the layout moved on by
+    /// `elapsed`, so they keep their speed at any frame rate
+    pub fn next_state_after(&mut self, layout: &KeyLayout, elapsed: Duration) {
         for effect in &mut self.effects {
-            effect.next_state(layout);
+            effect.next_state_after(layout, elapsed);
         }
     }
 
@@ -116,8 +191,9 @@ impl AdvancedEffects {
         };
 
         for effect in &self.effects {
-            let c = effect.colour();
-            usb_packets.set(effect.led(), c.r, c.g, c.b);
+            for (led, c) in effect.colours() {
+                usb_packets.set(led, c.r, c.g, c.b);
+            }
         }
         usb_packets.into()
     }
@@ -142,37 +218,68 @@ macro_rules! effect_state_impl {
     };
 }
 
-/// A helper macro to quickly add new effects to the matching on `Effect`
+/// A helper macro to quickly add new effects to the matching on `Effect`. The
+/// effects on a single key are listed first, then those across the layout.
 macro_rules! effect_impl {
-    ($($effect:ident),*) => {
+    ($($effect:ident),*; $($layout_effect:ident),*) => {
         impl Effect {
-            /// Get the type of LED set
+            /// Get the type of LED set. Effects across the layout set many, see
+            /// `colours()`, and give the first of those.
             pub fn led(&self) -> $crate::keyboard::LedCode {
                 match self {
                     $(Effect::$effect(c) => c.get_led(),)*
+                    $(Effect::$layout_effect(c) => {
+                        c.get_colours().first().map(|(led, _)| *led).unwrap_or_default()
+                    })*
                 }
             }
 
-            /// Change the led type (can be used to change location of the effect)
+            /// Change the led type (can be used to change location of the effect).
+            /// Does nothing for effects across the layout.
             pub fn set_led(&mut self, address: $crate::keyboard::LedCode) {
                 match self {
                     $(Effect::$effect(c) => c.set_led(address),)*
+                    $(Effect::$layout_effect(_) => {})*
                 }
             }
 
             /// Calculate the next state of the effect
             pub fn next_state(&mut self, layout: &KeyLayout) {
+                self.next_state_after(layout, FRAME_TIME);
+            }
+
+            /// Calculate the state of the effect `elapsed` after the last one.
+            /// Effects on a single key take one step whatever the time.
+            pub fn next_state_after(&mut self, layout: &KeyLayout, elapsed: Duration) {
                 match self {
-                    $(Effect::$effect(c) => c.next_colour_state(layout),)*
+                    $(Effect::$effect(c) => EffectState::next_colour_state(c, layout),)*
+                    $(Effect::$layout_effect(c) => {
+                        LayoutEffectState::next_colour_state(c, layout, elapsed)
+                    })*
                 }
             }
 
-            /// Get the calculated colour
+            /// Get the calculated colour. Effects across the layout set many,
+            /// see `colours()`, and give the first of those.
             pub fn colour(&self) -> $crate::Colour {
                 match self {
                     $(Effect::$effect(c) => c.get_colour(),)*
+                    $(Effect::$layout_effect(c) => {
+                        c.get_colours().first().map(|(_, c)| *c).unwrap_or_default()
+                    })*
                 }
             }
+
+            /// Get the calculated colour of each LED the effect sets
+            pub fn colours(
+                &self,
+            ) -> impl Iterator<Item = ($crate::keyboard::LedCode, $crate::Colour)> + '_ {
+                let (key, layout) = match self {
+                    $(Effect::$effect(c) => (Some((c.get_led(), c.get_colour())), &[][..]),)*
+                    $(Effect::$layout_effect(c) => (None, c.get_colours()),)*
+                };
+                key.into_iter().chain(layout.iter().copied())
+            }
         }
     };
 }
@@ -187,6 +294,11 @@ pub enum Effect {
     Breathe(Breathe),
     DoomFlicker(DoomFlicker),
     DoomLightFlash(DoomLightFlash),
+    Wave(Wave),
+    Rainbow(Rainbow),
+    Gradient(Gradient),
+    Ripple(Ripple),
+    Reactive(Reactive),
 }
 
 impl Default for Effect {
@@ -195,13 +307,50 @@ impl Default for Effect {
     }
 }
 
-effect_impl!(Static, Breathe, DoomFlicker, DoomLightFlash);
+effect_impl!(
+    Static, Breathe, DoomFlicker, DoomLightFlash;
+    Wave, Rainbow, Gradient, Ripple, Reactive
+);
+
+impl Effect {
+    /// If the effect is triggered by keys being pressed
+    pub fn is_reactive(&self) -> bool {
+        matches!(self, Effect::Ripple(_) | Effect::Reactive(_))
+    }
+
+    /// Trigger the effect at `led` if it reacts to keys
+    pub fn trigger(&mut self, led: LedCode) {
+        match self {
+            Effect::Ripple(c) => c.trigger(led),
+            Effect::Reactive(c) => c.trigger(led),
+            _ => {}
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::effects::{AdvancedEffects, Breathe, DoomFlicker, Effect, Static};
+    use crate::effects::{
+        AdvancedEffects, Breathe, DoomFlicker, Effect, Gradient, Rainbow, Reactive, Ripple, Static,
+        Wave,
+    };
+    use std::time::Duration;
+
     use crate::keyboard::{KeyLayout, LedCode};
-    use crate::{Colour, Speed};
+    use crate::{Colour, Direction, Speed};
+
+    fn colour_of(effect: &Effect, led: LedCode) -> Option<Colour> {
+        effect.colours().find(|(l, _)| *l == led).map(|(_, c)| c)
+    }
+
+    /// The colour in `packets` starting at `row` and `col`
+    fn packet_colour(packets: &[Vec<u8>], row: usize, col: usize) -> Colour {
+        Colour {
+            r: packets[row][col],
+            g: packets[row][col + 1],
+            b: packets[row][col + 2],
+        }
+    }
 
     #[test]
     fn single_key_next_state_then_create() {
@@ -304,4 +453,241 @@ mod tests {
         assert_eq!(packets[5][34], 87);
         assert_eq!(packets[5][35], 40);
     }
+
+    #[test]
+    fn gradient_across_layout() {
+        let layout = KeyLayout::default_layout();
+        let red = Colour { r: 255, g: 0, b: 0 };
+        let blue = Colour { r: 0, g: 0, b: 255 };
+        let mut effect = Effect::Gradient(Gradient::new(vec![red, blue], Direction::Right));
+        effect.next_state(&layout);
+
+        assert_eq!(effect.colours().count(), layout.key_positions().len());
+        assert_eq!(colour_of(&effect, LedCode::Esc), Some(red));
+        assert_eq!(colour_of(&effect, LedCode::Backspace), Some(blue));
+        let middle = colour_of(&effect, LedCode::N6).unwrap();
+        assert!(middle.r > 0 && middle.b > 0 && middle.g == 0);
+
+        effect = Effect::Gradient(Gradient::new(vec![red, blue], Direction::Up));
+        effect.next_state(&layout);
+        assert_eq!(colour_of(&effect, LedCode::Esc), Some(blue));
+        assert_eq!(colour_of(&effect, LedCode::Spacebar), Some(red));
+    }
+
+    #[test]
+    fn wave_half_a_wavelength_apart() {
+        let layout = KeyLayout::default_layout();
+        let grey = Colour {
+            r: 200,
+            g: 200,
+            b: 200,
+        };
+        // Keys are 1.2 apart, so neighbours are half a wave apart
+        let mut effect = Effect::Wave(Wave::new(
+            Colour { r: 0, g: 0, b: 0 },
+            grey,
+            Direction::Right,
+            Speed::Med,
+            2.4,
+        ));
+        effect.next_state(&layout);
+        let q = colour_of(&effect, LedCode::Q).unwrap();
+        let w = colour_of(&effect, LedCode::W).unwrap();
+        assert!((q.r as i32 + w.r as i32 - 200).abs() <= 1);
+
+        effect.next_state(&layout);
+        assert_ne!(colour_of(&effect, LedCode::Q), Some(q));
+    }
+
+    #[test]
+    fn rainbow_cycles_hue() {
+        let layout = KeyLayout::default_layout();
+        let mut effect = Effect::Rainbow(Rainbow::new(Direction::Right, Speed::Med, 0.0));
+        effect.next_state(&layout);
+        assert!(effect.colours().all(|(_, c)| c
+            == Colour {
+                r: 255,
+                g: 13,
+                b: 0
+            }));
+
+        let mut effect = Effect::Rainbow(Rainbow::new(Direction::Right, Speed::Med, 30.0));
+        effect.next_state(&layout);
+        assert_ne!(
+            colour_of(&effect, LedCode::Q),
+            colour_of(&effect, LedCode::P)
+        );
+    }
+
+    #[test]
+    fn ripple_spreads_from_key() {
+        let layout = KeyLayout::default_layout();
+        let colour = Colour { r: 0, g: 255, b: 0 };
+        let mut effect = Effect::Ripple(Ripple::new(LedCode::F, colour, Speed::Med, 1.0, true));
+        effect.next_state(&layout);
+        assert!(colour_of(&effect, LedCode::F).is_some());
+        assert!(colour_of(&effect, LedCode::J).is_none());
+        assert!(colour_of(&effect, LedCode::Esc).is_none());
+
+        for _ in 0..10 {
+            effect.next_state(&layout);
+        }
+        assert!(colour_of(&effect, LedCode::F).is_none());
+        let j = colour_of(&effect, LedCode::J).unwrap();
+        assert!(j.g > 0 && j.g < 255);
+
+        // Without repeat ripples wait to be started
+        let mut ripple = Ripple::new(LedCode::F, colour, Speed::Med, 1.0, false);
+        let mut effect = Effect::Ripple(ripple.clone());
+        effect.next_state(&layout);
+        assert!(effect.colours().next().is_none());
+        ripple.start_at(LedCode::Esc);
+        let mut effect = Effect::Ripple(ripple);
+        effect.next_state(&layout);
+        assert!(colour_of(&effect, LedCode::Esc).is_some());
+    }
+
+    #[test]
+    fn reactive_keys_fade() {
+        let layout = KeyLayout::default_layout();
+        let colour = Colour {
+            r: 255,
+            g: 0,
+            b: 255,
+        };
+        let mut seq = AdvancedEffects::new(false);
+        seq.push(Effect::Static(Static::new(LedCode::Esc, colour)));
+        assert!(!seq.is_reactive());
+        seq.push(Effect::Reactive(Reactive::new(colour, Speed::Med)));
+        assert!(seq.is_reactive());
+
+        seq.next_state(&layout);
+        assert!(seq.effects[1].colours().next().is_none());
+
+        seq.trigger(LedCode::A);
+        seq.next_state(&layout);
+        assert_eq!(
+            seq.effects[1].colours().collect::<Vec<_>>(),
+            vec![(LedCode::A, colour)]
+        );
+        let mut expected = AdvancedEffects::new(false);
+        expected.push(Effect::Static(Static::new(LedCode::Esc, colour)));
+        expected.push(Effect::Static(Static::new(LedCode::A, colour)));
+        expected.next_state(&layout);
+        assert_eq!(seq.create_packets(), expected.create_packets());
+
+        seq.next_state(&layout);
+        let faded = colour_of(&seq.effects[1], LedCode::A).unwrap();
+        assert!(faded.r < 255 && faded.r > 0);
+        // Pressing again lights the key fully
+        seq.trigger(LedCode::A);
+        seq.next_state(&layout);
+        assert_eq!(colour_of(&seq.effects[1], LedCode::A), Some(colour));
+
+        // Gone after a second at 30 frames a second
+        for _ in 0..31 {
+            seq.next_state(&layout);
+        }
+        assert!(seq.effects[1].colours().next().is_none());
+    }
+
+    #[test]
+    fn layout_effects_serde() {
+        let mut seq = AdvancedEffects::new(false);
+        seq.push(Effect::Gradient(Gradient::new(
+            vec![Colour { r: 255, g: 0, b: 0 }, Colour { r: 0, g: 0, b: 255 }],
+            Direction::Down,
+        )));
+        seq.push(Effect::Wave(Wave::new(
+            Colour { r: 0, g: 0, b: 0 },
+            Colour { r: 0, g: 0, b: 255 },
+            Direction::Left,
+            Speed::High,
+            4.0,
+        )));
+        seq.push(Effect::Rainbow(Rainbow::new(
+            Direction::Up,
+            Speed::Low,
+            10.0,
+        )));
+        seq.push(Effect::Ripple(Ripple::new(
+            LedCode::F,
+            Colour {
+                r: 255,
+                g: 255,
+                b: 255,
+            },
+            Speed::Med,
+            1.5,
+            true,
+        )));
+
+        let s = ron::ser::to_string_pretty(&seq, ron::ser::PrettyConfig::new()).unwrap();
+        let mut loaded: AdvancedEffects = ron::from_str(&s).unwrap();
+        assert_eq!(loaded.effects.len(), 4);
+
+        let layout = KeyLayout::default_layout();
+        seq.next_state(&layout);
+        loaded.next_state(&layout);
+        let packets = loaded.create_packets();
+        assert_eq!(packets, seq.create_packets());
+
+        // Later effects are drawn over earlier ones, the rainbow covers every
+        // key and the ripple is only around F
+        let rainbow_esc = colour_of(&loaded.effects[2], LedCode::Esc).unwrap();
+        assert_ne!(
+            colour_of(&loaded.effects[0], LedCode::Esc),
+            Some(rainbow_esc)
+        );
+        assert_eq!(packet_colour(&packets, 1, 24), rainbow_esc);
+        assert!(colour_of(&loaded.effects[3], LedCode::Esc).is_none());
+        let ripple_f = colour_of(&loaded.effects[3], LedCode::F).unwrap();
+        assert!(ripple_f.r > 0 && ripple_f.r == ripple_f.g && ripple_f.g == ripple_f.b);
+        assert_eq!(packet_colour(&packets, 5, 33), ripple_f);
+    }
+
+    #[test]
+    fn layout_effects_keep_speed_at_any_frame_rate() {
+        let layout = KeyLayout::default_layout();
+        let wave = Effect::Wave(Wave::new(
+            Colour { r: 0, g: 0, b: 0 },
+            Colour { r: 0, g: 255, b: 0 },
+            Direction::Right,
+            Speed::High,
+            6.0,
+        ));
+        let mut at_25fps = wave.clone();
+        at_25fps.next_state_after(&layout, Duration::from_millis(40));
+        let mut at_50fps = wave;
+        at_50fps.next_state_after(&layout, Duration::from_millis(20));
+        assert_ne!(
+            at_25fps.colours().collect::<Vec<_>>(),
+            at_50fps.colours().collect::<Vec<_>>()
+        );
+        at_50fps.next_state_after(&layout, Duration::from_millis(20));
+        assert_eq!(
+            at_25fps.colours().collect::<Vec<_>>(),
+            at_50fps.colours().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn led_and_colour_of_layout_effects() {
+        let layout = KeyLayout::default_layout();
+        let colour = Colour { r: 0, g: 0, b: 255 };
+        let mut effect = Effect::Static(Static::new(LedCode::F, colour));
+        effect.next_state(&layout);
+        assert_eq!(effect.led(), LedCode::F);
+        assert_eq!(effect.colour(), colour);
+
+        // Effects across the layout give the first key they set
+        let mut effect = Effect::Reactive(Reactive::new(colour, Speed::Med));
+        effect.next_state(&layout);
+        assert_eq!(effect.led(), LedCode::default());
+        assert_eq!(effect.colour(), Colour::default());
+        effect.trigger(LedCode::J);
+        effect.next_state(&layout);
+        assert_eq!(effect.led(), LedCode::J);
+        assert_eq!(effect.colour(), colour);
+    }
 }
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{along, cycles_in, LayoutEffectState};
+use crate::keyboard::{KeyLayout, LedCode};
+use crate::{Colour, Direction, Speed};
+
+/// Cycle every key through the colours of the rainbow
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Rainbow {
+    /// The way the colours move when `spread` is used
+    direction: Direction,
+    speed: Speed,
+    /// Degrees of hue between keys one key width apart, or 0.0 for the whole
+    /// layout to be one colour
+    spread: f32,
+    #[serde(skip)]
+    hue: f32,
+    #[serde(skip)]
+    colours: Vec<(LedCode, Colour)>,
+}
+
+impl Rainbow {
+    pub fn new(direction: Direction, speed: Speed, spread: f32) -> Self {
+        Self {
+            direction,
+            speed,
+            spread,
+            hue: 0.0,
+            colours: Vec::new(),
+        }
+    }
+}
+
+impl LayoutEffectState for Rainbow {
+    fn next_colour_state(&mut self, layout: &KeyLayout, elapsed: Duration) {
+        // A quarter of the speed of a wave, or the colours flash past
+        self.hue = (self.hue + 90.0 * cycles_in(self.speed, elapsed)) % 360.0;
+        self.colours = layout
+            .key_positions()
+            .iter()
+            .map(|pos| {
+                let hue = self.hue - self.spread * along(self.direction, pos);
+                (pos.led, Colour::from_hue(hue))
+            })
+            .collect();
+    }
+
+    fn get_colours(&self) -> &[(LedCode, Colour)] {
+        &self.colours
+    }
+}
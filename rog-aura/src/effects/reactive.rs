@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{cycles_in, LayoutEffectState, TriggerForEffect};
+use crate::keyboard::{KeyLayout, LedCode};
+use crate::{Colour, Speed};
+
+/// Keys light up when pressed and fade out. Only the keys still fading are
+/// set, so other effects show under the rest.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Reactive {
+    colour: Colour,
+    /// How quickly a pressed key fades out
+    speed: Speed,
+    /// The keys pressed and how lit they still are
+    #[serde(skip)]
+    lit: Vec<(LedCode, f32)>,
+    #[serde(skip)]
+    colours: Vec<(LedCode, Colour)>,
+}
+
+impl Reactive {
+    pub fn new(colour: Colour, speed: Speed) -> Self {
+        Self {
+            colour,
+            speed,
+            lit: Vec::new(),
+            colours: Vec::new(),
+        }
+    }
+}
+
+impl TriggerForEffect for Reactive {
+    fn trigger(&mut self, led: LedCode) {
+        if let Some(key) = self.lit.iter_mut().find(|(l, _)| *l == led) {
+            key.1 = 1.0;
+        } else {
+            self.lit.push((led, 1.0));
+        }
+    }
+}
+
+impl LayoutEffectState for Reactive {
+    fn next_colour_state(&mut self, _layout: &KeyLayout, elapsed: Duration) {
+        self.colours = self
+            .lit
+            .iter()
+            .map(|(led, amount)| (*led, self.colour.scale(*amount)))
+            .collect();
+
+        let step = cycles_in(self.speed, elapsed);
+        for key in &mut self.lit {
+            key.1 -= step;
+        }
+        self.lit.retain(|(_, amount)| *amount > 0.0);
+    }
+
+    fn get_colours(&self) -> &[(LedCode, Colour)] {
+        &self.colours
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{cycles_in, LayoutEffectState, TriggerForEffect};
+use crate::keyboard::{KeyLayout, LedCode};
+use crate::{Colour, Speed};
+
+#[derive(Debug, Clone, Copy)]
+struct Ring {
+    x: f32,
+    y: f32,
+    radius: f32,
+}
+
+/// Rings of colour spreading out from a key and fading as they go. Only the
+/// keys a ring is passing over are set, so other effects show between them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Ripple {
+    /// The key a ripple starts from when `repeat` is set
+    led: LedCode,
+    colour: Colour,
+    speed: Speed,
+    /// How wide a ring is, in key widths
+    width: f32,
+    /// Start a new ripple from `led` each time the last is gone. Otherwise
+    /// ripples only start when `start_at()` is called.
+    repeat: bool,
+    #[serde(skip)]
+    starting: Vec<LedCode>,
+    #[serde(skip)]
+    rings: Vec<Ring>,
+    #[serde(skip)]
+    colours: Vec<(LedCode, Colour)>,
+}
+
+impl Ripple {
+    pub fn new(led: LedCode, colour: Colour, speed: Speed, width: f32, repeat: bool) -> Self {
+        Self {
+            led,
+            colour,
+            speed,
+            width,
+            repeat,
+            starting: Vec::new(),
+            rings: Vec::new(),
+            colours: Vec::new(),
+        }
+    }
+
+    /// Start a ripple from `led` on the next frame
+    pub fn start_at(&mut self, led: LedCode) {
+        self.starting.push(led);
+    }
+}
+
+impl TriggerForEffect for Ripple {
+    fn trigger(&mut self, led: LedCode) {
+        self.start_at(led);
+    }
+}
+
+impl LayoutEffectState for Ripple {
+    fn next_colour_state(&mut self, layout: &KeyLayout, elapsed: Duration) {
+        let positions = layout.key_positions();
+        if self.repeat && self.rings.is_empty() && self.starting.is_empty() {
+            self.starting.push(self.led);
+        }
+        for led in self.starting.drain(..) {
+            if let Some(pos) = positions.iter().find(|p| p.led == led) {
+                self.rings.push(Ring {
+                    x: pos.x,
+                    y: pos.y,
+                    radius: 0.0,
+                });
+            }
+        }
+
+        // Far enough to have crossed the whole layout from any key
+        let max_radius = layout.max_width().max(layout.max_height()).max(1.0);
+        let step = 10.0 * cycles_in(self.speed, elapsed);
+        for ring in &mut self.rings {
+            ring.radius += step;
+        }
+        self.rings.retain(|r| r.radius < max_radius);
+
+        let width = self.width.max(0.1);
+        self.colours = positions
+            .iter()
+            .filter_map(|pos| {
+                let intensity = self
+                    .rings
+                    .iter()
+                    .map(|ring| {
+                        let distance = (pos.x - ring.x).hypot(pos.y - ring.y);
+                        let off_ring = (distance - ring.radius).abs() / width;
+                        (1.0 - off_ring).max(0.0) * (1.0 - ring.radius / max_radius)
+                    })
+                    .fold(0.0, f32::max);
+                (intensity > 0.0).then(|| (pos.led, self.colour.scale(intensity)))
+            })
+            .collect();
+    }
+
+    fn get_colours(&self) -> &[(LedCode, Colour)] {
+        &self.colours
+    }
+}
@@ -0,0 +1,64 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{along, cycles_in, LayoutEffectState};
+use crate::keyboard::{KeyLayout, LedCode};
+use crate::{Colour, Direction, Speed};
+
+/// Bands of colour moving across the layout, blending between two colours
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Wave {
+    colour1: Colour,
+    colour2: Colour,
+    /// The way the wave moves
+    direction: Direction,
+    speed: Speed,
+    /// The distance between peaks of `colour2`, in key widths
+    wavelength: f32,
+    #[serde(skip)]
+    phase: f32,
+    #[serde(skip)]
+    colours: Vec<(LedCode, Colour)>,
+}
+
+impl Wave {
+    pub fn new(
+        colour1: Colour,
+        colour2: Colour,
+        direction: Direction,
+        speed: Speed,
+        wavelength: f32,
+    ) -> Self {
+        Self {
+            colour1,
+            colour2,
+            direction,
+            speed,
+            wavelength,
+            phase: 0.0,
+            colours: Vec::new(),
+        }
+    }
+}
+
+impl LayoutEffectState for Wave {
+    fn next_colour_state(&mut self, layout: &KeyLayout, elapsed: Duration) {
+        self.phase = (self.phase + cycles_in(self.speed, elapsed)).fract();
+        let wavelength = self.wavelength.max(0.1);
+        self.colours = layout
+            .key_positions()
+            .iter()
+            .map(|pos| {
+                let cycle = along(self.direction, pos) / wavelength - self.phase;
+                let t = (1.0 - (cycle * TAU).cos()) / 2.0;
+                (pos.led, self.colour1.lerp(&self.colour2, t))
+            })
+            .collect();
+    }
+
+    fn get_colours(&self) -> &[(LedCode, Colour)] {
+        &self.colours
+    }
+}
@@ -0,0 +1,270 @@
+use super::LedCode;
+
+// Key codes from linux/input-event-codes.h
+const KEY_ESC: u16 = 1;
+const KEY_1: u16 = 2;
+const KEY_2: u16 = 3;
+const KEY_3: u16 = 4;
+const KEY_4: u16 = 5;
+const KEY_5: u16 = 6;
+const KEY_6: u16 = 7;
+const KEY_7: u16 = 8;
+const KEY_8: u16 = 9;
+const KEY_9: u16 = 10;
+const KEY_0: u16 = 11;
+const KEY_MINUS: u16 = 12;
+const KEY_EQUAL: u16 = 13;
+const KEY_BACKSPACE: u16 = 14;
+const KEY_TAB: u16 = 15;
+const KEY_Q: u16 = 16;
+const KEY_W: u16 = 17;
+const KEY_E: u16 = 18;
+const KEY_R: u16 = 19;
+const KEY_T: u16 = 20;
+const KEY_Y: u16 = 21;
+const KEY_U: u16 = 22;
+const KEY_I: u16 = 23;
+const KEY_O: u16 = 24;
+const KEY_P: u16 = 25;
+const KEY_LEFTBRACE: u16 = 26;
+const KEY_RIGHTBRACE: u16 = 27;
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_A: u16 = 30;
+const KEY_S: u16 = 31;
+const KEY_D: u16 = 32;
+const KEY_F: u16 = 33;
+const KEY_G: u16 = 34;
+const KEY_H: u16 = 35;
+const KEY_J: u16 = 36;
+const KEY_K: u16 = 37;
+const KEY_L: u16 = 38;
+const KEY_SEMICOLON: u16 = 39;
+const KEY_APOSTROPHE: u16 = 40;
+const KEY_GRAVE: u16 = 41;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_BACKSLASH: u16 = 43;
+const KEY_Z: u16 = 44;
+const KEY_X: u16 = 45;
+const KEY_C: u16 = 46;
+const KEY_V: u16 = 47;
+const KEY_B: u16 = 48;
+const KEY_N: u16 = 49;
+const KEY_M: u16 = 50;
+const KEY_COMMA: u16 = 51;
+const KEY_DOT: u16 = 52;
+const KEY_SLASH: u16 = 53;
+const KEY_RIGHTSHIFT: u16 = 54;
+const KEY_KPASTERISK: u16 = 55;
+const KEY_LEFTALT: u16 = 56;
+const KEY_SPACE: u16 = 57;
+const KEY_CAPSLOCK: u16 = 58;
+const KEY_F1: u16 = 59;
+const KEY_F2: u16 = 60;
+const KEY_F3: u16 = 61;
+const KEY_F4: u16 = 62;
+const KEY_F5: u16 = 63;
+const KEY_F6: u16 = 64;
+const KEY_F7: u16 = 65;
+const KEY_F8: u16 = 66;
+const KEY_F9: u16 = 67;
+const KEY_F10: u16 = 68;
+const KEY_NUMLOCK: u16 = 69;
+const KEY_KPPLUS: u16 = 78;
+const KEY_F11: u16 = 87;
+const KEY_F12: u16 = 88;
+const KEY_KPENTER: u16 = 96;
+const KEY_RIGHTCTRL: u16 = 97;
+const KEY_SYSRQ: u16 = 99;
+const KEY_RIGHTALT: u16 = 100;
+const KEY_HOME: u16 = 102;
+const KEY_UP: u16 = 103;
+const KEY_PAGEUP: u16 = 104;
+const KEY_LEFT: u16 = 105;
+const KEY_RIGHT: u16 = 106;
+const KEY_END: u16 = 107;
+const KEY_DOWN: u16 = 108;
+const KEY_PAGEDOWN: u16 = 109;
+const KEY_DELETE: u16 = 111;
+const KEY_VOLUMEDOWN: u16 = 114;
+const KEY_VOLUMEUP: u16 = 115;
+const KEY_PAUSE: u16 = 119;
+const KEY_LEFTMETA: u16 = 125;
+/// The ROG key, as mapped by asus-wmi and hid-asus
+const KEY_PROG1: u16 = 148;
+const KEY_NEXTSONG: u16 = 163;
+const KEY_PLAYPAUSE: u16 = 164;
+const KEY_PREVIOUSSONG: u16 = 165;
+const KEY_STOPCD: u16 = 166;
+/// The fan key, as mapped by hid-asus
+const KEY_PROG4: u16 = 203;
+const KEY_MICMUTE: u16 = 248;
+
+impl LedCode {
+    /// The LEDs of the key with the evdev key `code`, as read from the
+    /// keyboard's `/dev/input/event*` node. Keys which can have more than one
+    /// LED, such as backspace, give all the codes used for them by the
+    /// different layouts. Empty if the key has no LED.
+    pub fn from_evdev(code: u16) -> &'static [LedCode] {
+        match code {
+            KEY_ESC => &[LedCode::Esc],
+            KEY_1 => &[LedCode::N1],
+            KEY_2 => &[LedCode::N2],
+            KEY_3 => &[LedCode::N3],
+            KEY_4 => &[LedCode::N4],
+            KEY_5 => &[LedCode::N5],
+            KEY_6 => &[LedCode::N6],
+            KEY_7 => &[LedCode::N7],
+            KEY_8 => &[LedCode::N8],
+            KEY_9 => &[LedCode::N9],
+            KEY_0 => &[LedCode::N0],
+            KEY_MINUS => &[LedCode::Hyphen],
+            KEY_EQUAL => &[LedCode::Equals],
+            KEY_BACKSPACE => &[
+                LedCode::Backspace,
+                LedCode::Backspace3_1,
+                LedCode::Backspace3_2,
+                LedCode::Backspace3_3,
+            ],
+            KEY_TAB => &[LedCode::Tab],
+            KEY_Q => &[LedCode::Q],
+            KEY_W => &[LedCode::W],
+            KEY_E => &[LedCode::E],
+            KEY_R => &[LedCode::R],
+            KEY_T => &[LedCode::T],
+            KEY_Y => &[LedCode::Y],
+            KEY_U => &[LedCode::U],
+            KEY_I => &[LedCode::I],
+            KEY_O => &[LedCode::O],
+            KEY_P => &[LedCode::P],
+            KEY_LEFTBRACE => &[LedCode::LBracket],
+            KEY_RIGHTBRACE => &[LedCode::RBracket],
+            KEY_ENTER => &[
+                LedCode::Return,
+                LedCode::Return3_1,
+                LedCode::Return3_2,
+                LedCode::Return3_3,
+            ],
+            KEY_LEFTCTRL => &[LedCode::LCtrl],
+            KEY_A => &[LedCode::A],
+            KEY_S => &[LedCode::S],
+            KEY_D => &[LedCode::D],
+            KEY_F => &[LedCode::F],
+            KEY_G => &[LedCode::G],
+            KEY_H => &[LedCode::H],
+            KEY_J => &[LedCode::J],
+            KEY_K => &[LedCode::K],
+            KEY_L => &[LedCode::L],
+            KEY_SEMICOLON => &[LedCode::SemiColon],
+            KEY_APOSTROPHE => &[LedCode::Quote],
+            KEY_GRAVE => &[LedCode::Tilde],
+            KEY_LEFTSHIFT => &[
+                LedCode::LShift,
+                LedCode::LShift3_1,
+                LedCode::LShift3_2,
+                LedCode::LShift3_3,
+            ],
+            KEY_BACKSLASH => &[LedCode::BackSlash],
+            KEY_Z => &[LedCode::Z],
+            KEY_X => &[LedCode::X],
+            KEY_C => &[LedCode::C],
+            KEY_V => &[LedCode::V],
+            KEY_B => &[LedCode::B],
+            KEY_N => &[LedCode::N],
+            KEY_M => &[LedCode::M],
+            KEY_COMMA => &[LedCode::Comma],
+            KEY_DOT => &[LedCode::Period],
+            KEY_SLASH => &[LedCode::FwdSlash],
+            KEY_RIGHTSHIFT => &[
+                LedCode::Rshift,
+                LedCode::Rshift3_1,
+                LedCode::Rshift3_2,
+                LedCode::Rshift3_3,
+            ],
+            KEY_KPASTERISK => &[LedCode::Star],
+            KEY_LEFTALT => &[LedCode::LAlt],
+            KEY_SPACE => &[
+                LedCode::Spacebar,
+                LedCode::Spacebar5_1,
+                LedCode::Spacebar5_2,
+                LedCode::Spacebar5_3,
+                LedCode::Spacebar5_4,
+                LedCode::Spacebar5_5,
+            ],
+            KEY_CAPSLOCK => &[LedCode::Caps],
+            KEY_F1 => &[LedCode::F1],
+            KEY_F2 => &[LedCode::F2],
+            KEY_F3 => &[LedCode::F3],
+            KEY_F4 => &[LedCode::F4],
+            KEY_F5 => &[LedCode::F5],
+            KEY_F6 => &[LedCode::F6],
+            KEY_F7 => &[LedCode::F7],
+            KEY_F8 => &[LedCode::F8],
+            KEY_F9 => &[LedCode::F9],
+            KEY_F10 => &[LedCode::F10],
+            KEY_F11 => &[LedCode::F11],
+            KEY_F12 => &[LedCode::F12],
+            KEY_NUMLOCK => &[LedCode::NumLock],
+            KEY_KPPLUS => &[LedCode::NumPadPlus],
+            KEY_KPENTER => &[LedCode::NumPadEnter],
+            KEY_RIGHTCTRL => &[LedCode::RCtrl],
+            KEY_SYSRQ => &[LedCode::PrtSc, LedCode::NumPadPrtSc],
+            KEY_RIGHTALT => &[LedCode::RAlt],
+            KEY_HOME => &[LedCode::Home, LedCode::NumPadHome],
+            KEY_UP => &[LedCode::Up],
+            KEY_PAGEUP => &[LedCode::PgUp],
+            KEY_LEFT => &[LedCode::Left],
+            KEY_RIGHT => &[LedCode::Right],
+            KEY_END => &[LedCode::End],
+            KEY_DOWN => &[LedCode::Down],
+            KEY_PAGEDOWN => &[LedCode::PgDn],
+            KEY_DELETE => &[LedCode::Del, LedCode::NumPadDel],
+            KEY_VOLUMEDOWN => &[LedCode::VolDown],
+            KEY_VOLUMEUP => &[LedCode::VolUp],
+            KEY_PAUSE => &[LedCode::Pause, LedCode::NumPadPause],
+            KEY_LEFTMETA => &[LedCode::Meta],
+            KEY_PROG1 => &[LedCode::RogApp],
+            KEY_NEXTSONG => &[LedCode::MediaNext],
+            KEY_PLAYPAUSE => &[LedCode::MediaPlay],
+            KEY_PREVIOUSSONG => &[LedCode::MediaPrev],
+            KEY_STOPCD => &[LedCode::MediaStop],
+            KEY_PROG4 => &[LedCode::RogFan],
+            KEY_MICMUTE => &[LedCode::MicMute],
+            _ => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keyboard::{KeyLayout, LedCode};
+
+    #[test]
+    fn evdev_codes_to_leds() {
+        assert_eq!(LedCode::from_evdev(1), &[LedCode::Esc]);
+        assert_eq!(LedCode::from_evdev(30), &[LedCode::A]);
+        assert_eq!(LedCode::from_evdev(33), &[LedCode::F]);
+        assert_eq!(LedCode::from_evdev(11), &[LedCode::N0]);
+        assert_eq!(LedCode::from_evdev(88), &[LedCode::F12]);
+        assert_eq!(LedCode::from_evdev(148), &[LedCode::RogApp]);
+        assert!(LedCode::from_evdev(14).contains(&LedCode::Backspace3_2));
+        // KEY_RESERVED and KEY_POWER have no LED
+        assert!(LedCode::from_evdev(0).is_empty());
+        assert!(LedCode::from_evdev(116).is_empty());
+    }
+
+    #[test]
+    fn evdev_codes_cover_default_layout() {
+        let layout = KeyLayout::default_layout();
+        let mapped: Vec<LedCode> = (0..=255u16)
+            .flat_map(|code| LedCode::from_evdev(code).iter().copied())
+            .collect();
+        for pos in layout.key_positions() {
+            // Fn is handled by the firmware and never reaches evdev
+            if matches!(pos.led, LedCode::LFn | LedCode::RFn) {
+                continue;
+            }
+            assert!(mapped.contains(&pos.led), "{:?} has no evdev code", pos.led);
+        }
+    }
+}
@@ -13,6 +13,9 @@ use crate::error::Error;
 use crate::keyboard::{AdvancedAuraType, LedCode};
 use crate::{AuraModeNum, AuraZone};
 
+/// Where asusd installs the key layouts, each in `layouts/`
+pub const LAYOUT_DATA_DIR: &str = "/usr/share/asusd/";
+
 /// The `key_type` plays a role in effects (eventually). You could for example
 /// add a `ShapeType::Spacing` to pad out an effect, such as a laserbeam across
 /// a row so that it doesn't appear to *jump* across a gap
@@ -163,6 +166,14 @@ impl KeyRow {
     }
 }
 
+/// Where an LED is in a `KeyLayout`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyPosition {
+    pub led: LedCode,
+    pub x: f32,
+    pub y: f32,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct KeyLayout {
     /// Localization of this keyboard layout
@@ -200,29 +211,65 @@ impl KeyLayout {
         } else {
             let mut data = ron::from_str::<Self>(&buf)?;
 
-            let mut unused = HashSet::new();
-            for k in data.key_shapes.keys() {
-                unused.insert(k);
+            let unused = data.build_rows();
+            if !unused.is_empty() {
+                warn!("The layout {path:?} had unused shapes {unused:?}",);
             }
 
-            let rows = &mut data.key_rows;
-            for row in rows {
-                for k in &mut row.row {
-                    if let Some(shape) = data.key_shapes.get(&k.1) {
-                        row.built_row.push((k.0, shape.clone()));
-                        unused.remove(&k.1);
-                    } else {
-                        warn!("Key {:?} was missing matching shape {}", k.0, k.1);
-                    }
+            Ok(data)
+        }
+    }
+
+    /// Merge the `key_shapes` in to each row, returning the names of any shapes
+    /// not used
+    fn build_rows(&mut self) -> HashSet<String> {
+        let mut unused: HashSet<String> = self.key_shapes.keys().cloned().collect();
+        for row in &mut self.key_rows {
+            row.built_row.clear();
+            for k in &row.row {
+                if let Some(shape) = self.key_shapes.get(&k.1) {
+                    row.built_row.push((k.0, shape.clone()));
+                    unused.remove(&k.1);
+                } else {
+                    warn!("Key {:?} was missing matching shape {}", k.0, k.1);
                 }
             }
+        }
+        unused
+    }
 
-            if !unused.is_empty() {
-                warn!("The layout {path:?} had unused shapes {unused:?}",);
+    /// The centre of every LED in the layout, with x to the right and y down
+    /// from the top left corner. The units are the same as the key shapes,
+    /// where 1.0 is the size of a typical key.
+    pub fn key_positions(&self) -> Vec<KeyPosition> {
+        let mut positions = Vec::new();
+        let mut y = 0.0;
+        for row in &self.key_rows {
+            y += row.pad_top;
+            let mut x = row.pad_left;
+            for (led, shape) in row.row() {
+                match shape {
+                    KeyShape::Led {
+                        width,
+                        height,
+                        pad_left,
+                        pad_right,
+                        pad_top,
+                        ..
+                    } => {
+                        positions.push(KeyPosition {
+                            led: *led,
+                            x: x + pad_left + width / 2.0,
+                            y: y + pad_top + height / 2.0,
+                        });
+                        x += pad_left + width + pad_right;
+                    }
+                    KeyShape::Blank { width, .. } => x += width,
+                }
             }
-
-            Ok(data)
+            y += row.height();
         }
+        positions
     }
 
     pub fn rows(&self) -> Iter<'_, KeyRow> {
@@ -321,7 +368,7 @@ impl KeyLayout {
 
 impl KeyLayout {
     pub fn default_layout() -> Self {
-        Self {
+        let mut layout = Self {
             locale: "US".to_owned(),
             basic_modes: vec![
                 AuraModeNum::Static,
@@ -447,7 +494,9 @@ impl KeyLayout {
                     ],
                 ),
             ],
-        }
+        };
+        layout.build_rows();
+        layout
     }
 }
 
@@ -459,7 +508,7 @@ mod tests {
     use std::path::PathBuf;
 
     use crate::aura_detection::LedSupportFile;
-    use crate::keyboard::KeyLayout;
+    use crate::keyboard::{KeyLayout, LedCode};
 
     #[test]
     fn check_parse_all() {
@@ -516,6 +565,21 @@ mod tests {
         // data).unwrap(); file.write_all(json.as_bytes()).unwrap();
     }
 
+    #[test]
+    fn default_layout_key_positions() {
+        let positions = KeyLayout::default_layout().key_positions();
+        let esc = positions[0];
+        assert_eq!(esc.led, LedCode::Esc);
+        assert!((esc.x - 0.7).abs() < 0.001 && (esc.y - 0.7).abs() < 0.001);
+        let f1 = positions[1];
+        assert_eq!(f1.led, LedCode::F1);
+        assert!((f1.x - 1.9).abs() < 0.001 && (f1.y - 0.7).abs() < 0.001);
+        // The second row starts below the first, plus its own padding
+        let tilde = positions[13];
+        assert_eq!(tilde.led, LedCode::Tilde);
+        assert!((tilde.x - 0.7).abs() < 0.001 && (tilde.y - 2.0).abs() < 0.001);
+    }
+
     #[test]
     fn check_layout_file_links() {
         const DATA_DIR: &str = env!("CARGO_MANIFEST_DIR");
@@ -9,6 +9,9 @@ pub use layouts::*;
 mod power;
 pub use power::*;
 
+/// Mapping of evdev key codes to `LedCode`, for effects reacting to typing
+mod evdev;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
 pub enum AdvancedAuraType {
     /// A `None` will apply the effect to the whole keyboard via basic-static
@@ -105,45 +105,30 @@ pub fn start_notifications(
             })
             .unwrap();
 
-        let mut last_state = power.get_online().unwrap_or_default();
-        loop {
-            if let Ok(p) = power.get_online() {
-                let mut ac = String::new();
-                let mut bat = String::new();
-                if let Ok(config) = config_copy.lock() {
-                    ac.clone_from(&config.ac_command);
-                    bat.clone_from(&config.bat_command);
-                }
-
-                if p == 0 && p != last_state {
-                    let prog: Vec<&str> = bat.split_whitespace().collect();
-                    if prog.len() > 1 {
-                        let mut cmd = Command::new(prog[0]);
+        power.watch_online(|plugged| {
+            let mut ac = String::new();
+            let mut bat = String::new();
+            if let Ok(config) = config_copy.lock() {
+                ac.clone_from(&config.ac_command);
+                bat.clone_from(&config.bat_command);
+            }
 
-                        for arg in prog.iter().skip(1) {
-                            cmd.arg(*arg);
-                        }
-                        cmd.spawn()
-                            .map_err(|e| error!("AC command error: {e:?}"))
-                            .ok();
-                    }
-                } else if p != last_state {
-                    let prog: Vec<&str> = ac.split_whitespace().collect();
-                    if prog.len() > 1 {
-                        let mut cmd = Command::new(prog[0]);
+            let prog: Vec<&str> = if plugged {
+                ac.split_whitespace().collect()
+            } else {
+                bat.split_whitespace().collect()
+            };
+            if prog.len() > 1 {
+                let mut cmd = Command::new(prog[0]);
 
-                        for arg in prog.iter().skip(1) {
-                            cmd.arg(*arg);
-                        }
-                        cmd.spawn()
-                            .map_err(|e| error!("AC command error: {e:?}"))
-                            .ok();
-                    }
+                for arg in prog.iter().skip(1) {
+                    cmd.arg(*arg);
                 }
-                last_state = p;
+                cmd.spawn()
+                    .map_err(|e| error!("AC command error: {e:?}"))
+                    .ok();
             }
-            std::thread::sleep(Duration::from_millis(500));
-        }
+        });
     });
 
     let enabled_notifications_copy = config.clone();
@@ -4,6 +4,8 @@ use log::{error, info};
 use rog_dbus::zbus_fan_curves::FanCurvesProxy;
 use rog_platform::platform::ThrottlePolicy;
 use rog_profiles::fan_curve_set::CurveData;
+use rog_profiles::fan_sensors::FanSensors;
+use rog_profiles::FanCurvePU;
 use slint::{ComponentHandle, Model, Weak};
 
 use crate::config::Config;
@@ -86,6 +88,22 @@ pub fn update_fan_data(
         .ok();
 }
 
+/// Move the marker on each graph to the current temperature and fan speed
+pub fn update_fan_sensors(handle: Weak<MainWindow>, sensors: FanSensors) {
+    handle
+        .upgrade_in_event_loop(move |handle| {
+            let global = handle.global::<FanPageData>();
+            global.set_cpu_temp(sensors.temp(FanCurvePU::CPU) as i32);
+            global.set_gpu_temp(sensors.temp(FanCurvePU::GPU) as i32);
+            global.set_mid_temp(sensors.temp(FanCurvePU::MID) as i32);
+            global.set_cpu_rpm(sensors.rpm(FanCurvePU::CPU) as i32);
+            global.set_gpu_rpm(sensors.rpm(FanCurvePU::GPU) as i32);
+            global.set_mid_rpm(sensors.rpm(FanCurvePU::MID) as i32);
+        })
+        .map_err(|e| error!("update_fan_sensors: upgrade_in_event_loop: {e:?}"))
+        .ok();
+}
+
 pub fn setup_fan_curve_page(ui: &MainWindow, _config: Arc<Mutex<Config>>) {
     let handle = ui.as_weak();
 
@@ -131,6 +149,22 @@ pub fn setup_fan_curve_page(ui: &MainWindow, _config: Arc<Mutex<Config>>) {
         };
         update_fan_data(handle, balanced, perf, quiet);
 
+        if let Ok(sensors) = fans.fan_sensors().await {
+            update_fan_sensors(handle_copy.clone(), sensors);
+        }
+        let handle_sensors = handle_copy.clone();
+        let fans_sensors = fans.clone();
+        // spawn required since the while let never exits
+        tokio::spawn(async move {
+            let mut x = fans_sensors.receive_fan_sensors_changed().await;
+            use zbus::export::futures_util::StreamExt;
+            while let Some(e) = x.next().await {
+                if let Ok(sensors) = e.get().await {
+                    update_fan_sensors(handle_sensors.clone(), sensors);
+                }
+            }
+        });
+
         let handle_next1 = handle_copy.clone();
         handle_copy
             .upgrade_in_event_loop(move |handle| {
@@ -200,5 +234,6 @@ fn fan_data_for(fan: FanType, enabled: bool, data: Vec<Node>) -> CurveData {
         pwm,
         temp,
         enabled,
+        ..Default::default()
     }
 }
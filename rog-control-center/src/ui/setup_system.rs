@@ -2,14 +2,47 @@ use std::sync::{Arc, Mutex};
 
 use rog_dbus::zbus_platform::{PlatformProxy, PlatformProxyBlocking};
 use rog_platform::platform::Properties;
+use rog_platform::power::BatteryInfo;
 use slint::ComponentHandle;
 
 use super::show_toast;
 use crate::config::Config;
 use crate::{
-    set_ui_callbacks, set_ui_props_async, AvailableSystemProperties, MainWindow, SystemPageData,
+    set_ui_callbacks, set_ui_props_async, AvailableSystemProperties, MainWindow, PptLimit,
+    PptLimits, SystemPageData,
 };
 
+/// Convert the limits from asusd to the slider ranges used by the UI
+fn ui_ppt_limits(limits: rog_platform::platform::PptLimits) -> PptLimits {
+    let convert = |limit: rog_platform::platform::PptLimit| PptLimit {
+        min: limit.min as f32,
+        max: limit.max as f32,
+    };
+    PptLimits {
+        ppt_pl1_spl: convert(limits.ppt_pl1_spl),
+        ppt_pl2_sppt: convert(limits.ppt_pl2_sppt),
+        ppt_fppt: convert(limits.ppt_fppt),
+        ppt_apu_sppt: convert(limits.ppt_apu_sppt),
+        ppt_platform_sppt: convert(limits.ppt_platform_sppt),
+        nv_dynamic_boost: convert(limits.nv_dynamic_boost),
+        nv_temp_target: convert(limits.nv_temp_target),
+    }
+}
+
+/// Read the battery properties from asusd, these are cached by the proxy
+async fn battery_info(platform: &PlatformProxy<'_>) -> zbus::Result<BatteryInfo> {
+    Ok(BatteryInfo {
+        capacity: platform.battery_capacity().await?,
+        status: platform.battery_status().await?,
+        energy_full: platform.battery_energy_full().await?,
+        energy_full_design: platform.battery_energy_full_design().await?,
+        cycle_count: platform.battery_cycle_count().await?,
+        power_now: platform.battery_power_draw().await?,
+        time_to_empty: platform.battery_time_to_empty().await?,
+        ..Default::default()
+    })
+}
+
 pub fn setup_system_page(ui: &MainWindow, _config: Arc<Mutex<Config>>) {
     let conn = zbus::blocking::Connection::system().unwrap();
     let platform = PlatformProxyBlocking::new(&conn).unwrap();
@@ -85,6 +118,62 @@ pub fn setup_system_page_callbacks(ui: &MainWindow, _states: Arc<Mutex<Config>>)
         set_ui_props_async!(handle, platform, SystemPageData, nv_dynamic_boost);
         set_ui_props_async!(handle, platform, SystemPageData, nv_temp_target);
 
+        let handle_copy = handle.clone();
+        let platform_copy = platform.clone();
+        tokio::spawn(async move {
+            use zbus::export::futures_util::{stream, StreamExt};
+            // The cached properties are updated before each change is received
+            let mut changes = stream::select_all([
+                platform_copy
+                    .receive_battery_capacity_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                platform_copy
+                    .receive_battery_status_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                platform_copy
+                    .receive_battery_energy_full_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                platform_copy
+                    .receive_battery_cycle_count_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                platform_copy
+                    .receive_battery_power_draw_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                platform_copy
+                    .receive_battery_time_to_empty_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+            ]);
+            // The first of each stream is the current value
+            while changes.next().await.is_some() {
+                let Ok(info) = battery_info(&platform_copy).await else {
+                    continue;
+                };
+                let summary = info.to_string();
+                if handle_copy
+                    .upgrade_in_event_loop(move |handle| {
+                        handle
+                            .global::<SystemPageData>()
+                            .set_battery_summary(summary.into());
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
         let sys_props = platform.supported_properties().await.unwrap();
         log::debug!("Available system properties: {sys_props:?}");
         let props = AvailableSystemProperties {
@@ -105,11 +194,19 @@ pub fn setup_system_page_callbacks(ui: &MainWindow, _states: Arc<Mutex<Config>>)
             ppt_platform_sppt: sys_props.contains(&Properties::PptPlatformSppt),
             throttle_thermal_policy: sys_props.contains(&Properties::ThrottlePolicy),
         };
+        let limits = platform
+            .ppt_limits()
+            .await
+            .map_err(|e| log::warn!("Couldn't get PPT limits: {e}"))
+            .unwrap_or(rog_platform::platform::PptLimits::GENERIC);
 
         // TODO: move the fail/sucess messages to slint
         handle
             .upgrade_in_event_loop(move |handle| {
                 handle.global::<SystemPageData>().set_available(props);
+                handle
+                    .global::<SystemPageData>()
+                    .set_ppt_limits(ui_ppt_limits(limits));
 
                 set_ui_callbacks!(handle,
                     SystemPageData(as f32),
@@ -2,6 +2,7 @@ pub use asusd::{DBUS_IFACE, DBUS_NAME, DBUS_PATH};
 
 pub mod zbus_anime;
 pub mod zbus_aura;
+pub mod zbus_cpu;
 pub mod zbus_fan_curves;
 pub mod zbus_platform;
 pub mod zbus_slash;
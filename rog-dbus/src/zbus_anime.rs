@@ -24,42 +24,42 @@ trait Anime {
     /// Brightness property
     #[zbus(property)]
     fn brightness(&self) -> zbus::Result<Brightness>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_brightness(&self, value: Brightness) -> zbus::Result<()>;
 
     /// BuiltinAnimations property
     #[zbus(property)]
     fn builtin_animations(&self) -> zbus::Result<Animations>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_builtin_animations(&self, value: Animations) -> zbus::Result<()>;
 
     /// BuiltinsEnabled property
     #[zbus(property)]
     fn builtins_enabled(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_builtins_enabled(&self, value: bool) -> zbus::Result<()>;
 
     /// EnableDisplay property
     #[zbus(property)]
     fn enable_display(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_enable_display(&self, value: bool) -> zbus::Result<()>;
 
     /// OffWhenLidClosed property
     #[zbus(property)]
     fn off_when_lid_closed(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_off_when_lid_closed(&self, value: bool) -> zbus::Result<()>;
 
     /// OffWhenSuspended property
     #[zbus(property)]
     fn off_when_suspended(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_off_when_suspended(&self, value: bool) -> zbus::Result<()>;
 
     /// OffWhenUnplugged property
     #[zbus(property)]
     fn off_when_unplugged(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_off_when_unplugged(&self, value: bool) -> zbus::Result<()>;
 }
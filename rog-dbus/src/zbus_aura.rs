@@ -41,10 +41,22 @@ trait Aura {
     /// DirectAddressingRaw method
     fn direct_addressing_raw(&self, data: UsbPackets) -> zbus::Result<()>;
 
+    /// AdvancedEffectsEnabled property
+    #[zbus(property)]
+    fn advanced_effects_enabled(&self) -> zbus::Result<bool>;
+    #[zbus(allow_interactive_auth)]
+    fn set_advanced_effects_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    /// AdvancedEffectsFps property
+    #[zbus(property)]
+    fn advanced_effects_fps(&self) -> zbus::Result<u8>;
+    #[zbus(allow_interactive_auth)]
+    fn set_advanced_effects_fps(&self, value: u8) -> zbus::Result<()>;
+
     /// Brightness property
     #[zbus(property)]
     fn brightness(&self) -> zbus::Result<LedBrightness>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_brightness(&self, value: LedBrightness) -> zbus::Result<()>;
 
     /// DeviceType property
@@ -54,19 +66,19 @@ trait Aura {
     /// LedMode property
     #[zbus(property)]
     fn led_mode(&self) -> zbus::Result<AuraModeNum>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_led_mode(&self, value: AuraModeNum) -> zbus::Result<()>;
 
     /// LedModeData property
     #[zbus(property)]
     fn led_mode_data(&self) -> zbus::Result<AuraEffect>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_led_mode_data(&self, value: AuraEffect) -> zbus::Result<()>;
 
     /// LedPower property
     #[zbus(property)]
     fn led_power(&self) -> zbus::Result<LaptopAuraPower>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_led_power(&self, value: LaptopAuraPower) -> zbus::Result<()>;
 
     /// SupportedBrightness property
@@ -0,0 +1,69 @@
+//! # `DBus` interface proxy for: `org.asuslinux.Cpu`
+//!
+//! This code was generated by `zbus-xmlgen` `1.0.0` from `DBus` introspection
+//! data. Source: `Interface '/org/asuslinux' from service
+//! 'org.asuslinux.Daemon' on system bus`.
+//!
+//! You may prefer to adapt it, instead of using it verbatim.
+//!
+//! More information can be found in the
+//! [Writing a client proxy](https://zeenix.pages.freedesktop.org/zbus/client.html)
+//! section of the zbus documentation.
+
+use cpuctl::{CoreState, CpuCapabilities, CpuSettings, PstateMode};
+use rog_platform::platform::ThrottlePolicy;
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.asuslinux.Cpu",
+    default_service = "org.asuslinux.Daemon",
+    default_path = "/org/asuslinux"
+)]
+trait Cpu {
+    /// The online state and frequency limits of each CPU
+    fn cores(&self) -> zbus::Result<Vec<CoreState>>;
+
+    /// The CPU settings applied with `policy`
+    fn policy_settings(&self, policy: ThrottlePolicy) -> zbus::Result<CpuSettings>;
+
+    /// Take a CPU offline or bring it back. This is kept for the active
+    /// throttle policy.
+    #[zbus(allow_interactive_auth)]
+    fn set_core_online(&self, cpu: u32, online: bool) -> zbus::Result<()>;
+
+    /// Replace the CPU settings applied with `policy`, applying them now if
+    /// `policy` is active
+    #[zbus(allow_interactive_auth)]
+    fn set_policy_settings(
+        &self,
+        policy: ThrottlePolicy,
+        settings: CpuSettings,
+    ) -> zbus::Result<()>;
+
+    /// Set the scaling frequency limits of every online CPU in kHz, `0` to
+    /// leave a limit as it is
+    #[zbus(allow_interactive_auth)]
+    fn set_scaling_freq(&self, min: u32, max: u32) -> zbus::Result<()>;
+
+    /// Boost property
+    #[zbus(property)]
+    fn boost(&self) -> zbus::Result<bool>;
+    #[zbus(allow_interactive_auth)]
+    fn set_boost(&self, value: bool) -> zbus::Result<()>;
+
+    /// Capabilities property
+    #[zbus(property)]
+    fn capabilities(&self) -> zbus::Result<CpuCapabilities>;
+
+    /// PstateMode property
+    #[zbus(property)]
+    fn pstate_mode(&self) -> zbus::Result<PstateMode>;
+    #[zbus(allow_interactive_auth)]
+    fn set_pstate_mode(&self, value: PstateMode) -> zbus::Result<()>;
+
+    /// Smt property
+    #[zbus(property)]
+    fn smt(&self) -> zbus::Result<bool>;
+    #[zbus(allow_interactive_auth)]
+    fn set_smt(&self, value: bool) -> zbus::Result<()>;
+}
@@ -21,7 +21,9 @@
 //! …consequently `zbus-xmlgen` did not generate code for the above interfaces.
 
 use rog_platform::platform::ThrottlePolicy;
+use rog_profiles::fan_curve_file::FanCurveExport;
 use rog_profiles::fan_curve_set::CurveData;
+use rog_profiles::fan_sensors::FanSensors;
 use rog_profiles::FanCurvePU;
 use zbus::proxy;
 
@@ -34,29 +36,54 @@ trait FanCurves {
     /// Get the fan-curve data for the currently active PlatformProfile
     fn fan_curve_data(&self, profile: ThrottlePolicy) -> zbus::Result<Vec<CurveData>>;
 
+    /// Get the fan curves of every ThrottlePolicy, with the board name, fans
+    /// and version
+    fn export_fan_curves(&self) -> zbus::Result<FanCurveExport>;
+
+    /// Replace the fan curves of every ThrottlePolicy with exported ones.
+    /// Curves for other fans are rejected unless `map_fans` is set.
+    #[zbus(allow_interactive_auth)]
+    fn import_fan_curves(&self, curves: FanCurveExport, map_fans: bool) -> zbus::Result<()>;
+
     /// Reset the stored (self) and device curve to the defaults of the
     /// platform.
     ///
     /// Each platform_profile has a different default and the defualt can be
     /// read only for the currently active profile.
+    #[zbus(allow_interactive_auth)]
     fn reset_profile_curves(&self, profile: ThrottlePolicy) -> zbus::Result<()>;
 
     /// SetActiveCurveToDefaults method
+    #[zbus(allow_interactive_auth)]
     fn set_curves_to_defaults(&self, profile: ThrottlePolicy) -> zbus::Result<()>;
 
     /// Set the fan curve for the specified profile, or the profile the user is
     /// currently in if profile == None. Will also activate the fan curve.
+    #[zbus(allow_interactive_auth)]
     fn set_fan_curve(&self, profile: ThrottlePolicy, curve: CurveData) -> zbus::Result<()>;
 
     /// Set a profile fan curve enabled status. Will also activate a fan curve.
+    #[zbus(allow_interactive_auth)]
     fn set_fan_curves_enabled(&self, profile: ThrottlePolicy, enabled: bool) -> zbus::Result<()>;
 
     /// Set a single fan curve for a profile to enabled status. Will also
     /// activate a fan curve.
+    #[zbus(allow_interactive_auth)]
     async fn set_profile_fan_curve_enabled(
         &self,
         profile: ThrottlePolicy,
         fan: FanCurvePU,
         enabled: bool,
     ) -> zbus::Result<()>;
+
+    /// The current fan speeds in RPM and temperatures in degrees C
+    #[zbus(property)]
+    fn fan_sensors(&self) -> zbus::Result<FanSensors>;
+
+    /// How often `FanSensors` is checked for changes to signal, in
+    /// milliseconds. `0` stops the signal.
+    #[zbus(property)]
+    fn sensor_interval(&self) -> zbus::Result<u64>;
+    #[zbus(allow_interactive_auth)]
+    fn set_sensor_interval(&self, value: u64) -> zbus::Result<()>;
 }
@@ -20,8 +20,13 @@
 //!
 //! …consequently `zbus-xmlgen` did not generate code for the above interfaces.
 
-use rog_platform::cpu::CPUEPP;
-use rog_platform::platform::{GpuMode, Properties, ThrottlePolicy};
+use rog_platform::cpu::{CpuFreqState, CpuWrite, CPUEPP};
+use rog_platform::firmware_attributes::FirmwareAttribute;
+use rog_platform::governor::GovernorDecision;
+use rog_platform::hooks::HookResult;
+use rog_platform::platform::{PlatformTuning, PptLimits, Properties, ThrottlePolicy};
+use rog_platform::power::{BatteryStatus, ChargeRule};
+use rog_profiles::user_profile::UserProfile;
 use zbus::proxy;
 
 #[proxy(
@@ -34,17 +39,144 @@ trait Platform {
     fn version(&self) -> zbus::Result<String>;
 
     /// NextThrottleThermalPolicy method
+    #[zbus(allow_interactive_auth)]
     fn next_throttle_thermal_policy(&self) -> zbus::Result<()>;
 
     /// SupportedProperties method
     fn supported_properties(&self) -> zbus::Result<Vec<Properties>>;
 
+    /// ThrottlePolicyTuning method
+    fn throttle_policy_tuning(
+        &self,
+        policy: ThrottlePolicy,
+        on_battery: bool,
+    ) -> zbus::Result<PlatformTuning>;
+
+    /// SetThrottlePolicyTuning method
+    #[zbus(allow_interactive_auth)]
+    fn set_throttle_policy_tuning(
+        &self,
+        policy: ThrottlePolicy,
+        on_battery: bool,
+        tuning: PlatformTuning,
+    ) -> zbus::Result<()>;
+
+    /// UserProfiles method
+    fn user_profiles(&self) -> zbus::Result<Vec<UserProfile>>;
+
+    /// SetUserProfile method
+    #[zbus(allow_interactive_auth)]
+    fn set_user_profile(&self, profile: &UserProfile) -> zbus::Result<()>;
+
+    /// RemoveUserProfile method
+    #[zbus(allow_interactive_auth)]
+    fn remove_user_profile(&self, name: &str) -> zbus::Result<()>;
+
+    /// ActivateUserProfile method
+    #[zbus(allow_interactive_auth)]
+    fn activate_user_profile(&self, name: &str) -> zbus::Result<()>;
+
+    /// ActiveUserProfile property
+    #[zbus(property)]
+    fn active_user_profile(&self) -> zbus::Result<String>;
+
+    /// UserProfileOrder property
+    #[zbus(property)]
+    fn user_profile_order(&self) -> zbus::Result<Vec<String>>;
+    #[zbus(allow_interactive_auth)]
+    fn set_user_profile_order(&self, value: &[String]) -> zbus::Result<()>;
+
+    /// GovernorEnabled property
+    #[zbus(property)]
+    fn governor_enabled(&self) -> zbus::Result<bool>;
+    #[zbus(allow_interactive_auth)]
+    fn set_governor_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    /// GovernorDecision property
+    #[zbus(property)]
+    fn governor_decision(&self) -> zbus::Result<GovernorDecision>;
+
+    /// FirmwareAttributes method
+    fn firmware_attributes(&self) -> zbus::Result<Vec<FirmwareAttribute>>;
+
+    /// FirmwareAttribute method
+    fn firmware_attribute(&self, name: &str) -> zbus::Result<FirmwareAttribute>;
+
+    /// SetFirmwareAttribute method
+    #[zbus(allow_interactive_auth)]
+    fn set_firmware_attribute(&self, name: &str, value: &str) -> zbus::Result<()>;
+
     /// ChargeControlEndThreshold property
     #[zbus(property)]
     fn charge_control_end_threshold(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_charge_control_end_threshold(&self, value: u8) -> zbus::Result<()>;
 
+    /// ChargeFullUntilUnplugged property
+    #[zbus(property)]
+    fn charge_full_until_unplugged(&self) -> zbus::Result<bool>;
+    #[zbus(allow_interactive_auth)]
+    fn set_charge_full_until_unplugged(&self, value: bool) -> zbus::Result<()>;
+
+    /// ChargeRules method
+    fn charge_rules(&self) -> zbus::Result<Vec<ChargeRule>>;
+
+    /// SetChargeRules method
+    #[zbus(allow_interactive_auth)]
+    fn set_charge_rules(&self, rules: &[ChargeRule]) -> zbus::Result<()>;
+
+    /// LastHookResult property
+    #[zbus(property)]
+    fn last_hook_result(&self) -> zbus::Result<HookResult>;
+
+    /// How each CPU took the last EPP change, and which were changed
+    #[zbus(property)]
+    fn last_epp_result(&self) -> zbus::Result<Vec<CpuWrite>>;
+
+    /// The governor, EPP, and core type of each online CPU
+    fn cpu_freq_states(&self) -> zbus::Result<Vec<CpuFreqState>>;
+
+    /// The EPP for the efficiency cores of hybrid CPUs with `policy`, empty
+    /// if they use the EPP of the performance cores
+    fn efficiency_core_epp(&self, policy: ThrottlePolicy) -> zbus::Result<Vec<CPUEPP>>;
+
+    /// Set the EPP for the efficiency cores with `policy`, empty to use the
+    /// EPP of the performance cores
+    #[zbus(allow_interactive_auth)]
+    fn set_efficiency_core_epp(&self, policy: ThrottlePolicy, epp: &[CPUEPP]) -> zbus::Result<()>;
+
+    /// BatteryCapacity property
+    #[zbus(property)]
+    fn battery_capacity(&self) -> zbus::Result<u8>;
+
+    /// BatteryStatus property
+    #[zbus(property)]
+    fn battery_status(&self) -> zbus::Result<BatteryStatus>;
+
+    /// BatteryHealth property
+    #[zbus(property)]
+    fn battery_health(&self) -> zbus::Result<u8>;
+
+    /// BatteryCycleCount property
+    #[zbus(property)]
+    fn battery_cycle_count(&self) -> zbus::Result<u32>;
+
+    /// BatteryEnergyFull property
+    #[zbus(property)]
+    fn battery_energy_full(&self) -> zbus::Result<u32>;
+
+    /// BatteryEnergyFullDesign property
+    #[zbus(property)]
+    fn battery_energy_full_design(&self) -> zbus::Result<u32>;
+
+    /// BatteryPowerDraw property
+    #[zbus(property)]
+    fn battery_power_draw(&self) -> zbus::Result<u32>;
+
+    /// BatteryTimeToEmpty property
+    #[zbus(property)]
+    fn battery_time_to_empty(&self) -> zbus::Result<u32>;
+
     /// DgpuDisable property
     #[zbus(property)]
     fn dgpu_disable(&self) -> zbus::Result<bool>;
@@ -56,120 +188,124 @@ trait Platform {
     /// GpuMuxMode property
     #[zbus(property)]
     fn gpu_mux_mode(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
-    fn set_gpu_mux_mode(&self, value: GpuMode) -> zbus::Result<()>;
+    #[zbus(allow_interactive_auth)]
+    fn set_gpu_mux_mode(&self, value: u8) -> zbus::Result<()>;
 
     /// MiniLedMode property
     #[zbus(property)]
     fn mini_led_mode(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_mini_led_mode(&self, value: bool) -> zbus::Result<()>;
 
     /// NvDynamicBoost property
     #[zbus(property)]
     fn nv_dynamic_boost(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_nv_dynamic_boost(&self, value: u8) -> zbus::Result<()>;
 
     /// NvTempTarget property
     #[zbus(property)]
     fn nv_temp_target(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_nv_temp_target(&self, value: u8) -> zbus::Result<()>;
 
     /// PanelOd property
     #[zbus(property)]
     fn panel_od(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_panel_od(&self, value: bool) -> zbus::Result<()>;
 
     /// PostAnimationSound property
     #[zbus(property)]
     fn boot_sound(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_boot_sound(&self, value: bool) -> zbus::Result<()>;
 
     /// PptApuSppt property
     #[zbus(property)]
     fn ppt_apu_sppt(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_ppt_apu_sppt(&self, value: u8) -> zbus::Result<()>;
 
     /// PptFppt property
     #[zbus(property)]
     fn ppt_fppt(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_ppt_fppt(&self, value: u8) -> zbus::Result<()>;
 
+    /// PptLimits property
+    #[zbus(property)]
+    fn ppt_limits(&self) -> zbus::Result<PptLimits>;
+
     /// PptPl1Spl property
     #[zbus(property)]
     fn ppt_pl1_spl(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_ppt_pl1_spl(&self, value: u8) -> zbus::Result<()>;
 
     /// PptPl2Sppt property
     #[zbus(property)]
     fn ppt_pl2_sppt(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_ppt_pl2_sppt(&self, value: u8) -> zbus::Result<()>;
 
     /// PptPlatformSppt property
     #[zbus(property)]
     fn ppt_platform_sppt(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_ppt_platform_sppt(&self, value: u8) -> zbus::Result<()>;
 
     /// ThrottleBalancedEpp property
     #[zbus(property)]
     fn throttle_balanced_epp(&self) -> zbus::Result<CPUEPP>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_balanced_epp(&self, epp: CPUEPP) -> zbus::Result<()>;
 
     /// ThrottlePerformanceEpp property
     #[zbus(property)]
     fn throttle_performance_epp(&self) -> zbus::Result<CPUEPP>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_performance_epp(&self, epp: CPUEPP) -> zbus::Result<()>;
 
     /// ThrottlePolicyLinkedEpp property
     #[zbus(property)]
     fn throttle_policy_linked_epp(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_policy_linked_epp(&self, value: bool) -> zbus::Result<()>;
 
     /// ThrottlePolicyOnAc property
     #[zbus(property)]
     fn throttle_policy_on_ac(&self) -> zbus::Result<ThrottlePolicy>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_policy_on_ac(&self, throttle_policy: ThrottlePolicy) -> zbus::Result<()>;
 
     /// ChangeThrottlePolicyOnAc property
     #[zbus(property)]
     fn change_throttle_policy_on_ac(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_change_throttle_policy_on_ac(&self, change: bool) -> zbus::Result<()>;
 
     /// ThrottlePolicyOnBattery property
     #[zbus(property)]
     fn throttle_policy_on_battery(&self) -> zbus::Result<ThrottlePolicy>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_policy_on_battery(&self, throttle_policy: ThrottlePolicy) -> zbus::Result<()>;
 
     /// ChangeThrottlePolicyOnAc property
     #[zbus(property)]
     fn change_throttle_policy_on_battery(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_change_throttle_policy_on_battery(&self, change: bool) -> zbus::Result<()>;
 
     /// ThrottleQuietEpp property
     #[zbus(property)]
     fn throttle_quiet_epp(&self) -> zbus::Result<CPUEPP>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_quiet_epp(&self, epp: CPUEPP) -> zbus::Result<()>;
 
     /// ThrottlePolicy property
     #[zbus(property)]
     fn throttle_thermal_policy(&self) -> zbus::Result<ThrottlePolicy>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_throttle_thermal_policy(&self, throttle_policy: ThrottlePolicy) -> zbus::Result<()>;
 }
@@ -10,24 +10,24 @@ trait Slash {
     /// EnableDisplay property
     #[zbus(property)]
     fn enabled(&self) -> zbus::Result<bool>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_enabled(&self, value: bool) -> zbus::Result<()>;
 
     /// Brightness property
     #[zbus(property)]
     fn brightness(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_brightness(&self, value: u8) -> zbus::Result<()>;
 
     /// Interval property
     #[zbus(property)]
     fn interval(&self) -> zbus::Result<u8>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_interval(&self, value: u8) -> zbus::Result<()>;
 
     /// Slash modes property
     #[zbus(property)]
     fn slash_mode(&self) -> zbus::Result<SlashMode>;
-    #[zbus(property)]
+    #[zbus(allow_interactive_auth)]
     fn set_slash_mode(&self, value: SlashMode) -> zbus::Result<()>;
 }
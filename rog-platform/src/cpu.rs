@@ -1,82 +1,177 @@
 use std::path::PathBuf;
 
-use log::{info, warn};
+use log::info;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 use zbus::zvariant::{OwnedValue, Type, Value};
 
+use crate::discovery::{sysname_of, Discovery};
 use crate::error::{PlatformError, Result};
 use crate::platform::ThrottlePolicy;
-use crate::{read_attr_string, to_device};
+use crate::{has_attr, read_attr_string, write_attr_string};
 
 const ATTR_AVAILABLE_GOVERNORS: &str = "cpufreq/scaling_available_governors";
 const ATTR_GOVERNOR: &str = "cpufreq/scaling_governor";
 const ATTR_AVAILABLE_EPP: &str = "cpufreq/energy_performance_available_preferences";
 const ATTR_EPP: &str = "cpufreq/energy_performance_preference";
+/// The CPUs of each core type on Intel hybrid CPUs, relative to the sysfs root
+const PERFORMANCE_CORE_CPUS: &str = "devices/cpu_core/cpus";
+const EFFICIENCY_CORE_CPUS: &str = "devices/cpu_atom/cpus";
+
+/// The kind of core a CPU is on hybrid CPUs
+#[typeshare]
+#[repr(u32)]
+#[derive(
+    Deserialize,
+    Serialize,
+    Type,
+    Value,
+    OwnedValue,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Clone,
+    Copy,
+)]
+#[zvariant(signature = "u")]
+pub enum CoreType {
+    /// Not a hybrid CPU, or sysfs doesn't say
+    #[default]
+    Unknown = 0,
+    Performance = 1,
+    Efficiency = 2,
+}
+
+/// The governor and EPP of one CPU
+#[typeshare]
+#[derive(Deserialize, Serialize, Type, Value, OwnedValue, Debug, Clone, PartialEq)]
+pub struct CpuFreqState {
+    pub cpu: u32,
+    pub core_type: CoreType,
+    pub governor: CPUGovernor,
+    pub epp: CPUEPP,
+}
+
+/// The outcome of a write to one CPU
+#[typeshare]
+#[derive(Deserialize, Serialize, Type, Value, OwnedValue, Default, Debug, Clone, PartialEq)]
+pub struct CpuWrite {
+    pub cpu: u32,
+    pub core_type: CoreType,
+    /// The value was different before the write
+    pub changed: bool,
+    /// Empty if the write worked
+    pub error: String,
+}
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`
+pub fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        if let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>()) {
+            cpus.extend(first..=last);
+        }
+    }
+    cpus
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
+struct CpuPath {
+    cpu: u32,
+    core_type: CoreType,
+    path: PathBuf,
+}
 
 /// Both modern AMD and Intel have cpufreq control if using `powersave`
 /// governor. What interests us the most here is `energy_performance_preference`
 /// which can drastically alter CPU performance.
+///
+/// Each CPU is read and written separately, as hybrid CPUs can differ between
+/// core types and some CPUs may be offline.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
 pub struct CPUControl {
-    paths: Vec<PathBuf>,
+    cpus: Vec<CpuPath>,
 }
 
 impl CPUControl {
     pub fn new() -> Result<Self> {
-        let mut enumerator = udev::Enumerator::new().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("enumerator failed".into(), err)
-        })?;
-        enumerator.match_subsystem("cpu").map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("match_subsystem failed".into(), err)
-        })?;
+        Self::new_with(&Discovery::from_env())
+    }
+
+    /// Find the CPUs using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        let read_list = |rel: &str| {
+            std::fs::read_to_string(discovery.sys_path(rel))
+                .map(|l| parse_cpu_list(&l))
+                .unwrap_or_default()
+        };
+        let performance = read_list(PERFORMANCE_CORE_CPUS);
+        let efficiency = read_list(EFFICIENCY_CORE_CPUS);
+        if !efficiency.is_empty() {
+            info!("Hybrid CPU, P-cores: {performance:?}, E-cores: {efficiency:?}");
+        }
 
         let mut supported = false;
-        let mut cpu = CPUControl { paths: Vec::new() };
-        for device in enumerator.scan_devices().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("CPU: scan_devices failed".into(), err)
-        })? {
+        let mut cpu = CPUControl { cpus: Vec::new() };
+        for path in discovery.scan("cpu")? {
             if !supported {
                 info!(
                     "Found CPU support at {:?}, checking supported items",
-                    device.sysname()
+                    sysname_of(&path)
                 );
 
-                match device.attribute_value(ATTR_AVAILABLE_GOVERNORS) {
-                    Some(g) => info!("{ATTR_AVAILABLE_GOVERNORS}: {g:?}"),
-                    None => {
+                match read_attr_string(&path, ATTR_AVAILABLE_GOVERNORS) {
+                    Ok(g) => info!("{ATTR_AVAILABLE_GOVERNORS}: {g:?}"),
+                    Err(_) => {
                         return Err(PlatformError::CPU(format!(
                             "{ATTR_AVAILABLE_GOVERNORS} not found"
                         )))
                     }
                 }
-                match device.attribute_value(ATTR_GOVERNOR) {
-                    Some(g) => info!("{ATTR_GOVERNOR}: {g:?}"),
-                    None => return Err(PlatformError::CPU(format!("{ATTR_GOVERNOR} not found"))),
+                match read_attr_string(&path, ATTR_GOVERNOR) {
+                    Ok(g) => info!("{ATTR_GOVERNOR}: {g:?}"),
+                    Err(_) => return Err(PlatformError::CPU(format!("{ATTR_GOVERNOR} not found"))),
                 }
-                match device.attribute_value(ATTR_AVAILABLE_EPP) {
-                    Some(g) => info!("{ATTR_AVAILABLE_EPP}: {g:?}"),
-                    None => {
+                match read_attr_string(&path, ATTR_AVAILABLE_EPP) {
+                    Ok(g) => info!("{ATTR_AVAILABLE_EPP}: {g:?}"),
+                    Err(_) => {
                         return Err(PlatformError::CPU(format!(
                             "{ATTR_AVAILABLE_EPP} not found"
                         )))
                     }
                 }
-                match device.attribute_value(ATTR_EPP) {
-                    Some(g) => info!("{ATTR_EPP}: {g:?}"),
-                    None => return Err(PlatformError::CPU(format!("{ATTR_EPP} not found"))),
+                match read_attr_string(&path, ATTR_EPP) {
+                    Ok(g) => info!("{ATTR_EPP}: {g:?}"),
+                    Err(_) => return Err(PlatformError::CPU(format!("{ATTR_EPP} not found"))),
                 }
                 supported = true;
             }
             if supported {
-                info!("Adding: {:?}", device.syspath());
-                cpu.paths.push(device.syspath().to_owned());
+                let Some(num) = sysname_of(&path)
+                    .strip_prefix("cpu")
+                    .and_then(|n| n.parse::<u32>().ok())
+                else {
+                    continue;
+                };
+                let core_type = if performance.contains(&num) {
+                    CoreType::Performance
+                } else if efficiency.contains(&num) {
+                    CoreType::Efficiency
+                } else {
+                    CoreType::Unknown
+                };
+                info!("Adding: {:?} as {core_type:?}", path);
+                cpu.cpus.push(CpuPath {
+                    cpu: num,
+                    core_type,
+                    path,
+                });
             }
         }
-        if cpu.paths.is_empty() {
+        if cpu.cpus.is_empty() {
             return Err(PlatformError::MissingFunction(
                 "asus-nb-wmi not found".into(),
             ));
@@ -84,66 +179,165 @@ impl CPUControl {
         Ok(cpu)
     }
 
+    /// Has both performance and efficiency cores
+    pub fn is_hybrid(&self) -> bool {
+        self.cpus
+            .iter()
+            .any(|c| c.core_type == CoreType::Efficiency)
+    }
+
+    /// The governor and EPP of each CPU which can be read. Offline CPUs are
+    /// left out.
+    pub fn states(&self) -> Vec<CpuFreqState> {
+        self.cpus
+            .iter()
+            .filter_map(|c| {
+                Some(CpuFreqState {
+                    cpu: c.cpu,
+                    core_type: c.core_type,
+                    governor: read_attr_string(&c.path, ATTR_GOVERNOR)
+                        .ok()?
+                        .as_str()
+                        .into(),
+                    epp: read_attr_string(&c.path, ATTR_EPP).ok()?.as_str().into(),
+                })
+            })
+            .collect()
+    }
+
+    /// Write `attr` of every CPU with a value picked by core type, skipping
+    /// those given `None`. CPUs without the attribute, such as offline ones,
+    /// are skipped.
+    fn write_each(
+        &self,
+        attr: &str,
+        value_for: impl Fn(CoreType) -> Option<String>,
+    ) -> Vec<CpuWrite> {
+        self.cpus
+            .iter()
+            .filter(|c| has_attr(&c.path, attr))
+            .filter_map(|c| {
+                let value = value_for(c.core_type)?;
+                let before = read_attr_string(&c.path, attr).ok();
+                let mut write = CpuWrite {
+                    cpu: c.cpu,
+                    core_type: c.core_type,
+                    ..Default::default()
+                };
+                match write_attr_string(&c.path, attr, &value) {
+                    Ok(()) => write.changed = before.as_deref() != Some(value.as_str()),
+                    Err(e) => write.error = e.to_string(),
+                }
+                Some(write)
+            })
+            .collect()
+    }
+
+    /// The governor of the first CPU, see `states()` for every CPU
     pub fn get_governor(&self) -> Result<CPUGovernor> {
-        if let Some(path) = self.paths.first() {
-            let s = read_attr_string(&to_device(path)?, ATTR_GOVERNOR)?;
+        if let Some(cpu) = self.cpus.first() {
+            let s = read_attr_string(&cpu.path, ATTR_GOVERNOR)?;
             Ok(s.as_str().into())
-            // TODO: check cpu are sync
         } else {
             Err(PlatformError::CPU("No CPU's?".to_string()))
         }
     }
 
     pub fn get_available_governors(&self) -> Result<Vec<CPUGovernor>> {
-        if let Some(path) = self.paths.first() {
-            read_attr_string(&to_device(path)?, ATTR_AVAILABLE_GOVERNORS)
+        if let Some(cpu) = self.cpus.first() {
+            read_attr_string(&cpu.path, ATTR_AVAILABLE_GOVERNORS)
                 .map(|s| s.split_whitespace().map(|s| s.into()).collect())
-            // TODO: check cpu are sync
         } else {
             Err(PlatformError::CPU("No CPU's?".to_string()))
         }
     }
 
-    pub fn set_governor(&self, gov: CPUGovernor) -> Result<()> {
+    /// Set the governor of every CPU, returning how each write went
+    pub fn set_governor(&self, gov: CPUGovernor) -> Result<Vec<CpuWrite>> {
         if !self.get_available_governors()?.contains(&gov) {
             return Err(PlatformError::CPU(format!("{gov:?} is not available")));
         }
-        for path in &self.paths {
-            let mut dev = to_device(path)?;
-            dev.set_attribute_value(ATTR_AVAILABLE_GOVERNORS, String::from(gov))?;
-        }
-        Ok(())
+        Ok(self.write_each(ATTR_GOVERNOR, |_| Some(String::from(gov))))
     }
 
+    /// The EPP of the first CPU, see `states()` for every CPU
     pub fn get_epp(&self) -> Result<CPUEPP> {
-        if let Some(path) = self.paths.first() {
-            let s = read_attr_string(&to_device(path)?, ATTR_EPP)?;
+        if let Some(cpu) = self.cpus.first() {
+            let s = read_attr_string(&cpu.path, ATTR_EPP)?;
             Ok(s.as_str().into())
-            // TODO: check cpu are sync
         } else {
             Err(PlatformError::CPU("No CPU's?".to_string()))
         }
     }
 
     pub fn get_available_epp(&self) -> Result<Vec<CPUEPP>> {
-        if let Some(path) = self.paths.first() {
-            read_attr_string(&to_device(path)?, ATTR_AVAILABLE_EPP)
+        if let Some(cpu) = self.cpus.first() {
+            read_attr_string(&cpu.path, ATTR_AVAILABLE_EPP)
                 .map(|s| s.split_whitespace().map(|s| s.into()).collect())
-            // TODO: check cpu are sync
         } else {
             Err(PlatformError::CPU("No CPU's?".to_string()))
         }
     }
 
-    pub fn set_epp(&self, epp: CPUEPP) -> Result<()> {
-        if !self.get_available_epp()?.contains(&epp) {
-            return Err(PlatformError::CPU(format!("{epp:?} is not available")));
+    /// The EPP values the CPUs of `core_type` take, read from the first of
+    /// them with cpufreq, as each core type has its own cpufreq policy. CPUs
+    /// of unknown type are treated as performance cores.
+    pub fn get_available_epp_for(&self, core_type: CoreType) -> Result<Vec<CPUEPP>> {
+        let efficiency = core_type == CoreType::Efficiency;
+        self.cpus
+            .iter()
+            .filter(|c| (c.core_type == CoreType::Efficiency) == efficiency)
+            .find(|c| has_attr(&c.path, ATTR_AVAILABLE_EPP))
+            .ok_or_else(|| PlatformError::CPU(format!("No {core_type:?} CPU's?")))
+            .and_then(|c| read_attr_string(&c.path, ATTR_AVAILABLE_EPP))
+            .map(|s| s.split_whitespace().map(|s| s.into()).collect())
+    }
+
+    /// Set the EPP of every CPU, returning how each write went
+    pub fn set_epp(&self, epp: CPUEPP) -> Result<Vec<CpuWrite>> {
+        self.set_epp_by_core_type(epp, epp)
+    }
+
+    /// Set the EPP of the performance cores to `performance` and of the
+    /// efficiency cores to `efficiency`. CPUs of unknown type are treated as
+    /// performance cores.
+    pub fn set_epp_by_core_type(
+        &self,
+        performance: CPUEPP,
+        efficiency: CPUEPP,
+    ) -> Result<Vec<CpuWrite>> {
+        let mut checks = vec![(CoreType::Performance, performance)];
+        if self.is_hybrid() {
+            checks.push((CoreType::Efficiency, efficiency));
+        }
+        for (core_type, epp) in checks {
+            if !self.get_available_epp_for(core_type)?.contains(&epp) {
+                return Err(PlatformError::CPU(format!(
+                    "{epp:?} is not available on {core_type:?} cores"
+                )));
+            }
         }
-        for path in &self.paths {
-            let mut dev = to_device(path)?;
-            dev.set_attribute_value(ATTR_EPP, String::from(epp))?;
+        Ok(self.write_each(ATTR_EPP, |core_type| {
+            if core_type == CoreType::Efficiency {
+                Some(String::from(efficiency))
+            } else {
+                Some(String::from(performance))
+            }
+        }))
+    }
+
+    /// Set the EPP of the CPUs of `core_type` only, leaving the others as they
+    /// are. CPUs of unknown type are treated as performance cores.
+    pub fn set_epp_for(&self, core_type: CoreType, epp: CPUEPP) -> Result<Vec<CpuWrite>> {
+        if !self.get_available_epp_for(core_type)?.contains(&epp) {
+            return Err(PlatformError::CPU(format!(
+                "{epp:?} is not available on {core_type:?} cores"
+            )));
         }
-        Ok(())
+        let efficiency = core_type == CoreType::Efficiency;
+        Ok(self.write_each(ATTR_EPP, |c| {
+            ((c == CoreType::Efficiency) == efficiency).then(|| String::from(epp))
+        }))
     }
 }
 
@@ -260,9 +454,16 @@ impl From<CPUEPP> for i32 {
 
 #[cfg(test)]
 mod tests {
-    use super::CPUControl;
+    use super::{parse_cpu_list, CPUControl};
     use crate::cpu::{CPUGovernor, CPUEPP};
 
+    #[test]
+    fn cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), [5]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
     #[test]
     #[ignore = "Can't run this in a docker image"]
     fn check_cpu() {
@@ -0,0 +1,116 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::error::{PlatformError, Result};
+
+/// Set this to a directory laid out like `/sys` to have every device lookup
+/// made through `Discovery::from_env()` use it instead of udev.
+pub const SYSFS_ROOT_ENV: &str = "ASUSD_SYSFS_ROOT";
+
+/// How devices are found. Once found a device is only a syspath, and all
+/// attribute reads and writes are plain file I/O on that path, so a fake tree
+/// behaves the same as the real `/sys`.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Clone)]
+pub enum Discovery {
+    /// Enumerate the real `/sys` with udev
+    #[default]
+    Udev,
+    /// A directory tree rooted anywhere which mirrors `/sys`. Devices are
+    /// looked for in `<root>/class/<subsystem>/*` and
+    /// `<root>/bus/<subsystem>/devices/*`.
+    Root(PathBuf),
+}
+
+impl Discovery {
+    /// Use the tree at `ASUSD_SYSFS_ROOT` if set, otherwise udev
+    pub fn from_env() -> Self {
+        match env::var_os(SYSFS_ROOT_ENV) {
+            Some(root) if !root.is_empty() => Self::Root(PathBuf::from(root)),
+            _ => Self::Udev,
+        }
+    }
+
+    /// Return the full path for something not found by subsystem, e.g.
+    /// `firmware/acpi`
+    pub fn sys_path(&self, relative: &str) -> PathBuf {
+        match self {
+            Discovery::Udev => Path::new("/sys").join(relative),
+            Discovery::Root(root) => root.join(relative),
+        }
+    }
+
+    /// Find the syspaths of all devices in `subsystem`, in a stable order
+    pub fn scan(&self, subsystem: &str) -> Result<Vec<PathBuf>> {
+        match self {
+            Discovery::Udev => {
+                let mut enumerator = udev::Enumerator::new().map_err(|err| {
+                    warn!("{}", err);
+                    PlatformError::Udev("enumerator failed".into(), err)
+                })?;
+                enumerator.match_subsystem(subsystem).map_err(|err| {
+                    warn!("{}", err);
+                    PlatformError::Udev("match_subsystem failed".into(), err)
+                })?;
+                let devices = enumerator.scan_devices().map_err(|err| {
+                    warn!("{}", err);
+                    PlatformError::Udev("scan_devices failed".into(), err)
+                })?;
+                Ok(devices.map(|d| d.syspath().to_owned()).collect())
+            }
+            Discovery::Root(root) => {
+                let mut paths = Vec::new();
+                for dir in [
+                    root.join("class").join(subsystem),
+                    root.join("bus").join(subsystem).join("devices"),
+                ] {
+                    let Ok(entries) = dir.read_dir() else {
+                        continue;
+                    };
+                    for entry in entries.flatten() {
+                        if entry.path().is_dir() {
+                            paths.push(entry.path());
+                        }
+                    }
+                }
+                paths.sort();
+                Ok(paths)
+            }
+        }
+    }
+
+    /// If the device at `syspath` has a parent in `subsystem`. Fake trees
+    /// don't model parents, so every device in one passes.
+    pub fn has_parent_in(&self, syspath: &Path, subsystem: &str) -> Result<bool> {
+        match self {
+            Discovery::Udev => {
+                let device = udev::Device::from_syspath(syspath).map_err(|err| {
+                    warn!("{}", err);
+                    PlatformError::Udev("from_syspath failed".into(), err)
+                })?;
+                let parent = device.parent_with_subsystem(subsystem).map_err(|err| {
+                    warn!("{}", err);
+                    PlatformError::Udev("parent_with_subsystem failed".into(), err)
+                })?;
+                Ok(parent.is_some())
+            }
+            Discovery::Root(_) => Ok(true),
+        }
+    }
+
+    /// Find the first device in `subsystem` named `sysname`
+    pub fn find(&self, subsystem: &str, sysname: &str) -> Result<Option<PathBuf>> {
+        Ok(self
+            .scan(subsystem)?
+            .into_iter()
+            .find(|p| sysname_of(p) == sysname))
+    }
+}
+
+/// The last component of a syspath, which is what udev calls the sysname
+pub fn sysname_of(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
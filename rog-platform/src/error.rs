@@ -22,6 +22,7 @@ pub enum PlatformError {
     NoAuraKeyboard,
     NoAuraNode,
     CPU(String),
+    InvalidValue(String),
 }
 
 impl fmt::Display for PlatformError {
@@ -49,6 +50,7 @@ impl fmt::Display for PlatformError {
             PlatformError::NoAuraKeyboard => write!(f, "No supported Aura keyboard"),
             PlatformError::NoAuraNode => write!(f, "No Aura keyboard node found"),
             PlatformError::CPU(s) => write!(f, "CPU control: {s}"),
+            PlatformError::InvalidValue(s) => write!(f, "Invalid value: {s}"),
         }
     }
 }
@@ -72,6 +74,7 @@ impl From<PlatformError> for FdoErr {
         log::error!("PlatformError: got: {error}");
         match error {
             PlatformError::NotSupported => FdoErr::NotSupported("".to_owned()),
+            PlatformError::InvalidValue(_) => FdoErr::InvalidArgs(error.to_string()),
             _ => FdoErr::Failed(format!("Failed with {error}")),
         }
     }
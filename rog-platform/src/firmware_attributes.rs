@@ -0,0 +1,222 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use zbus::zvariant::Type;
+
+use crate::discovery::{sysname_of, Discovery};
+use crate::error::{PlatformError, Result};
+use crate::{has_attr, read_attr_string, write_attr_string, AttrDir};
+
+/// The type of a firmware attribute, from its `type` file
+#[typeshare]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq, PartialOrd)]
+#[zvariant(signature = "s")]
+pub enum AttrType {
+    /// One of `possible_values`
+    Enumeration,
+    /// A number between `min_value` and `max_value`
+    Integer,
+    String,
+    #[default]
+    Unknown,
+}
+
+impl FromStr for AttrType {
+    type Err = PlatformError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.trim() {
+            "enumeration" => Self::Enumeration,
+            "integer" => Self::Integer,
+            "string" => Self::String,
+            _ => Self::Unknown,
+        })
+    }
+}
+
+/// A snapshot of one firmware attribute, its value, and its constraints.
+/// Values are kept as the strings sysfs uses, `attr_type` tells how to
+/// interpret them.
+#[typeshare]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Type, PartialEq, Eq, PartialOrd)]
+pub struct FirmwareAttribute {
+    pub name: String,
+    pub display_name: String,
+    pub attr_type: AttrType,
+    pub current_value: String,
+    pub default_value: String,
+    /// Only for `AttrType::Enumeration`
+    pub possible_values: Vec<String>,
+    /// Only for `AttrType::Integer`
+    pub min_value: i32,
+    /// Only for `AttrType::Integer`
+    pub max_value: i32,
+    /// Only for `AttrType::Integer`, `0` if any step is allowed
+    pub scalar_increment: i32,
+}
+
+impl FirmwareAttribute {
+    /// Read all of the attribute at `path`
+    fn read(path: &Path) -> Result<Self> {
+        let int = |attr: &str| {
+            read_attr_string(path, attr)
+                .ok()
+                .and_then(|v| v.trim().parse::<i32>().ok())
+                .unwrap_or_default()
+        };
+        let attr_type = AttrType::from_str(&read_attr_string(path, "type")?)?;
+        Ok(Self {
+            name: sysname_of(path),
+            display_name: read_attr_string(path, "display_name").unwrap_or_default(),
+            attr_type,
+            current_value: read_attr_string(path, "current_value")?,
+            default_value: read_attr_string(path, "default_value").unwrap_or_default(),
+            possible_values: read_attr_string(path, "possible_values")
+                .map(|v| {
+                    v.split(';')
+                        .filter(|v| !v.is_empty())
+                        .map(|v| v.trim().to_owned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_value: int("min_value"),
+            max_value: int("max_value"),
+            scalar_increment: int("scalar_increment"),
+        })
+    }
+
+    /// Check that `value` fits the constraints of the attribute
+    pub fn validate(&self, value: &str) -> Result<()> {
+        let invalid = |why: String| {
+            Err(PlatformError::InvalidValue(format!(
+                "{} {value}: {why}",
+                self.name
+            )))
+        };
+        match self.attr_type {
+            AttrType::Enumeration => {
+                if !self.possible_values.iter().any(|v| v == value) {
+                    return invalid(format!("must be one of {:?}", self.possible_values));
+                }
+            }
+            AttrType::Integer => {
+                let Ok(num) = value.parse::<i32>() else {
+                    return invalid("not a number".into());
+                };
+                if num < self.min_value || num > self.max_value {
+                    return invalid(format!(
+                        "must be within {}-{}",
+                        self.min_value, self.max_value
+                    ));
+                }
+                if self.scalar_increment > 1 && (num - self.min_value) % self.scalar_increment != 0
+                {
+                    return invalid(format!("must be a step of {}", self.scalar_increment));
+                }
+            }
+            AttrType::String | AttrType::Unknown => {}
+        }
+        Ok(())
+    }
+}
+
+impl Display for FirmwareAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.current_value)?;
+        match self.attr_type {
+            AttrType::Enumeration => write!(f, " [{}]", self.possible_values.join(", "))?,
+            AttrType::Integer => write!(f, " [{}-{}]", self.min_value, self.max_value)?,
+            AttrType::String | AttrType::Unknown => {}
+        }
+        if !self.display_name.is_empty() {
+            write!(f, ", {}", self.display_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The asus-armoury firmware attributes, found at
+/// `/sys/class/firmware-attributes/asus-armoury/attributes`. Each attribute
+/// is a directory with the value in `current_value` along with files
+/// describing its type and constraints.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
+pub struct FirmwareAttributes {
+    path: PathBuf,
+}
+
+impl FirmwareAttributes {
+    pub fn new() -> Result<Self> {
+        Self::new_with(&Discovery::from_env())
+    }
+
+    /// Find the firmware attributes using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        if let Some(path) = discovery.find("firmware-attributes", "asus-armoury")? {
+            let path = path.join("attributes");
+            if path.is_dir() {
+                info!("Found firmware attributes at {:?}", path);
+                return Ok(Self { path });
+            }
+        }
+        Err(PlatformError::MissingFunction(
+            "asus-armoury firmware attributes not found".into(),
+        ))
+    }
+
+    /// The `attributes` directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The names of all attributes, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Ok(entries) = self.path.read_dir() {
+            for entry in entries.flatten() {
+                if has_attr(&entry.path(), "current_value") {
+                    names.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Read every attribute. Any which can't be read are skipped.
+    pub fn list(&self) -> Vec<FirmwareAttribute> {
+        self.names()
+            .iter()
+            .filter_map(|name| self.get(name).ok())
+            .collect()
+    }
+
+    /// If `name` is one of `names()`. Anything else, such as a path to
+    /// somewhere outside of the attributes, is not an attribute.
+    pub fn has(&self, name: &str) -> bool {
+        self.names().iter().any(|n| n == name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<FirmwareAttribute> {
+        if !self.has(name) {
+            return Err(PlatformError::AttrNotFound(name.to_owned()));
+        }
+        FirmwareAttribute::read(&self.path.join(name))
+    }
+
+    /// Validate `value` against the attribute constraints, then write it
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        self.get(name)?.validate(value)?;
+        write_attr_string(&self.path.join(name), "current_value", value)
+    }
+}
+
+impl AttrDir for FirmwareAttributes {
+    fn attr_file(&self, attr_name: &str) -> PathBuf {
+        self.path.join(attr_name).join("current_value")
+    }
+}
@@ -0,0 +1,314 @@
+//! Choose the throttle policy from the CPU temperature and load.
+//!
+//! The policy steps up to Balanced or Performance once either the temperature
+//! or the load reaches the threshold of that policy, and steps down once both
+//! are under it by the hysteresis. After a change the policy is held for a
+//! minimum dwell time so it doesn't flap.
+
+use std::fmt::Display;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use zbus::zvariant::{OwnedValue, Type, Value};
+
+use crate::error::{PlatformError, Result};
+use crate::platform::ThrottlePolicy;
+
+/// The temperature in degrees C and load in percent at which a policy is used
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GovernorThreshold {
+    pub temp: u8,
+    pub load: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct GovernorConfig {
+    pub enabled: bool,
+    /// How often the temperature and load are checked, in milliseconds
+    pub interval: u64,
+    pub balanced: GovernorThreshold,
+    pub performance: GovernorThreshold,
+    /// How far under a threshold the temperature must be to step down
+    pub temp_hysteresis: u8,
+    /// How far under a threshold the load must be to step down
+    pub load_hysteresis: u8,
+    /// The shortest time a policy is kept, in seconds
+    pub min_dwell: u64,
+}
+
+impl Default for GovernorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 2000,
+            balanced: GovernorThreshold { temp: 60, load: 30 },
+            performance: GovernorThreshold { temp: 80, load: 70 },
+            temp_hysteresis: 5,
+            load_hysteresis: 10,
+            min_dwell: 30,
+        }
+    }
+}
+
+/// A policy change made by the governor and why
+#[typeshare]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Type, Value, OwnedValue, PartialEq, Eq)]
+pub struct GovernorDecision {
+    pub previous: ThrottlePolicy,
+    pub policy: ThrottlePolicy,
+    pub temp: u8,
+    pub load: u8,
+    pub reason: String,
+}
+
+impl Display for GovernorDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} -> {:?} at {}C and {}% load: {}",
+            self.previous, self.policy, self.temp, self.load, self.reason
+        )
+    }
+}
+
+/// Quiet, Balanced and Performance as 0, 1 and 2
+const fn level(policy: ThrottlePolicy) -> u8 {
+    match policy {
+        ThrottlePolicy::Quiet => 0,
+        ThrottlePolicy::Balanced => 1,
+        ThrottlePolicy::Performance => 2,
+    }
+}
+
+const fn from_level(level: u8) -> ThrottlePolicy {
+    match level {
+        0 => ThrottlePolicy::Quiet,
+        1 => ThrottlePolicy::Balanced,
+        _ => ThrottlePolicy::Performance,
+    }
+}
+
+/// Tracks the policy in use and how long it has been
+#[derive(Debug, Clone)]
+pub struct Governor {
+    current: ThrottlePolicy,
+    since: Instant,
+}
+
+impl Governor {
+    pub fn new(current: ThrottlePolicy, now: Instant) -> Self {
+        Self {
+            current,
+            since: now,
+        }
+    }
+
+    /// Note the policy actually in use. A change made elsewhere is held for
+    /// the dwell time like one made by the governor.
+    pub fn observe(&mut self, policy: ThrottlePolicy, now: Instant) {
+        if policy != self.current {
+            self.current = policy;
+            self.since = now;
+        }
+    }
+
+    /// The policy to change to for `temp` and `load`, if any. `ceiling` is
+    /// the highest policy allowed, and is applied without waiting out the
+    /// dwell time.
+    pub fn decide(
+        &mut self,
+        config: &GovernorConfig,
+        temp: u8,
+        load: u8,
+        ceiling: ThrottlePolicy,
+        now: Instant,
+    ) -> Option<GovernorDecision> {
+        let thresholds = [None, Some(config.balanced), Some(config.performance)];
+        let meets = |t: GovernorThreshold| temp >= t.temp || load >= t.load;
+        let under = |t: GovernorThreshold| {
+            temp.saturating_add(config.temp_hysteresis) < t.temp
+                && load.saturating_add(config.load_hysteresis) < t.load
+        };
+
+        let current = level(self.current);
+        let mut wanted = current;
+        let mut reason = String::new();
+        while let Some(Some(t)) = thresholds.get(wanted as usize + 1) {
+            if !meets(*t) {
+                break;
+            }
+            wanted += 1;
+            reason = if temp >= t.temp {
+                format!("temperature reached {}C", t.temp)
+            } else {
+                format!("load reached {}%", t.load)
+            };
+        }
+        if wanted == current {
+            while let Some(Some(t)) = thresholds.get(wanted as usize) {
+                if !under(*t) {
+                    break;
+                }
+                wanted -= 1;
+                reason = format!(
+                    "temperature under {}C and load under {}%",
+                    t.temp.saturating_sub(config.temp_hysteresis),
+                    t.load.saturating_sub(config.load_hysteresis)
+                );
+            }
+        }
+
+        let over_ceiling = current > level(ceiling);
+        if wanted > level(ceiling) {
+            wanted = level(ceiling);
+            reason = format!("limited to {ceiling:?} by the power source");
+        }
+        if wanted == current
+            || (!over_ceiling
+                && now.duration_since(self.since) < Duration::from_secs(config.min_dwell))
+        {
+            return None;
+        }
+
+        let decision = GovernorDecision {
+            previous: self.current,
+            policy: from_level(wanted),
+            temp,
+            load,
+            reason,
+        };
+        self.current = decision.policy;
+        self.since = now;
+        Some(decision)
+    }
+}
+
+/// CPU time from the `cpu` line of `/proc/stat`, in clock ticks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub total: u64,
+    /// Idle and waiting on IO
+    pub idle: u64,
+}
+
+impl CpuTimes {
+    pub fn parse(stat: &str) -> Option<Self> {
+        let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+        let fields: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            // Guest time is already counted in user time
+            .take(8)
+            .map(|n| n.parse().ok())
+            .collect::<Option<_>>()?;
+        Some(Self {
+            total: fields.iter().sum(),
+            idle: fields.get(3)? + fields.get(4).copied().unwrap_or_default(),
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let stat = std::fs::read_to_string(path)
+            .map_err(|e| PlatformError::Read(path.to_string_lossy().to_string(), e))?;
+        Self::parse(&stat).ok_or(PlatformError::ParseNum)
+    }
+
+    /// The percentage of time spent busy since `earlier`
+    pub fn load_since(&self, earlier: &Self) -> u8 {
+        let total = self.total.saturating_sub(earlier.total);
+        let idle = self.idle.saturating_sub(earlier.idle);
+        if total == 0 {
+            return 0;
+        }
+        (total.saturating_sub(idle) * 100 / total) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{CpuTimes, Governor, GovernorConfig};
+    use crate::platform::ThrottlePolicy;
+
+    #[test]
+    fn governor_steps_with_hysteresis_and_dwell() {
+        let config = GovernorConfig {
+            min_dwell: 10,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut gov = Governor::new(ThrottlePolicy::Quiet, start);
+        let max = ThrottlePolicy::Performance;
+
+        // Held for the dwell time
+        assert!(gov.decide(&config, 85, 10, max, at(5)).is_none());
+        let decision = gov.decide(&config, 85, 10, max, at(10)).unwrap();
+        assert_eq!(decision.previous, ThrottlePolicy::Quiet);
+        assert_eq!(decision.policy, ThrottlePolicy::Performance);
+        assert_eq!(decision.reason, "temperature reached 80C");
+
+        // Under the threshold but not by the hysteresis
+        assert!(gov.decide(&config, 77, 10, max, at(30)).is_none());
+        let decision = gov.decide(&config, 70, 50, max, at(30)).unwrap();
+        assert_eq!(decision.policy, ThrottlePolicy::Balanced);
+        let decision = gov.decide(&config, 40, 10, max, at(40)).unwrap();
+        assert_eq!(decision.policy, ThrottlePolicy::Quiet);
+        assert_eq!(decision.reason, "temperature under 55C and load under 20%");
+
+        let decision = gov.decide(&config, 40, 40, max, at(50)).unwrap();
+        assert_eq!(decision.policy, ThrottlePolicy::Balanced);
+        assert_eq!(decision.reason, "load reached 30%");
+    }
+
+    #[test]
+    fn governor_ceiling_and_external_changes() {
+        let config = GovernorConfig::default();
+        let start = Instant::now();
+        let mut gov = Governor::new(ThrottlePolicy::Performance, start);
+
+        // The ceiling doesn't wait for the dwell time
+        let decision = gov
+            .decide(&config, 90, 90, ThrottlePolicy::Balanced, start)
+            .unwrap();
+        assert_eq!(decision.policy, ThrottlePolicy::Balanced);
+        assert_eq!(decision.reason, "limited to Balanced by the power source");
+        let later = start + Duration::from_secs(60);
+        assert!(gov
+            .decide(&config, 90, 90, ThrottlePolicy::Balanced, later)
+            .is_none());
+
+        gov.observe(ThrottlePolicy::Quiet, later);
+        assert!(gov
+            .decide(&config, 90, 90, ThrottlePolicy::Balanced, later)
+            .is_none());
+        let decision = gov
+            .decide(
+                &config,
+                90,
+                90,
+                ThrottlePolicy::Balanced,
+                later + Duration::from_secs(30),
+            )
+            .unwrap();
+        assert_eq!(decision.previous, ThrottlePolicy::Quiet);
+        assert_eq!(decision.policy, ThrottlePolicy::Balanced);
+    }
+
+    #[test]
+    fn cpu_load_from_stat() {
+        let before =
+            CpuTimes::parse("cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 50 0 50 350 50 0 0 0 0 0\n")
+                .unwrap();
+        assert_eq!(before.total, 1000);
+        assert_eq!(before.idle, 800);
+        let after = CpuTimes::parse("cpu  400 0 200 1300 100 0 0 0 0 0\n").unwrap();
+        assert_eq!(after.load_since(&before), 40);
+        assert_eq!(after.load_since(&after), 0);
+        assert!(CpuTimes::parse("intr 1 2 3").is_none());
+    }
+}
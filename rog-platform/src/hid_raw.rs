@@ -1,12 +1,14 @@
 use std::cell::RefCell;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use log::{info, warn};
 use udev::Device;
 
+use crate::discovery::{sysname_of, Discovery};
 use crate::error::{PlatformError, Result};
+use crate::{read_attr_string, write_attr_string};
 
 /// A USB device that utilizes hidraw for I/O
 #[derive(Debug)]
@@ -23,6 +25,26 @@ pub struct HidRaw {
 
 impl HidRaw {
     pub fn new(id_product: &str) -> Result<Self> {
+        Self::new_with(id_product, &Discovery::from_env())
+    }
+
+    /// Find the hidraw endpoint for `id_product` using the given `Discovery`
+    /// backend
+    pub fn new_with(id_product: &str, discovery: &Discovery) -> Result<Self> {
+        if let Discovery::Root(root) = discovery {
+            for syspath in discovery.scan("hidraw")? {
+                if read_attr_string(&syspath, "idProduct")
+                    .is_ok_and(|id| id.eq_ignore_ascii_case(id_product))
+                {
+                    return Self::from_fake(root, &syspath);
+                }
+            }
+            return Err(PlatformError::MissingFunction(format!(
+                "hidraw dev {} not found",
+                id_product
+            )));
+        }
+
         let mut enumerator = udev::Enumerator::new().map_err(|err| {
             warn!("{}", err);
             PlatformError::Udev("enumerator failed".into(), err)
@@ -103,6 +125,27 @@ impl HidRaw {
         ))
     }
 
+    /// Make `HidRaw` device from an endpoint in a fake sysfs tree. The
+    /// endpoint directory holds the `idProduct` of its USB parent as a plain
+    /// file, and the device node is `<root>/dev/<sysname>` which has all
+    /// writes appended to it.
+    pub fn from_fake(root: &Path, syspath: &Path) -> Result<Self> {
+        let prod_id = read_attr_string(syspath, "idProduct")?;
+        let dev_node = root.join("dev").join(sysname_of(syspath));
+        info!("Using fake device at: {:?} for hidraw control", dev_node);
+        Ok(Self {
+            file: RefCell::new(
+                OpenOptions::new()
+                    .append(true)
+                    .open(&dev_node)
+                    .map_err(|e| PlatformError::IoPath(dev_node.to_string_lossy().into(), e))?,
+            ),
+            devfs_path: dev_node,
+            prod_id,
+            syspath: syspath.into(),
+        })
+    }
+
     pub fn prod_id(&self) -> &str {
         &self.prod_id
     }
@@ -122,7 +165,6 @@ impl HidRaw {
     /// This method was added for certain devices like AniMe to prevent them
     /// waking the laptop
     pub fn set_wakeup_disabled(&self) -> Result<()> {
-        let mut dev = Device::from_syspath(&self.syspath)?;
-        Ok(dev.set_attribute_value("power/wakeup", "disabled")?)
+        write_attr_string(&self.syspath, "power/wakeup", "disabled")
     }
 }
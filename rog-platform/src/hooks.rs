@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use zbus::zvariant::{OwnedValue, Type, Value};
+
+/// The events which asusd runs hooks for
+#[typeshare]
+#[repr(u32)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Type,
+    Value,
+    OwnedValue,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Hash,
+)]
+#[zvariant(signature = "u")]
+pub enum HookEvent {
+    /// Power changed to AC
+    #[default]
+    Ac = 0,
+    /// Power changed to battery
+    Battery = 1,
+    /// The throttle policy was changed
+    ThrottlePolicy = 2,
+    Suspend = 3,
+    Resume = 4,
+    LidClosed = 5,
+    LidOpened = 6,
+}
+
+impl HookEvent {
+    /// The name used for the hook directory and the `ASUSD_EVENT` variable
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Ac => "ac",
+            Self::Battery => "battery",
+            Self::ThrottlePolicy => "throttle-policy",
+            Self::Suspend => "suspend",
+            Self::Resume => "resume",
+            Self::LidClosed => "lid-closed",
+            Self::LidOpened => "lid-opened",
+        }
+    }
+}
+
+impl Display for HookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The outcome of running one hook
+#[typeshare]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Type, Value, OwnedValue, PartialEq, Eq)]
+pub struct HookResult {
+    pub event: HookEvent,
+    /// The hook argv joined with spaces, or the script path
+    pub command: String,
+    /// `-1` if the hook didn't exit normally, such as if it failed to start,
+    /// was killed, or timed out
+    pub exit_code: i32,
+    pub timed_out: bool,
+    /// The end of anything written to stderr, or why the hook couldn't run
+    pub stderr: String,
+}
+
+impl HookResult {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0 && !self.timed_out
+    }
+}
+
+impl Display for HookResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} hook `{}`", self.event, self.command)?;
+        if self.timed_out {
+            write!(f, " timed out")?;
+        } else {
+            write!(f, " exited with {}", self.exit_code)?;
+        }
+        if !self.stderr.is_empty() {
+            write!(f, ": {}", self.stderr)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::ffi::c_long;
+use std::fs::File;
+use std::io::Read;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+
+use log::info;
+
+use crate::discovery::{sysname_of, Discovery};
+use crate::error::{PlatformError, Result};
+use crate::read_attr_string;
+
+/// The names of the input devices of laptop keyboards, matched anywhere in
+/// the name
+const KEYBOARD_NAMES: [&str; 3] = [
+    "AT Translated Set 2 keyboard",
+    "Asus Keyboard",
+    "N-KEY Device",
+];
+/// `EV_KEY` from linux/input-event-codes.h
+const EV_KEY: u16 = 0x01;
+/// A `struct input_event`: a `struct timeval`, then the type, code, and value
+const INPUT_EVENT_LEN: usize = 2 * size_of::<c_long>() + 8;
+
+/// The key presses of a keyboard, read from its evdev node. The reads block,
+/// so this is best kept on its own thread.
+#[derive(Debug)]
+pub struct KeyEvents {
+    /// The path to the `/dev/input/event*` of the device
+    devfs_path: PathBuf,
+    file: File,
+}
+
+impl KeyEvents {
+    /// Open the evdev node of every laptop keyboard
+    pub fn find_all() -> Result<Vec<Self>> {
+        Self::find_all_with(&Discovery::from_env())
+    }
+
+    /// Open the evdev node of every laptop keyboard found using the given
+    /// `Discovery` backend. In a fake sysfs tree the node of `event<N>` is
+    /// `<root>/dev/input/event<N>`.
+    pub fn find_all_with(discovery: &Discovery) -> Result<Vec<Self>> {
+        let dev_dir = match discovery {
+            Discovery::Udev => PathBuf::from("/dev/input"),
+            Discovery::Root(root) => root.join("dev/input"),
+        };
+        let mut found = Vec::new();
+        for syspath in discovery.scan("input")? {
+            let sysname = sysname_of(&syspath);
+            if !sysname.starts_with("event") || !is_laptop_keyboard(&syspath) {
+                continue;
+            }
+            let dev_node = dev_dir.join(&sysname);
+            info!("Using {:?} for key presses", dev_node);
+            found.push(Self {
+                file: File::open(&dev_node)
+                    .map_err(|e| PlatformError::IoPath(dev_node.to_string_lossy().into(), e))?,
+                devfs_path: dev_node,
+            });
+        }
+        if found.is_empty() {
+            return Err(PlatformError::MissingFunction(
+                "No laptop keyboard input device found".to_owned(),
+            ));
+        }
+        Ok(found)
+    }
+
+    pub fn devfs_path(&self) -> &Path {
+        &self.devfs_path
+    }
+
+    /// Wait for the next key to be pressed and return its evdev key code.
+    /// Releases and auto-repeats are skipped.
+    pub fn next_press(&mut self) -> Result<u16> {
+        let mut event = [0u8; INPUT_EVENT_LEN];
+        loop {
+            self.file
+                .read_exact(&mut event)
+                .map_err(|e| PlatformError::IoPath(self.devfs_path.to_string_lossy().into(), e))?;
+            let data = &event[INPUT_EVENT_LEN - 8..];
+            let kind = u16::from_ne_bytes([data[0], data[1]]);
+            let code = u16::from_ne_bytes([data[2], data[3]]);
+            let value = i32::from_ne_bytes([data[4], data[5], data[6], data[7]]);
+            if kind == EV_KEY && value == 1 {
+                return Ok(code);
+            }
+        }
+    }
+}
+
+/// If the input device at `syspath` is a laptop keyboard. The name is that of
+/// the parent `input<N>`.
+fn is_laptop_keyboard(syspath: &Path) -> bool {
+    read_attr_string(syspath, "device/name")
+        .is_ok_and(|name| KEYBOARD_NAMES.iter().any(|k| name.contains(k)))
+}
@@ -1,9 +1,10 @@
 use std::path::PathBuf;
 
-use log::{info, warn};
+use log::info;
 
+use crate::discovery::{sysname_of, Discovery};
 use crate::error::{PlatformError, Result};
-use crate::{attr_u8, has_attr, set_attr_u8_array, to_device};
+use crate::{attr_u8, has_attr, set_attr_u8_array};
 
 /// The sysfs control for backlight levels. This is only for the 3-step
 /// backlight setting, and for TUF laptops. It is not a hard requirement
@@ -33,33 +34,14 @@ impl KeyboardBacklight {
     );
 
     pub fn new() -> Result<Self> {
-        let mut enumerator = udev::Enumerator::new().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("enumerator failed".into(), err)
-        })?;
-
-        enumerator.match_subsystem("leds").map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("match_subsystem failed".into(), err)
-        })?;
-
-        enumerator
-            .match_sysname("asus::kbd_backlight")
-            .map_err(|err| {
-                warn!("{}", err);
-                PlatformError::Udev("match_subsystem failed".into(), err)
-            })?;
+        Self::new_with(&Discovery::from_env())
+    }
 
-        if let Some(device) = (enumerator.scan_devices().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("scan_devices failed".into(), err)
-        })?)
-        .next()
-        {
-            info!("Found keyboard LED controls at {:?}", device.sysname());
-            return Ok(Self {
-                path: device.syspath().to_owned(),
-            });
+    /// Find the keyboard LED device using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        if let Some(path) = discovery.find("leds", "asus::kbd_backlight")? {
+            info!("Found keyboard LED controls at {:?}", sysname_of(&path));
+            return Ok(Self { path });
         }
         Err(PlatformError::MissingFunction(
             "KeyboardLed:new(), asus::kbd_backlight not found".into(),
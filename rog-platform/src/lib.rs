@@ -2,103 +2,147 @@
 //! on ROG, Strix, and TUF laptops.
 
 pub mod cpu;
+pub mod discovery;
 pub mod error;
+pub mod firmware_attributes;
+pub mod governor;
 pub mod hid_raw;
+pub mod hooks;
+pub mod key_events;
 pub mod keyboard_led;
 pub(crate) mod macros;
 pub mod platform;
 pub mod power;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod usb_raw;
 
-use std::path::Path;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use error::{PlatformError, Result};
-use udev::Device;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub(crate) fn to_device(sys_path: &Path) -> Result<Device> {
-    Device::from_syspath(sys_path)
-        .map_err(|e| PlatformError::Udev("Couldn't transform syspath to device".to_owned(), e))
+/// List the names of all attribute files of the device at `sys_path`
+pub fn list_attrs(sys_path: &Path) -> Vec<String> {
+    let mut attrs = Vec::new();
+    if let Ok(entries) = sys_path.read_dir() {
+        for entry in entries.flatten() {
+            if entry.path().is_file() {
+                attrs.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    attrs.sort();
+    attrs
 }
 
-pub fn has_attr(device: &Device, attr_name: &str) -> bool {
-    for attr in device.attributes() {
-        if attr.name() == attr_name {
-            return true;
-        }
+/// Something which holds attributes. Usually this is a device syspath with a
+/// file per attribute, but it can also map attributes elsewhere.
+pub trait AttrDir {
+    /// The path of the file holding the value of `attr_name`
+    fn attr_file(&self, attr_name: &str) -> PathBuf;
+}
+
+impl AttrDir for Path {
+    fn attr_file(&self, attr_name: &str) -> PathBuf {
+        self.join(attr_name)
     }
-    false
 }
 
-pub fn read_attr_bool(device: &Device, attr_name: &str) -> Result<bool> {
-    if let Some(value) = device.attribute_value(attr_name) {
-        let tmp = value.to_string_lossy();
-        if tmp.trim() == "0" {
-            return Ok(false);
-        }
-        return Ok(true);
+impl AttrDir for PathBuf {
+    fn attr_file(&self, attr_name: &str) -> PathBuf {
+        self.join(attr_name)
     }
-    Err(PlatformError::AttrNotFound(attr_name.to_owned()))
 }
 
-pub fn write_attr_bool(device: &mut Device, attr: &str, value: bool) -> Result<()> {
-    device
-        .set_attribute_value(attr, &(value as u8).to_string())
-        .map_err(|e| PlatformError::IoPath(attr.into(), e))
+pub fn has_attr(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> bool {
+    sys_path.attr_file(attr_name).is_file()
 }
 
-pub fn read_attr_u8(device: &Device, attr_name: &str) -> Result<u8> {
-    if let Some(value) = device.attribute_value(attr_name) {
-        let tmp = value.to_string_lossy();
-        return tmp.parse::<u8>().map_err(|_e| PlatformError::ParseNum);
-    }
-    Err(PlatformError::AttrNotFound(attr_name.to_owned()))
+fn attribute_value(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> Result<String> {
+    let path = sys_path.attr_file(attr_name);
+    fs::read_to_string(&path)
+        .map(|s| s.trim_end_matches('\n').to_owned())
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                PlatformError::AttrNotFound(attr_name.to_owned())
+            } else {
+                PlatformError::Read(path.to_string_lossy().to_string(), e)
+            }
+        })
 }
 
-pub fn write_attr_u8(device: &mut Device, attr: &str, value: u8) -> Result<()> {
-    device
-        .set_attribute_value(attr, &(value).to_string())
+/// Attributes are never created, only written, the same as sysfs behaves
+fn set_attribute_value(sys_path: &(impl AttrDir + ?Sized), attr: &str, value: &str) -> Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(sys_path.attr_file(attr))
+        .and_then(|mut file| file.write_all(value.as_bytes()))
         .map_err(|e| PlatformError::IoPath(attr.into(), e))
 }
 
-pub fn read_attr_u8_array(device: &Device, attr_name: &str) -> Result<Vec<u8>> {
-    if let Some(value) = device.attribute_value(attr_name) {
-        let tmp = value.to_string_lossy();
-        let tmp = tmp
-            .split(' ')
-            .map(|v| v.parse::<u8>().unwrap_or(0))
-            .collect();
-        return Ok(tmp);
+pub fn read_attr_bool(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> Result<bool> {
+    let value = attribute_value(sys_path, attr_name)?;
+    if value.trim() == "0" {
+        return Ok(false);
     }
-    Err(PlatformError::AttrNotFound(attr_name.to_owned()))
+    Ok(true)
 }
 
-pub fn write_attr_u8_array(device: &mut Device, attr: &str, values: &[u8]) -> Result<()> {
+pub fn write_attr_bool(sys_path: &(impl AttrDir + ?Sized), attr: &str, value: bool) -> Result<()> {
+    set_attribute_value(sys_path, attr, &(value as u8).to_string())
+}
+
+pub fn read_attr_u8(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> Result<u8> {
+    let value = attribute_value(sys_path, attr_name)?;
+    value
+        .trim()
+        .parse::<u8>()
+        .map_err(|_e| PlatformError::ParseNum)
+}
+
+pub fn write_attr_u8(sys_path: &(impl AttrDir + ?Sized), attr: &str, value: u8) -> Result<()> {
+    set_attribute_value(sys_path, attr, &(value).to_string())
+}
+
+pub fn read_attr_u8_array(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> Result<Vec<u8>> {
+    let value = attribute_value(sys_path, attr_name)?;
+    let tmp = value
+        .split(' ')
+        .map(|v| v.parse::<u8>().unwrap_or(0))
+        .collect();
+    Ok(tmp)
+}
+
+pub fn write_attr_u8_array(
+    sys_path: &(impl AttrDir + ?Sized),
+    attr: &str,
+    values: &[u8],
+) -> Result<()> {
     let mut tmp = String::new();
     for n in values {
         tmp.push_str(&n.to_string());
         tmp.push(' '); // space padding required
     }
     tmp.pop();
-    device
-        .set_attribute_value(attr, tmp.trim())
-        .map_err(|e| PlatformError::IoPath(attr.into(), e))
+    set_attribute_value(sys_path, attr, tmp.trim())
 }
 
-pub fn read_attr_string(device: &Device, attr_name: &str) -> Result<String> {
-    if let Some(value) = device.attribute_value(attr_name) {
-        let tmp = value.to_string_lossy().to_string();
-        return Ok(tmp);
-    }
-    Err(PlatformError::AttrNotFound(attr_name.to_owned()))
+pub fn read_attr_string(sys_path: &(impl AttrDir + ?Sized), attr_name: &str) -> Result<String> {
+    attribute_value(sys_path, attr_name)
 }
 
-pub fn write_attr_string(device: &mut Device, attr: &str, value: &str) -> Result<()> {
+pub fn write_attr_string(
+    sys_path: &(impl AttrDir + ?Sized),
+    attr: &str,
+    value: &str,
+) -> Result<()> {
     let tmp = value.trim();
-    device
-        .set_attribute_value(attr, tmp)
-        .map_err(|e| PlatformError::IoPath(attr.into(), e))
+    set_attribute_value(sys_path, attr, tmp)
 }
 
 #[cfg(test)]
@@ -4,10 +4,7 @@ macro_rules! has_attr {
         concat_idents::concat_idents!(fn_name = has_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> bool {
-                match to_device(&self.$item) {
-                    Ok(p) => $crate::has_attr(&p, $attr_name),
-                    Err(_) => false,
-                }
+                $crate::has_attr(&self.$item, $attr_name)
             }
         });
     };
@@ -19,8 +16,7 @@ macro_rules! watch_attr {
         concat_idents::concat_idents!(fn_name = monitor_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> Result<inotify::Inotify> {
-                let mut path = self.$item.clone();
-                path.push($attr_name);
+                let path = $crate::AttrDir::attr_file(&self.$item, $attr_name);
                 if let Some(path) = path.to_str() {
                     let inotify = inotify::Inotify::init()?;
                     inotify.watches().add(path, inotify::WatchMask::MODIFY)
@@ -45,7 +41,7 @@ macro_rules! get_attr_bool {
         concat_idents::concat_idents!(fn_name = get_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> Result<bool> {
-                $crate::read_attr_bool(&to_device(&self.$item)?, $attr_name)
+                $crate::read_attr_bool(&self.$item, $attr_name)
             }
         });
     };
@@ -57,7 +53,7 @@ macro_rules! set_attr_bool {
         concat_idents::concat_idents!(fn_name = set_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self, value: bool) -> Result<()> {
-                $crate::write_attr_bool(&mut to_device(&self.$item)?, $attr_name, value)
+                $crate::write_attr_bool(&self.$item, $attr_name, value)
             }
         });
     };
@@ -79,7 +75,7 @@ macro_rules! get_attr_u8 {
         concat_idents::concat_idents!(fn_name = get_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> Result<u8> {
-                $crate::read_attr_u8(&to_device(&self.$item)?, $attr_name)
+                $crate::read_attr_u8(&self.$item, $attr_name)
             }
         });
     };
@@ -92,7 +88,7 @@ macro_rules! set_attr_u8 {
         concat_idents::concat_idents!(fn_name = set_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self, value: u8) -> Result<()> {
-                $crate::write_attr_u8(&mut to_device(&self.$item)?, $attr_name, value)
+                $crate::write_attr_u8(&self.$item, $attr_name, value)
             }
         });
     };
@@ -114,7 +110,7 @@ macro_rules! get_attr_u8_array {
         concat_idents::concat_idents!(fn_name = get_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> Result<Vec<u8>> {
-                $crate::read_attr_u8_array(&to_device(&self.$item)?, $attr_name)
+                $crate::read_attr_u8_array(&self.$item, $attr_name)
             }
         });
     };
@@ -126,7 +122,7 @@ macro_rules! set_attr_u8_array {
         concat_idents::concat_idents!(fn_name = set_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self, values: &[u8]) -> Result<()> {
-                $crate::write_attr_u8_array(&mut to_device(&self.$item)?, $attr_name, values)
+                $crate::write_attr_u8_array(&self.$item, $attr_name, values)
             }
         });
     };
@@ -148,7 +144,7 @@ macro_rules! get_attr_string {
         concat_idents::concat_idents!(fn_name = get_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self) -> Result<String> {
-                $crate::read_attr_string(&to_device(&self.$item)?, $attr_name)
+                $crate::read_attr_string(&self.$item, $attr_name)
             }
         });
     };
@@ -160,7 +156,7 @@ macro_rules! set_attr_string {
         concat_idents::concat_idents!(fn_name = set_, $attr_name {
             $(#[$attr])*
             pub fn fn_name(&self, values: &str) -> Result<()> {
-                $crate::write_attr_string(&mut to_device(&self.$item)?, $attr_name, values)
+                $crate::write_attr_string(&self.$item, $attr_name, values)
             }
         });
     };
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -5,10 +6,12 @@ use std::str::FromStr;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
-use zbus::zvariant::{OwnedValue, Type, Value};
+use zbus::zvariant::{OwnedValue, Signature, Type, Value};
 
+use crate::discovery::{sysname_of, Discovery};
 use crate::error::{PlatformError, Result};
-use crate::{attr_bool, attr_string, attr_u8, to_device};
+use crate::firmware_attributes::FirmwareAttributes;
+use crate::{attr_bool, attr_string, attr_u8, read_attr_string, AttrDir};
 
 /// The "platform" device provides access to things like:
 /// - `dgpu_disable`
@@ -20,8 +23,26 @@ use crate::{attr_bool, attr_string, attr_u8, to_device};
 /// - `keyboard_state`, set keyboard power states
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
 pub struct RogPlatform {
-    path: PathBuf,
+    path: PlatformPath,
     pp_path: PathBuf,
+    board_name: String,
+}
+
+/// Where the platform attributes are. The asus-armoury firmware attributes
+/// are used for any attribute they have, with the rest from `asus-nb-wmi`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
+struct PlatformPath {
+    wmi: PathBuf,
+    firmware: Option<FirmwareAttributes>,
+}
+
+impl AttrDir for PlatformPath {
+    fn attr_file(&self, attr_name: &str) -> PathBuf {
+        match &self.firmware {
+            Some(firmware) if firmware.has(attr_name) => firmware.attr_file(attr_name),
+            _ => self.wmi.join(attr_name),
+        }
+    }
 }
 
 impl RogPlatform {
@@ -50,51 +71,45 @@ impl RogPlatform {
 
     attr_u8!(
         /// Package Power Target total of CPU: PL1 on Intel, SPL on AMD.
-        /// Shown on Intel+Nvidia or AMD+Nvidia based systems:
-        /// * min=5, max=250
+        /// Shown on Intel+Nvidia or AMD+Nvidia based systems. See `ppt_limits()`
         "ppt_pl1_spl",
         path
     );
 
     attr_u8!(
         /// Slow Package Power Tracking Limit of CPU: PL2 on Intel, SPPT,
-        /// on AMD. Shown on Intel+Nvidia or AMD+Nvidia based systems:
-        /// * min=5, max=250
+        /// on AMD. Shown on Intel+Nvidia or AMD+Nvidia based systems. See
+        /// `ppt_limits()`
         "ppt_pl2_sppt",
         path
     );
 
     attr_u8!(
-        /// Fast Package Power Tracking Limit of CPU. AMD+Nvidia only:
-        /// * min=5, max=250
+        /// Fast Package Power Tracking Limit of CPU. AMD+Nvidia only
         "ppt_fppt",
         path
     );
 
     attr_u8!(
-        /// APU SPPT limit. Shown on full AMD systems only:
-        /// * min=5, max=130
+        /// APU SPPT limit. Shown on full AMD systems only
         "ppt_apu_sppt",
         path
     );
 
     attr_u8!(
-        /// Platform SPPT limit. Shown on full AMD systems only:
-        /// * min=5, max=130
+        /// Platform SPPT limit. Shown on full AMD systems only
         "ppt_platform_sppt",
         path
     );
 
     attr_u8!(
-        /// Dynamic boost limit of the Nvidia dGPU:
-        /// * min=5, max=25
+        /// Dynamic boost limit of the Nvidia dGPU
         "nv_dynamic_boost",
         path
     );
 
     attr_u8!(
-        /// Target temperature limit of the Nvidia dGPU:
-        /// * min=75, max=87
+        /// Target temperature limit of the Nvidia dGPU
         "nv_temp_target",
         path
     );
@@ -106,34 +121,74 @@ impl RogPlatform {
     );
 
     pub fn new() -> Result<Self> {
-        let mut enumerator = udev::Enumerator::new().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("enumerator failed".into(), err)
-        })?;
-        enumerator.match_subsystem("platform").map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("match_subsystem failed".into(), err)
-        })?;
-        enumerator.match_sysname("asus-nb-wmi").map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("match_subsystem failed".into(), err)
-        })?;
-
-        if let Some(device) = (enumerator.scan_devices().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("scan_devices failed".into(), err)
-        })?)
-        .next()
-        {
-            info!("Found platform support at {:?}", device.sysname());
-            return Ok(Self {
-                path: device.syspath().to_owned(),
-                pp_path: PathBuf::from_str("/sys/firmware/acpi").unwrap(),
-            });
+        Self::new_with(&Discovery::from_env())
+    }
+
+    /// Find the platform device using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        let wmi = discovery.find("platform", "asus-nb-wmi")?;
+        let firmware = FirmwareAttributes::new_with(discovery).ok();
+        if wmi.is_none() && firmware.is_none() {
+            return Err(PlatformError::MissingFunction(
+                "asus-nb-wmi not found".into(),
+            ));
         }
-        Err(PlatformError::MissingFunction(
-            "asus-nb-wmi not found".into(),
-        ))
+        let wmi = match wmi {
+            Some(wmi) => {
+                info!("Found platform support at {:?}", sysname_of(&wmi));
+                wmi
+            }
+            None => discovery.sys_path("bus/platform/devices/asus-nb-wmi"),
+        };
+        let board_name =
+            read_attr_string(&discovery.sys_path("class/dmi/id"), "board_name").unwrap_or_default();
+        Ok(Self {
+            path: PlatformPath { wmi, firmware },
+            pp_path: discovery.sys_path("firmware/acpi"),
+            board_name,
+        })
+    }
+
+    /// The DMI `board_name`, empty if it couldn't be read
+    pub fn board_name(&self) -> &str {
+        &self.board_name
+    }
+
+    /// The asus-armoury firmware attributes, if the kernel has them
+    pub fn firmware_attributes(&self) -> Option<&FirmwareAttributes> {
+        self.path.firmware.as_ref()
+    }
+
+    /// The limits of the PPT and Nvidia attributes. These are the `min_value`
+    /// and `max_value` of the firmware attributes where the kernel has them,
+    /// otherwise the generic asus-wmi ranges.
+    pub fn ppt_limits(&self) -> PptLimits {
+        let mut limits = PptLimits::GENERIC;
+        let Some(firmware) = self.firmware_attributes() else {
+            return limits;
+        };
+        for attr in PptLimits::ATTRS {
+            let Ok(fw_attr) = firmware.get(attr) else {
+                continue;
+            };
+            let (Ok(min), Ok(max)) = (
+                u8::try_from(fw_attr.min_value),
+                u8::try_from(fw_attr.max_value),
+            ) else {
+                continue;
+            };
+            if max == 0 {
+                continue;
+            }
+            if let Some(limit) = limits.get_mut(attr) {
+                *limit = PptLimit {
+                    min,
+                    max,
+                    default: fw_attr.default_value.parse().unwrap_or_default(),
+                };
+            }
+        }
+        limits
     }
 }
 
@@ -141,8 +196,12 @@ impl Default for RogPlatform {
     fn default() -> Self {
         unsafe {
             Self {
-                path: PathBuf::from_str("/this_shouldNeVErr_exisid").unwrap_unchecked(),
+                path: PlatformPath {
+                    wmi: PathBuf::from_str("/this_shouldNeVErr_exisid").unwrap_unchecked(),
+                    firmware: None,
+                },
                 pp_path: PathBuf::from_str("/this_shouldNeVErr_exisid").unwrap_unchecked(),
+                board_name: String::new(),
             }
         }
     }
@@ -373,3 +432,255 @@ pub enum Properties {
     NvDynamicBoost,
     NvTempTarget,
 }
+
+/// A set of PPT and Nvidia tuning values which are applied together, such as
+/// when a `ThrottlePolicy` is activated. An unset value leaves the current
+/// value of that attribute as is. Sent over D-Bus as a dict of the set values.
+#[typeshare]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(try_from = "HashMap<String, u8>", into = "BTreeMap<String, u8>")]
+pub struct PlatformTuning {
+    pub ppt_pl1_spl: Option<u8>,
+    pub ppt_pl2_sppt: Option<u8>,
+    pub ppt_fppt: Option<u8>,
+    pub ppt_apu_sppt: Option<u8>,
+    pub ppt_platform_sppt: Option<u8>,
+    pub nv_dynamic_boost: Option<u8>,
+    pub nv_temp_target: Option<u8>,
+}
+
+impl PlatformTuning {
+    /// True if no value is set
+    pub fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Take any unset values from `other`
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            ppt_pl1_spl: self.ppt_pl1_spl.or(other.ppt_pl1_spl),
+            ppt_pl2_sppt: self.ppt_pl2_sppt.or(other.ppt_pl2_sppt),
+            ppt_fppt: self.ppt_fppt.or(other.ppt_fppt),
+            ppt_apu_sppt: self.ppt_apu_sppt.or(other.ppt_apu_sppt),
+            ppt_platform_sppt: self.ppt_platform_sppt.or(other.ppt_platform_sppt),
+            nv_dynamic_boost: self.nv_dynamic_boost.or(other.nv_dynamic_boost),
+            nv_temp_target: self.nv_temp_target.or(other.nv_temp_target),
+        }
+    }
+
+    /// The values in the order of `PptLimits::ATTRS`
+    fn values(&self) -> [Option<u8>; 7] {
+        [
+            self.ppt_pl1_spl,
+            self.ppt_pl2_sppt,
+            self.ppt_fppt,
+            self.ppt_apu_sppt,
+            self.ppt_platform_sppt,
+            self.nv_dynamic_boost,
+            self.nv_temp_target,
+        ]
+    }
+
+    /// Get a value by its sysfs name
+    pub fn get_mut(&mut self, attr: &str) -> Option<&mut Option<u8>> {
+        match attr {
+            "ppt_pl1_spl" => Some(&mut self.ppt_pl1_spl),
+            "ppt_pl2_sppt" => Some(&mut self.ppt_pl2_sppt),
+            "ppt_fppt" => Some(&mut self.ppt_fppt),
+            "ppt_apu_sppt" => Some(&mut self.ppt_apu_sppt),
+            "ppt_platform_sppt" => Some(&mut self.ppt_platform_sppt),
+            "nv_dynamic_boost" => Some(&mut self.nv_dynamic_boost),
+            "nv_temp_target" => Some(&mut self.nv_temp_target),
+            _ => None,
+        }
+    }
+}
+
+impl Type for PlatformTuning {
+    fn signature() -> Signature<'static> {
+        <HashMap<String, u8>>::signature()
+    }
+}
+
+impl TryFrom<HashMap<String, u8>> for PlatformTuning {
+    type Error = String;
+
+    fn try_from(values: HashMap<String, u8>) -> std::result::Result<Self, Self::Error> {
+        let mut tuning = Self::default();
+        for (attr, value) in values {
+            *tuning
+                .get_mut(&attr)
+                .ok_or_else(|| format!("{attr} is not a tuning attribute"))? = Some(value);
+        }
+        Ok(tuning)
+    }
+}
+
+impl From<PlatformTuning> for BTreeMap<String, u8> {
+    fn from(tuning: PlatformTuning) -> Self {
+        PptLimits::ATTRS
+            .into_iter()
+            .zip(tuning.values())
+            .filter_map(|(attr, value)| Some((attr.to_owned(), value?)))
+            .collect()
+    }
+}
+
+impl Display for PlatformTuning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (attr, value)) in PptLimits::ATTRS.iter().zip(self.values()).enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            let value = value.map_or_else(|| "unset".to_owned(), |v| v.to_string());
+            write!(f, "{:<19}{value}", format!("{attr}:"))?;
+        }
+        Ok(())
+    }
+}
+
+/// The allowed range of a PPT or Nvidia attribute. A `default` of `0` means
+/// the firmware default is unknown.
+#[typeshare]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Type,
+    Value,
+    OwnedValue,
+    PartialEq,
+    Eq,
+    PartialOrd,
+)]
+pub struct PptLimit {
+    pub min: u8,
+    pub max: u8,
+    pub default: u8,
+}
+
+impl PptLimit {
+    const fn new(min: u8, max: u8, default: u8) -> Self {
+        Self { min, max, default }
+    }
+
+    pub fn contains(&self, value: u8) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+impl Display for PptLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "min={}, max={}", self.min, self.max)?;
+        if self.default != 0 {
+            write!(f, ", default={}", self.default)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `PptLimit` of each attribute in a `PlatformTuning`
+#[typeshare]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Type,
+    Value,
+    OwnedValue,
+    PartialEq,
+    Eq,
+    PartialOrd,
+)]
+pub struct PptLimits {
+    pub ppt_pl1_spl: PptLimit,
+    pub ppt_pl2_sppt: PptLimit,
+    pub ppt_fppt: PptLimit,
+    pub ppt_apu_sppt: PptLimit,
+    pub ppt_platform_sppt: PptLimit,
+    pub nv_dynamic_boost: PptLimit,
+    pub nv_temp_target: PptLimit,
+}
+
+impl PptLimits {
+    /// The sysfs names of the attributes
+    pub const ATTRS: [&'static str; 7] = [
+        "ppt_pl1_spl",
+        "ppt_pl2_sppt",
+        "ppt_fppt",
+        "ppt_apu_sppt",
+        "ppt_platform_sppt",
+        "nv_dynamic_boost",
+        "nv_temp_target",
+    ];
+    /// The ranges documented by asus-wmi, which cover every supported board
+    pub const GENERIC: Self = Self {
+        ppt_pl1_spl: PptLimit::new(5, 250, 0),
+        ppt_pl2_sppt: PptLimit::new(5, 250, 0),
+        ppt_fppt: PptLimit::new(5, 250, 0),
+        ppt_apu_sppt: PptLimit::new(5, 130, 0),
+        ppt_platform_sppt: PptLimit::new(5, 130, 0),
+        nv_dynamic_boost: PptLimit::new(5, 25, 0),
+        nv_temp_target: PptLimit::new(75, 87, 0),
+    };
+
+    /// Get the limit of an attribute by its sysfs name
+    pub fn get(&self, attr: &str) -> Option<PptLimit> {
+        match attr {
+            "ppt_pl1_spl" => Some(self.ppt_pl1_spl),
+            "ppt_pl2_sppt" => Some(self.ppt_pl2_sppt),
+            "ppt_fppt" => Some(self.ppt_fppt),
+            "ppt_apu_sppt" => Some(self.ppt_apu_sppt),
+            "ppt_platform_sppt" => Some(self.ppt_platform_sppt),
+            "nv_dynamic_boost" => Some(self.nv_dynamic_boost),
+            "nv_temp_target" => Some(self.nv_temp_target),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, attr: &str) -> Option<&mut PptLimit> {
+        match attr {
+            "ppt_pl1_spl" => Some(&mut self.ppt_pl1_spl),
+            "ppt_pl2_sppt" => Some(&mut self.ppt_pl2_sppt),
+            "ppt_fppt" => Some(&mut self.ppt_fppt),
+            "ppt_apu_sppt" => Some(&mut self.ppt_apu_sppt),
+            "ppt_platform_sppt" => Some(&mut self.ppt_platform_sppt),
+            "nv_dynamic_boost" => Some(&mut self.nv_dynamic_boost),
+            "nv_temp_target" => Some(&mut self.nv_temp_target),
+            _ => None,
+        }
+    }
+
+    /// Find the first set value of `tuning` which is out of range, returning
+    /// the attribute name and value
+    pub fn check_tuning(&self, tuning: &PlatformTuning) -> Option<(&'static str, u8)> {
+        Self::ATTRS
+            .into_iter()
+            .zip(tuning.values())
+            .filter_map(|(attr, value)| Some((attr, value?)))
+            .find(|(attr, value)| self.get(attr).is_some_and(|limit| !limit.contains(*value)))
+    }
+}
+
+impl Display for PptLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, attr) in Self::ATTRS.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{:<19}{}",
+                format!("{attr}:"),
+                self.get(attr).unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
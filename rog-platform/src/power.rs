@@ -1,9 +1,16 @@
+use std::fmt::Display;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use zbus::zvariant::{OwnedValue, Type, Value};
 
+use crate::discovery::{sysname_of, Discovery};
 use crate::error::{PlatformError, Result};
-use crate::{attr_u8, to_device};
+use crate::{attr_u8, read_attr_string};
 
 /// The "platform" device provides access to things like:
 /// - `dgpu_disable`
@@ -14,15 +21,158 @@ use crate::{attr_u8, to_device};
 /// - `keyboard_state`, set keyboard power states
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
 pub struct AsusPower {
-    mains: PathBuf,
+    /// Not every machine has a mains `power_supply`, such as some desktops
+    mains: Option<PathBuf>,
     battery: PathBuf,
     usb: Option<PathBuf>,
+    /// Found with udev, so udev events can be used to watch for changes
+    udev: bool,
 }
 
 impl AsusPower {
+    /// How often `watch()` checks for changes if udev events can't be used
+    pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
     attr_u8!("charge_control_end_threshold", battery);
 
-    attr_u8!("online", mains);
+    /// Whether a mains supply was found and it has `online`
+    pub fn has_online(&self) -> bool {
+        self.mains
+            .as_ref()
+            .is_some_and(|mains| crate::has_attr(mains, "online"))
+    }
+
+    /// Read mains `online`, or `NotSupported` if no mains supply was found
+    pub fn get_online(&self) -> Result<u8> {
+        let mains = self.mains.as_ref().ok_or(PlatformError::NotSupported)?;
+        crate::read_attr_u8(mains, "online")
+    }
+
+    /// Call `on_change` with the new mains `online` state every time it
+    /// changes, see `watch()`.
+    ///
+    /// This never returns, so should be run on its own thread.
+    pub fn watch_online(&self, on_change: impl FnMut(bool)) {
+        self.watch(on_change, |_| {})
+    }
+
+    /// Call `on_online` with the new mains `online` state, and `on_battery`
+    /// with the new `battery_info()`, every time they change. udev
+    /// `power_supply` events are used to wake, or if they can't be (such as
+    /// with a fake sysfs tree) the state is polled every `POLL_INTERVAL`.
+    ///
+    /// This never returns, so should be run on its own thread.
+    pub fn watch(&self, mut on_online: impl FnMut(bool), mut on_battery: impl FnMut(&BatteryInfo)) {
+        let mut last = self.get_online().map(|o| o > 0).unwrap_or(true);
+        let mut last_battery = self.battery_info();
+        let mut check = || {
+            if let Ok(online) = self.get_online().map(|o| o > 0) {
+                if online != last {
+                    last = online;
+                    on_online(online);
+                }
+            }
+            let battery = self.battery_info();
+            if battery != last_battery {
+                on_battery(&battery);
+                last_battery = battery;
+            }
+        };
+
+        if self.udev {
+            if let Err(e) = Self::wait_udev_events(&mut check) {
+                warn!("Power: udev monitor failed, polling instead: {e}");
+            }
+        }
+        loop {
+            std::thread::sleep(Self::POLL_INTERVAL);
+            check();
+        }
+    }
+
+    /// Call `on_event` after every batch of `power_supply` events. Only
+    /// returns on error.
+    fn wait_udev_events(mut on_event: impl FnMut()) -> Result<()> {
+        let mut monitor = udev::MonitorBuilder::new()
+            .and_then(|m| m.match_subsystem("power_supply"))
+            .and_then(|m| m.listen())
+            .map_err(|e| PlatformError::Udev("power_supply monitor failed".into(), e))?;
+        let mut poll = mio::Poll::new().map_err(PlatformError::Io)?;
+        let mut events = mio::Events::with_capacity(64);
+        poll.registry()
+            .register(&mut monitor, mio::Token(0), mio::Interest::READABLE)
+            .map_err(PlatformError::Io)?;
+
+        loop {
+            if poll.poll(&mut events, None).is_err() {
+                continue;
+            }
+            // Only the new state matters, not what the events were
+            for _ in monitor.iter() {}
+            on_event();
+        }
+    }
+
+    /// Read a plain number from the battery, as used by most `power_supply`
+    /// attributes
+    fn battery_num(&self, attr_name: &str) -> Option<u64> {
+        read_attr_string(&self.battery, attr_name)
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+    }
+
+    /// Read `current_now` in µA. Some firmware reports it as negative while
+    /// discharging, so only the magnitude is used.
+    fn battery_current(&self) -> Option<u64> {
+        read_attr_string(&self.battery, "current_now")
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .map(i64::unsigned_abs)
+    }
+
+    /// Read the state and health of the battery. Batteries which report
+    /// charge (µAh) and current (µA) instead of energy (µWh) and power (µW)
+    /// are converted using their voltage. Anything the battery doesn't report
+    /// is left at `0`.
+    pub fn battery_info(&self) -> BatteryInfo {
+        let energy = |name: &str| {
+            self.battery_num(&format!("energy_{name}")).or_else(|| {
+                let voltage = self
+                    .battery_num("voltage_min_design")
+                    .or_else(|| self.battery_num("voltage_now"))?;
+                Some(self.battery_num(&format!("charge_{name}"))? * voltage / 1_000_000)
+            })
+        };
+        let power = self.battery_num("power_now").or_else(|| {
+            Some(self.battery_current()? * self.battery_num("voltage_now")? / 1_000_000)
+        });
+        let status = read_attr_string(&self.battery, "status")
+            .map(|s| BatteryStatus::from_str(&s).unwrap_or_default())
+            .unwrap_or_default();
+
+        let energy_now = energy("now").unwrap_or_default();
+        let power_now = power.unwrap_or_default();
+        let time_to_empty = if status != BatteryStatus::Discharging {
+            0
+        } else if let Some(secs) = self.battery_num("time_to_empty_now") {
+            secs / 60
+        } else if power_now > 0 {
+            energy_now * 60 / power_now
+        } else {
+            0
+        };
+
+        BatteryInfo {
+            capacity: self.battery_num("capacity").unwrap_or_default() as u8,
+            status,
+            energy_now: (energy_now / 1000) as u32,
+            energy_full: (energy("full").unwrap_or_default() / 1000) as u32,
+            energy_full_design: (energy("full_design").unwrap_or_default() / 1000) as u32,
+            cycle_count: self.battery_num("cycle_count").unwrap_or_default() as u32,
+            power_now: (power_now / 1000) as u32,
+            time_to_empty: time_to_empty as u32,
+        }
+    }
 
     /// When checking for battery this will look in order:
     /// - if attr `manufacturer` contains `asus`
@@ -31,62 +181,56 @@ impl AsusPower {
     /// - if syspath end conatins `BAT`
     /// - if attr `type` is `battery` (last resort)
     pub fn new() -> Result<Self> {
-        let mut mains = PathBuf::new();
+        Self::new_with(&Discovery::from_env())
+    }
+
+    /// Find the power supplies using the given `Discovery` backend
+    pub fn new_with(discovery: &Discovery) -> Result<Self> {
+        let mut mains = None;
         let mut battery = None;
         let mut usb = None;
 
-        let mut enumerator = udev::Enumerator::new().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("enumerator failed".into(), err)
-        })?;
-        enumerator.match_subsystem("power_supply").map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("match_subsystem failed".into(), err)
-        })?;
-
-        for device in enumerator.scan_devices().map_err(|err| {
-            warn!("{}", err);
-            PlatformError::Udev("scan_devices failed".into(), err)
-        })? {
-            if let Some(attr) = device.attribute_value("type") {
-                info!("Power: Checking {:?}", device.syspath());
-                match attr.to_string_lossy().to_ascii_lowercase().trim() {
+        for path in discovery.scan("power_supply")? {
+            let sysname = sysname_of(&path);
+            if let Ok(attr) = read_attr_string(&path, "type") {
+                info!("Power: Checking {:?}", path);
+                match attr.to_ascii_lowercase().trim() {
                     "mains" => {
-                        info!("Found mains power at {:?}", device.sysname());
-                        mains = device.syspath().to_path_buf();
+                        info!("Found mains power at {:?}", sysname);
+                        mains = Some(path);
                     }
                     "battery" => {
                         // Priortised list of checks
                         info!("Found a battery");
                         if battery.is_none() {
                             info!("Checking battery attributes");
-                            if let Some(current) =
-                                device.attribute_value("charge_control_end_threshold")
+                            if let Ok(current) =
+                                read_attr_string(&path, "charge_control_end_threshold")
                             {
                                 info!(
                                     "Found battery power at {:?}, matched \
                                      charge_control_end_threshold. Current level: {current:?}",
-                                    device.sysname()
+                                    sysname
                                 );
-                                battery = Some(device.syspath().to_path_buf());
-                            } else if device.sysname().to_string_lossy().starts_with("BAT") {
+                                battery = Some(path);
+                            } else if sysname.starts_with("BAT") {
                                 info!(
                                     "Found battery power at {:?}, sysfs path ended with BAT<n>",
-                                    device.sysname()
+                                    sysname
                                 );
-                                battery = Some(device.syspath().to_path_buf());
+                                battery = Some(path);
                             } else {
                                 info!(
                                     "Last resort: Found battery power at {:?} using type = Battery",
-                                    device.sysname()
+                                    sysname
                                 );
-                                battery = Some(device.syspath().to_path_buf());
+                                battery = Some(path);
                             }
                         }
                     }
                     "usb" => {
-                        info!("Found USB-C power at {:?}", device.sysname());
-                        usb = Some(device.syspath().to_path_buf());
+                        info!("Found USB-C power at {:?}", sysname);
+                        usb = Some(path);
                     }
                     _ => {}
                 };
@@ -98,6 +242,7 @@ impl AsusPower {
                 mains,
                 battery,
                 usb,
+                udev: *discovery == Discovery::Udev,
             });
         }
 
@@ -106,3 +251,364 @@ impl AsusPower {
         ))
     }
 }
+
+/// The `status` of a battery
+#[typeshare]
+#[repr(u32)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Type,
+    Value,
+    OwnedValue,
+    PartialEq,
+    Eq,
+    PartialOrd,
+)]
+#[zvariant(signature = "u")]
+pub enum BatteryStatus {
+    #[default]
+    Unknown = 0,
+    Charging = 1,
+    Discharging = 2,
+    /// Plugged in but held at the charge limit
+    NotCharging = 3,
+    Full = 4,
+}
+
+impl FromStr for BatteryStatus {
+    type Err = PlatformError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.trim() {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Not charging" => Self::NotCharging,
+            "Full" => Self::Full,
+            _ => Self::Unknown,
+        })
+    }
+}
+
+impl Display for BatteryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Unknown => "Unknown",
+            Self::Charging => "Charging",
+            Self::Discharging => "Discharging",
+            Self::NotCharging => "Not charging",
+            Self::Full => "Full",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A snapshot of the battery state and health, energy is in mWh and power in
+/// mW. Values the battery doesn't report are `0`.
+#[typeshare]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq, PartialOrd)]
+pub struct BatteryInfo {
+    /// Percent charged
+    pub capacity: u8,
+    pub status: BatteryStatus,
+    pub energy_now: u32,
+    pub energy_full: u32,
+    pub energy_full_design: u32,
+    pub cycle_count: u32,
+    /// The current draw, or charge rate when charging
+    pub power_now: u32,
+    /// Minutes until empty, only while discharging
+    pub time_to_empty: u32,
+}
+
+impl BatteryInfo {
+    /// How much of the design capacity the battery still holds, in percent,
+    /// or `0` if unknown
+    pub fn health(&self) -> u8 {
+        if self.energy_full_design == 0 {
+            return 0;
+        }
+        let full = self.energy_full as u64 * 100;
+        let design = self.energy_full_design as u64;
+        ((full + design / 2) / design).min(u8::MAX as u64) as u8
+    }
+}
+
+impl Display for BatteryInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}%, {}", self.capacity, self.status)?;
+        if self.power_now > 0 {
+            write!(f, ", {:.1}W", self.power_now as f32 / 1000.0)?;
+        }
+        if self.time_to_empty > 0 {
+            write!(
+                f,
+                ", {}:{:02} remaining",
+                self.time_to_empty / 60,
+                self.time_to_empty % 60
+            )?;
+        }
+        if self.energy_full_design > 0 {
+            write!(
+                f,
+                "\nHealth {}%, {:.1}Wh of {:.1}Wh design",
+                self.health(),
+                self.energy_full as f32 / 1000.0,
+                self.energy_full_design as f32 / 1000.0
+            )?;
+        }
+        if self.cycle_count > 0 {
+            write!(f, ", {} cycles", self.cycle_count)?;
+        }
+        Ok(())
+    }
+}
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const FULL_DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// A time of day window on some days of the week during which a different
+/// charge limit is used, such as allowing a full charge before work. Times
+/// are minutes from local midnight, and if `end` is before `start` the window
+/// runs past midnight into the next day.
+#[typeshare]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq, PartialOrd)]
+pub struct ChargeRule {
+    /// Bit 0 is Monday through to bit 6 for Sunday
+    pub days: u8,
+    pub start: u16,
+    pub end: u16,
+    pub limit: u8,
+}
+
+impl ChargeRule {
+    pub const EVERY_DAY: u8 = 0b111_1111;
+
+    /// If the rule applies at `minute` past midnight on `weekday`, where
+    /// Monday is `0`
+    pub fn is_active(&self, weekday: u8, minute: u16) -> bool {
+        let on = |day: u8| self.days & (1 << (day % 7)) != 0;
+        if self.start <= self.end {
+            on(weekday) && (self.start..self.end).contains(&minute)
+        } else {
+            // The early hours belong to the window started the day before
+            (on(weekday) && minute >= self.start) || (on(weekday + 6) && minute < self.end)
+        }
+    }
+
+    /// The minutes from `minute` past midnight until this rule next starts or
+    /// ends on any day, between `1` and a full day
+    pub fn minutes_to_change(&self, minute: u16) -> u16 {
+        const DAY: i32 = 24 * 60;
+        [self.start, self.end]
+            .iter()
+            .map(|&at| ((at as i32 - minute as i32 - 1).rem_euclid(DAY) + 1) as u16)
+            .min()
+            .unwrap_or(DAY as u16)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.days & Self::EVERY_DAY == 0 {
+            return Err(PlatformError::InvalidValue(format!("{self}: no days set")));
+        }
+        if self.start >= 24 * 60 || self.end > 24 * 60 || self.start == self.end {
+            return Err(PlatformError::InvalidValue(format!(
+                "{self}: invalid times"
+            )));
+        }
+        if !(20..=100).contains(&self.limit) {
+            return Err(PlatformError::InvalidValue(format!(
+                "{self}: limit must be 20-100"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn parse_day(day: &str) -> Result<u8> {
+    let lower = day.to_ascii_lowercase();
+    DAY_NAMES
+        .iter()
+        .zip(FULL_DAY_NAMES)
+        .position(|(short, full)| lower == *short || lower == full)
+        .map(|d| d as u8)
+        .ok_or_else(|| PlatformError::InvalidValue(format!("unknown day {day}")))
+}
+
+fn parse_time(time: &str) -> Result<u16> {
+    let invalid = || PlatformError::InvalidValue(format!("invalid time {time}, expected HH:MM"));
+    let (hour, min) = time.split_once(':').ok_or_else(invalid)?;
+    let hour: u16 = hour.parse().map_err(|_| invalid())?;
+    let min: u16 = min.parse().map_err(|_| invalid())?;
+    if min >= 60 {
+        return Err(invalid());
+    }
+    Ok(hour * 60 + min)
+}
+
+/// Parses `<days> <HH:MM-HH:MM> <limit>`, for example `mon-fri 00:00-07:00 100`.
+/// Days are a comma separated list of days or day ranges, or `daily`.
+impl FromStr for ChargeRule {
+    type Err = PlatformError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [days_str, times, limit] = parts[..] else {
+            return Err(PlatformError::InvalidValue(format!(
+                "{s}: expected <days> <HH:MM-HH:MM> <limit>"
+            )));
+        };
+
+        let mut days = 0;
+        if days_str == "daily" {
+            days = Self::EVERY_DAY;
+        } else {
+            for range in days_str.split(',') {
+                let (first, last) = range.split_once('-').unwrap_or((range, range));
+                let (first, last) = (parse_day(first)?, parse_day(last)?);
+                let mut day = first;
+                loop {
+                    days |= 1 << day;
+                    if day == last {
+                        break;
+                    }
+                    day = (day + 1) % 7;
+                }
+            }
+        }
+
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| PlatformError::InvalidValue(format!("{times}: expected HH:MM-HH:MM")))?;
+        let rule = Self {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+            limit: limit
+                .trim_end_matches('%')
+                .parse()
+                .map_err(|_| PlatformError::InvalidValue(format!("invalid limit {limit}")))?,
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+impl Display for ChargeRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.days & Self::EVERY_DAY == Self::EVERY_DAY {
+            write!(f, "daily")?;
+        } else {
+            // Collapse runs of days in to ranges
+            let mut ranges = Vec::new();
+            let mut day = 0;
+            while day < 7 {
+                if self.days & (1 << day) == 0 {
+                    day += 1;
+                    continue;
+                }
+                let first = day;
+                while day < 6 && self.days & (1 << (day + 1)) != 0 {
+                    day += 1;
+                }
+                if first == day {
+                    ranges.push(DAY_NAMES[first].to_owned());
+                } else {
+                    ranges.push(format!("{}-{}", DAY_NAMES[first], DAY_NAMES[day]));
+                }
+                day += 1;
+            }
+            write!(f, "{}", ranges.join(","))?;
+        }
+        write!(
+            f,
+            " {:02}:{:02}-{:02}:{:02} {}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60,
+            self.limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChargeRule;
+
+    #[test]
+    fn charge_rule_parse_and_display() {
+        let rule: ChargeRule = "mon-fri 00:00-07:00 100".parse().unwrap();
+        assert_eq!(
+            rule,
+            ChargeRule {
+                days: 0b001_1111,
+                start: 0,
+                end: 7 * 60,
+                limit: 100,
+            }
+        );
+        assert_eq!(rule.to_string(), "mon-fri 00:00-07:00 100");
+
+        let rule: ChargeRule = "sat,sun,wed 22:30-06:00 90".parse().unwrap();
+        assert_eq!(rule.days, 0b110_0100);
+        assert_eq!(rule.to_string(), "wed,sat-sun 22:30-06:00 90");
+        // Ranges can wrap around the end of the week
+        let rule: ChargeRule = "fri-mon 08:00-09:00 80".parse().unwrap();
+        assert_eq!(rule.days, 0b111_0001);
+        let rule: ChargeRule = "daily 08:00-09:00 80".parse().unwrap();
+        assert_eq!(rule.to_string(), "daily 08:00-09:00 80");
+
+        assert!("mon-fri 00:00-07:00 10".parse::<ChargeRule>().is_err());
+        assert!("mon-fri 07:00-07:00 100".parse::<ChargeRule>().is_err());
+        assert!("mon-fri 00:00-7 100".parse::<ChargeRule>().is_err());
+        assert!("someday 00:00-07:00 100".parse::<ChargeRule>().is_err());
+        assert!("monster 00:00-07:00 100".parse::<ChargeRule>().is_err());
+        assert!("tuesdayz 00:00-07:00 100".parse::<ChargeRule>().is_err());
+        assert!("mon-sunny 00:00-07:00 100".parse::<ChargeRule>().is_err());
+        let rule: ChargeRule = "Monday-FRIDAY 00:00-07:00 100".parse().unwrap();
+        assert_eq!(rule.days, 0b001_1111);
+        assert!("mon-fri 00:00-07:00".parse::<ChargeRule>().is_err());
+    }
+
+    #[test]
+    fn charge_rule_active() {
+        let rule: ChargeRule = "mon-fri 00:00-07:00 100".parse().unwrap();
+        assert!(rule.is_active(0, 0));
+        assert!(rule.is_active(4, 6 * 60 + 59));
+        assert!(!rule.is_active(4, 7 * 60));
+        assert!(!rule.is_active(5, 60));
+
+        // Friday night runs in to Saturday morning, but Sunday night is off
+        let rule: ChargeRule = "fri 22:00-06:00 100".parse().unwrap();
+        assert!(rule.is_active(4, 23 * 60));
+        assert!(rule.is_active(5, 5 * 60));
+        assert!(!rule.is_active(5, 23 * 60));
+        assert!(!rule.is_active(4, 5 * 60));
+    }
+
+    #[test]
+    fn charge_rule_minutes_to_change() {
+        let rule: ChargeRule = "fri 22:00-06:00 100".parse().unwrap();
+        assert_eq!(rule.minutes_to_change(21 * 60), 60);
+        assert_eq!(rule.minutes_to_change(23 * 60), 7 * 60);
+        // At a boundary the next one is wanted, not this one
+        assert_eq!(rule.minutes_to_change(6 * 60), 16 * 60);
+        assert_eq!(rule.minutes_to_change(6 * 60 - 1), 1);
+
+        let rule: ChargeRule = "daily 08:00-24:00 80".parse().unwrap();
+        assert_eq!(rule.minutes_to_change(23 * 60 + 59), 1);
+    }
+}
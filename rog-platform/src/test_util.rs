@@ -0,0 +1,55 @@
+//! Helpers for tests run against the sysfs fixtures kept in
+//! `rog-platform/tests/data/sysfs`. Built only with the `test-util` feature.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::discovery::Discovery;
+
+/// The fixture tree for `model`, e.g. `gu604`
+pub fn fixture_dir(model: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/sysfs")
+        .join(model)
+}
+
+pub fn copy_tree(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap().flatten() {
+        let dest = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_tree(&entry.path(), &dest);
+        } else {
+            fs::copy(entry.path(), dest).unwrap();
+        }
+    }
+}
+
+/// Copy the fixture tree for `model` to `root`, replacing anything already
+/// there, so that tests can write to it
+pub fn copy_fixture(model: &str, root: &Path) {
+    if root.exists() {
+        fs::remove_dir_all(root).unwrap();
+    }
+    copy_tree(&fixture_dir(model), root);
+}
+
+/// As `copy_fixture`, and return a `Discovery` rooted at the copy
+pub fn fixture_at(model: &str, root: PathBuf) -> (Discovery, PathBuf) {
+    copy_fixture(model, &root);
+    (Discovery::Root(root.clone()), root)
+}
+
+/// Read a file, trimmed
+pub fn read_trimmed(path: &Path) -> String {
+    fs::read_to_string(path).unwrap().trim().to_owned()
+}
+
+/// A `struct input_event` as read from an evdev node, with a zeroed time
+pub fn input_event(kind: u16, code: u16, value: i32) -> Vec<u8> {
+    let mut event = vec![0u8; 2 * std::mem::size_of::<std::ffi::c_long>()];
+    event.extend(kind.to_ne_bytes());
+    event.extend(code.to_ne_bytes());
+    event.extend(value.to_ne_bytes());
+    event
+}
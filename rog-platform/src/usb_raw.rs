@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use rusb::{Device, DeviceHandle};
 
+use crate::discovery::Discovery;
 use crate::error::{PlatformError, Result};
 
 #[derive(Debug, PartialEq, Eq)]
@@ -9,6 +10,14 @@ pub struct USBRaw(DeviceHandle<rusb::GlobalContext>);
 
 impl USBRaw {
     pub fn new(id_product: u16) -> Result<Self> {
+        // libusb can't be pointed at a fake tree, so callers fall back to hidraw
+        if let Discovery::Root(_) = Discovery::from_env() {
+            return Err(PlatformError::MissingFunction(format!(
+                "USBRaw dev {} not available with a fake sysfs root",
+                id_product
+            )));
+        }
+
         for device in rusb::devices()?.iter() {
             let device_desc = device.device_descriptor()?;
             if device_desc.vendor_id() == 0x0b05 && device_desc.product_id() == id_product {
@@ -0,0 +1,17 @@
+use std::path::{Path, PathBuf};
+
+use rog_platform::discovery::Discovery;
+use rog_platform::test_util::{fixture_at, read_trimmed};
+
+/// Copy the fixture tree for `model` to a scratch dir so that tests can write
+/// to it, and return a `Discovery` rooted there.
+pub fn fixture(model: &str, test: &str) -> (Discovery, PathBuf) {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("sysfs")
+        .join(format!("{model}-{test}"));
+    fixture_at(model, root)
+}
+
+pub fn read(root: &Path, rel: &str) -> String {
+    read_trimmed(&root.join(rel))
+}
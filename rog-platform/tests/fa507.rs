@@ -0,0 +1,189 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_platform::firmware_attributes::{AttrType, FirmwareAttributes};
+    use rog_platform::keyboard_led::KeyboardBacklight;
+    use rog_platform::platform::{PptLimit, PptLimits, RogPlatform, ThrottlePolicy};
+
+    use crate::common::{fixture, read};
+
+    const WMI: &str = "bus/platform/devices/asus-nb-wmi";
+    const ARMOURY: &str = "class/firmware-attributes/asus-armoury/attributes";
+
+    #[test]
+    fn fa507_platform_supported() {
+        let (discovery, _) = fixture("fa507", "supported");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert!(!platform.has_egpu_enable());
+        assert!(!platform.has_mini_led_mode());
+        assert!(!platform.has_boot_sound());
+        assert!(platform.has_ppt_apu_sppt());
+        assert!(platform.has_ppt_platform_sppt());
+    }
+
+    #[test]
+    fn fa507_platform_get_set() {
+        let (discovery, root) = fixture("fa507", "get_set");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert_eq!(
+            ThrottlePolicy::from(platform.get_throttle_thermal_policy().unwrap()),
+            ThrottlePolicy::Quiet
+        );
+        assert_eq!(platform.get_ppt_apu_sppt().unwrap(), 45);
+
+        // FA507 has this one as a firmware attribute
+        platform.set_ppt_apu_sppt(30).unwrap();
+        assert_eq!(
+            read(&root, &format!("{ARMOURY}/ppt_apu_sppt/current_value")),
+            "30"
+        );
+        platform.set_ppt_platform_sppt(35).unwrap();
+        assert_eq!(platform.get_ppt_platform_sppt().unwrap(), 35);
+        assert!(platform.get_egpu_enable().is_err());
+    }
+
+    #[test]
+    fn fa507_ppt_limits() {
+        let (discovery, _) = fixture("fa507", "ppt_limits");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+        let limits = platform.ppt_limits();
+
+        // Firmware attributes take precedence over the generic ranges
+        assert_eq!(
+            limits.ppt_pl1_spl,
+            PptLimit {
+                min: 10,
+                max: 90,
+                default: 70
+            }
+        );
+        assert_eq!(limits.ppt_apu_sppt.max, 65);
+        assert_eq!(
+            limits.ppt_platform_sppt,
+            PptLimits::GENERIC.ppt_platform_sppt
+        );
+        assert!(!limits.ppt_apu_sppt.contains(70));
+    }
+
+    #[test]
+    fn fa507_firmware_attribute_limits() {
+        let (discovery, _) = fixture("fa507", "firmware_attribute_limits");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+        let limits = platform.ppt_limits();
+
+        // Every integer attribute of the firmware gives a limit
+        assert_eq!(
+            limits.ppt_pl2_sppt,
+            PptLimit {
+                min: 10,
+                max: 65,
+                default: 50
+            }
+        );
+        assert!(!limits.ppt_pl2_sppt.contains(70));
+        // Not in the firmware, so the generic range
+        assert_eq!(limits.ppt_platform_sppt.max, 130);
+    }
+
+    #[test]
+    fn fa507_firmware_attributes() {
+        let (discovery, root) = fixture("fa507", "firmware_attributes");
+        let attrs = FirmwareAttributes::new_with(&discovery).unwrap();
+
+        assert_eq!(
+            attrs.names(),
+            [
+                "apu_mem",
+                "mcu_powersave",
+                "ppt_apu_sppt",
+                "ppt_pl1_spl",
+                "ppt_pl2_sppt"
+            ]
+        );
+        let apu_mem = attrs.get("apu_mem").unwrap();
+        assert_eq!(apu_mem.attr_type, AttrType::Enumeration);
+        assert_eq!(apu_mem.current_value, "3");
+        assert_eq!(apu_mem.possible_values.len(), 9);
+        let pl2 = attrs.get("ppt_pl2_sppt").unwrap();
+        assert_eq!(pl2.attr_type, AttrType::Integer);
+        assert_eq!((pl2.min_value, pl2.max_value), (10, 65));
+        assert_eq!(pl2.display_name, "Set the CPU fast package limit");
+
+        attrs.set("apu_mem", "8").unwrap();
+        assert_eq!(
+            read(&root, &format!("{ARMOURY}/apu_mem/current_value")),
+            "8"
+        );
+        assert!(attrs.set("apu_mem", "9").is_err());
+        assert!(attrs.set("ppt_pl2_sppt", "66").is_err());
+        assert!(attrs.set("ppt_pl2_sppt", "fast").is_err());
+        assert!(attrs.get("ppt_fppt").is_err());
+    }
+
+    #[test]
+    fn fa507_firmware_attributes_reject_paths() {
+        let (discovery, root) = fixture("fa507", "armoury_paths");
+        let attrs = FirmwareAttributes::new_with(&discovery).unwrap();
+        // Looks like an attribute, but is outside of `attributes`
+        let outside = root.join(ARMOURY).join("../x");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("current_value"), "1").unwrap();
+
+        for name in ["../x", "", ".", "..", "apu_mem/..", "/x"] {
+            assert!(!attrs.has(name), "{name}");
+            assert!(attrs.get(name).is_err(), "{name}");
+            assert!(attrs.set(name, "2").is_err(), "{name}");
+        }
+        assert_eq!(
+            read(
+                &root,
+                "class/firmware-attributes/asus-armoury/x/current_value"
+            ),
+            "1"
+        );
+    }
+
+    #[test]
+    fn fa507_platform_maps_to_firmware_attributes() {
+        let (discovery, root) = fixture("fa507", "armoury_mapping");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+        assert_eq!(platform.firmware_attributes().unwrap().list().len(), 5);
+
+        // Attributes the firmware has are read and written there
+        assert_eq!(platform.get_ppt_pl1_spl().unwrap(), 70);
+        platform.set_ppt_pl1_spl(40).unwrap();
+        assert_eq!(
+            read(&root, &format!("{ARMOURY}/ppt_pl1_spl/current_value")),
+            "40"
+        );
+        assert_eq!(read(&root, &format!("{WMI}/ppt_pl1_spl")), "50");
+        // and everything else still from asus-nb-wmi
+        assert!(platform.has_ppt_fppt());
+        platform.set_ppt_fppt(50).unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/ppt_fppt")), "50");
+    }
+
+    #[test]
+    fn fa507_keyboard_rgb() {
+        let (discovery, root) = fixture("fa507", "keyboard_rgb");
+        let kbd = KeyboardBacklight::new_with(&discovery).unwrap();
+
+        assert!(kbd.has_kbd_rgb_mode());
+        assert!(kbd.has_kbd_rgb_state());
+        kbd.set_kbd_rgb_mode(&[1, 0, 255, 0, 0, 0]).unwrap();
+        assert_eq!(
+            read(&root, "class/leds/asus::kbd_backlight/kbd_rgb_mode"),
+            "1 0 255 0 0 0"
+        );
+    }
+
+    #[test]
+    fn fa507_missing_platform() {
+        let (discovery, root) = fixture("fa507", "missing_platform");
+        std::fs::remove_dir_all(root.join(WMI)).unwrap();
+        assert!(RogPlatform::new_with(&discovery).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_platform::cpu::{CPUControl, CPUGovernor, CPUEPP};
+    use rog_platform::keyboard_led::KeyboardBacklight;
+    use rog_platform::platform::{PlatformTuning, PptLimits, RogPlatform, ThrottlePolicy};
+    use rog_platform::power::{AsusPower, BatteryInfo, BatteryStatus};
+
+    use crate::common::{fixture, read};
+
+    const WMI: &str = "bus/platform/devices/asus-nb-wmi";
+
+    #[test]
+    fn ga402_platform_supported() {
+        let (discovery, _) = fixture("ga402", "supported");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert!(platform.has_dgpu_disable());
+        assert!(platform.has_egpu_enable());
+        assert!(platform.has_gpu_mux_mode());
+        assert!(platform.has_panel_od());
+        assert!(!platform.has_mini_led_mode());
+        assert!(platform.has_throttle_thermal_policy());
+        assert!(platform.has_platform_profile());
+        assert!(platform.has_ppt_pl1_spl());
+        assert!(platform.has_ppt_pl2_sppt());
+        assert!(platform.has_ppt_fppt());
+        assert!(!platform.has_ppt_apu_sppt());
+        assert!(!platform.has_ppt_platform_sppt());
+        assert!(platform.has_nv_dynamic_boost());
+        assert!(platform.has_nv_temp_target());
+        assert!(platform.has_boot_sound());
+    }
+
+    #[test]
+    fn ga402_platform_get_set() {
+        let (discovery, root) = fixture("ga402", "get_set");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert!(!platform.get_dgpu_disable().unwrap());
+        assert_eq!(platform.get_gpu_mux_mode().unwrap(), 1);
+        assert_eq!(
+            ThrottlePolicy::from(platform.get_throttle_thermal_policy().unwrap()),
+            ThrottlePolicy::Balanced
+        );
+        assert_eq!(platform.get_platform_profile().unwrap(), "balanced");
+        assert_eq!(platform.get_ppt_pl1_spl().unwrap(), 80);
+        assert_eq!(platform.get_nv_temp_target().unwrap(), 87);
+
+        platform.set_panel_od(true).unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/panel_od")), "1");
+        assert!(platform.get_panel_od().unwrap());
+
+        platform
+            .set_throttle_thermal_policy(ThrottlePolicy::Quiet.into())
+            .unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/throttle_thermal_policy")), "2");
+
+        platform.set_ppt_fppt(65).unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/ppt_fppt")), "65");
+        platform.set_nv_dynamic_boost(5).unwrap();
+        assert_eq!(platform.get_nv_dynamic_boost().unwrap(), 5);
+
+        platform.set_platform_profile("quiet").unwrap();
+        assert_eq!(read(&root, "firmware/acpi/platform_profile"), "quiet");
+
+        // Attributes which don't exist must not be created
+        assert!(platform.set_mini_led_mode(true).is_err());
+        assert!(!platform.has_mini_led_mode());
+    }
+
+    #[test]
+    fn ga402_ppt_limits() {
+        let (discovery, _) = fixture("ga402", "ppt_limits");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+        let limits = platform.ppt_limits();
+
+        // No firmware attributes, so the generic ranges
+        assert_eq!(limits, PptLimits::GENERIC);
+        assert_eq!(limits.get("nv_temp_target").unwrap().max, 87);
+
+        let mut tuning = PlatformTuning {
+            ppt_pl1_spl: Some(80),
+            nv_dynamic_boost: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(limits.check_tuning(&tuning), None);
+        tuning.nv_temp_target = Some(90);
+        assert_eq!(limits.check_tuning(&tuning), Some(("nv_temp_target", 90)));
+    }
+
+    #[test]
+    fn ga402_power() {
+        let (discovery, root) = fixture("ga402", "power");
+        let power = AsusPower::new_with(&discovery).unwrap();
+
+        assert!(power.has_charge_control_end_threshold());
+        assert_eq!(power.get_charge_control_end_threshold().unwrap(), 80);
+        assert_eq!(power.get_online().unwrap(), 1);
+
+        power.set_charge_control_end_threshold(60).unwrap();
+        assert_eq!(
+            read(
+                &root,
+                "class/power_supply/BAT0/charge_control_end_threshold"
+            ),
+            "60"
+        );
+
+        let info = power.battery_info();
+        assert_eq!(
+            info,
+            BatteryInfo {
+                capacity: 64,
+                status: BatteryStatus::Charging,
+                energy_now: 43776,
+                energy_full: 68400,
+                energy_full_design: 76000,
+                cycle_count: 120,
+                power_now: 35000,
+                time_to_empty: 0,
+            }
+        );
+        assert_eq!(info.health(), 90);
+    }
+
+    #[test]
+    fn ga402_cpu() {
+        let (discovery, root) = fixture("ga402", "cpu");
+        let cpu = CPUControl::new_with(&discovery).unwrap();
+
+        assert_eq!(cpu.get_governor().unwrap(), CPUGovernor::Powersave);
+        assert_eq!(
+            cpu.get_available_governors().unwrap(),
+            vec![CPUGovernor::Performance, CPUGovernor::Powersave]
+        );
+        assert_eq!(cpu.get_epp().unwrap(), CPUEPP::BalancePerformance);
+
+        cpu.set_epp(CPUEPP::Power).unwrap();
+        for n in 0..4 {
+            assert_eq!(
+                read(
+                    &root,
+                    &format!("bus/cpu/devices/cpu{n}/cpufreq/energy_performance_preference")
+                ),
+                "power"
+            );
+        }
+
+        cpu.set_governor(CPUGovernor::Performance).unwrap();
+        assert_eq!(cpu.get_governor().unwrap(), CPUGovernor::Performance);
+        assert_eq!(
+            read(
+                &root,
+                "bus/cpu/devices/cpu0/cpufreq/scaling_available_governors"
+            ),
+            "performance powersave"
+        );
+    }
+
+    #[test]
+    fn ga402_keyboard() {
+        let (discovery, root) = fixture("ga402", "keyboard");
+        let kbd = KeyboardBacklight::new_with(&discovery).unwrap();
+
+        assert!(!kbd.has_kbd_rgb_mode());
+        assert_eq!(kbd.get_brightness().unwrap(), 2);
+        kbd.set_brightness(3).unwrap();
+        assert_eq!(
+            read(&root, "class/leds/asus::kbd_backlight/brightness"),
+            "3"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rog_platform::cpu::{CPUControl, CPUGovernor, CoreType, CpuWrite, CPUEPP};
+    use rog_platform::error::PlatformError;
+    use rog_platform::key_events::KeyEvents;
+    use rog_platform::platform::{GpuMode, RogPlatform, ThrottlePolicy};
+    use rog_platform::power::{AsusPower, BatteryStatus};
+    use rog_platform::test_util::input_event;
+
+    use crate::common::{fixture, read};
+
+    const WMI: &str = "bus/platform/devices/asus-nb-wmi";
+
+    #[test]
+    fn gu604_platform_supported() {
+        let (discovery, _) = fixture("gu604", "supported");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert!(platform.has_mini_led_mode());
+        assert!(platform.has_panel_od());
+        assert!(platform.has_ppt_pl1_spl());
+        assert!(platform.has_ppt_pl2_sppt());
+        assert!(!platform.has_ppt_fppt());
+        assert!(!platform.has_ppt_apu_sppt());
+        assert!(!platform.has_ppt_platform_sppt());
+        assert!(platform.has_nv_dynamic_boost());
+    }
+
+    #[test]
+    fn gu604_platform_get_set() {
+        let (discovery, root) = fixture("gu604", "get_set");
+        let platform = RogPlatform::new_with(&discovery).unwrap();
+
+        assert!(platform.get_mini_led_mode().unwrap());
+        assert!(platform.get_boot_sound().unwrap());
+        assert_eq!(
+            ThrottlePolicy::from(platform.get_throttle_thermal_policy().unwrap()),
+            ThrottlePolicy::Performance
+        );
+        assert_eq!(platform.get_ppt_pl2_sppt().unwrap(), 175);
+
+        platform.set_mini_led_mode(false).unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/mini_led_mode")), "0");
+        platform.set_boot_sound(false).unwrap();
+        assert!(!platform.get_boot_sound().unwrap());
+
+        platform
+            .set_gpu_mux_mode(GpuMode::Ultimate.to_mux_attr())
+            .unwrap();
+        assert_eq!(
+            GpuMode::from_mux(platform.get_gpu_mux_mode().unwrap()),
+            GpuMode::Ultimate
+        );
+
+        platform.set_ppt_pl1_spl(90).unwrap();
+        assert_eq!(read(&root, &format!("{WMI}/ppt_pl1_spl")), "90");
+        assert!(platform.set_ppt_fppt(90).is_err());
+    }
+
+    #[test]
+    fn gu604_power_no_mains() {
+        let (discovery, root) = fixture("gu604", "power_no_mains");
+        std::fs::remove_dir_all(root.join("class/power_supply/ACAD")).unwrap();
+        let power = AsusPower::new_with(&discovery).unwrap();
+        assert!(!power.has_online());
+        assert!(matches!(
+            power.get_online(),
+            Err(PlatformError::NotSupported)
+        ));
+        assert_eq!(power.battery_info().status, BatteryStatus::Discharging);
+    }
+
+    #[test]
+    fn gu604_power_unplugged() {
+        let (discovery, root) = fixture("gu604", "power_unplugged");
+        std::fs::write(root.join("class/power_supply/ACAD/online"), "0\n").unwrap();
+        let power = AsusPower::new_with(&discovery).unwrap();
+        assert_eq!(power.get_online().unwrap(), 0);
+
+        // This battery reports charge and current, converted to energy and power
+        let info = power.battery_info();
+        assert_eq!(info.status, BatteryStatus::Discharging);
+        assert_eq!(info.energy_now, 37647);
+        assert_eq!(info.energy_full, 58824);
+        assert_eq!(info.energy_full_design, 61920);
+        assert_eq!(info.health(), 95);
+        assert_eq!(info.power_now, 24000);
+        assert_eq!(info.time_to_empty, 94);
+        assert_eq!(info.cycle_count, 45);
+        assert_eq!(
+            info.to_string(),
+            "64%, Discharging, 24.0W, 1:34 remaining\nHealth 95%, 58.8Wh of 61.9Wh design, 45 \
+             cycles"
+        );
+
+        // Some firmware reports the current as negative while discharging
+        std::fs::write(
+            root.join("class/power_supply/BAT0/current_now"),
+            "-1500000\n",
+        )
+        .unwrap();
+        let info = power.battery_info();
+        assert_eq!(info.power_now, 24000);
+        assert_eq!(info.time_to_empty, 94);
+    }
+
+    #[test]
+    fn gu604_hybrid_cpu() {
+        let (discovery, root) = fixture("gu604", "hybrid_cpu");
+        let cpu = CPUControl::new_with(&discovery).unwrap();
+        assert!(cpu.is_hybrid());
+
+        let written = cpu
+            .set_epp_by_core_type(CPUEPP::Performance, CPUEPP::BalancePerformance)
+            .unwrap();
+        // The E-cores were already on balance_performance
+        let changed: Vec<(u32, CoreType)> = written
+            .iter()
+            .filter(|w| w.changed)
+            .map(|w| (w.cpu, w.core_type))
+            .collect();
+        assert_eq!(
+            changed,
+            [(0, CoreType::Performance), (1, CoreType::Performance)]
+        );
+        assert!(written.iter().all(|w| w.error.is_empty()));
+
+        let states = cpu.states();
+        assert_eq!(states.len(), 4);
+        assert_eq!(states[1].epp, CPUEPP::Performance);
+        assert_eq!(states[2].core_type, CoreType::Efficiency);
+        assert_eq!(states[2].epp, CPUEPP::BalancePerformance);
+        assert_eq!(states[3].governor, CPUGovernor::Powersave);
+        assert!(cpu
+            .set_epp_by_core_type(CPUEPP::Power, CPUEPP::Default)
+            .is_ok());
+        assert_eq!(
+            read(
+                &root,
+                "bus/cpu/devices/cpu3/cpufreq/energy_performance_preference"
+            ),
+            "default"
+        );
+
+        // An offline CPU has no cpufreq and is skipped
+        std::fs::remove_dir_all(root.join("bus/cpu/devices/cpu1/cpufreq")).unwrap();
+        let written = cpu.set_epp(CPUEPP::Power).unwrap();
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            written[0],
+            CpuWrite {
+                cpu: 0,
+                core_type: CoreType::Performance,
+                changed: false,
+                error: String::new(),
+            }
+        );
+        assert_eq!(written[1].cpu, 2);
+        assert!(written[1].changed);
+        assert_eq!(cpu.states().len(), 3);
+
+        // Each core type has its own available EPP
+        for num in [2, 3] {
+            std::fs::write(
+                root.join(format!(
+                    "bus/cpu/devices/cpu{num}/cpufreq/energy_performance_available_preferences"
+                )),
+                "default balance_performance balance_power power\n",
+            )
+            .unwrap();
+        }
+        assert!(cpu
+            .get_available_epp_for(CoreType::Performance)
+            .unwrap()
+            .contains(&CPUEPP::Performance));
+        assert!(!cpu
+            .get_available_epp_for(CoreType::Efficiency)
+            .unwrap()
+            .contains(&CPUEPP::Performance));
+        assert!(cpu
+            .set_epp_by_core_type(CPUEPP::Performance, CPUEPP::BalancePower)
+            .is_ok());
+        assert!(cpu
+            .set_epp_by_core_type(CPUEPP::Performance, CPUEPP::Performance)
+            .is_err());
+        // One core type can be set alone
+        assert!(cpu
+            .set_epp_for(CoreType::Efficiency, CPUEPP::Performance)
+            .is_err());
+        let written = cpu
+            .set_epp_for(CoreType::Efficiency, CPUEPP::BalancePerformance)
+            .unwrap();
+        assert!(written.iter().all(|w| w.core_type == CoreType::Efficiency));
+        assert!(!written.is_empty());
+    }
+
+    #[test]
+    fn gu604_key_presses() {
+        let (discovery, root) = fixture("gu604", "key_presses");
+        let events = [
+            // A pressed, repeated, and released, then F pressed
+            input_event(1, 30, 1),
+            input_event(0, 0, 0),
+            input_event(1, 30, 2),
+            input_event(1, 30, 0),
+            input_event(4, 4, 458756),
+            input_event(1, 33, 1),
+        ]
+        .concat();
+        std::fs::write(root.join("dev/input/event4"), events).unwrap();
+
+        // The Video Bus isn't a keyboard
+        let mut keyboards = KeyEvents::find_all_with(&discovery).unwrap();
+        assert_eq!(keyboards.len(), 1);
+        let keyboard = &mut keyboards[0];
+        assert!(keyboard.devfs_path().ends_with("dev/input/event4"));
+        assert_eq!(keyboard.next_press().unwrap(), 30);
+        assert_eq!(keyboard.next_press().unwrap(), 33);
+        assert!(keyboard.next_press().is_err());
+
+        std::fs::remove_dir_all(root.join("class/input/event4")).unwrap();
+        assert!(KeyEvents::find_all_with(&discovery).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use std::fmt;
 
 use log::error;
+use rog_platform::error::PlatformError;
 use zbus::fdo::Error as FdoErr;
 
+use crate::FanCurvePU;
+
 #[derive(Debug)]
 pub enum ProfileError {
     Path(String, std::io::Error),
@@ -17,6 +20,13 @@ pub enum ProfileError {
     ParseFanCurvePrevHigher(&'static str, u8, u8),
     ParseFanCurvePercentOver100(u8),
     NotEnoughPoints,
+    /// A curve which breaks the safety rules, and why
+    FanCurveUnsafe(FanCurvePU, String),
+    /// Imported fan curves which don't fit this machine, and why
+    FanCurveImport(String),
+    /// A user profile which can't be used, and why
+    UserProfile(String),
+    Platform(PlatformError),
     // Zbus(zbus::Error),
 }
 
@@ -29,6 +39,10 @@ impl fmt::Display for ProfileError {
             ProfileError::Write(path, error) => write!(f, "Write {}: {}", path, error),
             ProfileError::NotSupported => write!(f, "Not supported"),
             ProfileError::NotEnoughPoints => write!(f, "Less than 8 curve points supplied"),
+            ProfileError::FanCurveUnsafe(fan, why) => write!(f, "Unsafe {fan:?} fan curve: {why}"),
+            ProfileError::FanCurveImport(why) => write!(f, "Can't import fan curves: {why}"),
+            ProfileError::UserProfile(why) => write!(f, "Invalid user profile: {why}"),
+            ProfileError::Platform(error) => write!(f, "Platform: {}", error),
             ProfileError::NotFound(deets) => write!(f, "Not found: {}", deets),
             ProfileError::Io(detail) => write!(f, "std::io error: {}", detail),
             ProfileError::ParseProfileName => write!(f, "Invalid profile name"),
@@ -56,11 +70,20 @@ impl From<std::io::Error> for ProfileError {
     }
 }
 
+impl From<PlatformError> for ProfileError {
+    fn from(err: PlatformError) -> Self {
+        ProfileError::Platform(err)
+    }
+}
+
 impl From<ProfileError> for FdoErr {
     fn from(error: ProfileError) -> Self {
         error!("ProfileError: got: {error}");
         match error {
             ProfileError::NotSupported => FdoErr::NotSupported("".to_owned()),
+            ProfileError::FanCurveUnsafe(..)
+            | ProfileError::FanCurveImport(_)
+            | ProfileError::UserProfile(_) => FdoErr::InvalidArgs(error.to_string()),
             _ => FdoErr::Failed(format!("Failed with {error}")),
         }
     }
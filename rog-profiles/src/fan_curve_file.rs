@@ -0,0 +1,167 @@
+//! A portable copy of the fan curves for every throttle policy, so a tuned
+//! set can be moved between machines. The machine it was made on is recorded
+//! so a set meant for different fans isn't applied by mistake.
+
+use log::warn;
+use rog_platform::platform::ThrottlePolicy;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+#[cfg(feature = "dbus")]
+use zbus::zvariant::Type;
+
+use crate::error::ProfileError;
+use crate::fan_curve_set::CurveData;
+use crate::{FanCurvePU, FanCurveProfiles};
+
+#[typeshare]
+#[cfg_attr(feature = "dbus", derive(Type))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct FanCurveExport {
+    /// The DMI `board_name` of the machine the curves were made on
+    pub board_name: String,
+    /// The fans of that machine
+    pub fans: Vec<FanCurvePU>,
+    /// The version of asusd which exported them
+    pub version: String,
+    pub profiles: FanCurveProfiles,
+}
+
+impl FanCurveExport {
+    pub fn new(board_name: &str, fans: Vec<FanCurvePU>, profiles: FanCurveProfiles) -> Self {
+        Self {
+            board_name: board_name.to_owned(),
+            fans,
+            version: crate::VERSION.to_owned(),
+            profiles,
+        }
+    }
+
+    /// Fit the curves to a machine with `fans`.
+    ///
+    /// Curves for a fan the machine doesn't have, or a fan left without a
+    /// curve, are an error unless `map_fans` is set. Then the curves for
+    /// missing fans are dropped, and a fan without one gets a copy of the CPU
+    /// fan curve, or failing that the first curve of the policy.
+    pub fn fit_to(
+        &self,
+        board_name: &str,
+        fans: &[FanCurvePU],
+        map_fans: bool,
+    ) -> Result<FanCurveProfiles, ProfileError> {
+        if self.board_name != board_name {
+            warn!(
+                "Fan curves were exported on {} for use on {board_name}",
+                self.board_name
+            );
+        }
+        let fit = |policy: ThrottlePolicy| -> Result<Vec<CurveData>, ProfileError> {
+            let curves = self.profiles.get_fan_curves_for(policy);
+            let Some(first) = curves.first() else {
+                return Err(ProfileError::FanCurveImport(format!(
+                    "no curves for {policy:?}"
+                )));
+            };
+            if !map_fans {
+                if let Some(extra) = curves.iter().find(|c| !fans.contains(&c.fan)) {
+                    return Err(ProfileError::FanCurveImport(format!(
+                        "{policy:?} has a curve for the {:?} fan which this machine doesn't \
+                         have",
+                        extra.fan
+                    )));
+                }
+            }
+            let fallback = curves
+                .iter()
+                .find(|c| c.fan == FanCurvePU::CPU)
+                .unwrap_or(first);
+            fans.iter()
+                .map(|fan| match curves.iter().find(|c| c.fan == *fan) {
+                    Some(curve) => Ok(curve.clone()),
+                    None if map_fans => {
+                        let mut curve = fallback.clone();
+                        curve.set_fan(*fan);
+                        Ok(curve)
+                    }
+                    None => Err(ProfileError::FanCurveImport(format!(
+                        "{policy:?} has no curve for the {fan:?} fan"
+                    ))),
+                })
+                .collect()
+        };
+        Ok(FanCurveProfiles {
+            balanced: fit(ThrottlePolicy::Balanced)?,
+            performance: fit(ThrottlePolicy::Performance)?,
+            quiet: fit(ThrottlePolicy::Quiet)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::FanCurveExport;
+    use crate::fan_curve_set::CurveData;
+    use crate::{FanCurvePU, FanCurveProfiles};
+
+    fn curve(fan: FanCurvePU, data: &str) -> CurveData {
+        let mut curve = CurveData::from_str(data).unwrap();
+        curve.set_fan(fan);
+        curve
+    }
+
+    fn export(fans: &[FanCurvePU]) -> FanCurveExport {
+        let curves: Vec<CurveData> = fans
+            .iter()
+            .map(|fan| {
+                curve(
+                    *fan,
+                    "30c:10%,40c:20%,50c:30%,60c:40%,70c:50%,80c:60%,90c:80%,100c:100%",
+                )
+            })
+            .collect();
+        FanCurveExport::new(
+            "GU604VI",
+            fans.to_vec(),
+            FanCurveProfiles {
+                balanced: curves.clone(),
+                performance: curves.clone(),
+                quiet: curves,
+            },
+        )
+    }
+
+    #[test]
+    fn fit_same_fans() {
+        let fans = [FanCurvePU::CPU, FanCurvePU::GPU];
+        let export = export(&fans);
+        assert_eq!(export.version, crate::VERSION);
+        let profiles = export.fit_to("GU604VI", &fans, false).unwrap();
+        assert_eq!(profiles, export.profiles);
+    }
+
+    #[test]
+    fn fit_other_fans() {
+        let export = export(&[FanCurvePU::CPU, FanCurvePU::GPU, FanCurvePU::MID]);
+        let fans = [FanCurvePU::CPU, FanCurvePU::GPU];
+        assert!(export.fit_to("GA402XV", &fans, false).is_err());
+        let profiles = export.fit_to("GA402XV", &fans, true).unwrap();
+        assert_eq!(profiles.quiet.len(), 2);
+        assert!(profiles.quiet.iter().all(|c| c.fan != FanCurvePU::MID));
+
+        // A missing fan takes the CPU curve
+        let mut export = export;
+        export
+            .profiles
+            .performance
+            .retain(|c| c.fan != FanCurvePU::GPU);
+        export.profiles.performance[0].pwm[7] = 200;
+        assert!(export.fit_to("GA402XV", &fans, false).is_err());
+        let profiles = export.fit_to("GA402XV", &fans, true).unwrap();
+        assert_eq!(profiles.performance[1].fan, FanCurvePU::GPU);
+        assert_eq!(profiles.performance[1].pwm[7], 200);
+
+        export.profiles.balanced.clear();
+        assert!(export.fit_to("GA402XV", &fans, true).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Fan curves with any number of points, as a user would draw them. The
+//! kernel only takes 8 points so these are resampled to 8 before being
+//! written, in a way that keeps the fan at or above the drawn curve.
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+#[cfg(feature = "dbus")]
+use zbus::zvariant::Type;
+
+use crate::error::ProfileError;
+use crate::fan_curve_set::CurveData;
+
+/// One point of a fan curve, fan power 0-255 at a temperature in degrees C
+#[typeshare]
+#[cfg_attr(feature = "dbus", derive(Type))]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurvePoint {
+    pub temp: u8,
+    pub pwm: u8,
+}
+
+/// How the fan power is found between the points of a curve
+#[typeshare]
+#[cfg_attr(feature = "dbus", derive(Type), zvariant(signature = "s"))]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight lines between the points
+    #[default]
+    Linear,
+    /// A smooth curve through the points which never overshoots them
+    MonotoneCubic,
+}
+
+/// Check there are at least 2 points, with rising temperatures and fan power
+/// which never drops
+pub fn check_points(points: &[CurvePoint]) -> Result<(), ProfileError> {
+    if points.len() < 2 {
+        return Err(ProfileError::NotEnoughPoints);
+    }
+    for pair in points.windows(2) {
+        if pair[0].temp >= pair[1].temp {
+            return Err(ProfileError::ParseFanCurvePrevHigher(
+                "temperature",
+                pair[0].temp,
+                pair[1].temp,
+            ));
+        }
+        if pair[0].pwm > pair[1].pwm {
+            return Err(ProfileError::ParseFanCurvePrevHigher(
+                "percentage",
+                pair[0].pwm,
+                pair[1].pwm,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The tangents at each point for a monotone cubic Hermite spline, using the
+/// Fritsch-Carlson method
+fn monotone_tangents(points: &[CurvePoint]) -> Vec<f32> {
+    let slopes: Vec<f32> = points
+        .windows(2)
+        .map(|p| (p[1].pwm as f32 - p[0].pwm as f32) / (p[1].temp as f32 - p[0].temp as f32))
+        .collect();
+    let n = points.len();
+    let mut tangents = vec![0.0; n];
+    tangents[0] = slopes[0];
+    tangents[n - 1] = slopes[n - 2];
+    for i in 1..n - 1 {
+        if slopes[i - 1] * slopes[i] > 0.0 {
+            tangents[i] = (slopes[i - 1] + slopes[i]) / 2.0;
+        }
+    }
+    for (i, slope) in slopes.iter().enumerate() {
+        if *slope == 0.0 {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let a = tangents[i] / slope;
+        let b = tangents[i + 1] / slope;
+        let h = a * a + b * b;
+        if h > 9.0 {
+            let t = 3.0 / h.sqrt();
+            tangents[i] = t * a * slope;
+            tangents[i + 1] = t * b * slope;
+        }
+    }
+    tangents
+}
+
+/// The fan power at `temp` of a curve checked by [`check_points`]. Below the
+/// first point or above the last the nearest point is used.
+///
+/// Linear interpolation rounds the same way as [`CurveData::pwm_at`] so that a
+/// curve of 8 points or fewer resamples to itself.
+pub fn pwm_at(points: &[CurvePoint], interpolation: Interpolation, temp: u8) -> u8 {
+    let Some(next) = points.iter().position(|p| temp < p.temp) else {
+        return points.last().map(|p| p.pwm).unwrap_or_default();
+    };
+    if next == 0 {
+        return points[0].pwm;
+    }
+    let (p0, p1) = (points[next - 1], points[next]);
+    match interpolation {
+        Interpolation::Linear => {
+            let (t0, t1) = (p0.temp as i32, p1.temp as i32);
+            let (y0, y1) = (p0.pwm as i32, p1.pwm as i32);
+            (y0 + (y1 - y0) * (temp as i32 - t0) / (t1 - t0)) as u8
+        }
+        Interpolation::MonotoneCubic => {
+            let tangents = monotone_tangents(points);
+            let h = p1.temp as f32 - p0.temp as f32;
+            let t = (temp as f32 - p0.temp as f32) / h;
+            let (t2, t3) = (t * t, t * t * t);
+            let y = (2.0 * t3 - 3.0 * t2 + 1.0) * p0.pwm as f32
+                + (t3 - 2.0 * t2 + t) * h * tangents[next - 1]
+                + (-2.0 * t3 + 3.0 * t2) * p1.pwm as f32
+                + (t3 - t2) * h * tangents[next];
+            y.round().clamp(0.0, 255.0) as u8
+        }
+    }
+}
+
+/// Resample a curve checked by [`check_points`] to the 8 points the kernel
+/// takes. The fan power of the result is never below the curve at any
+/// temperature.
+///
+/// A linear curve of 8 points or fewer keeps its points, otherwise the points
+/// are spread evenly from the first temperature to the last. Where a straight
+/// line between two of those would dip below the curve the later point, or
+/// if it is already at full power the earlier one, is raised until it
+/// doesn't.
+pub fn resample(points: &[CurvePoint], interpolation: Interpolation) -> ([u8; 8], [u8; 8]) {
+    let mut temp = [0u8; 8];
+    let first = points[0].temp as u32;
+    let last = points[points.len() - 1].temp as u32;
+    if points.len() <= 8 && interpolation == Interpolation::Linear {
+        for (i, t) in temp.iter_mut().enumerate() {
+            *t = points[i.min(points.len() - 1)].temp;
+        }
+    } else {
+        for (i, t) in temp.iter_mut().enumerate() {
+            *t = (first + (2 * (last - first) * i as u32 + 7) / 14) as u8;
+        }
+    }
+
+    let mut curve = CurveData {
+        temp,
+        ..Default::default()
+    };
+    for (pwm, t) in curve.pwm.iter_mut().zip(temp.iter()) {
+        *pwm = pwm_at(points, interpolation, *t);
+    }
+    for k in 0..7 {
+        let dips = |curve: &CurveData| {
+            (curve.temp[k]..curve.temp[k + 1])
+                .any(|t| curve.pwm_at(t) < pwm_at(points, interpolation, t))
+        };
+        while dips(&curve) {
+            if curve.pwm[k + 1] < u8::MAX {
+                curve.pwm[k + 1] += 1;
+            } else {
+                curve.pwm[k] += 1;
+            }
+        }
+        if k < 6 {
+            curve.pwm[k + 2] = curve.pwm[k + 2].max(curve.pwm[k + 1]);
+        }
+    }
+    (curve.temp, curve.pwm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_points, pwm_at, resample, CurvePoint, Interpolation};
+    use crate::fan_curve_set::CurveData;
+
+    fn points(pairs: &[(u8, u8)]) -> Vec<CurvePoint> {
+        pairs
+            .iter()
+            .map(|(temp, pwm)| CurvePoint {
+                temp: *temp,
+                pwm: *pwm,
+            })
+            .collect()
+    }
+
+    fn assert_never_below(points: &[CurvePoint], interpolation: Interpolation) {
+        let (temp, pwm) = resample(points, interpolation);
+        let curve = CurveData {
+            temp,
+            pwm,
+            ..Default::default()
+        };
+        for t in 0..=u8::MAX {
+            let authored = pwm_at(points, interpolation, t);
+            assert!(
+                curve.pwm_at(t) >= authored,
+                "{interpolation:?} {t}c: resampled {} < authored {authored}, {curve:?}",
+                curve.pwm_at(t)
+            );
+        }
+        assert!(pwm.windows(2).all(|p| p[0] <= p[1]), "{pwm:?}");
+        assert!(temp.windows(2).all(|t| t[0] <= t[1]), "{temp:?}");
+    }
+
+    #[test]
+    fn check_points_order() {
+        assert!(check_points(&points(&[(30, 10), (40, 20)])).is_ok());
+        assert!(check_points(&points(&[(30, 10)])).is_err());
+        assert!(check_points(&points(&[(30, 10), (30, 20)])).is_err());
+        assert!(check_points(&points(&[(30, 20), (40, 10)])).is_err());
+    }
+
+    #[test]
+    fn monotone_cubic_passes_through_points() {
+        let curve = points(&[(30, 0), (50, 40), (60, 40), (70, 120), (90, 255)]);
+        for p in &curve {
+            assert_eq!(pwm_at(&curve, Interpolation::MonotoneCubic, p.temp), p.pwm);
+        }
+        // Flat between equal points, and never overshooting
+        assert_eq!(pwm_at(&curve, Interpolation::MonotoneCubic, 55), 40);
+        let mut prev = 0;
+        for t in 0..=u8::MAX {
+            let pwm = pwm_at(&curve, Interpolation::MonotoneCubic, t);
+            assert!(pwm >= prev);
+            prev = pwm;
+        }
+    }
+
+    #[test]
+    fn few_linear_points_are_kept() {
+        let curve = points(&[(40, 20), (60, 80), (80, 200)]);
+        let (temp, pwm) = resample(&curve, Interpolation::Linear);
+        assert_eq!(temp, [40, 60, 80, 80, 80, 80, 80, 80]);
+        assert_eq!(pwm, [20, 80, 200, 200, 200, 200, 200, 200]);
+        assert_never_below(&curve, Interpolation::Linear);
+    }
+
+    #[test]
+    fn resampled_never_below_authored() {
+        let curves = [
+            points(&[(20, 0), (100, 255)]),
+            points(&[(40, 20), (60, 80), (80, 200)]),
+            points(&[(30, 10), (95, 255)]),
+            points(&[
+                (30, 0),
+                (35, 5),
+                (40, 10),
+                (45, 30),
+                (50, 35),
+                (55, 60),
+                (60, 62),
+                (65, 90),
+                (70, 140),
+                (75, 141),
+                (80, 200),
+                (90, 250),
+                (100, 255),
+            ]),
+            // Steps which a straight line between evenly spread points would cut
+            points(&[
+                (30, 0),
+                (50, 0),
+                (51, 200),
+                (60, 200),
+                (61, 255),
+                (70, 255),
+                (80, 255),
+                (90, 255),
+                (100, 255),
+            ]),
+            points(&[
+                (0, 0),
+                (1, 255),
+                (2, 255),
+                (3, 255),
+                (4, 255),
+                (5, 255),
+                (6, 255),
+                (7, 255),
+                (8, 255),
+            ]),
+            points(&[(40, 50), (41, 60), (42, 70)]),
+        ];
+        for curve in &curves {
+            check_points(curve).unwrap();
+            assert_never_below(curve, Interpolation::Linear);
+            assert_never_below(curve, Interpolation::MonotoneCubic);
+        }
+    }
+}
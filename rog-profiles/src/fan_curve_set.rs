@@ -1,11 +1,14 @@
+use std::path::Path;
+
 use log::{error, trace};
+use rog_platform::{list_attrs, read_attr_u8, write_attr_string};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
-use udev::Device;
 #[cfg(feature = "dbus")]
 use zbus::zvariant::Type;
 
 use crate::error::ProfileError;
+use crate::fan_curve_points::{check_points, resample, CurvePoint, Interpolation};
 use crate::FanCurvePU;
 
 fn set_sysfs_name(string: &mut [u8], fan: char, index: usize) {
@@ -33,12 +36,43 @@ pub(crate) fn temp_str(fan: char, index: usize) -> String {
 
 #[typeshare]
 #[cfg_attr(feature = "dbus", derive(Type))]
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct CurveData {
     pub fan: FanCurvePU,
     pub pwm: [u8; 8],
     pub temp: [u8; 8],
     pub enabled: bool,
+    /// The curve as the user made it, with any number of points. If set then
+    /// `pwm` and `temp` are resampled from it, see
+    /// [`crate::fan_curve_points::resample`].
+    #[serde(default)]
+    pub points: Vec<CurvePoint>,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+/// Safety rules checked by [`CurveData::validate`] before a curve is accepted
+/// from a user. Percentages are of full fan power.
+#[typeshare]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct FanCurveSafety {
+    /// Points at or above this temperature (C) must be at least
+    /// `min_percent`
+    pub min_percent_temp: u8,
+    pub min_percent: u8,
+    /// Points at or above this temperature (C) must be at 100%
+    pub full_speed_temp: u8,
+}
+
+impl Default for FanCurveSafety {
+    fn default() -> Self {
+        Self {
+            min_percent_temp: 70,
+            min_percent: 20,
+            full_speed_temp: 110,
+        }
+    }
 }
 
 impl From<&CurveData> for String {
@@ -71,7 +105,8 @@ impl std::str::FromStr for CurveData {
     type Err = ProfileError;
 
     /// Parse a string to the correct values that the fan curve kernel driver
-    /// expects. The returned `CurveData` is not enabled by default.
+    /// expects. The returned `CurveData` is not enabled by default. More than
+    /// 8 points are kept in `points` and linearly resampled to 8.
     ///
     /// If the fan curve is given with percentage char '%' then the fan power
     /// values are converted otherwise the expected fan power range is
@@ -80,8 +115,7 @@ impl std::str::FromStr for CurveData {
     /// Temperature range is 0-255 in degrees C. You don't want to be setting
     /// over 100.
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut temp = [0u8; 8];
-        let mut pwm = [0u8; 8];
+        let mut points = Vec::with_capacity(8);
         let mut temp_prev = 0;
         let mut pwm_prev = 0;
         let mut percentages = false;
@@ -90,7 +124,8 @@ impl std::str::FromStr for CurveData {
             return Err(ProfileError::NotEnoughPoints);
         }
 
-        for (index, value) in input.split(',').enumerate() {
+        for value in input.split(',') {
+            let mut point = CurvePoint::default();
             for (select, num) in value.splitn(2, |c| c == 'c' || c == ':').enumerate() {
                 if num.contains('%') {
                     percentages = true;
@@ -107,7 +142,7 @@ impl std::str::FromStr for CurveData {
                         ));
                     }
                     temp_prev = r;
-                    temp[index] = r;
+                    point.temp = r;
                 } else {
                     let mut p = r;
                     if percentages {
@@ -124,67 +159,169 @@ impl std::str::FromStr for CurveData {
                         ));
                     }
                     pwm_prev = p;
-                    pwm[index] = p;
+                    point.pwm = p;
                 }
             }
+            points.push(point);
+        }
+
+        if points.len() > 8 {
+            return Self::from_points(FanCurvePU::CPU, points, Interpolation::Linear);
+        }
+        let mut curve = Self::default();
+        for (i, point) in points.iter().enumerate() {
+            curve.temp[i] = point.temp;
+            curve.pwm[i] = point.pwm;
         }
-        Ok(Self {
-            fan: FanCurvePU::CPU,
-            pwm,
-            temp,
-            enabled: false,
-        })
+        Ok(curve)
     }
 }
 
 impl CurveData {
+    /// Make a curve from any number of points, resampled to the 8 the kernel
+    /// takes
+    pub fn from_points(
+        fan: FanCurvePU,
+        points: Vec<CurvePoint>,
+        interpolation: Interpolation,
+    ) -> Result<Self, ProfileError> {
+        let mut curve = Self {
+            fan,
+            points,
+            interpolation,
+            ..Default::default()
+        };
+        curve.resample()?;
+        Ok(curve)
+    }
+
+    /// Set `pwm` and `temp` from `points`, if there are any
+    pub fn resample(&mut self) -> Result<(), ProfileError> {
+        if !self.points.is_empty() {
+            check_points(&self.points)?;
+            (self.temp, self.pwm) = resample(&self.points, self.interpolation);
+        }
+        Ok(())
+    }
+
+    /// The curve as the user made it, which is `points` if set or otherwise
+    /// the 8 kernel points
+    pub fn authored_points(&self) -> Vec<CurvePoint> {
+        if !self.points.is_empty() {
+            return self.points.clone();
+        }
+        self.temp
+            .iter()
+            .zip(self.pwm.iter())
+            .map(|(temp, pwm)| CurvePoint {
+                temp: *temp,
+                pwm: *pwm,
+            })
+            .collect()
+    }
+
     pub fn set_fan(&mut self, fan: FanCurvePU) {
         self.fan = fan;
     }
 
-    fn set_val_from_attr(tmp: &str, device: &Device, buf: &mut [u8; 8]) {
+    /// Check the curve is monotonic and follows the `safety` rules
+    pub fn validate(&self, safety: &FanCurveSafety) -> Result<(), ProfileError> {
+        let unsafe_curve = |why: String| Err(ProfileError::FanCurveUnsafe(self.fan, why));
+        for i in 1..8 {
+            if self.temp[i - 1] > self.temp[i] {
+                return unsafe_curve(format!(
+                    "temperature {}c at point {} is lower than the previous {}c",
+                    self.temp[i],
+                    i + 1,
+                    self.temp[i - 1]
+                ));
+            }
+            if self.pwm[i - 1] > self.pwm[i] {
+                return unsafe_curve(format!(
+                    "fan power {} at point {} is lower than the previous {}",
+                    self.pwm[i],
+                    i + 1,
+                    self.pwm[i - 1]
+                ));
+            }
+        }
+
+        let min_pwm = (safety.min_percent as f32 * 2.55).round() as u8;
+        for (temp, pwm) in self.temp.iter().zip(self.pwm.iter()) {
+            if *temp >= safety.full_speed_temp && *pwm < 255 {
+                return unsafe_curve(format!(
+                    "fan must be at 100% at {}c or above, but is {}% at {temp}c",
+                    safety.full_speed_temp,
+                    (*pwm as u32) * 100 / 255
+                ));
+            }
+            if *temp >= safety.min_percent_temp && *pwm < min_pwm {
+                return unsafe_curve(format!(
+                    "fan must be at least {}% at {}c or above, but is {}% at {temp}c",
+                    safety.min_percent,
+                    safety.min_percent_temp,
+                    (*pwm as u32) * 100 / 255
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The fan power for `temp`, linearly interpolated between the points.
+    /// Below the first point or above the last the nearest point is used.
+    pub fn pwm_at(&self, temp: u8) -> u8 {
+        let Some(next) = self.temp.iter().position(|t| temp < *t) else {
+            return self.pwm[7];
+        };
+        if next == 0 {
+            return self.pwm[0];
+        }
+        let (t0, t1) = (self.temp[next - 1] as i32, self.temp[next] as i32);
+        let (p0, p1) = (self.pwm[next - 1] as i32, self.pwm[next] as i32);
+        (p0 + (p1 - p0) * (temp as i32 - t0) / (t1 - t0)) as u8
+    }
+
+    fn set_val_from_attr(tmp: &str, device: &Path, buf: &mut [u8; 8]) {
         if let Some(n) = tmp.chars().nth(15) {
             let i = n.to_digit(10).unwrap() as usize;
-            let d = device.attribute_value(tmp).unwrap();
-            let d: u8 = d.to_string_lossy().parse().unwrap();
+            let d = read_attr_u8(device, tmp).unwrap();
             buf[i - 1] = d;
         }
     }
 
-    pub fn read_from_device(&mut self, device: &Device) {
-        for attr in device.attributes() {
-            let tmp = attr.name().to_string_lossy();
+    pub fn read_from_device(&mut self, device: &Path) {
+        for attr in list_attrs(device) {
+            let tmp = attr.as_str();
             let pwm_num: char = self.fan.into();
             let pwm = format!("pwm{pwm_num}");
             if tmp.starts_with(&pwm) && tmp.ends_with("_temp") {
-                Self::set_val_from_attr(tmp.as_ref(), device, &mut self.temp);
+                Self::set_val_from_attr(tmp, device, &mut self.temp);
             }
             if tmp.starts_with(&pwm) && tmp.ends_with("_pwm") {
-                Self::set_val_from_attr(tmp.as_ref(), device, &mut self.pwm);
+                Self::set_val_from_attr(tmp, device, &mut self.pwm);
             }
         }
     }
 
     /// Write this curve to the device fan specified by `self.fan`
-    pub fn write_to_device(&self, device: &mut Device) -> std::io::Result<()> {
+    pub fn write_to_device(&self, device: &Path) -> Result<(), ProfileError> {
         let pwm_num: char = self.fan.into();
         let enable = if self.enabled { '1' } else { '2' };
 
         for (index, out) in self.pwm.iter().enumerate() {
             let pwm = pwm_str(pwm_num, index);
             trace!("writing {pwm}");
-            device.set_attribute_value(&pwm, &out.to_string())?;
+            write_attr_string(device, &pwm, &out.to_string())?;
         }
 
         for (index, out) in self.temp.iter().enumerate() {
             let temp = temp_str(pwm_num, index);
             trace!("writing {temp}");
-            device.set_attribute_value(&temp, &out.to_string())?;
+            write_attr_string(device, &temp, &out.to_string())?;
         }
 
         // Enable must be done *after* all points are written pwm3_enable
-        device
-            .set_attribute_value(format!("pwm{pwm_num}_enable"), enable.to_string())
+        write_attr_string(device, &format!("pwm{pwm_num}_enable"), &enable.to_string())
             .map_err(|e| error!("Failed to set pwm{pwm_num}_enable to {enable}: {e:?}"))
             .ok();
         Ok(())
@@ -238,6 +375,92 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn curve_data_from_str_many_points() {
+        let curve =
+            CurveData::from_str("30:0,40:10,50:20,60:30,65:60,70:90,75:120,80:150,90:200,100:255")
+                .unwrap();
+        assert_eq!(curve.points.len(), 10);
+        assert_eq!(curve.interpolation, Interpolation::Linear);
+        assert_eq!(curve.temp, [30, 40, 50, 60, 70, 80, 90, 100]);
+        assert_eq!(curve.pwm, [0, 10, 20, 30, 90, 150, 200, 255]);
+        assert_eq!(curve.authored_points(), curve.points);
+
+        // Points must have rising temperatures to be resampled
+        let curve = CurveData::from_str("30:0,30:10,50:20,60:30,65:60,70:90,75:120,80:150,90:200");
+        assert!(matches!(
+            curve,
+            Err(ProfileError::ParseFanCurvePrevHigher("temperature", 30, 30))
+        ));
+    }
+
+    #[test]
+    fn curve_data_validate() {
+        let safety = FanCurveSafety::default();
+        let curve =
+            CurveData::from_str("30c:1%,49c:2%,59c:3%,69c:4%,79c:31%,89c:49%,99c:56%,109c:58%")
+                .unwrap();
+        assert!(curve.validate(&safety).is_ok());
+
+        // 0% at 95c
+        let curve =
+            CurveData::from_str("30c:0%,40c:0%,50c:0%,60c:0%,70c:0%,80c:0%,90c:0%,95c:0%").unwrap();
+        let err = curve.validate(&safety).unwrap_err();
+        assert!(matches!(
+            err,
+            ProfileError::FanCurveUnsafe(FanCurvePU::CPU, _)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Unsafe CPU fan curve: fan must be at least 20% at 70c or above, but is 0% at 70c"
+        );
+
+        let curve = CurveData::from_str(
+            "30c:10%,40c:20%,50c:30%,60c:40%,70c:50%,80c:60%,100c:70%,110c:90%",
+        )
+        .unwrap();
+        assert!(matches!(
+            curve.validate(&safety),
+            Err(ProfileError::FanCurveUnsafe(_, why)) if why.contains("100% at 110c")
+        ));
+
+        // Curves given over dbus aren't parsed so may not be monotonic
+        let mut curve =
+            CurveData::from_str("30c:10%,40c:20%,50c:30%,60c:40%,70c:50%,80c:60%,90c:70%,100c:90%")
+                .unwrap();
+        curve.pwm[3] = 0;
+        assert!(matches!(
+            curve.validate(&safety),
+            Err(ProfileError::FanCurveUnsafe(_, why)) if why.contains("point 4")
+        ));
+        curve.pwm[3] = 102;
+        curve.temp[6] = 20;
+        assert!(curve.validate(&safety).is_err());
+
+        let relaxed = FanCurveSafety {
+            min_percent_temp: 255,
+            min_percent: 0,
+            full_speed_temp: 255,
+        };
+        let curve =
+            CurveData::from_str("30c:0%,40c:0%,50c:0%,60c:0%,70c:0%,80c:0%,90c:0%,95c:0%").unwrap();
+        assert!(curve.validate(&relaxed).is_ok());
+    }
+
+    #[test]
+    fn curve_data_pwm_at() {
+        let curve =
+            CurveData::from_str("40:10,50:20,60:30,70:40,80:80,80:90,90:100,100:255").unwrap();
+        assert_eq!(curve.pwm_at(0), 10);
+        assert_eq!(curve.pwm_at(40), 10);
+        assert_eq!(curve.pwm_at(45), 15);
+        assert_eq!(curve.pwm_at(75), 60);
+        // Two points at the same temperature use the higher
+        assert_eq!(curve.pwm_at(80), 90);
+        assert_eq!(curve.pwm_at(95), 177);
+        assert_eq!(curve.pwm_at(120), 255);
+    }
+
     #[test]
     fn check_pwm_str() {
         assert_eq!(pwm_str('1', 0), "pwm1_auto_point1_pwm");
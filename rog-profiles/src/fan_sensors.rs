@@ -0,0 +1,171 @@
+//! Live fan speeds and temperatures, for seeing where the system currently
+//! sits on its fan curves.
+//!
+//! The fan speeds come from `fanN_input` of the `asus` hwmon, numbered the
+//! same as the fan curves. The temperatures are the hottest of the configured
+//! [`TempSensor`]s which can be found.
+
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use rog_platform::discovery::Discovery;
+use rog_platform::{has_attr, read_attr_string};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+#[cfg(feature = "dbus")]
+use zbus::zvariant::{OwnedValue, Type, Value};
+
+use crate::soft_fan_curve::{read_temp, TempSensor};
+use crate::FanCurvePU;
+
+/// Fan speeds in RPM and temperatures in degrees C. Anything which can't be
+/// read is `0`.
+#[typeshare]
+#[cfg_attr(feature = "dbus", derive(Type, Value, OwnedValue))]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanSensors {
+    pub cpu_fan: u32,
+    pub gpu_fan: u32,
+    pub mid_fan: u32,
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+}
+
+impl FanSensors {
+    pub fn rpm(&self, fan: FanCurvePU) -> u32 {
+        match fan {
+            FanCurvePU::CPU => self.cpu_fan,
+            FanCurvePU::GPU => self.gpu_fan,
+            FanCurvePU::MID => self.mid_fan,
+        }
+    }
+
+    /// The temperature to show `fan` against. The mid fan cools both so the
+    /// hotter of the two is used.
+    pub fn temp(&self, fan: FanCurvePU) -> u8 {
+        match fan {
+            FanCurvePU::CPU => self.cpu_temp,
+            FanCurvePU::GPU => self.gpu_temp,
+            FanCurvePU::MID => self.cpu_temp.max(self.gpu_temp),
+        }
+    }
+}
+
+/// The sensors the CPU temperature is read from, which are also used by the
+/// software fan curve engine
+pub fn default_cpu_sensors() -> Vec<TempSensor> {
+    vec![
+        TempSensor::ThermalZone("x86_pkg_temp".to_owned()),
+        TempSensor::Hwmon {
+            name: "k10temp".to_owned(),
+            input: "temp1_input".to_owned(),
+        },
+        TempSensor::Hwmon {
+            name: "coretemp".to_owned(),
+            input: "temp1_input".to_owned(),
+        },
+    ]
+}
+
+/// The sensors the GPU temperature is read from. The proprietary nvidia
+/// driver has no hwmon so its temperature can't be read.
+pub fn default_gpu_sensors() -> Vec<TempSensor> {
+    vec![
+        TempSensor::Hwmon {
+            name: "amdgpu".to_owned(),
+            input: "temp1_input".to_owned(),
+        },
+        TempSensor::Hwmon {
+            name: "nouveau".to_owned(),
+            input: "temp1_input".to_owned(),
+        },
+    ]
+}
+
+/// Configuration of the fan speed and temperature reporting
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
+#[serde(default)]
+pub struct FanSensorsConfig {
+    /// How often the sensors are checked for changes to signal, in
+    /// milliseconds. `0` stops the signal.
+    pub interval: u64,
+    pub cpu: Vec<TempSensor>,
+    pub gpu: Vec<TempSensor>,
+}
+
+impl Default for FanSensorsConfig {
+    fn default() -> Self {
+        Self {
+            interval: 2000,
+            cpu: default_cpu_sensors(),
+            gpu: default_gpu_sensors(),
+        }
+    }
+}
+
+/// Find the `asus` hwmon node if it reports the speed of any fan
+pub fn find_fan_speed_node_with(discovery: &Discovery) -> Option<PathBuf> {
+    discovery.scan("hwmon").ok()?.into_iter().find(|path| {
+        read_attr_string(path, "name").is_ok_and(|name| name.trim() == "asus")
+            && has_attr(path, "fan1_input")
+    })
+}
+
+/// The sensor files found for a [`FanSensorsConfig`], read with
+/// [`SensorReader::read`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SensorReader {
+    fan_node: Option<PathBuf>,
+    cpu: Vec<PathBuf>,
+    gpu: Vec<PathBuf>,
+}
+
+impl SensorReader {
+    pub fn new_with(discovery: &Discovery, config: &FanSensorsConfig) -> Self {
+        let find = |sensors: &[TempSensor]| -> Vec<PathBuf> {
+            sensors
+                .iter()
+                .filter_map(|sensor| sensor.find_with(discovery).ok())
+                .collect()
+        };
+        let reader = Self {
+            fan_node: find_fan_speed_node_with(discovery),
+            cpu: find(&config.cpu),
+            gpu: find(&config.gpu),
+        };
+        debug!("SensorReader: {reader:?}");
+        reader
+    }
+
+    pub fn read(&self) -> FanSensors {
+        let rpm = |fan: FanCurvePU| {
+            self.fan_node
+                .as_deref()
+                .map(|node| read_rpm(node, fan))
+                .unwrap_or_default()
+        };
+        FanSensors {
+            cpu_fan: rpm(FanCurvePU::CPU),
+            gpu_fan: rpm(FanCurvePU::GPU),
+            mid_fan: rpm(FanCurvePU::MID),
+            cpu_temp: hottest(&self.cpu),
+            gpu_temp: hottest(&self.gpu),
+        }
+    }
+}
+
+fn read_rpm(node: &Path, fan: FanCurvePU) -> u32 {
+    let num: char = fan.into();
+    read_attr_string(node, &format!("fan{num}_input"))
+        .ok()
+        .and_then(|rpm| rpm.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+fn hottest(sensors: &[PathBuf]) -> u8 {
+    sensors
+        .iter()
+        .filter_map(|path| read_temp(path).ok())
+        .max()
+        .unwrap_or_default()
+}
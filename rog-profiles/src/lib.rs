@@ -1,31 +1,43 @@
 pub mod error;
+pub mod fan_curve_file;
+pub mod fan_curve_points;
 pub mod fan_curve_set;
+pub mod fan_sensors;
+pub mod soft_fan_curve;
+pub mod user_profile;
+
+use std::path::{Path, PathBuf};
 
 use error::ProfileError;
 use fan_curve_set::CurveData;
 use log::debug;
+use rog_platform::discovery::Discovery;
 use rog_platform::platform::ThrottlePolicy;
+use rog_platform::{list_attrs, read_attr_string, write_attr_string};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
-pub use udev::Device;
 #[cfg(feature = "dbus")]
 use zbus::zvariant::Type;
 use zbus::zvariant::{OwnedValue, Value};
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Use udev system to find the fan curve path/node which is labelled with
-/// "asus_custom_fan_curve" in the kernel
-pub fn find_fan_curve_node() -> Result<Device, ProfileError> {
-    let mut enumerator = udev::Enumerator::new()?;
-    enumerator.match_subsystem("hwmon")?;
+/// Find the fan curve path/node which is labelled with "asus_custom_fan_curve"
+/// in the kernel. Uses udev unless `ASUSD_SYSFS_ROOT` points to a fake tree.
+pub fn find_fan_curve_node() -> Result<PathBuf, ProfileError> {
+    find_fan_curve_node_with(&Discovery::from_env())
+}
 
-    for device in enumerator.scan_devices()? {
-        if device.parent_with_subsystem("platform")?.is_some() {
-            if let Some(name) = device.attribute_value("name") {
-                if name == "asus_custom_fan_curve" {
-                    return Ok(device);
-                }
+/// Find the "asus_custom_fan_curve" hwmon node using the given `Discovery`
+/// backend
+pub fn find_fan_curve_node_with(discovery: &Discovery) -> Result<PathBuf, ProfileError> {
+    for path in discovery.scan("hwmon")? {
+        if !discovery.has_parent_in(&path, "platform")? {
+            continue;
+        }
+        if let Ok(name) = read_attr_string(&path, "name") {
+            if name.trim() == "asus_custom_fan_curve" {
+                return Ok(path);
             }
         }
     }
@@ -33,6 +45,20 @@ pub fn find_fan_curve_node() -> Result<Device, ProfileError> {
     Err(ProfileError::NotSupported)
 }
 
+/// Write `curves` to the device. Enabled curves are written last because the
+/// kernel currently resets *all* if one is disabled.
+pub fn write_curves_to_device(curves: &[CurveData], device: &Path) -> Result<(), ProfileError> {
+    for curve in curves.iter().filter(|c| !c.enabled) {
+        debug!("write_curves_to_device: {curve:?}");
+        curve.write_to_device(device)?;
+    }
+    for curve in curves.iter().filter(|c| c.enabled) {
+        debug!("write_curves_to_device: {curve:?}");
+        curve.write_to_device(device)?;
+    }
+    Ok(())
+}
+
 #[typeshare]
 #[cfg_attr(
     feature = "dbus",
@@ -47,15 +73,14 @@ pub enum FanCurvePU {
 }
 
 impl FanCurvePU {
-    fn which_fans(device: &Device) -> Vec<Self> {
+    fn which_fans(device: &Path) -> Vec<Self> {
         let mut fans = Vec::with_capacity(3);
         for fan in [Self::CPU, Self::GPU, Self::MID] {
             let pwm_num: char = fan.into();
             let pwm_enable = format!("pwm{pwm_num}_enable");
             debug!("Looking for {pwm_enable}");
-            for attr in device.attributes() {
-                let tmp = attr.name().to_string_lossy();
-                if tmp.contains(&pwm_enable) {
+            for attr in list_attrs(device) {
+                if attr.contains(&pwm_enable) {
                     debug!("Found {pwm_enable}");
                     fans.push(fan);
                 }
@@ -107,7 +132,7 @@ impl Default for FanCurvePU {
 /// Main purpose of `FanCurves` is to enable restoring state on system boot
 #[typeshare]
 #[cfg_attr(feature = "dbus", derive(Type))]
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
 pub struct FanCurveProfiles {
     pub balanced: Vec<CurveData>,
     pub performance: Vec<CurveData>,
@@ -122,12 +147,17 @@ impl FanCurveProfiles {
         Ok(FanCurvePU::which_fans(&device))
     }
 
+    /// Return an array of `FanCurvePU` found on the fan curve node at `device`
+    pub fn supported_fans_of(device: &Path) -> Vec<FanCurvePU> {
+        FanCurvePU::which_fans(device)
+    }
+
     pub fn read_from_dev_profile(
         &mut self,
         profile: ThrottlePolicy,
-        device: &Device,
+        device: &Path,
     ) -> Result<(), ProfileError> {
-        let fans = Self::supported_fans()?;
+        let fans = Self::supported_fans_of(device);
         let mut curves = Vec::with_capacity(3);
 
         for fan in fans {
@@ -157,14 +187,14 @@ impl FanCurveProfiles {
     pub fn set_active_curve_to_defaults(
         &mut self,
         profile: ThrottlePolicy,
-        device: &mut Device,
+        device: &Path,
     ) -> Result<(), ProfileError> {
-        let fans = Self::supported_fans()?;
+        let fans = Self::supported_fans_of(device);
         // Do reset for all
         for fan in fans {
             let pwm_num: char = fan.into();
             let pwm = format!("pwm{pwm_num}_enable");
-            device.set_attribute_value(&pwm, "3")?;
+            write_attr_string(device, &pwm, "3")?;
         }
         self.read_from_dev_profile(profile, device)?;
         Ok(())
@@ -175,24 +205,10 @@ impl FanCurveProfiles {
     pub fn write_profile_curve_to_platform(
         &mut self,
         profile: ThrottlePolicy,
-        device: &mut Device,
+        device: &Path,
     ) -> Result<(), ProfileError> {
-        let fans = match profile {
-            ThrottlePolicy::Balanced => &mut self.balanced,
-            ThrottlePolicy::Performance => &mut self.performance,
-            ThrottlePolicy::Quiet => &mut self.quiet,
-        };
-        for fan in fans.iter().filter(|f| !f.enabled) {
-            debug!("write_profile_curve_to_platform: writing profile:{profile}, {fan:?}");
-            fan.write_to_device(device)?;
-        }
-        // Write enabled fans last because the kernel currently resets *all* if one is
-        // disabled
-        for fan in fans.iter().filter(|f| f.enabled) {
-            debug!("write_profile_curve_to_platform: writing profile:{profile}, {fan:?}");
-            fan.write_to_device(device)?;
-        }
-        Ok(())
+        debug!("write_profile_curve_to_platform: writing profile:{profile}");
+        write_curves_to_device(self.get_fan_curves_for(profile), device)
     }
 
     pub fn set_profile_curves_enabled(&mut self, profile: ThrottlePolicy, enabled: bool) {
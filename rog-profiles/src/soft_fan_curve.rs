@@ -0,0 +1,253 @@
+//! A userspace fan curve engine for laptops where the `asus` hwmon has
+//! `pwmN` and `pwmN_enable` but there is no `asus_custom_fan_curve` node to
+//! give the curve to the firmware.
+//!
+//! The temperature is read from hwmon or thermal zone sensors, the PWM for it
+//! is interpolated from the same 8 point `CurveData` as the firmware curves,
+//! and written to `pwmN` with the fan in manual mode. Anything that goes
+//! wrong should put the fans back in automatic mode with
+//! [`SoftFanCurve::set_all_auto`].
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use rog_platform::discovery::Discovery;
+use rog_platform::{has_attr, read_attr_string, write_attr_string};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ProfileError;
+use crate::fan_curve_set::CurveData;
+use crate::fan_sensors::default_cpu_sensors;
+use crate::FanCurvePU;
+
+const PWM_ENABLE_MANUAL: &str = "1";
+const PWM_ENABLE_AUTO: &str = "2";
+
+/// A temperature source in degrees C
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub enum TempSensor {
+    /// An hwmon device by `name`, and the attribute to read, e.g. `k10temp`
+    /// and `temp1_input`
+    Hwmon { name: String, input: String },
+    /// A thermal zone by `type`, e.g. `x86_pkg_temp`
+    ThermalZone(String),
+}
+
+impl TempSensor {
+    /// Find the file the sensor is read from
+    pub fn find_with(&self, discovery: &Discovery) -> Result<PathBuf, ProfileError> {
+        let (subsystem, id_attr, id, input) = match self {
+            TempSensor::Hwmon { name, input } => ("hwmon", "name", name, input.as_str()),
+            TempSensor::ThermalZone(zone) => ("thermal", "type", zone, "temp"),
+        };
+        for path in discovery.scan(subsystem)? {
+            if read_attr_string(&path, id_attr).is_ok_and(|v| v.trim() == id)
+                && has_attr(&path, input)
+            {
+                return Ok(path.join(input));
+            }
+        }
+        Err(ProfileError::NotFound(format!("{self:?}")))
+    }
+}
+
+/// Read a sensor file found by [`TempSensor::find_with`]. The kernel gives
+/// millidegrees.
+pub fn read_temp(path: &Path) -> Result<u8, ProfileError> {
+    let value = std::fs::read_to_string(path)
+        .map_err(|e| ProfileError::Read(path.to_string_lossy().to_string(), e))?;
+    let milli = value
+        .trim()
+        .parse::<i64>()
+        .map_err(ProfileError::ParseFanCurveDigit)?;
+    Ok((milli / 1000).clamp(0, u8::MAX as i64) as u8)
+}
+
+/// Configuration of the software fan curve engine
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
+#[serde(default)]
+pub struct SoftFanCurveConfig {
+    /// Run the engine if there is no firmware fan curve support
+    pub enabled: bool,
+    /// The hottest of these which can be found is used for all fans
+    pub sensors: Vec<TempSensor>,
+    /// How far in degrees C the temperature must drop before the fan slows
+    pub hysteresis: u8,
+    /// How often the sensors are read, in milliseconds
+    pub poll_interval: u64,
+    /// The least time between changes to a fan, in milliseconds
+    pub step_interval: u64,
+}
+
+impl Default for SoftFanCurveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensors: default_cpu_sensors(),
+            hysteresis: 4,
+            poll_interval: 1000,
+            step_interval: 2000,
+        }
+    }
+}
+
+/// Find the `asus` hwmon node if it allows manual control of any fan
+pub fn find_pwm_node_with(discovery: &Discovery) -> Result<PathBuf, ProfileError> {
+    for path in discovery.scan("hwmon")? {
+        if read_attr_string(&path, "name").is_ok_and(|name| name.trim() == "asus")
+            && !pwm_fans_of(&path).is_empty()
+        {
+            return Ok(path);
+        }
+    }
+    Err(ProfileError::NotSupported)
+}
+
+/// The fans with both `pwmN` and `pwmN_enable` on the hwmon node at `device`
+pub fn pwm_fans_of(device: &Path) -> Vec<FanCurvePU> {
+    [FanCurvePU::CPU, FanCurvePU::GPU, FanCurvePU::MID]
+        .into_iter()
+        .filter(|fan| {
+            let num: char = (*fan).into();
+            has_attr(device, &format!("pwm{num}")) && has_attr(device, &format!("pwm{num}_enable"))
+        })
+        .collect()
+}
+
+/// The curve used for each fan until the user sets one
+pub fn default_soft_curve(fan: FanCurvePU) -> CurveData {
+    CurveData {
+        fan,
+        temp: [40, 50, 60, 70, 80, 90, 95, 100],
+        pwm: [38, 51, 77, 102, 140, 191, 230, 255],
+        ..Default::default()
+    }
+}
+
+/// The state of one fan, deciding when it is changed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FanStep {
+    /// What was last written, `None` if the fan is in automatic mode
+    pwm: Option<u8>,
+    /// The temperature `pwm` was set for
+    temp: u8,
+    changed: Option<Instant>,
+}
+
+impl FanStep {
+    /// The PWM to write for `temp`, or `None` if the fan should be left as is.
+    ///
+    /// Changes are at least `interval` apart, and the fan is only slowed once
+    /// the temperature is `hysteresis` below the one the current speed was
+    /// set for.
+    pub fn step(
+        &mut self,
+        curve: &CurveData,
+        temp: u8,
+        hysteresis: u8,
+        interval: Duration,
+        now: Instant,
+    ) -> Option<u8> {
+        let target = curve.pwm_at(temp);
+        if let Some(pwm) = self.pwm {
+            let waited = self
+                .changed
+                .map_or(true, |changed| now.duration_since(changed) >= interval);
+            if target == pwm || !waited {
+                return None;
+            }
+            if target < pwm && temp.saturating_add(hysteresis) > self.temp {
+                return None;
+            }
+        }
+        self.pwm = Some(target);
+        self.temp = temp;
+        self.changed = Some(now);
+        Some(target)
+    }
+
+    /// Forget the last speed, such as when the fan is put in automatic mode
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.pwm.is_some()
+    }
+}
+
+/// The fans of the `asus` hwmon node driven by the software engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftFanCurve {
+    device: PathBuf,
+    fans: Vec<FanCurvePU>,
+}
+
+impl SoftFanCurve {
+    pub fn new_with(discovery: &Discovery) -> Result<Self, ProfileError> {
+        let device = find_pwm_node_with(discovery)?;
+        let fans = pwm_fans_of(&device);
+        Ok(Self { device, fans })
+    }
+
+    pub fn fans(&self) -> &[FanCurvePU] {
+        &self.fans
+    }
+
+    /// Put `fan` in manual mode and set its speed
+    pub fn set_pwm(&self, fan: FanCurvePU, pwm: u8) -> Result<(), ProfileError> {
+        let num: char = fan.into();
+        debug!("SoftFanCurve: pwm{num} {pwm}");
+        write_attr_string(&self.device, &format!("pwm{num}_enable"), PWM_ENABLE_MANUAL)?;
+        write_attr_string(&self.device, &format!("pwm{num}"), &pwm.to_string())?;
+        Ok(())
+    }
+
+    /// Give `fan` back to the firmware
+    pub fn set_auto(&self, fan: FanCurvePU) -> Result<(), ProfileError> {
+        let num: char = fan.into();
+        write_attr_string(&self.device, &format!("pwm{num}_enable"), PWM_ENABLE_AUTO)?;
+        Ok(())
+    }
+
+    /// Give every fan back to the firmware, logging any failures
+    pub fn set_all_auto(&self) {
+        for fan in &self.fans {
+            self.set_auto(*fan)
+                .map_err(|e| warn!("SoftFanCurve: could not set {fan:?} to auto: {e}"))
+                .ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{default_soft_curve, FanStep};
+    use crate::FanCurvePU;
+
+    #[test]
+    fn fan_step_hysteresis_and_interval() {
+        let curve = default_soft_curve(FanCurvePU::CPU);
+        let interval = Duration::from_secs(2);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut step = FanStep::default();
+        assert!(!step.is_manual());
+
+        assert_eq!(step.step(&curve, 70, 4, interval, at(0)), Some(102));
+        assert!(step.is_manual());
+        // Too soon after the last change
+        assert_eq!(step.step(&curve, 80, 4, interval, at(1)), None);
+        assert_eq!(step.step(&curve, 80, 4, interval, at(2)), Some(140));
+        // Not far enough below 80c to slow down
+        assert_eq!(step.step(&curve, 77, 4, interval, at(10)), None);
+        assert_eq!(step.step(&curve, 76, 4, interval, at(11)), Some(124));
+
+        step.reset();
+        assert!(!step.is_manual());
+        assert_eq!(step.step(&curve, 40, 4, interval, at(12)), Some(38));
+    }
+}
@@ -0,0 +1,105 @@
+//! Named profiles made by the user, such as "Meeting" or "Gaming-Docked".
+//! Each is based on a `ThrottlePolicy` and can replace its fan curves, PPT
+//! tuning and EPP, and set an Aura mode, so they all switch together.
+
+use rog_platform::cpu::CPUEPP;
+use rog_platform::platform::{PlatformTuning, ThrottlePolicy};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+#[cfg(feature = "dbus")]
+use zbus::zvariant::Type;
+
+use crate::error::ProfileError;
+use crate::fan_curve_set::CurveData;
+
+#[typeshare]
+#[cfg_attr(feature = "dbus", derive(Type))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct UserProfile {
+    pub name: String,
+    pub throttle_policy: ThrottlePolicy,
+    /// Used in place of the fan curves of `throttle_policy`. Empty to keep
+    /// those.
+    pub fan_curves: Vec<CurveData>,
+    /// Used in place of the tuning of `throttle_policy`, any unset value
+    /// falls back to it
+    pub tuning: PlatformTuning,
+    /// Set in place of the EPP linked to `throttle_policy`, unless `Default`
+    pub epp: CPUEPP,
+    /// Set each Aura keyboard to `aura_mode` if it has that mode
+    pub set_aura_mode: bool,
+    /// The number of an Aura mode, as in `rog_aura::AuraModeNum`
+    pub aura_mode: u8,
+}
+
+impl UserProfile {
+    /// The fan curves to use while `policy` is active, if this profile has
+    /// its own for it
+    pub fn fan_curves_for(&self, policy: ThrottlePolicy) -> Option<&[CurveData]> {
+        (policy == self.throttle_policy && !self.fan_curves.is_empty())
+            .then_some(self.fan_curves.as_slice())
+    }
+
+    /// Check the profile has a name and at most one curve per fan, and
+    /// resample any curves made of `points`
+    pub fn check(&mut self) -> Result<(), ProfileError> {
+        if self.name.trim().is_empty() || self.name.trim() != self.name {
+            return Err(ProfileError::UserProfile(format!(
+                "\"{}\" is not a usable name",
+                self.name
+            )));
+        }
+        for (i, curve) in self.fan_curves.iter().enumerate() {
+            if self.fan_curves[..i].iter().any(|c| c.fan == curve.fan) {
+                return Err(ProfileError::UserProfile(format!(
+                    "{} has more than one {:?} fan curve",
+                    self.name, curve.fan
+                )));
+            }
+        }
+        for curve in &mut self.fan_curves {
+            curve.resample()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rog_platform::platform::ThrottlePolicy;
+
+    use super::UserProfile;
+    use crate::fan_curve_set::CurveData;
+    use crate::FanCurvePU;
+
+    #[test]
+    fn check_user_profile() {
+        let mut profile = UserProfile {
+            name: "Compile".to_owned(),
+            throttle_policy: ThrottlePolicy::Performance,
+            ..Default::default()
+        };
+        assert!(profile.check().is_ok());
+        assert!(profile
+            .fan_curves_for(ThrottlePolicy::Performance)
+            .is_none());
+
+        profile.fan_curves = vec![CurveData::default(), CurveData::default()];
+        assert!(profile.check().is_err());
+        profile.fan_curves[1].fan = FanCurvePU::GPU;
+        assert!(profile.check().is_ok());
+        assert_eq!(
+            profile
+                .fan_curves_for(ThrottlePolicy::Performance)
+                .map(|c| c.len()),
+            Some(2)
+        );
+        assert!(profile.fan_curves_for(ThrottlePolicy::Quiet).is_none());
+
+        for name in ["", " Compile", "Compile "] {
+            profile.name = name.to_owned();
+            assert!(profile.check().is_err());
+        }
+    }
+}
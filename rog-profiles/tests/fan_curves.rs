@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    use rog_platform::discovery::Discovery;
+    use rog_platform::platform::ThrottlePolicy;
+    use rog_platform::test_util::{fixture_at, read_trimmed};
+    use rog_profiles::fan_curve_set::CurveData;
+    use rog_profiles::fan_sensors::{FanSensors, FanSensorsConfig, SensorReader};
+    use rog_profiles::soft_fan_curve::{read_temp, SoftFanCurve, SoftFanCurveConfig, TempSensor};
+    use rog_profiles::{find_fan_curve_node_with, FanCurvePU, FanCurveProfiles};
+
+    /// The sysfs fixtures are shared with `rog-platform`
+    fn fixture(model: &str, test: &str) -> (Discovery, PathBuf) {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("sysfs")
+            .join(format!("{model}-{test}"));
+        fixture_at(model, root)
+    }
+
+    #[test]
+    fn find_node_and_fans() {
+        let (discovery, root) = fixture("ga402", "find_node");
+        let node = find_fan_curve_node_with(&discovery).unwrap();
+        assert_eq!(node, root.join("class/hwmon/hwmon5"));
+        assert_eq!(
+            FanCurveProfiles::supported_fans_of(&node),
+            vec![FanCurvePU::CPU, FanCurvePU::GPU]
+        );
+
+        let (discovery, _) = fixture("gu604", "find_node");
+        let node = find_fan_curve_node_with(&discovery).unwrap();
+        assert_eq!(
+            FanCurveProfiles::supported_fans_of(&node),
+            vec![FanCurvePU::CPU, FanCurvePU::GPU, FanCurvePU::MID]
+        );
+    }
+
+    #[test]
+    fn no_fan_curve_node() {
+        let (discovery, root) = fixture("fa507", "no_node");
+        fs::remove_dir_all(root.join("class/hwmon/hwmon5")).unwrap();
+        assert!(find_fan_curve_node_with(&discovery).is_err());
+    }
+
+    #[test]
+    fn read_and_write_curves() {
+        let (discovery, _) = fixture("gu604", "read_write");
+        let node = find_fan_curve_node_with(&discovery).unwrap();
+
+        let mut profiles = FanCurveProfiles::default();
+        profiles
+            .read_from_dev_profile(ThrottlePolicy::Balanced, &node)
+            .unwrap();
+        let curves = profiles.get_fan_curves_for(ThrottlePolicy::Balanced);
+        assert_eq!(curves.len(), 3);
+        assert_eq!(curves[0].temp, [30, 49, 59, 69, 79, 89, 99, 109]);
+        assert_eq!(curves[0].pwm, [8, 10, 20, 34, 76, 122, 150, 168]);
+        assert_eq!(curves[2].fan, FanCurvePU::MID);
+
+        let mut curve = CurveData::from_str(
+            "40c:10%,50c:20%,60c:30%,70c:40%,80c:50%,90c:60%,95c:80%,100c:100%",
+        )
+        .unwrap();
+        curve.set_fan(FanCurvePU::GPU);
+        curve.enabled = true;
+        profiles
+            .save_fan_curve(curve, ThrottlePolicy::Balanced)
+            .unwrap();
+        profiles
+            .write_profile_curve_to_platform(ThrottlePolicy::Balanced, &node)
+            .unwrap();
+
+        assert_eq!(read_trimmed(&node.join("pwm2_auto_point1_temp")), "40");
+        assert_eq!(read_trimmed(&node.join("pwm2_auto_point8_pwm")), "255");
+        assert_eq!(read_trimmed(&node.join("pwm2_enable")), "1");
+        assert_eq!(read_trimmed(&node.join("pwm1_enable")), "2");
+    }
+
+    #[test]
+    fn reset_to_defaults() {
+        let (discovery, _) = fixture("ga402", "defaults");
+        let node = find_fan_curve_node_with(&discovery).unwrap();
+
+        let mut profiles = FanCurveProfiles::default();
+        profiles
+            .set_active_curve_to_defaults(ThrottlePolicy::Quiet, &node)
+            .unwrap();
+        assert_eq!(read_trimmed(&node.join("pwm1_enable")), "3");
+        assert_eq!(read_trimmed(&node.join("pwm2_enable")), "3");
+        assert_eq!(profiles.get_fan_curves_for(ThrottlePolicy::Quiet).len(), 2);
+    }
+
+    #[test]
+    fn soft_fan_curve() {
+        let (discovery, root) = fixture("fx506", "soft");
+        assert!(find_fan_curve_node_with(&discovery).is_err());
+
+        let soft = SoftFanCurve::new_with(&discovery).unwrap();
+        assert_eq!(soft.fans(), &[FanCurvePU::CPU, FanCurvePU::GPU]);
+        let node = root.join("class/hwmon/hwmon3");
+
+        soft.set_pwm(FanCurvePU::GPU, 128).unwrap();
+        assert_eq!(read_trimmed(&node.join("pwm2_enable")), "1");
+        assert_eq!(read_trimmed(&node.join("pwm2")), "128");
+        assert_eq!(read_trimmed(&node.join("pwm1_enable")), "2");
+        soft.set_all_auto();
+        assert_eq!(read_trimmed(&node.join("pwm2_enable")), "2");
+
+        // Only k10temp of the defaults is in the fixture
+        let found: Vec<_> = SoftFanCurveConfig::default()
+            .sensors
+            .iter()
+            .filter_map(|s| s.find_with(&discovery).ok())
+            .collect();
+        assert_eq!(found, vec![root.join("class/hwmon/hwmon1/temp1_input")]);
+        assert_eq!(read_temp(&found[0]).unwrap(), 65);
+
+        let zone = TempSensor::ThermalZone("acpitz".to_owned())
+            .find_with(&discovery)
+            .unwrap();
+        assert_eq!(read_temp(&zone).unwrap(), 52);
+    }
+
+    #[test]
+    fn fan_speeds_and_temps() {
+        let (discovery, root) = fixture("ga402", "sensors");
+        let reader = SensorReader::new_with(&discovery, &FanSensorsConfig::default());
+        let sensors = reader.read();
+        assert_eq!(
+            sensors,
+            FanSensors {
+                cpu_fan: 2900,
+                gpu_fan: 2700,
+                mid_fan: 0,
+                cpu_temp: 58,
+                gpu_temp: 49,
+            }
+        );
+        assert_eq!(sensors.rpm(FanCurvePU::GPU), 2700);
+        assert_eq!(sensors.temp(FanCurvePU::MID), 58);
+
+        // Sensors are read each time, and any which fail are left out
+        fs::write(root.join("class/hwmon/hwmon3/fan1_input"), "3400").unwrap();
+        fs::write(root.join("class/hwmon/hwmon2/temp1_input"), "nope").unwrap();
+        let sensors = reader.read();
+        assert_eq!(sensors.cpu_fan, 3400);
+        assert_eq!(sensors.gpu_temp, 0);
+
+        // Nothing to read on a machine without the sensors
+        let (discovery, _) = fixture("gu604", "sensors");
+        let sensors = SensorReader::new_with(&discovery, &FanSensorsConfig::default()).read();
+        assert_eq!(sensors.cpu_temp, 0);
+        assert_eq!(sensors.gpu_fan, 0);
+    }
+}